@@ -0,0 +1,133 @@
+//! Pipeline benchmarks: tokenizing, tree building, layout and a full-frame
+//! paint, each over small, medium and huge synthetic documents.
+//!
+//! ```sh
+//! cargo bench                    # everything
+//! cargo bench -- layout          # only benchmarks whose name contains "layout"
+//! ```
+//!
+//! Every benchmark runs for about a second and reports the median time per
+//! iteration with the fastest and slowest runs. The paint benchmarks need a
+//! font (see `radium::load_font_set`) and are skipped without one.
+
+use std::fmt::Write;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use radium::layout::Estimate;
+use radium::renderer::backend::Raster;
+use radium::{parser, Viewport};
+
+/// Approximate measuring time per benchmark.
+const MEASURE: Duration = Duration::from_secs(1);
+/// Fewest iterations measured, however slow.
+const MIN_SAMPLES: usize = 10;
+
+const FRAME: (u32, u32) = (800, 600);
+
+/// Document sizes, in sections (a heading, two paragraphs, a list and a rule).
+const SIZES: [(&str, usize); 3] = [("small", 4), ("medium", 200), ("huge", 10_000)];
+
+fn main() {
+    // `cargo bench` passes `--bench`; any other argument filters by name.
+    let filter: Vec<String> = std::env::args().skip(1).filter(|arg| !arg.starts_with("--")).collect();
+    let selected = |name: &str| filter.is_empty() || filter.iter().any(|f| name.contains(f.as_str()));
+
+    let fonts = match radium::load_font_set() {
+        Ok(fonts) => Some(fonts),
+        Err(e) => {
+            println!("paint benchmarks skipped: {e}");
+            None
+        }
+    };
+
+    for (size, sections) in SIZES {
+        let html = document(sections);
+        let bytes = html.len();
+
+        let name = format!("tokenize/{size}");
+        if selected(&name) {
+            bench(&name, bytes, || (), |()| parser::tokenize(&html));
+        }
+
+        let name = format!("build_tree/{size}");
+        if selected(&name) {
+            let tokens = parser::tokenize(&html);
+            bench(&name, bytes, || tokens.clone(), parser::dom::build_tree);
+        }
+
+        let document = radium::parse(&html);
+        let viewport = Viewport::new(FRAME.0, FRAME.1);
+
+        let name = format!("layout/{size}");
+        if selected(&name) {
+            bench(&name, bytes, || (), |()| radium::layout(&document, viewport, &Estimate));
+        }
+
+        let name = format!("paint/{size}");
+        if let (Some(fonts), true) = (&fonts, selected(&name)) {
+            let list = radium::layout(&document, viewport, fonts);
+            let mut target = Raster::blank(FRAME.0, FRAME.1, 0);
+            bench(&name, bytes, || (), |()| radium::paint(&list, &mut target, viewport, fonts));
+        }
+    }
+}
+
+/// Time `routine` on fresh input from `setup` (which is not timed) and print
+/// a summary line. `bytes` is the document size, for throughput.
+fn bench<I, O>(name: &str, bytes: usize, mut setup: impl FnMut() -> I, mut routine: impl FnMut(I) -> O) {
+    // Warm up caches and the allocator.
+    black_box(routine(setup()));
+
+    let mut samples = Vec::new();
+    let start = Instant::now();
+    while samples.len() < MIN_SAMPLES || start.elapsed() < MEASURE {
+        let input = setup();
+        let t = Instant::now();
+        black_box(routine(input));
+        samples.push(t.elapsed());
+    }
+    samples.sort();
+
+    let median = samples[samples.len() / 2];
+    let throughput = bytes as f64 / median.as_secs_f64() / 1e6;
+    println!(
+        "{name:<20} {:>12} ({} … {}) {throughput:>9.1} MB/s  {} iterations",
+        format_duration(median),
+        format_duration(samples[0]),
+        format_duration(samples[samples.len() - 1]),
+        samples.len(),
+    );
+}
+
+fn format_duration(d: Duration) -> String {
+    let ns = d.as_nanos() as f64;
+    match ns {
+        _ if ns < 1e3 => format!("{ns:.0} ns"),
+        _ if ns < 1e6 => format!("{:.1} µs", ns / 1e3),
+        _ if ns < 1e9 => format!("{:.1} ms", ns / 1e6),
+        _ => format!("{:.2} s", ns / 1e9),
+    }
+}
+
+/// A synthetic document of `sections` repetitions of typical content: a
+/// heading with an anchor, paragraphs with inline markup and links, a list
+/// and a horizontal rule.
+fn document(sections: usize) -> String {
+    let mut html = String::from("<!DOCTYPE html>\n<html><head><title>Benchmark</title></head><body>\n");
+    for i in 0..sections {
+        let _ = write!(
+            html,
+            "<h2 id=\"s{i}\">Section {i}</h2>\n\
+             <p>Lorem ipsum dolor sit amet, <b>consectetur</b> adipiscing elit, sed do \
+             <em>eiusmod</em> tempor incididunt ut labore et dolore magna aliqua.</p>\n\
+             <p>See <a href=\"#s{next}\" title=\"Next section\">section {next}</a> &amp; \
+             <a href=\"https://example.com/{i}\">elsewhere</a>.</p>\n\
+             <ul><li>First item</li><li>Second item</li><li>Third item</li></ul>\n\
+             <hr>\n",
+            next = (i + 1) % sections,
+        );
+    }
+    html.push_str("</body></html>\n");
+    html
+}
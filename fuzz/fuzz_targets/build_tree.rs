@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use radium::layout::Estimate;
+use radium::net::Url;
+use radium::parser::dom;
+use radium::renderer::LAYOUT_WIDTH;
+
+// Everything downstream of the tree walks it recursively, so lay it out and
+// serialize it too. The base is remote so `<img src>` never touches the disk.
+fuzz_target!(|data: &[u8]| {
+    let nodes = dom::build_tree_lossy(data);
+    let _ = dom::to_html(&nodes, &mut |_, _, _| None);
+    let _ = radium::dump::dom(&nodes);
+    let base = Url::parse("http://fuzz.invalid/").unwrap();
+    let page = radium::layout::layout(&nodes, LAYOUT_WIDTH, &base, &Default::default(), &Estimate);
+    let _ = radium::dump::layout(&page);
+});
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let tokens = radium::parser::tokenize_bytes(data);
+    let _ = radium::dump::tokens(&tokens);
+});
@@ -0,0 +1,81 @@
+/*
+ * radium C API: render HTML for lightweight previews.
+ *
+ * Build the shared library with
+ *
+ *     cargo rustc --lib --release --features ffi --crate-type cdylib
+ *
+ * and link against target/release/libradium.so (.dylib, .dll).
+ */
+
+#ifndef RADIUM_H
+#define RADIUM_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Return codes. */
+#define RADIUM_OK             0
+#define RADIUM_ERROR_ARGUMENT 1 /* null pointer, HTML not UTF-8, or zero size */
+#define RADIUM_ERROR_FONT     2 /* no usable font was found */
+#define RADIUM_ERROR_RENDER   3 /* any other rendering failure */
+
+/* RadiumPaintCmd.kind */
+#define RADIUM_PAINT_TEXT  0
+#define RADIUM_PAINT_RECT  1
+#define RADIUM_PAINT_HLINE 2
+#define RADIUM_PAINT_IMAGE 3
+
+/* RadiumPaintCmd.flags, for text */
+#define RADIUM_TEXT_BOLD      1
+#define RADIUM_TEXT_ITALIC    2
+#define RADIUM_TEXT_UNDERLINE 4
+#define RADIUM_TEXT_MONOSPACE 8
+
+/*
+ * One entry of a display list. Coordinates are logical pixels from the
+ * top-left of the document; pointers are only valid during the callback.
+ */
+typedef struct RadiumPaintCmd {
+    uint32_t kind;          /* RADIUM_PAINT_* */
+    float x, y, width, height;
+    uint32_t color;         /* 0xTTRRGGBB, TT = 255 - alpha; unused for images */
+    float font_size;        /* text only */
+    uint32_t flags;         /* text only: RADIUM_TEXT_* */
+    const uint8_t *text;    /* text only: UTF-8, not NUL-terminated */
+    size_t text_len;
+    const uint8_t *image;   /* images only: RGBA8, image_width * image_height * 4 bytes */
+    uint32_t image_width;
+    uint32_t image_height;
+} RadiumPaintCmd;
+
+typedef void (*RadiumPaintCallback)(const RadiumPaintCmd *cmd, void *user_data);
+
+/*
+ * Render `html` (NUL-terminated UTF-8) into `out_rgba`, which must hold
+ * width * height * 4 bytes (RGBA8, row-major). Fonts are found as by the
+ * radium binary, once per process.
+ */
+int radium_render_html(const char *html, uint32_t width, uint32_t height, uint8_t *out_rgba);
+
+/*
+ * Lay `html` out `width` logical pixels wide and call `callback` with each
+ * paint command in order, passing `user_data` through. Needs no fonts.
+ */
+int radium_paint_html(const char *html, uint32_t width, RadiumPaintCallback callback, void *user_data);
+
+/*
+ * The message for the last error on this thread, or NULL. Valid until the
+ * next radium call on the same thread.
+ */
+const char *radium_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RADIUM_H */
@@ -0,0 +1,154 @@
+use std::path::PathBuf;
+
+use radium::config::parse_color;
+use radium::renderer::Options;
+use radium::stats::Stats;
+
+const USAGE: &str = "\
+Usage: radium [options] <directory|file.html|url>[#fragment]
+       radium --control stdio [options] [document]";
+
+const HELP: &str = "\
+Options:
+      --watch                  Reload when the document or its assets change
+      --location-bar           Always show the location bar
+      --markdown               Treat the document as Markdown
+      --width <px>             Window (or screenshot) width in logical pixels
+      --height <px>            Window (or screenshot) height in logical pixels
+      --scale <factor>         Physical pixels per logical pixel, instead of the display's
+      --zoom <factor>          Initial zoom, instead of the remembered one
+      --font-family <name>     Document font family, e.g. \"DejaVu Serif\"
+      --font-dir <dir>         Extra directory to search for fonts (repeatable)
+      --background <color>     Page background, #rgb or #rrggbb
+      --dark                   Dark mode: invert the lightness of page colors
+      --user-stylesheet <file> Stylesheet applied on top of every document's
+      --screenshot <out.png>   Render to a PNG instead of opening a window
+      --full-page              With --screenshot, capture the whole page
+      --dump-tokens            Print the token stream and exit
+      --dump-dom               Print the DOM tree and exit
+      --dump-layout            Print the layout boxes and exit
+      --dump-text              Print the document as plain text and exit
+      --control stdio          Take JSON commands on stdin instead of opening a window
+      --stats                  Print pipeline statistics as JSON after every load in a window
+  -h, --help                   Show this help";
+
+/// Window size when only one of `--width` and `--height` is given.
+const DEFAULT_SIZE: (u32, u32) = (800, 600);
+
+/// Options followed by a value.
+const VALUE_FLAGS: &[&str] = &[
+    "--width", "--height", "--scale", "--zoom", "--font-family", "--font-dir",
+    "--background", "--user-stylesheet", "--screenshot", "--control",
+];
+
+/// Intermediate representation to print instead of opening a window.
+pub enum Dump {
+    Tokens,
+    Dom,
+    Layout,
+    Text,
+}
+
+/// Everything given on the command line.
+pub struct Args {
+    /// Always set unless `control`.
+    pub target: Option<String>,
+    pub options: Options,
+    pub markdown: bool,
+    pub screenshot: Option<PathBuf>,
+    pub full_page: bool,
+    pub dump: Option<Dump>,
+    /// `--control stdio`: run a headless session driven by JSON commands.
+    pub control: bool,
+}
+
+/// Parse the process arguments on top of `defaults` (from the config file),
+/// exiting with a usage message on any error.
+pub fn parse(defaults: Options) -> Args {
+    parse_from(std::env::args().skip(1), defaults).unwrap_or_else(|e| {
+        eprintln!("radium: {e}");
+        eprintln!("{USAGE}");
+        std::process::exit(1);
+    })
+}
+
+fn parse_from(args: impl IntoIterator<Item = String>, mut options: Options) -> Result<Args, String> {
+    let mut target = None;
+    let mut markdown = false;
+    let mut screenshot = None;
+    let mut full_page = false;
+    let mut dump = None;
+    let mut control = false;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        // `--flag=value` and `--flag value` are both accepted.
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+            _ => (arg.clone(), None),
+        };
+        if inline.is_some() && !VALUE_FLAGS.contains(&flag.as_str()) {
+            return Err(format!("{flag} does not take a value"));
+        }
+        let mut value = || {
+            inline.clone().or_else(|| args.next()).ok_or_else(|| format!("{flag} needs a value"))
+        };
+        match flag.as_str() {
+            "-h" | "--help" => {
+                println!("{USAGE}\n\n{HELP}");
+                std::process::exit(0);
+            }
+            "--watch" => options.watch = true,
+            "--location-bar" => options.location_bar = true,
+            "--markdown" => markdown = true,
+            "--width" => options.size.get_or_insert(DEFAULT_SIZE).0 = parse_pixels(&flag, &value()?)?,
+            "--height" => options.size.get_or_insert(DEFAULT_SIZE).1 = parse_pixels(&flag, &value()?)?,
+            "--scale" => options.scale = Some(parse_factor(&flag, &value()?)?),
+            "--zoom" => options.zoom = Some(parse_factor(&flag, &value()?)?),
+            "--font-family" => options.fonts.family = Some(value()?),
+            "--font-dir" => options.fonts.dirs.push(PathBuf::from(value()?)),
+            "--background" => {
+                let color = value()?;
+                options.theme.background = Some(parse_color(&color).ok_or_else(|| format!("invalid color '{color}'"))?);
+            }
+            "--dark" => options.theme.dark = true,
+            "--user-stylesheet" => {
+                let path = value()?;
+                let css = std::fs::read_to_string(&path).map_err(|e| format!("reading {path}: {e}"))?;
+                options.user_stylesheet = Some(css);
+            }
+            "--screenshot" => screenshot = Some(PathBuf::from(value()?)),
+            "--full-page" => full_page = true,
+            "--stats" => options.stats = Some(Stats::default()),
+            "--dump-tokens" => dump = Some(Dump::Tokens),
+            "--dump-dom" => dump = Some(Dump::Dom),
+            "--dump-layout" => dump = Some(Dump::Layout),
+            "--dump-text" => dump = Some(Dump::Text),
+            "--control" => match value()?.as_str() {
+                "stdio" => control = true,
+                other => return Err(format!("unsupported --control channel '{other}' (only stdio)")),
+            },
+            _ if target.is_none() && !arg.starts_with('-') => target = Some(arg),
+            _ if arg.starts_with('-') => return Err(format!("unknown option {flag}")),
+            _ => return Err(format!("unexpected argument '{arg}'")),
+        }
+    }
+
+    if target.is_none() && !control {
+        return Err("no document given".into());
+    }
+    if full_page && screenshot.is_none() {
+        return Err("--full-page needs --screenshot".into());
+    }
+    Ok(Args { target, options, markdown, screenshot, full_page, dump, control })
+}
+
+fn parse_pixels(flag: &str, value: &str) -> Result<u32, String> {
+    value.parse().ok().filter(|&px| px > 0).ok_or_else(|| format!("{flag} must be a positive whole number, not '{value}'"))
+}
+
+fn parse_factor(flag: &str, value: &str) -> Result<f32, String> {
+    value.parse().ok()
+        .filter(|f: &f32| f.is_finite() && *f > 0.0)
+        .ok_or_else(|| format!("{flag} must be a positive number, not '{value}'"))
+}
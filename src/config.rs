@@ -0,0 +1,287 @@
+//! Defaults read from `~/.config/radium/config.toml`.
+//!
+//! Only the part of TOML a settings file needs is understood: `[tables]`,
+//! `key = value` lines with strings, numbers, booleans and one-line arrays,
+//! and `#` comments. For example:
+//!
+//! ```toml
+//! [window]
+//! width = 1024
+//! height = 768
+//! zoom = 1.2
+//! scroll_speed = 60
+//!
+//! [fonts]
+//! family = "DejaVu Serif"
+//! dirs = ["~/fonts"]
+//!
+//! [colors]
+//! background = "#fdf6e3"
+//! dark = false
+//!
+//! [keys]
+//! reload = ["Ctrl+R", "F5"]
+//! reader = "Ctrl+Shift+R"
+//! ```
+//!
+//! Settings are applied to [`Options`] before the command line, so flags
+//! override them.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::renderer::keys::{Action, Binding};
+use crate::renderer::Options;
+
+/// Window size when only one of `width` and `height` is set.
+const DEFAULT_SIZE: (u32, u32) = (800, 600);
+
+/// `$XDG_CONFIG_HOME/radium/config.toml` (`~/.config/...` by default), or
+/// `%APPDATA%\radium\config.toml` on Windows.
+pub fn path() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    Some(base?.join("radium").join("config.toml"))
+}
+
+/// Apply the config file, if there is one, to `options`. Problems are
+/// reported on stderr and the offending setting skipped.
+pub fn load(options: &mut Options) {
+    let Some(path) = path() else { return };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+        Err(e) => {
+            eprintln!("radium: reading {}: {e}", path.display());
+            return;
+        }
+    };
+    let dir = path.parent().unwrap_or(Path::new("."));
+    for (line, error) in apply(&text, dir, options) {
+        eprintln!("radium: {}:{line}: {error}", path.display());
+    }
+}
+
+/// Apply settings from `text` to `options`, resolving relative paths against
+/// `dir`. Returns the line number and message of each setting skipped.
+pub fn apply(text: &str, dir: &Path, options: &mut Options) -> Vec<(usize, String)> {
+    let mut errors = Vec::new();
+    let mut table = String::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        let result = if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            table = name.trim().to_string();
+            Ok(())
+        } else {
+            match line.split_once('=') {
+                Some((key, value)) => parse_value(value.trim())
+                    .and_then(|value| set(&table, key.trim(), value, dir, options)),
+                None => Err(format!("expected 'key = value', found '{line}'")),
+            }
+        };
+        if let Err(e) = result {
+            errors.push((i + 1, e));
+        }
+    }
+    errors
+}
+
+/// Store one setting.
+fn set(table: &str, key: &str, value: Value, dir: &Path, options: &mut Options) -> Result<(), String> {
+    match (table, key) {
+        ("window", "width" | "height") => {
+            let px = value.positive(key)?.round() as u32;
+            let (width, height) = options.size.get_or_insert(DEFAULT_SIZE);
+            *if key == "width" { width } else { height } = px.max(1);
+        }
+        ("window", "scale") => options.scale = Some(value.positive(key)?),
+        ("window", "zoom") => options.zoom = Some(value.positive(key)?),
+        ("window", "scroll_speed") => options.scroll_speed = Some(value.positive(key)?),
+        ("window", "location_bar") => options.location_bar = value.boolean(key)?,
+        ("fonts", "family") => options.fonts.family = Some(value.string(key)?),
+        ("fonts", "dirs") => {
+            for item in value.list() {
+                options.fonts.dirs.push(resolve(&item.string(key)?, dir));
+            }
+        }
+        ("colors", "background") => {
+            let color = value.string(key)?;
+            options.theme.background = Some(parse_color(&color).ok_or_else(|| format!("invalid color '{color}'"))?);
+        }
+        ("colors", "dark") => options.theme.dark = value.boolean(key)?,
+        ("", "user_stylesheet") => {
+            let path = resolve(&value.string(key)?, dir);
+            let css = fs::read_to_string(&path).map_err(|e| format!("reading {}: {e}", path.display()))?;
+            options.user_stylesheet = Some(css);
+        }
+        ("keys", name) => {
+            let action = Action::from_name(name).ok_or_else(|| format!("unknown action '{name}'"))?;
+            let bindings = value.list().into_iter()
+                .map(|item| item.string(name).and_then(|text| Binding::parse(&text)))
+                .collect::<Result<_, _>>()?;
+            options.keys.bind(action, bindings);
+        }
+        _ if table.is_empty() => return Err(format!("unknown setting '{key}'")),
+        _ => return Err(format!("unknown setting '{key}' in [{table}]")),
+    }
+    Ok(())
+}
+
+/// `~/x` under the home directory; other relative paths under `dir`.
+fn resolve(path: &str, dir: &Path) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => dir.join(path),
+    }
+}
+
+/// `#rgb`, `#rrggbb` (the `#` is optional) or `white` / `black`, as `0xRRGGBB`.
+pub fn parse_color(value: &str) -> Option<u32> {
+    match value.to_ascii_lowercase().as_str() {
+        "white" => return Some(0xFFFFFF),
+        "black" => return Some(0x000000),
+        _ => {}
+    }
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    match hex.len() {
+        3 => {
+            let rgb = u32::from_str_radix(hex, 16).ok()?;
+            // Double each digit: #abc → #aabbcc.
+            Some([8, 4, 0].iter().fold(0, |acc, shift| (acc << 8) | (((rgb >> shift) & 0xF) * 0x11)))
+        }
+        6 => u32::from_str_radix(hex, 16).ok(),
+        _ => None,
+    }
+}
+
+// ── Values ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug)]
+enum Value {
+    String(String),
+    Number(f64),
+    Bool(bool),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    fn string(self, key: &str) -> Result<String, String> {
+        match self {
+            Value::String(s) => Ok(s),
+            _ => Err(format!("{key} must be a string")),
+        }
+    }
+
+    fn positive(self, key: &str) -> Result<f32, String> {
+        match self {
+            Value::Number(n) if n.is_finite() && n > 0.0 => Ok(n as f32),
+            _ => Err(format!("{key} must be a positive number")),
+        }
+    }
+
+    fn boolean(self, key: &str) -> Result<bool, String> {
+        match self {
+            Value::Bool(b) => Ok(b),
+            _ => Err(format!("{key} must be true or false")),
+        }
+    }
+
+    /// The items of an array, or the value itself as a one-item list.
+    fn list(self) -> Vec<Value> {
+        match self {
+            Value::Array(items) => items,
+            value => vec![value],
+        }
+    }
+}
+
+fn parse_value(text: &str) -> Result<Value, String> {
+    let (value, rest) = parse_prefix(text)?;
+    if !rest.trim().is_empty() {
+        return Err(format!("unexpected '{}' after value", rest.trim()));
+    }
+    Ok(value)
+}
+
+/// Parse one value from the start of `text`, returning it and what follows.
+fn parse_prefix(text: &str) -> Result<(Value, &str), String> {
+    let text = text.trim_start();
+    if let Some(rest) = text.strip_prefix('"') {
+        let mut out = String::new();
+        let mut chars = rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Ok((Value::String(out), &rest[i + 1..])),
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some(c @ ('"' | '\\')) => out.push(c),
+                    _ => return Err("unsupported escape in string".into()),
+                },
+                c => out.push(c),
+            }
+        }
+        return Err("unterminated string".into());
+    }
+    if let Some(rest) = text.strip_prefix('\'') {
+        let end = rest.find('\'').ok_or("unterminated string")?;
+        return Ok((Value::String(rest[..end].to_string()), &rest[end + 1..]));
+    }
+    if let Some(mut rest) = text.strip_prefix('[') {
+        let mut items = Vec::new();
+        loop {
+            rest = rest.trim_start();
+            if let Some(after) = rest.strip_prefix(']') {
+                return Ok((Value::Array(items), after));
+            }
+            let (item, after) = parse_prefix(rest)?;
+            items.push(item);
+            rest = after.trim_start();
+            rest = rest.strip_prefix(',').unwrap_or(rest);
+            if rest.is_empty() {
+                return Err("unterminated array".into());
+            }
+        }
+    }
+
+    let end = text.find(|c: char| c == ',' || c == ']' || c.is_whitespace()).unwrap_or(text.len());
+    let (word, rest) = text.split_at(end);
+    let value = match word {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => Value::Number(word.replace('_', "").parse().map_err(|_| format!("invalid value '{word}'"))?),
+    };
+    Ok((value, rest))
+}
+
+/// `line` without a trailing `# comment` (a `#` inside a string is kept).
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(q), c) if c == q && !escaped => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '#') => return &line[..i],
+            _ => {}
+        }
+        escaped = false;
+    }
+    line
+}
@@ -0,0 +1,545 @@
+//! `--control stdio`: a headless session driven by JSON commands, for
+//! automated visual testing.
+//!
+//! Each line of input is one command object; each line of output is a reply
+//! or an event. A command's `"id"`, if any, is echoed in its reply:
+//!
+//! ```text
+//! → {"id": 1, "command": "load", "url": "docs/index.html"}
+//! ← {"event": "title_changed", "title": "Docs"}
+//! ← {"event": "loaded", "url": "file:///home/me/docs/index.html", "title": "Docs"}
+//! ← {"id": 1, "ok": true}
+//! → {"command": "screenshot", "path": "top.png"}
+//! ← {"ok": true}
+//! ```
+//!
+//! Commands:
+//!
+//! - `load` `url`: open a URL or path (a `#fragment` scrolls to it)
+//! - `viewport` `width`, `height`, `scale` (each optional): resize
+//! - `scroll` `y` or `fragment`: scroll to a logical offset or an element;
+//!   the reply's `y` is where it ended up
+//! - `screenshot` `path`, `full_page` (optional): write a PNG
+//! - `text`: the reply's `text` is the page's text, as copied with select-all
+//! - `quit`
+//!
+//! Failures reply `{"ok": false, "error": "..."}` and leave the session as it
+//! was.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Write};
+
+use image::RgbaImage;
+
+use crate::document::Source;
+use crate::layout::{self, Estimate, Page, TextMeasurer};
+use crate::renderer::backend::Raster;
+use crate::renderer::selection::{select_all, selected_text};
+use crate::renderer::Options;
+use crate::{headless_layout, paint, Document, FontSet, RenderOptions, Viewport};
+
+/// Viewport size, in logical pixels, unless the options give one.
+const DEFAULT_SIZE: (u32, u32) = (800, 600);
+
+/// Read commands from `input` until `quit` or the end of input, writing
+/// replies and events to `output`. `target`, if given, is loaded first
+/// (scrolled to `options.fragment`); if that fails, the session starts
+/// empty.
+pub fn run(input: impl BufRead, output: impl Write, options: &Options, target: Option<Source>) -> io::Result<()> {
+    layout::set_user_stylesheet(options.user_stylesheet.as_deref());
+    let mut session = Session::new(options, output);
+    if let Some(source) = target {
+        let opened = session.open(source)
+            .and_then(|()| options.fragment.as_ref().map_or(Ok(()), |f| session.scroll_to_fragment(f)));
+        if let Err(e) = opened {
+            eprintln!("radium: {e}");
+        }
+    }
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let command = match parse(&line) {
+            Ok(Value::Object(fields)) => fields,
+            Ok(_) => vec![],
+            Err(e) => {
+                session.send(&[("ok", false.into()), ("error", format!("invalid JSON: {e}").into())])?;
+                continue;
+            }
+        };
+        let name = field(&command, "command").and_then(Value::as_str).unwrap_or_default().to_string();
+
+        let mut reply = Vec::new();
+        if let Some(id) = field(&command, "id") {
+            reply.push(("id", id.clone()));
+        }
+        match session.execute(&name, &command) {
+            Ok(fields) => {
+                reply.push(("ok", true.into()));
+                reply.extend(fields);
+            }
+            Err(e) => {
+                reply.push(("ok", false.into()));
+                reply.push(("error", e.into()));
+            }
+        }
+        session.send(&reply)?;
+        if name == "quit" {
+            break;
+        }
+    }
+    Ok(())
+}
+
+// ── Session ───────────────────────────────────────────────────────────────────
+
+struct Session<W> {
+    output: W,
+    /// Viewport in logical pixels.
+    size: (u32, u32),
+    /// Physical pixels per logical pixel, before zoom.
+    scale: f32,
+    zoom: f32,
+    render: RenderOptions,
+    /// Loaded with the first document, to measure its text; a session
+    /// without fonts lays text out by [`Estimate`] until screenshots need them.
+    fonts: Option<FontSet>,
+    document: Option<(Source, Document, Page)>,
+    scroll_y: f32,
+    title: Option<String>,
+}
+
+type Reply = Vec<(&'static str, Value)>;
+
+impl<W: Write> Session<W> {
+    fn new(options: &Options, output: W) -> Session<W> {
+        let scale = options.scale.unwrap_or(1.0);
+        let zoom = options.zoom.unwrap_or(1.0);
+        Session {
+            output,
+            size: options.size.unwrap_or(DEFAULT_SIZE),
+            scale,
+            zoom,
+            render: RenderOptions {
+                scale: scale * zoom,
+                fragment: None,
+                theme: options.theme,
+                fonts: options.fonts.clone(),
+            },
+            fonts: None,
+            document: None,
+            scroll_y: 0.0,
+            title: None,
+        }
+    }
+
+    fn execute(&mut self, name: &str, args: &[(String, Value)]) -> Result<Reply, String> {
+        match name {
+            "load" => {
+                let url = string(args, "url")?.ok_or("load needs a url")?;
+                let (source, fragment) = Source::resolve(url)?;
+                self.open(source)?;
+                if let Some(fragment) = fragment {
+                    self.scroll_to_fragment(&fragment)?;
+                }
+                Ok(vec![])
+            }
+            "viewport" => {
+                let positive = |key: &str| -> Result<Option<f64>, String> {
+                    match number(args, key)? {
+                        Some(n) if !(n.is_finite() && n > 0.0) => Err(format!("{key} must be positive")),
+                        n => Ok(n),
+                    }
+                };
+                let (width, height, scale) = (positive("width")?, positive("height")?, positive("scale")?);
+                self.size = (
+                    width.map_or(self.size.0, |w| (w.round() as u32).max(1)),
+                    height.map_or(self.size.1, |h| (h.round() as u32).max(1)),
+                );
+                if let Some(scale) = scale {
+                    self.scale = scale as f32;
+                    self.render.scale = self.scale * self.zoom;
+                }
+                self.relayout();
+                Ok(vec![])
+            }
+            "scroll" => {
+                match (number(args, "y")?, string(args, "fragment")?) {
+                    (Some(y), _) => self.scroll_y = self.clamp_scroll(y as f32),
+                    (None, Some(fragment)) => self.scroll_to_fragment(fragment)?,
+                    (None, None) => return Err("scroll needs y or fragment".into()),
+                }
+                Ok(vec![("y", self.scroll_y.into())])
+            }
+            "screenshot" => {
+                let path = string(args, "path")?.ok_or("screenshot needs a path")?;
+                let full_page = matches!(field(args, "full_page"), Some(Value::Bool(true)));
+                self.screenshot(path, full_page).map_err(|e| format!("{path}: {e}"))?;
+                Ok(vec![])
+            }
+            "text" => {
+                let (_, _, page) = self.document.as_ref().ok_or("no document loaded")?;
+                let text = select_all(page).map(|sel| selected_text(page, &sel)).unwrap_or_default();
+                Ok(vec![("text", text.into())])
+            }
+            "quit" => Ok(vec![]),
+            "" => Err("missing command".into()),
+            other => Err(format!("unknown command '{other}'")),
+        }
+    }
+
+    /// Load `source`, replacing the current document, and announce it.
+    fn open(&mut self, mut source: Source) -> Result<(), String> {
+        let nodes = source.load()?;
+        let title = source.title(&nodes);
+        let url = source.url.to_string();
+        let document = Document { nodes, base: source.url.clone() };
+        self.load_fonts();
+        let measurer: &dyn TextMeasurer = self.fonts.as_ref().map_or(&Estimate, |fonts| fonts);
+        let page = headless_layout(&document, self.physical_size().0, &self.render, measurer);
+        self.document = Some((source, document, page));
+        self.scroll_y = 0.0;
+
+        if self.title.as_ref() != Some(&title) {
+            self.title = Some(title.clone());
+            self.send_event(&[("event", "title_changed".into()), ("title", title.clone().into())]);
+        }
+        self.send_event(&[("event", "loaded".into()), ("url", url.into()), ("title", title.into())]);
+        Ok(())
+    }
+
+    /// Load the fonts, unless they are loaded or cannot be.
+    fn load_fonts(&mut self) {
+        if self.fonts.is_none() {
+            self.fonts = FontSet::load(&self.render.fonts).ok();
+        }
+    }
+
+    /// Lay the current document out again for a changed viewport.
+    fn relayout(&mut self) {
+        let width = self.physical_size().0;
+        let measurer: &dyn TextMeasurer = self.fonts.as_ref().map_or(&Estimate, |fonts| fonts);
+        if let Some((_, document, page)) = &mut self.document {
+            *page = headless_layout(document, width, &self.render, measurer);
+        }
+        self.scroll_y = self.clamp_scroll(self.scroll_y);
+    }
+
+    fn scroll_to_fragment(&mut self, fragment: &str) -> Result<(), String> {
+        let (_, _, page) = self.document.as_ref().ok_or("no document loaded")?;
+        let rect = page.anchors.get(fragment).ok_or_else(|| format!("no element with id '{fragment}'"))?;
+        self.scroll_y = self.clamp_scroll(rect.y);
+        Ok(())
+    }
+
+    /// `y` limited to the scrollable range of the current document.
+    fn clamp_scroll(&self, y: f32) -> f32 {
+        let Some((_, _, page)) = &self.document else { return 0.0 };
+        let visible = self.physical_size().1 as f32 / self.render.scale;
+        y.min(page.height() - visible).max(0.0)
+    }
+
+    fn physical_size(&self) -> (u32, u32) {
+        let (width, height) = self.size;
+        ((width as f32 * self.scale).round() as u32, (height as f32 * self.scale).round() as u32)
+    }
+
+    fn screenshot(&mut self, path: &str, full_page: bool) -> crate::Result<()> {
+        let (width, height) = self.physical_size();
+        let Some((_, document, page)) = &self.document else {
+            return Err(crate::Error::Io(io::Error::other("no document loaded")));
+        };
+        if full_page {
+            let file = BufWriter::new(File::create(path)?);
+            return crate::full_page_png(document, width, &self.render, file);
+        }
+        let fonts = match &mut self.fonts {
+            Some(fonts) => fonts,
+            fonts => fonts.insert(FontSet::load(&self.render.fonts)?),
+        };
+        let viewport = Viewport { width, height, scale: self.render.scale, scroll_y: self.scroll_y, theme: self.render.theme };
+        let mut target = Raster::blank(width, height, 0);
+        paint(page, &mut target, viewport, fonts);
+        let rgba = target.to_rgba();
+        let len = rgba.len() / 4;
+        let image = RgbaImage::from_raw(width, height, rgba).ok_or(crate::Error::Buffer { len, width, height })?;
+        Ok(image.save(path)?)
+    }
+
+    fn send(&mut self, fields: &[(&str, Value)]) -> io::Result<()> {
+        let mut line = String::new();
+        write_object(fields, &mut line);
+        line.push('\n');
+        self.output.write_all(line.as_bytes())?;
+        self.output.flush()
+    }
+
+    /// Events are best-effort: a closed output shows up on the next reply.
+    fn send_event(&mut self, fields: &[(&str, Value)]) {
+        let _ = self.send(fields);
+    }
+}
+
+/// The member `key` of a command.
+fn field<'a>(args: &'a [(String, Value)], key: &str) -> Option<&'a Value> {
+    args.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+}
+
+fn number(args: &[(String, Value)], key: &str) -> Result<Option<f64>, String> {
+    match field(args, key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Number(n)) => Ok(Some(*n)),
+        Some(_) => Err(format!("{key} must be a number")),
+    }
+}
+
+fn string<'a>(args: &'a [(String, Value)], key: &str) -> Result<Option<&'a str>, String> {
+    match field(args, key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s)),
+        Some(_) => Err(format!("{key} must be a string")),
+    }
+}
+
+// ── JSON ──────────────────────────────────────────────────────────────────────
+
+/// A parsed JSON value. Object members keep their order.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Value {
+        Value::Bool(b)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Value {
+        Value::Number(n)
+    }
+}
+
+impl From<f32> for Value {
+    /// Via the shortest decimal for the `f32`, so 108.8 is not written as
+    /// 108.80001831054688.
+    fn from(n: f32) -> Value {
+        Value::Number(n.to_string().parse().unwrap_or_default())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Value {
+        Value::String(s)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Value {
+        Value::String(s.to_string())
+    }
+}
+
+fn parse(text: &str) -> Result<Value, String> {
+    let mut parser = Parser { chars: text.char_indices().peekable(), text };
+    let value = parser.value(0)?;
+    parser.skip_whitespace();
+    match parser.chars.next() {
+        None => Ok(value),
+        Some((i, _)) => Err(format!("unexpected '{}' at {i}", &text[i..].chars().next().unwrap_or_default())),
+    }
+}
+
+/// Nesting limit, so hostile input cannot overflow the stack.
+const MAX_NESTING: usize = 64;
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    text: &'a str,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| matches!(c, ' ' | '\t' | '\n' | '\r')).is_some() {}
+    }
+
+    fn expect(&mut self, want: char) -> Result<(), String> {
+        match self.chars.next() {
+            Some((_, c)) if c == want => Ok(()),
+            Some((i, c)) => Err(format!("expected '{want}', found '{c}' at {i}")),
+            None => Err(format!("expected '{want}', found end of input")),
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, String> {
+        if depth > MAX_NESTING {
+            return Err("nested too deeply".into());
+        }
+        self.skip_whitespace();
+        let Some(&(start, c)) = self.chars.peek() else { return Err("unexpected end of input".into()) };
+        match c {
+            '"' => self.string().map(Value::String),
+            '[' => {
+                self.chars.next();
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.chars.next_if(|&(_, c)| c == ']').is_some() {
+                    return Ok(Value::Array(items));
+                }
+                loop {
+                    items.push(self.value(depth + 1)?);
+                    self.skip_whitespace();
+                    if self.chars.next_if(|&(_, c)| c == ',').is_none() {
+                        self.expect(']')?;
+                        return Ok(Value::Array(items));
+                    }
+                }
+            }
+            '{' => {
+                self.chars.next();
+                let mut members = Vec::new();
+                self.skip_whitespace();
+                if self.chars.next_if(|&(_, c)| c == '}').is_some() {
+                    return Ok(Value::Object(members));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.skip_whitespace();
+                    self.expect(':')?;
+                    members.push((key, self.value(depth + 1)?));
+                    self.skip_whitespace();
+                    if self.chars.next_if(|&(_, c)| c == ',').is_none() {
+                        self.expect('}')?;
+                        return Ok(Value::Object(members));
+                    }
+                }
+            }
+            _ => {
+                let mut end = start;
+                while let Some((i, c)) = self.chars.next_if(|(_, c)| c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.')) {
+                    end = i + c.len_utf8();
+                }
+                match &self.text[start..end] {
+                    "null" => Ok(Value::Null),
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    word => word.parse().ok().filter(|n: &f64| n.is_finite()).map(Value::Number)
+                        .ok_or_else(|| format!("unexpected '{}' at {start}", if word.is_empty() { &self.text[start..] } else { word })),
+                }
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            match self.chars.next().ok_or("unterminated string")?.1 {
+                '"' => return Ok(out),
+                '\\' => match self.chars.next().ok_or("unterminated string")?.1 {
+                    c @ ('"' | '\\' | '/') => out.push(c),
+                    'b' => out.push('\u{8}'),
+                    'f' => out.push('\u{c}'),
+                    'n' => out.push('\n'),
+                    'r' => out.push('\r'),
+                    't' => out.push('\t'),
+                    'u' => {
+                        let unit = self.hex4()?;
+                        let c = if (0xD800..0xDC00).contains(&unit) {
+                            // A high surrogate must be followed by `\u` and a low one.
+                            self.expect('\\')?;
+                            self.expect('u')?;
+                            let low = self.hex4()?;
+                            char::from_u32(0x10000 + ((unit - 0xD800) << 10) + low.wrapping_sub(0xDC00))
+                                .filter(|_| (0xDC00..0xE000).contains(&low))
+                        } else {
+                            char::from_u32(unit)
+                        };
+                        out.push(c.ok_or("invalid \\u escape")?);
+                    }
+                    c => return Err(format!("invalid escape '\\{c}'")),
+                },
+                c if c < ' ' => return Err("control character in string".into()),
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        (0..4).try_fold(0, |acc, _| {
+            let digit = self.chars.next().and_then(|(_, c)| c.to_digit(16)).ok_or("invalid \\u escape")?;
+            Ok(acc * 16 + digit)
+        })
+    }
+}
+
+fn write_object(fields: &[(&str, Value)], out: &mut String) {
+    out.push('{');
+    for (i, (key, value)) in fields.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        write_string(key, out);
+        out.push_str(": ");
+        write_value(value, out);
+    }
+    out.push('}');
+}
+
+fn write_value(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        // Whole numbers without a fraction, as other JSON writers do.
+        Value::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => out.push_str(&format!("{}", *n as i64)),
+        Value::Number(n) => out.push_str(&n.to_string()),
+        Value::String(s) => write_string(s, out),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_value(item, out);
+            }
+            out.push(']');
+        }
+        Value::Object(members) => {
+            let fields: Vec<(&str, Value)> = members.iter().map(|(k, v)| (k.as_str(), v.clone())).collect();
+            write_object(&fields, out);
+        }
+    }
+}
+
+pub(crate) fn write_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
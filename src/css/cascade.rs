@@ -0,0 +1,56 @@
+//! Ordering the declarations that apply to an element by precedence.
+
+use std::collections::HashMap;
+
+use super::{Declaration, Specificity, Stylesheet};
+
+/// Where a style sheet comes from. Normal declarations of a later origin
+/// win over those of an earlier one; `!important` ones reverse that, and
+/// win over every normal one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Origin {
+    /// The browser's defaults for each tag.
+    UserAgent,
+    /// The reader's own style sheet (`--user-stylesheet`).
+    User,
+    /// The document's `<style>` elements and linked style sheets.
+    Author,
+}
+
+/// The declarations of `sheets` matching an element with `tag` and `attrs`,
+/// and of its `style` attribute (`inline`), in increasing precedence: by
+/// origin and importance, then selector specificity, then the order they
+/// are written in. Applying them in turn leaves the winner of each property.
+pub fn cascade<'a>(
+    sheets: &[(Origin, &'a Stylesheet)],
+    inline: &'a [Declaration],
+    tag: &'a str,
+    attrs: &'a HashMap<String, String>,
+) -> Vec<&'a Declaration> {
+    let mut matched: Vec<(u8, Specificity, &Declaration)> = Vec::new();
+    for &(origin, sheet) in sheets {
+        for (specificity, rule) in sheet.matching(tag, attrs) {
+            matched.extend(rule.declarations.iter().map(|d| (rank(origin, false, d.important), specificity, d)));
+        }
+    }
+    // The style attribute is author style more specific than any selector.
+    matched.extend(inline.iter().map(|d| (rank(Origin::Author, true, d.important), Specificity::default(), d)));
+    // Stable, so declarations otherwise equal stay in the order written.
+    matched.sort_by_key(|&(rank, specificity, _)| (rank, specificity));
+    matched.into_iter().map(|(_, _, d)| d).collect()
+}
+
+/// Precedence of a declaration from `origin` (from a `style` attribute if
+/// `inline`), lowest first.
+fn rank(origin: Origin, inline: bool, important: bool) -> u8 {
+    match (origin, inline, important) {
+        (Origin::UserAgent, _, false) => 0,
+        (Origin::User, _, false) => 1,
+        (Origin::Author, false, false) => 2,
+        (Origin::Author, true, false) => 3,
+        (Origin::Author, false, true) => 4,
+        (Origin::Author, true, true) => 5,
+        (Origin::User, _, true) => 6,
+        (Origin::UserAgent, _, true) => 7,
+    }
+}
@@ -0,0 +1,490 @@
+//! A CSS subset: the tokenizer, declaration lists (`color: red; margin: 0`),
+//! style sheets of simple selectors, the cascade, and the values layout
+//! understands.
+
+mod cascade;
+mod color;
+mod stylesheet;
+
+pub use cascade::{cascade, Origin};
+pub use stylesheet::{Rule, Selector, Specificity, Stylesheet};
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Font size `rem` lengths are relative to: the default one.
+pub const ROOT_FONT_SIZE: f32 = 16.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Ident(String),
+    /// A name followed by `(`, as in `rgb(`; the `(` is taken with it.
+    Function(String),
+    AtKeyword(String),
+    /// `#` and the name after it, as in `#fff` or `#main`.
+    Hash(String),
+    String(String),
+    Number(f32),
+    Percentage(f32),
+    /// A number with a unit, as in `12px`; the unit is lower-cased.
+    Dimension(f32, String),
+    /// A run of whitespace (and comments).
+    Whitespace,
+    Colon,
+    Semicolon,
+    Comma,
+    OpenParen,
+    CloseParen,
+    OpenBracket,
+    CloseBracket,
+    OpenBrace,
+    CloseBrace,
+    Delim(char),
+}
+
+pub fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        let token = match c {
+            _ if c.is_whitespace() || starts_comment(&chars) => {
+                skip_whitespace_and_comments(&mut chars);
+                Token::Whitespace
+            }
+            '"' | '\'' => {
+                chars.next();
+                Token::String(read_string(&mut chars, c))
+            }
+            '#' => {
+                chars.next();
+                if chars.peek().is_some_and(|&c| is_name_char(c) || c == '\\') {
+                    Token::Hash(read_name(&mut chars))
+                } else {
+                    Token::Delim('#')
+                }
+            }
+            '@' => {
+                chars.next();
+                if starts_ident(&chars) {
+                    Token::AtKeyword(read_name(&mut chars))
+                } else {
+                    Token::Delim('@')
+                }
+            }
+            _ if starts_number(&chars) => read_numeric(&mut chars),
+            _ if starts_ident(&chars) => {
+                let name = read_name(&mut chars);
+                if chars.peek() == Some(&'(') {
+                    chars.next();
+                    Token::Function(name)
+                } else {
+                    Token::Ident(name)
+                }
+            }
+            _ => {
+                chars.next();
+                match c {
+                    ':' => Token::Colon,
+                    ';' => Token::Semicolon,
+                    ',' => Token::Comma,
+                    '(' => Token::OpenParen,
+                    ')' => Token::CloseParen,
+                    '[' => Token::OpenBracket,
+                    ']' => Token::CloseBracket,
+                    '{' => Token::OpenBrace,
+                    '}' => Token::CloseBrace,
+                    _ => Token::Delim(c),
+                }
+            }
+        };
+        tokens.push(token);
+    }
+
+    tokens
+}
+
+fn starts_comment(chars: &Peekable<Chars>) -> bool {
+    let mut ahead = chars.clone();
+    ahead.next() == Some('/') && ahead.next() == Some('*')
+}
+
+fn skip_whitespace_and_comments(chars: &mut Peekable<Chars>) {
+    loop {
+        if chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        } else if starts_comment(chars) {
+            chars.next();
+            chars.next();
+            // An unclosed comment runs to the end of the input.
+            let mut star = false;
+            for c in chars.by_ref() {
+                if star && c == '/' {
+                    break;
+                }
+                star = c == '*';
+            }
+        } else {
+            break;
+        }
+    }
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || !c.is_ascii()
+}
+
+fn is_name_char(c: char) -> bool {
+    is_name_start(c) || c.is_ascii_digit() || c == '-'
+}
+
+/// Whether an identifier starts here: a name-start character or an escape,
+/// after at most one `-` (or two, for custom property names).
+fn starts_ident(chars: &Peekable<Chars>) -> bool {
+    let mut ahead = chars.clone();
+    match ahead.next() {
+        Some('-') => matches!(ahead.next(), Some(c) if is_name_start(c) || c == '-' || c == '\\'),
+        Some(c) => is_name_start(c) || c == '\\',
+        None => false,
+    }
+}
+
+/// Whether a number starts here: a digit, or a sign or `.` before one.
+fn starts_number(chars: &Peekable<Chars>) -> bool {
+    let mut ahead = chars.clone();
+    let mut c = ahead.next();
+    if matches!(c, Some('+' | '-')) {
+        c = ahead.next();
+    }
+    if c == Some('.') {
+        c = ahead.next();
+    }
+    c.is_some_and(|c| c.is_ascii_digit())
+}
+
+/// Read a name, resolving escapes (`\31 0` is `10`, `\:` is `:`).
+fn read_name(chars: &mut Peekable<Chars>) -> String {
+    let mut name = String::new();
+    while let Some(&c) = chars.peek() {
+        if c == '\\' {
+            chars.next();
+            name.extend(read_escape(chars));
+        } else if is_name_char(c) {
+            name.push(c);
+            chars.next();
+        } else {
+            break;
+        }
+    }
+    name
+}
+
+/// Read what follows a `\`: up to six hex digits (and one whitespace
+/// character after them) naming a code point, or the character itself.
+fn read_escape(chars: &mut Peekable<Chars>) -> Option<char> {
+    let mut hex = String::new();
+    while hex.len() < 6 && chars.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+        hex.extend(chars.next());
+    }
+    if hex.is_empty() {
+        return chars.next();
+    }
+    if chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+    let code = u32::from_str_radix(&hex, 16).unwrap_or(0);
+    Some(char::from_u32(code).filter(|&c| c != '\0').unwrap_or('\u{FFFD}'))
+}
+
+/// Read a string up to its closing `quote` (already past the opening one).
+/// An escaped line break continues the string; an unescaped one ends it.
+fn read_string(chars: &mut Peekable<Chars>, quote: char) -> String {
+    let mut s = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            _ if c == quote => break,
+            '\n' => break,
+            '\\' if chars.peek() == Some(&'\n') => {
+                chars.next();
+            }
+            '\\' => s.extend(read_escape(chars)),
+            _ => s.push(c),
+        }
+    }
+    s
+}
+
+fn read_numeric(chars: &mut Peekable<Chars>) -> Token {
+    let mut digits = String::new();
+    if let Some(&sign @ ('+' | '-')) = chars.peek() {
+        digits.push(sign);
+        chars.next();
+    }
+    let mut seen_dot = false;
+    while let Some(&c) = chars.peek() {
+        let fraction_follows = || {
+            let mut ahead = chars.clone();
+            ahead.next();
+            ahead.next().is_some_and(|c| c.is_ascii_digit())
+        };
+        if c.is_ascii_digit() || (c == '.' && !seen_dot && fraction_follows()) {
+            seen_dot |= c == '.';
+            digits.push(c);
+            chars.next();
+        } else {
+            break;
+        }
+    }
+    let value = digits.parse().unwrap_or(0.0);
+    if chars.peek() == Some(&'%') {
+        chars.next();
+        Token::Percentage(value)
+    } else if starts_ident(chars) {
+        Token::Dimension(value, read_name(chars).to_ascii_lowercase())
+    } else {
+        Token::Number(value)
+    }
+}
+
+// ── Declarations ──────────────────────────────────────────────────────────────
+
+/// One `name: value` of a declaration list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration {
+    /// Property name, lower-cased.
+    pub name: String,
+    /// The value's tokens, without whitespace at either end or `!important`.
+    pub value: Vec<Token>,
+    pub important: bool,
+}
+
+/// The declarations of a `style` attribute, such as `color: red; margin: 0`.
+pub fn parse_declarations(input: &str) -> Vec<Declaration> {
+    declarations(&tokenize(input))
+}
+
+/// The declarations in `tokens`, separated by `;`. Ones without a name,
+/// colon or value are dropped, as is anything after an unmatched `}`.
+pub fn declarations(tokens: &[Token]) -> Vec<Declaration> {
+    let mut out = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::OpenParen | Token::Function(_) | Token::OpenBracket | Token::OpenBrace => depth += 1,
+            Token::CloseParen | Token::CloseBracket => depth = depth.saturating_sub(1),
+            Token::CloseBrace if depth == 0 => {
+                out.extend(declaration(&tokens[start..i]));
+                return out;
+            }
+            Token::CloseBrace => depth -= 1,
+            Token::Semicolon if depth == 0 => {
+                out.extend(declaration(&tokens[start..i]));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    out.extend(declaration(&tokens[start..]));
+    out
+}
+
+fn declaration(tokens: &[Token]) -> Option<Declaration> {
+    let tokens = trim(tokens);
+    let [Token::Ident(name), rest @ ..] = tokens else { return None };
+    let [Token::Colon, value @ ..] = trim(rest) else { return None };
+    let mut value = trim(value);
+    let mut important = false;
+    if let [before @ .., Token::Ident(word)] = value {
+        if let [before @ .., Token::Delim('!')] = trim(before) {
+            if word.eq_ignore_ascii_case("important") {
+                value = trim(before);
+                important = true;
+            }
+        }
+    }
+    if value.is_empty() {
+        return None;
+    }
+    Some(Declaration { name: name.to_ascii_lowercase(), value: value.to_vec(), important })
+}
+
+/// `tokens` without whitespace at either end.
+fn trim(tokens: &[Token]) -> &[Token] {
+    let start = tokens.iter().position(|t| *t != Token::Whitespace).unwrap_or(tokens.len());
+    let end = tokens.iter().rposition(|t| *t != Token::Whitespace).map_or(start, |i| i + 1);
+    &tokens[start..end]
+}
+
+/// The value's components: its tokens without whitespace, a function
+/// call (with its arguments) counting as one.
+pub fn components(value: &[Token]) -> Vec<&[Token]> {
+    let mut parts = Vec::new();
+    let mut i = 0;
+    while i < value.len() {
+        if value[i] == Token::Whitespace {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let mut depth = 0usize;
+        loop {
+            match value[i] {
+                Token::Function(_) | Token::OpenParen | Token::OpenBracket | Token::OpenBrace => depth += 1,
+                Token::CloseParen | Token::CloseBracket | Token::CloseBrace => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            i += 1;
+            if depth == 0 || i == value.len() {
+                break;
+            }
+        }
+        parts.push(&value[start..i]);
+    }
+    parts
+}
+
+// ── Values ────────────────────────────────────────────────────────────────────
+
+/// The colour `value` names, packed as `PaintCmd` colours are: `0xRRGGBB`
+/// with its transparency (255 minus alpha) in the top byte, so that plain
+/// `0xRRGGBB` is opaque. A named colour or `transparent`, `#rgb`, `#rgba`,
+/// `#rrggbb`, `#rrggbbaa`, `rgb()` / `rgba()` with numbers or percentages,
+/// or `hsl()` / `hsla()`; the functions take commas or spaces and an
+/// optional alpha (after a `/` when space-separated).
+pub fn color(value: &[Token]) -> Option<u32> {
+    match value {
+        [Token::Ident(name)] if name.eq_ignore_ascii_case("transparent") => Some(rgba([0.0; 3], 0.0)),
+        [Token::Ident(name)] => color::named(&name.to_ascii_lowercase()),
+        [Token::Hash(hex)] => hex_color(hex),
+        [Token::Function(name), args @ ..] => {
+            let args = args.strip_suffix(&[Token::CloseParen]).unwrap_or(args);
+            let args: Vec<&[Token]> = components(args).into_iter()
+                .filter(|part| !matches!(part, [Token::Comma] | [Token::Delim('/')]))
+                .collect();
+            let (channels, alpha) = match args[..] {
+                [a, b, c] => ([a, b, c], 1.0),
+                [a, b, c, alpha] => ([a, b, c], alpha_value(alpha)?),
+                _ => return None,
+            };
+            match name.to_ascii_lowercase().as_str() {
+                "rgb" | "rgba" => {
+                    let rgb = channels.map(|part| match part {
+                        [Token::Number(n)] => Some(*n),
+                        [Token::Percentage(p)] => Some(p * 2.55),
+                        _ => None,
+                    });
+                    let [Some(r), Some(g), Some(b)] = rgb else { return None };
+                    Some(rgba([r, g, b], alpha))
+                }
+                "hsl" | "hsla" => {
+                    let [hue, saturation, lightness] = channels;
+                    let hue = match hue {
+                        [Token::Number(degrees)] => *degrees,
+                        [Token::Dimension(n, unit)] => n * match unit.as_str() {
+                            "deg" => 1.0,
+                            "rad" => 180.0 / std::f32::consts::PI,
+                            "grad" => 0.9,
+                            "turn" => 360.0,
+                            _ => return None,
+                        },
+                        _ => return None,
+                    };
+                    let ([Token::Percentage(s)], [Token::Percentage(l)]) = (saturation, lightness) else { return None };
+                    Some(rgba(hsl_to_rgb(hue, s / 100.0, l / 100.0), alpha))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// How opaque a packed colour is, from 0 (transparent) to 255.
+pub fn alpha(color: u32) -> u32 {
+    255 - (color >> 24)
+}
+
+/// A packed colour as CSS writes it: `#rrggbb`, or `#rrggbbaa` if it is
+/// not opaque.
+pub fn hex(color: u32) -> String {
+    match alpha(color) {
+        255 => format!("#{:06x}", color & 0xFF_FFFF),
+        alpha => format!("#{:06x}{alpha:02x}", color & 0xFF_FFFF),
+    }
+}
+
+/// Pack channels from 0 to 255 and an alpha from 0 to 1.
+fn rgba(channels: [f32; 3], alpha: f32) -> u32 {
+    let rgb = channels.iter().fold(0, |rgb, c| (rgb << 8) | c.round().clamp(0.0, 255.0) as u32);
+    let transparency = 255 - (alpha.clamp(0.0, 1.0) * 255.0).round() as u32;
+    (transparency << 24) | rgb
+}
+
+/// An alpha value: a number from 0 to 1 or a percentage.
+fn alpha_value(value: &[Token]) -> Option<f32> {
+    match value {
+        [Token::Number(n)] => Some(*n),
+        [Token::Percentage(p)] => Some(p / 100.0),
+        _ => None,
+    }
+}
+
+/// Channels from 0 to 255 of the colour with `hue` in degrees and
+/// `saturation` and `lightness` from 0 to 1.
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> [f32; 3] {
+    let (saturation, lightness) = (saturation.clamp(0.0, 1.0), lightness.clamp(0.0, 1.0));
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    // Each channel is the lightness, shifted towards the hue's primaries
+    // (CSS Color 4's `hslToRgb`).
+    [0.0, 8.0, 4.0].map(|n: f32| {
+        let k = (n + hue.rem_euclid(360.0) / 30.0) % 12.0;
+        let c = lightness - chroma / 2.0 * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0);
+        c * 255.0
+    })
+}
+
+fn hex_color(hex: &str) -> Option<u32> {
+    if !hex.is_ascii() || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digits: Vec<f32> = match hex.len() {
+        // Double each digit: #abc → #aabbcc.
+        3 | 4 => hex.chars().map(|c| (c.to_digit(16).unwrap_or(0) * 0x11) as f32).collect(),
+        6 | 8 => (0..hex.len()).step_by(2).map(|i| u32::from_str_radix(&hex[i..i + 2], 16).unwrap_or(0) as f32).collect(),
+        _ => return None,
+    };
+    let alpha = digits.get(3).map_or(1.0, |a| a / 255.0);
+    Some(rgba([digits[0], digits[1], digits[2]], alpha))
+}
+
+/// The length `value` gives, in pixels: `px`, `pt`, `pc`, `in`, `cm`, `mm`,
+/// `em` and `ex` (of `font_size`), `rem`, or a percentage of
+/// `percent_of`. Bare numbers other than 0 are not lengths.
+pub fn length(value: &Token, font_size: f32, percent_of: f32) -> Option<f32> {
+    match value {
+        Token::Number(n) if *n == 0.0 => Some(0.0),
+        Token::Percentage(p) => Some(p / 100.0 * percent_of),
+        Token::Dimension(n, unit) => Some(n * match unit.as_str() {
+            "px" => 1.0,
+            "pt" => 96.0 / 72.0,
+            "pc" => 16.0,
+            "in" => 96.0,
+            "cm" => 96.0 / 2.54,
+            "mm" => 96.0 / 25.4,
+            "em" => font_size,
+            "ex" => font_size / 2.0,
+            "rem" => ROOT_FONT_SIZE,
+            _ => return None,
+        }),
+        _ => None,
+    }
+}
+
+/// The identifier `value` is, if that is all it is.
+pub fn keyword(value: &[Token]) -> Option<String> {
+    match value {
+        [Token::Ident(word)] => Some(word.to_ascii_lowercase()),
+        _ => None,
+    }
+}
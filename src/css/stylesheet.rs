@@ -0,0 +1,144 @@
+//! Style sheets: rules of selectors and the declarations they give the
+//! elements they match.
+
+use std::collections::HashMap;
+
+use super::{declarations, tokenize, trim, Declaration, Token};
+
+/// Ids, classes and tags in a selector, compared in that order.
+pub type Specificity = (usize, usize, usize);
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stylesheet {
+    pub rules: Vec<Rule>,
+}
+
+/// `selectors { declarations }`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub selectors: Vec<Selector>,
+    pub declarations: Vec<Declaration>,
+}
+
+/// A compound selector: a tag (or `*`), an id and classes, each optional,
+/// as in `p`, `.note`, `#main` or `a.external`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Selector {
+    /// Lower-cased; `None` for `*` or no tag at all.
+    pub tag: Option<String>,
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+}
+
+impl Stylesheet {
+    /// Parse the rules of a style sheet. At-rules (`@media`, `@import`, ...)
+    /// and rules with a selector radium cannot match (combinators,
+    /// pseudo-classes, attribute selectors) are dropped whole.
+    pub fn parse(input: &str) -> Stylesheet {
+        let tokens = tokenize(input);
+        let mut rules = Vec::new();
+        let mut i = 0;
+        while i < tokens.len() {
+            let start = i;
+            // The prelude runs to the block, or to `;` for an at-rule without one.
+            while i < tokens.len() && !matches!(tokens[i], Token::OpenBrace | Token::Semicolon) {
+                i += 1;
+            }
+            let prelude = trim(&tokens[start..i]);
+            if tokens.get(i) != Some(&Token::OpenBrace) {
+                i += 1;
+                continue;
+            }
+            let block_start = i + 1;
+            let mut depth = 0usize;
+            while i < tokens.len() {
+                match tokens[i] {
+                    Token::OpenBrace => depth += 1,
+                    Token::CloseBrace => {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+            let block = &tokens[block_start..i.min(tokens.len())];
+            i += 1;
+            if matches!(prelude.first(), Some(Token::AtKeyword(_))) {
+                continue;
+            }
+            let selectors: Option<Vec<Selector>> = prelude.split(|t| *t == Token::Comma).map(Selector::parse).collect();
+            if let Some(selectors) = selectors.filter(|s| !s.is_empty()) {
+                rules.push(Rule { selectors, declarations: declarations(block) });
+            }
+        }
+        Stylesheet { rules }
+    }
+
+    /// Append `other`'s rules, which then come after (and win over) these.
+    pub fn extend(&mut self, other: Stylesheet) {
+        self.rules.extend(other.rules);
+    }
+
+    /// Every rule matching an element with `tag` and `attrs`, in sheet
+    /// order, with the specificity of its most specific selector that does.
+    pub fn matching<'a>(&'a self, tag: &'a str, attrs: &'a HashMap<String, String>) -> impl Iterator<Item = (Specificity, &'a Rule)> + 'a {
+        self.rules.iter().filter_map(move |rule| {
+            let specificity = rule.selectors.iter().filter(|s| s.matches(tag, attrs)).map(Selector::specificity).max()?;
+            Some((specificity, rule))
+        })
+    }
+}
+
+impl Selector {
+    /// Parse one selector of a selector list; `None` if it is empty or not
+    /// a compound of tag, id and class selectors.
+    fn parse(tokens: &[Token]) -> Option<Selector> {
+        let tokens = trim(tokens);
+        let (tag, rest) = match tokens {
+            [] => return None,
+            [Token::Ident(tag), rest @ ..] => (Some(tag.to_ascii_lowercase()), rest),
+            [Token::Delim('*'), rest @ ..] => (None, rest),
+            _ => (None, tokens),
+        };
+        parse_qualifiers(rest, Selector { tag, ..Selector::default() })
+    }
+
+    /// How many ids, classes and tags the selector names; of two matching
+    /// rules, the one with the greater specificity wins.
+    pub fn specificity(&self) -> Specificity {
+        (self.id.is_some() as usize, self.classes.len(), self.tag.is_some() as usize)
+    }
+
+    /// Whether an element with `tag` and `attrs` matches.
+    pub fn matches(&self, tag: &str, attrs: &HashMap<String, String>) -> bool {
+        if self.tag.as_deref().is_some_and(|t| t != tag) {
+            return false;
+        }
+        if self.id.as_deref().is_some_and(|id| attrs.get("id").map(|v| v.trim()) != Some(id)) {
+            return false;
+        }
+        let classes = attrs.get("class").map(String::as_str).unwrap_or_default();
+        self.classes.iter().all(|class| classes.split_ascii_whitespace().any(|c| c == class))
+    }
+}
+
+/// Parse the `#id` and `.class` parts of a compound selector into `selector`.
+fn parse_qualifiers(mut tokens: &[Token], mut selector: Selector) -> Option<Selector> {
+    loop {
+        match tokens {
+            [] => return Some(selector),
+            [Token::Hash(id), rest @ ..] if selector.id.is_none() => {
+                selector.id = Some(id.clone());
+                tokens = rest;
+            }
+            [Token::Delim('.'), Token::Ident(class), rest @ ..] => {
+                selector.classes.push(class.clone());
+                tokens = rest;
+            }
+            _ => return None,
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use flate2::read::GzDecoder;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+
+use crate::parser::dom::Node;
+
+/// How a response body should be presented.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentKind {
+    Html,
+    /// Shown line by line.
+    Text,
+    /// Converted to HTML structure (`.md`, `--markdown`).
+    Markdown,
+    /// Shown as a document containing just the image.
+    Image,
+}
+
+// ── Sniffing ──────────────────────────────────────────────────────────────────
+
+/// Decide how to present `body`, trusting (in order) the `Content-Type`
+/// header, the file extension, then the leading bytes.
+pub fn classify(content_type: Option<&str>, path: Option<&Path>, body: &[u8]) -> ContentKind {
+    if let Some(kind) = content_type.and_then(from_mime) {
+        return kind;
+    }
+    let ext = path.and_then(|p| p.extension()).and_then(|e| e.to_str()).map(str::to_ascii_lowercase);
+    match ext.as_deref() {
+        Some("html" | "htm" | "xhtml") => return ContentKind::Html,
+        Some("txt" | "text" | "log") => return ContentKind::Text,
+        Some("md" | "markdown") => return ContentKind::Markdown,
+        Some("png" | "jpg" | "jpeg" | "gif" | "webp" | "bmp") => return ContentKind::Image,
+        _ => {}
+    }
+    sniff(body)
+}
+
+fn from_mime(content_type: &str) -> Option<ContentKind> {
+    let essence = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    match essence.as_str() {
+        "text/html" | "application/xhtml+xml" => Some(ContentKind::Html),
+        "text/markdown" | "text/x-markdown" => Some(ContentKind::Markdown),
+        s if s.starts_with("image/") => Some(ContentKind::Image),
+        s if s.starts_with("text/") => Some(ContentKind::Text),
+        "application/json" | "application/javascript" | "application/xml" => Some(ContentKind::Text),
+        // application/octet-stream, unknown/unknown, etc.: look at the bytes.
+        _ => None,
+    }
+}
+
+/// Guess from the first bytes: image signatures, then markup vs plain text.
+fn sniff(body: &[u8]) -> ContentKind {
+    const IMAGE_MAGIC: [&[u8]; 5] = [b"\x89PNG\r\n\x1a\n", b"\xFF\xD8\xFF", b"GIF87a", b"GIF89a", b"BM"];
+    if IMAGE_MAGIC.iter().any(|magic| body.starts_with(magic)) {
+        return ContentKind::Image;
+    }
+
+    // Historically every document was parsed as HTML; keep doing so for
+    // anything that plausibly contains markup.
+    let head = &body[..body.len().min(512)];
+    if head.contains(&b'<') { ContentKind::Html } else { ContentKind::Text }
+}
+
+// ── Compression ───────────────────────────────────────────────────────────────
+
+/// The first two bytes of every gzip stream.
+pub const GZIP_MAGIC: &[u8] = b"\x1f\x8b";
+
+/// Decompress a gzip file (e.g. a `.html.gz` document).
+pub fn gunzip(body: &[u8]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    GzDecoder::new(body).read_to_end(&mut out).map_err(|e| format!("decompressing: {e}"))?;
+    Ok(out)
+}
+
+// ── Character decoding ────────────────────────────────────────────────────────
+
+/// The `charset` parameter of a `Content-Type` value.
+pub fn charset(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim().eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches(['"', '\'']).to_ascii_lowercase())
+    })
+}
+
+/// Decode `body` as `charset`, defaulting to UTF-8. A byte-order mark wins
+/// over the declared charset. Unknown charsets fall back to lossy UTF-8.
+pub fn decode(body: &[u8], charset: Option<&str>) -> String {
+    if let Some(rest) = body.strip_prefix(b"\xEF\xBB\xBF") {
+        return String::from_utf8_lossy(rest).into_owned();
+    }
+    if let Some(rest) = body.strip_prefix(b"\xFF\xFE") {
+        return decode_utf16(rest, u16::from_le_bytes);
+    }
+    if let Some(rest) = body.strip_prefix(b"\xFE\xFF") {
+        return decode_utf16(rest, u16::from_be_bytes);
+    }
+
+    match charset.unwrap_or("utf-8") {
+        "utf-16le" | "utf-16" => decode_utf16(body, u16::from_le_bytes),
+        "utf-16be" => decode_utf16(body, u16::from_be_bytes),
+        "iso-8859-1" | "latin1" | "l1" | "us-ascii" | "ascii" | "windows-1252" | "cp1252" => {
+            // Browsers treat all of these as windows-1252.
+            body.iter().map(|&b| windows_1252(b)).collect()
+        }
+        _ => String::from_utf8_lossy(body).into_owned(),
+    }
+}
+
+fn decode_utf16(body: &[u8], unit: fn([u8; 2]) -> u16) -> String {
+    let units = body.chunks_exact(2).map(|pair| unit([pair[0], pair[1]]));
+    char::decode_utf16(units).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)).collect()
+}
+
+/// windows-1252 is Latin-1 except for printable characters in 0x80–0x9F.
+fn windows_1252(byte: u8) -> char {
+    const HIGH: [char; 32] = [
+        '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž', '\u{8F}',
+        '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}', 'ž', 'Ÿ',
+    ];
+    match byte {
+        0x80..=0x9F => HIGH[(byte - 0x80) as usize],
+        _ => byte as char,
+    }
+}
+
+// ── Synthesized documents ─────────────────────────────────────────────────────
+
+/// Plain text, one line per text box, with blank lines kept.
+pub fn text_document(text: &str) -> Vec<Node> {
+    let lines = text
+        .lines()
+        .map(|line| match line.trim_end() {
+            "" => element("br", &[], Vec::new()),
+            line => element("div", &[], vec![Node::Text(line.replace('\t', "    "))]),
+        })
+        .collect();
+    vec![element("body", &[], lines)]
+}
+
+/// A document showing the single image at `src`.
+pub fn image_document(src: &str) -> Vec<Node> {
+    vec![element("body", &[], vec![element("img", &[("src", src)], Vec::new())])]
+}
+
+/// A readable page for a failed HTTP request.
+pub fn error_document(status: u16, reason: &str, url: &str) -> Vec<Node> {
+    let heading = format!("{status} {reason}").trim_end().to_string();
+    vec![element("body", &[], vec![
+        element("h1", &[], vec![Node::Text(heading)]),
+        element("p", &[], vec![Node::Text(format!("The server could not deliver {url}."))]),
+        element("p", &[], vec![Node::Text("Press Ctrl+R to try again.".into())]),
+    ])]
+}
+
+/// A page explaining why `target` could not be opened. `remote` picks the
+/// hint: connectivity for URLs, the path for local files.
+pub fn failure_document(error: &str, target: &str, remote: bool) -> Vec<Node> {
+    let hint = if remote {
+        "Check the address and your connection, then press Ctrl+R to try again."
+    } else {
+        "Check that the file exists and is readable, then press Ctrl+R to try again, or Ctrl+L to open something else."
+    };
+    vec![element("body", &[], vec![
+        element("h1", &[], vec![Node::Text("This document could not be opened".into())]),
+        element("p", &[], vec![Node::Text(target.to_string())]),
+        element("p", &[], vec![Node::Text(error.to_string())]),
+        element("p", &[], vec![Node::Text(hint.into())]),
+    ])]
+}
+
+/// A page reporting that radium itself failed (panicked) while showing
+/// `target`: the panic message and where it happened.
+pub fn crash_document(target: &str, message: &str, location: Option<&str>, backtrace: &[String]) -> Vec<Node> {
+    let mut children = vec![
+        element("h1", &[], vec![Node::Text("radium crashed while showing this document".into())]),
+        element("p", &[], vec![Node::Text(target.to_string())]),
+        element("p", &[], vec![Node::Text(message.to_string())]),
+    ];
+    if let Some(location) = location {
+        children.push(element("p", &[], vec![Node::Text(format!("at {location}"))]));
+    }
+    if !backtrace.is_empty() {
+        children.push(element("h2", &[], vec![Node::Text("Backtrace".into())]));
+        let frames = backtrace.iter().map(|frame| element("li", &[], vec![Node::Text(frame.clone())])).collect();
+        children.push(element("ol", &[], frames));
+    }
+    children.push(element("p", &[], vec![Node::Text(
+        "This is a bug in radium. Press Ctrl+R to try again, or Ctrl+L to open something else.".into(),
+    )]));
+    vec![element("body", &[], children)]
+}
+
+/// An index page for a directory: subdirectories first, then files, each
+/// linking to the entry with its size and modification time.
+pub fn directory_document(dir: &Path) -> Result<Vec<Node>, String> {
+    // Escape what would otherwise end the path or start a fragment/query.
+    const HREF_ESCAPE: &AsciiSet = &CONTROLS.add(b' ').add(b'"').add(b'#').add(b'%').add(b'?');
+
+    let mut entries: Vec<(bool, String, u64, Option<SystemTime>)> = fs::read_dir(dir)
+        .map_err(|e| format!("listing {}: {e}", dir.display()))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let meta = entry.metadata().ok()?;
+            (!name.starts_with('.')).then(|| (!meta.is_dir(), name, meta.len(), meta.modified().ok()))
+        })
+        .collect();
+    entries.sort_by_key(|(is_file, name, ..)| (*is_file, name.to_lowercase()));
+
+    let mut items = Vec::new();
+    if dir.parent().is_some() {
+        items.push(element("li", &[], vec![element("a", &[("href", "../")], vec![Node::Text("../".into())])]));
+    }
+    for (is_file, name, size, modified) in entries {
+        let slash = if is_file { "" } else { "/" };
+        let href = format!("{}{slash}", utf8_percent_encode(&name, HREF_ESCAPE));
+        let mut details = Vec::new();
+        if is_file {
+            details.push(format_size(size));
+        }
+        details.extend(modified.map(format_time));
+        let label = match details.is_empty() {
+            true => format!("{name}{slash}"),
+            false => format!("{name}{slash}  ({})", details.join(", ")),
+        };
+        items.push(element("li", &[], vec![element("a", &[("href", &href)], vec![Node::Text(label)])]));
+    }
+
+    let heading = format!("Index of {}", dir.display());
+    Ok(vec![element("body", &[], vec![element("h1", &[], vec![Node::Text(heading)]), element("ul", &[], items)])])
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{bytes} B"),
+        1024..1_048_576 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+/// `YYYY-MM-DD HH:MM` in UTC.
+fn format_time(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    // Civil-from-days (Howard Hinnant's algorithm), for days since 1970-01-01.
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02} {:02}:{:02}", rem / 3600, rem % 3600 / 60)
+}
+
+/// Text of the first `tag` element.
+pub fn find_text(nodes: &[Node], tag: &str) -> Option<String> {
+    nodes.iter().find_map(|node| match node {
+        Node::Element { tag: t, children, .. } if t == tag => children.iter().find_map(|c| match c {
+            Node::Text(text) if !text.trim().is_empty() => Some(text.trim().to_string()),
+            _ => None,
+        }),
+        Node::Element { children, .. } => find_text(children, tag),
+        Node::Text(_) => None,
+    })
+}
+
+pub fn element(tag: &str, attrs: &[(&str, &str)], children: Vec<Node>) -> Node {
+    Node::Element {
+        tag: tag.to_string(),
+        attrs: attrs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<HashMap<_, _>>(),
+        children,
+    }
+}
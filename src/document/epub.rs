@@ -0,0 +1,195 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Component, Path, PathBuf};
+
+use crate::net::Url;
+use crate::parser::{self, dom::Node, Token};
+
+use super::content::{self, element};
+use super::zip::Archive;
+
+/// Present an EPUB as one continuous document: a table of contents followed
+/// by every chapter of the spine, in reading order.
+///
+/// The archive is extracted to a temporary directory so images and other
+/// in-book resources load like any local file. Links between chapters are
+/// rewritten to fragments of the combined document.
+pub fn load(path: &Path) -> Result<Vec<Node>, String> {
+    let data = fs::read(path).map_err(|e| format!("reading {}: {e}", path.display()))?;
+    let archive = Archive::new(data).map_err(|e| format!("{}: {e}", path.display()))?;
+    let dir = extract(path, &archive)?;
+
+    let opf_name = rootfile(&archive)?;
+    let opf = content::decode(&archive.read(&opf_name)?, None);
+    let package = Package::parse(&opf);
+    let opf_url = Url::from_file_path(&dir.join(&opf_name));
+
+    let chapters: Vec<Url> = package.spine.iter()
+        .filter_map(|idref| package.manifest.get(idref))
+        .filter_map(|href| opf_url.join(href).ok())
+        .collect();
+    if chapters.is_empty() {
+        return Err(format!("{}: the book has no chapters", path.display()));
+    }
+
+    let mut toc = Vec::new();
+    let mut sections = Vec::new();
+    for (i, url) in chapters.iter().enumerate() {
+        let file = url.to_file_path().unwrap_or_default();
+        let Ok(bytes) = fs::read(&file) else {
+            eprintln!("radium: missing chapter {}", file.display());
+            continue;
+        };
+        let mut nodes = parser::dom::build_tree(parser::tokenize(&content::decode(&bytes, None)));
+        let title = content::find_text(&nodes, "title").unwrap_or_else(|| format!("Chapter {}", i + 1));
+        rewrite_links(&mut nodes, url, &chapters);
+
+        let body = take_body(nodes);
+        toc.push(element("li", &[], vec![element("a", &[("href", &format!("#{}", chapter_id(i)))], vec![Node::Text(title)])]));
+        let mut section = vec![element("hr", &[], Vec::new())];
+        section.extend(body);
+        sections.push(element("div", &[("id", &chapter_id(i))], section));
+    }
+
+    let title = package.title.unwrap_or_else(|| path.file_stem().unwrap_or_default().to_string_lossy().into_owned());
+    let mut body = vec![element("h1", &[], vec![Node::Text(title)]), element("ol", &[], toc)];
+    body.extend(sections);
+    Ok(vec![element("body", &[], body)])
+}
+
+fn chapter_id(index: usize) -> String {
+    format!("epub-chapter-{}", index + 1)
+}
+
+// ── Container and package ─────────────────────────────────────────────────────
+
+/// Path of the OPF package document, from `META-INF/container.xml`.
+fn rootfile(archive: &Archive) -> Result<String, String> {
+    let container = content::decode(&archive.read("META-INF/container.xml")?, None);
+    parser::tokenize(&container).into_iter()
+        .find_map(|token| match token {
+            Token::OpenTag { name, mut attrs, .. } if name == "rootfile" => attrs.remove("full-path"),
+            _ => None,
+        })
+        .ok_or_else(|| "container.xml names no rootfile".to_string())
+}
+
+/// The parts of an OPF package document needed for reading.
+struct Package {
+    title: Option<String>,
+    /// Manifest item id → href (relative to the OPF file).
+    manifest: HashMap<String, String>,
+    /// Item ids in reading order.
+    spine: Vec<String>,
+}
+
+impl Package {
+    fn parse(opf: &str) -> Package {
+        let mut package = Package { title: None, manifest: HashMap::new(), spine: Vec::new() };
+        let mut in_title = false;
+        for token in parser::tokenize(opf) {
+            match token {
+                Token::OpenTag { name, mut attrs, .. } => {
+                    in_title = name == "dc:title" && package.title.is_none();
+                    match name.as_str() {
+                        "item" => {
+                            if let (Some(id), Some(href)) = (attrs.remove("id"), attrs.remove("href")) {
+                                package.manifest.insert(id, href);
+                            }
+                        }
+                        "itemref" => package.spine.extend(attrs.remove("idref")),
+                        _ => {}
+                    }
+                }
+                Token::Text(text) if in_title => {
+                    package.title = Some(text);
+                    in_title = false;
+                }
+                _ => in_title = false,
+            }
+        }
+        package
+    }
+}
+
+// ── Extraction ────────────────────────────────────────────────────────────────
+
+/// Unpack the archive under the temp directory, once per version of the file.
+fn extract(path: &Path, archive: &Archive) -> Result<PathBuf, String> {
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    if let Ok(meta) = fs::metadata(path) {
+        meta.len().hash(&mut hasher);
+        meta.modified().ok().hash(&mut hasher);
+    }
+    let dir = std::env::temp_dir().join(format!("radium-epub-{:016x}", hasher.finish()));
+    let done = dir.join(".extracted");
+    if done.exists() {
+        return Ok(dir);
+    }
+
+    for name in archive.names() {
+        // Skip directories and anything that would escape `dir`.
+        let relative = Path::new(name);
+        let safe = relative.components().all(|c| matches!(c, Component::Normal(_)));
+        if name.ends_with('/') || !safe {
+            continue;
+        }
+        let target = dir.join(relative);
+        let bytes = archive.read(name)?;
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("creating {}: {e}", parent.display()))?;
+        }
+        fs::write(&target, bytes).map_err(|e| format!("writing {}: {e}", target.display()))?;
+    }
+    fs::write(&done, b"").map_err(|e| format!("writing {}: {e}", done.display()))?;
+    Ok(dir)
+}
+
+// ── Tree helpers ──────────────────────────────────────────────────────────────
+
+/// Make `a href` and `img src` absolute, pointing links to other chapters at
+/// their place in the combined document.
+fn rewrite_links(nodes: &mut [Node], base: &Url, chapters: &[Url]) {
+    for node in nodes {
+        let Node::Element { tag, attrs, children } = node else { continue };
+        let attr = match tag.as_str() {
+            "a" => "href",
+            "img" => "src",
+            _ => "",
+        };
+        if let Some(value) = attrs.get_mut(attr) {
+            if let Ok(url) = base.join(value) {
+                *value = match chapters.iter().position(|c| *c == url.without_fragment()) {
+                    Some(_) if url.fragment.as_deref().is_some_and(|f| !f.is_empty()) => {
+                        format!("#{}", url.fragment.unwrap_or_default())
+                    }
+                    Some(i) => format!("#{}", chapter_id(i)),
+                    None => url.to_string(),
+                };
+            }
+        }
+        rewrite_links(children, base, chapters);
+    }
+}
+
+/// The children of the first `body` element, or everything if there is none.
+fn take_body(nodes: Vec<Node>) -> Vec<Node> {
+    fn find(nodes: &mut Vec<Node>) -> Option<Vec<Node>> {
+        for node in nodes {
+            if let Node::Element { tag, children, .. } = node {
+                if tag == "body" {
+                    return Some(std::mem::take(children));
+                }
+                if let Some(found) = find(children) {
+                    return Some(found);
+                }
+            }
+        }
+        None
+    }
+    let mut nodes = nodes;
+    find(&mut nodes).unwrap_or(nodes)
+}
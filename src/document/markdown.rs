@@ -0,0 +1,375 @@
+use crate::parser::dom::Node;
+
+use super::content::element;
+
+/// Convert Markdown to a DOM tree.
+///
+/// Covers the everyday CommonMark subset: ATX and setext headings,
+/// paragraphs, emphasis, inline code, links, images, autolinks, fenced and
+/// indented code blocks, block quotes, nested lists and thematic breaks.
+/// Raw HTML is shown as text.
+pub fn to_document(text: &str) -> Vec<Node> {
+    let lines: Vec<&str> = text.lines().collect();
+    vec![element("body", &[], blocks(&lines))]
+}
+
+// ── Blocks ────────────────────────────────────────────────────────────────────
+
+fn blocks(lines: &[&str]) -> Vec<Node> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+
+        if trimmed.is_empty() {
+            i += 1;
+        } else if let Some(fence) = fence(trimmed) {
+            // Fenced code runs to the matching fence (or the end).
+            let end = lines[i + 1..].iter().position(|l| l.trim_start().starts_with(fence)).map_or(lines.len(), |p| i + 1 + p);
+            out.push(code_block(lines[i + 1..end].iter().map(|l| strip_indent(l, indent))));
+            i = end + 1;
+        } else if indent >= 4 {
+            let end = lines[i..].iter().position(|l| !l.trim().is_empty() && indent_of(l) < 4).map_or(lines.len(), |p| i + p);
+            let mut code: Vec<&str> = lines[i..end].iter().map(|l| strip_indent(l, 4)).collect();
+            while code.last().is_some_and(|l| l.trim().is_empty()) {
+                code.pop();
+            }
+            out.push(code_block(code.into_iter()));
+            i = end;
+        } else if let Some((level, title)) = atx_heading(trimmed) {
+            out.push(element(&format!("h{level}"), &[], inline(title)));
+            i += 1;
+        } else if is_rule(trimmed) {
+            out.push(element("hr", &[], Vec::new()));
+            i += 1;
+        } else if trimmed.starts_with('>') {
+            let end = lines[i..].iter().position(|l| !l.trim_start().starts_with('>')).map_or(lines.len(), |p| i + p);
+            let inner: Vec<&str> = lines[i..end].iter().map(|l| {
+                let rest = &l.trim_start()[1..];
+                rest.strip_prefix(' ').unwrap_or(rest)
+            }).collect();
+            out.push(element("blockquote", &[], blocks(&inner)));
+            i = end;
+        } else if let Some((ordered, _)) = list_marker(trimmed) {
+            let (list, end) = list(lines, i, indent, ordered);
+            out.push(list);
+            i = end;
+        } else {
+            let (node, end) = paragraph(lines, i);
+            out.push(node);
+            i = end;
+        }
+    }
+    out
+}
+
+/// A paragraph, or a setext heading if underlined with `===` / `---`.
+fn paragraph(lines: &[&str], start: usize) -> (Node, usize) {
+    let mut text = Vec::new();
+    let mut i = start;
+    while i < lines.len() {
+        let trimmed = lines[i].trim();
+        if !text.is_empty() && !trimmed.is_empty() {
+            if trimmed.chars().all(|c| c == '=') {
+                return (element("h1", &[], inline(&text.join(" "))), i + 1);
+            }
+            if trimmed.chars().all(|c| c == '-') {
+                return (element("h2", &[], inline(&text.join(" "))), i + 1);
+            }
+        }
+        let interrupts = trimmed.is_empty()
+            || fence(trimmed).is_some()
+            || atx_heading(trimmed).is_some()
+            || is_rule(trimmed)
+            || trimmed.starts_with('>')
+            || (!text.is_empty() && list_marker(trimmed).is_some());
+        if interrupts && !text.is_empty() {
+            break;
+        }
+        text.push(trimmed);
+        i += 1;
+    }
+    (element("p", &[], inline(&text.join(" "))), i)
+}
+
+/// A list starting at `start` whose markers sit at `indent`.
+fn list(lines: &[&str], start: usize, indent: usize, ordered: bool) -> (Node, usize) {
+    let mut items = Vec::new();
+    let mut i = start;
+    while i < lines.len() {
+        let trimmed = lines[i].trim_start();
+        match list_marker(trimmed) {
+            Some((o, width)) if o == ordered && indent_of(lines[i]) == indent => {
+                // The item's content column; continuation lines must reach it.
+                let column = indent + width;
+                let mut content = vec![&trimmed[width..]];
+                i += 1;
+                while i < lines.len() {
+                    let line = lines[i];
+                    if line.trim().is_empty() {
+                        // A blank line continues the item only if indented content follows.
+                        let next = lines[i + 1..].iter().find(|l| !l.trim().is_empty());
+                        if next.is_some_and(|l| indent_of(l) >= column) {
+                            content.push("");
+                            i += 1;
+                            continue;
+                        }
+                        break;
+                    }
+                    let line_indent = indent_of(line);
+                    if line_indent >= column {
+                        content.push(strip_indent(line, column));
+                    } else if line_indent <= indent && (list_marker(line.trim_start()).is_some() || is_block_start(line.trim_start())) {
+                        break;
+                    } else {
+                        // Lazy paragraph continuation.
+                        content.push(line.trim_start());
+                    }
+                    i += 1;
+                }
+                items.push(element("li", &[], unwrap_paragraphs(blocks(&content))));
+                // Skip blank lines between items.
+                while i < lines.len() && lines[i].trim().is_empty() {
+                    i += 1;
+                }
+            }
+            _ => break,
+        }
+    }
+    // An ordered list counts from its first item's number.
+    let digits: String = lines[start].trim_start().chars().take_while(char::is_ascii_digit).collect();
+    let first = digits.parse::<u32>().ok().filter(|&n| ordered && n != 1).map(|n| n.to_string());
+    let attrs: Vec<(&str, &str)> = first.iter().map(|n| ("start", n.as_str())).collect();
+    (element(if ordered { "ol" } else { "ul" }, &attrs, items), i)
+}
+
+/// List items hold inline content directly, keeping lists tight.
+fn unwrap_paragraphs(nodes: Vec<Node>) -> Vec<Node> {
+    nodes.into_iter().flat_map(|node| match node {
+        Node::Element { tag, children, .. } if tag == "p" => children,
+        other => vec![other],
+    }).collect()
+}
+
+fn code_block<'a>(lines: impl Iterator<Item = &'a str>) -> Node {
+    let lines = lines.map(|line| element("div", &[], vec![Node::Text(line.replace('\t', "    "))])).collect();
+    element("pre", &[], vec![element("code", &[], lines)])
+}
+
+fn is_block_start(trimmed: &str) -> bool {
+    fence(trimmed).is_some() || atx_heading(trimmed).is_some() || is_rule(trimmed) || trimmed.starts_with('>')
+}
+
+/// The fence string (```` ``` ```` or `~~~`) opening a code block.
+fn fence(trimmed: &str) -> Option<&'static str> {
+    if trimmed.starts_with("```") {
+        Some("```")
+    } else if trimmed.starts_with("~~~") {
+        Some("~~~")
+    } else {
+        None
+    }
+}
+
+fn atx_heading(trimmed: &str) -> Option<(usize, &str)> {
+    let level = trimmed.chars().take_while(|&c| c == '#').count();
+    let rest = &trimmed[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    // Optional closing sequence: `## Title ##`.
+    let title = rest.trim().trim_end_matches('#').trim_end();
+    Some((level, title))
+}
+
+/// `---`, `***` or `___` (optionally spaced), three or more.
+fn is_rule(trimmed: &str) -> bool {
+    let Some(first) = trimmed.chars().next().filter(|c| matches!(c, '-' | '*' | '_')) else { return false };
+    trimmed.chars().all(|c| c == first || c == ' ') && trimmed.chars().filter(|&c| c == first).count() >= 3
+}
+
+/// Whether `trimmed` starts with a list marker: `(ordered, marker width)`.
+fn list_marker(trimmed: &str) -> Option<(bool, usize)> {
+    let bytes = trimmed.as_bytes();
+    let followed_by_space = |at: usize| bytes.get(at).is_none_or(|&b| b == b' ' || b == b'\t');
+    match bytes.first()? {
+        b'-' | b'*' | b'+' if followed_by_space(1) && !is_rule(trimmed) => Some((false, 2.min(trimmed.len()))),
+        b'0'..=b'9' => {
+            let digits = bytes.iter().take_while(|b| b.is_ascii_digit()).count();
+            let delimited = digits <= 9 && matches!(bytes.get(digits), Some(b'.' | b')'));
+            (delimited && followed_by_space(digits + 1)).then(|| (true, (digits + 2).min(trimmed.len())))
+        }
+        _ => None,
+    }
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Remove up to `n` leading spaces.
+fn strip_indent(line: &str, n: usize) -> &str {
+    let skip = line.bytes().take(n).take_while(|&b| b == b' ').count();
+    &line[skip..]
+}
+
+// ── Inlines ───────────────────────────────────────────────────────────────────
+
+fn inline(text: &str) -> Vec<Node> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = Vec::new();
+    let mut buf = String::new();
+    let flush = |buf: &mut String, out: &mut Vec<Node>| {
+        if !buf.is_empty() {
+            out.push(Node::Text(std::mem::take(buf)));
+        }
+    };
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' if chars.get(i + 1).is_some_and(|c| c.is_ascii_punctuation()) => {
+                buf.push(chars[i + 1]);
+                i += 2;
+                continue;
+            }
+            '`' => {
+                let run = run_len(&chars, i, '`');
+                if let Some(close) = find_closing_backticks(&chars, i + run, run) {
+                    flush(&mut buf, &mut out);
+                    let code: String = chars[i + run..close].iter().collect();
+                    out.push(element("code", &[], vec![Node::Text(code.trim().to_string())]));
+                    i = close + run;
+                    continue;
+                }
+                buf.extend(&chars[i..i + run]);
+                i += run;
+                continue;
+            }
+            '*' | '_' => {
+                let run = run_len(&chars, i, c).min(3);
+                if let Some(close) = find_closing_emphasis(&chars, i, run, c) {
+                    flush(&mut buf, &mut out);
+                    let inner: String = chars[i + run..close].iter().collect();
+                    out.push(match run {
+                        1 => element("em", &[], inline(&inner)),
+                        2 => element("strong", &[], inline(&inner)),
+                        _ => element("strong", &[], vec![element("em", &[], inline(&inner))]),
+                    });
+                    i = close + run;
+                    continue;
+                }
+                buf.extend(&chars[i..i + run]);
+                i += run;
+                continue;
+            }
+            '!' if chars.get(i + 1) == Some(&'[') => {
+                if let Some((alt, dest, end)) = link(&chars, i + 1) {
+                    flush(&mut buf, &mut out);
+                    out.push(element("img", &[("src", &dest), ("alt", &alt)], Vec::new()));
+                    i = end;
+                    continue;
+                }
+            }
+            '[' => {
+                if let Some((label, dest, end)) = link(&chars, i) {
+                    flush(&mut buf, &mut out);
+                    out.push(element("a", &[("href", &dest)], inline(&label)));
+                    i = end;
+                    continue;
+                }
+            }
+            '<' => {
+                let close = chars[i..].iter().position(|&c| c == '>').map(|p| i + p);
+                if let Some(close) = close {
+                    let target: String = chars[i + 1..close].iter().collect();
+                    if !target.contains(' ') && (target.contains("://") || target.starts_with("mailto:")) {
+                        flush(&mut buf, &mut out);
+                        out.push(element("a", &[("href", &target)], vec![Node::Text(target.clone())]));
+                        i = close + 1;
+                        continue;
+                    }
+                }
+            }
+            _ => {}
+        }
+        buf.push(c);
+        i += 1;
+    }
+    flush(&mut buf, &mut out);
+    out
+}
+
+fn run_len(chars: &[char], at: usize, c: char) -> usize {
+    chars[at..].iter().take_while(|&&x| x == c).count()
+}
+
+fn find_closing_backticks(chars: &[char], from: usize, run: usize) -> Option<usize> {
+    let mut j = from;
+    while j < chars.len() {
+        if chars[j] == '`' {
+            let len = run_len(chars, j, '`');
+            if len == run {
+                return Some(j);
+            }
+            j += len;
+        } else {
+            j += 1;
+        }
+    }
+    None
+}
+
+/// Index of the delimiter run closing the `run`-long `delim` run at `open`.
+/// Underscores only count at word boundaries, so `snake_case` stays literal.
+fn find_closing_emphasis(chars: &[char], open: usize, run: usize, delim: char) -> Option<usize> {
+    let after_open = chars.get(open + run)?;
+    if after_open.is_whitespace() || (delim == '_' && open > 0 && chars[open - 1].is_alphanumeric()) {
+        return None;
+    }
+    let mut j = open + run + 1;
+    while j + run <= chars.len() {
+        let closes = chars[j..j + run].iter().all(|&c| c == delim)
+            && chars.get(j + run) != Some(&delim)
+            && !chars[j - 1].is_whitespace()
+            && !(delim == '_' && chars.get(j + run).is_some_and(|c| c.is_alphanumeric()));
+        if closes {
+            return Some(j);
+        }
+        j += 1;
+    }
+    None
+}
+
+/// Parse `[label](destination "title")` starting at the `[`.
+/// Returns the label, destination and the index after the `)`.
+fn link(chars: &[char], open: usize) -> Option<(String, String, usize)> {
+    let mut depth = 0;
+    let mut close = None;
+    for (j, &c) in chars.iter().enumerate().skip(open) {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(j);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let close = close?;
+    if chars.get(close + 1) != Some(&'(') {
+        return None;
+    }
+    let end = chars[close + 2..].iter().position(|&c| c == ')').map(|p| close + 2 + p)?;
+    let label: String = chars[open + 1..close].iter().collect();
+    let inside: String = chars[close + 2..end].iter().collect();
+    let dest = inside.split_whitespace().next().unwrap_or_default();
+    let dest = dest.strip_prefix('<').and_then(|d| d.strip_suffix('>')).unwrap_or(dest);
+    Some((label, dest.to_string(), end + 1))
+}
@@ -0,0 +1,316 @@
+mod content;
+mod epub;
+mod markdown;
+pub mod reader;
+pub mod save;
+pub mod watch;
+mod zip;
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use percent_encoding::percent_decode_str;
+
+use crate::layout::Page;
+use crate::net::{self, Response, Url};
+use crate::parser::{self, dom::Node};
+use crate::stats::Stats;
+
+pub use content::ContentKind;
+pub(crate) use content::crash_document;
+
+/// Where a document comes from, so it can be (re)loaded on demand.
+#[derive(Debug, Clone)]
+pub struct Source {
+    /// The document itself: a `file:` URL for local files, or `http(s):`.
+    /// Never carries a fragment; relative links and assets resolve against it.
+    pub url: Url,
+    /// Overrides content sniffing, e.g. `--markdown`.
+    pub kind: Option<ContentKind>,
+}
+
+impl Source {
+    /// Resolve a command-line or location-bar target: an `http(s)://` or
+    /// `file://` URL, or a local path to a file or directory (its
+    /// `index.html`, or else a listing). A trailing `#fragment` is split off
+    /// and returned.
+    pub fn resolve(input: &str) -> Result<(Source, Option<String>), String> {
+        if let Ok(url) = Url::parse(input) {
+            if matches!(url.scheme.as_str(), "http" | "https" | "file") {
+                return Source::from_url(url);
+            }
+        }
+
+        let (path, fragment) = match input.rsplit_once('#') {
+            Some((path, frag)) if !Path::new(input).exists() => (path, Some(frag.to_string())),
+            _ => (input, None),
+        };
+        let (source, _) = Source::from_url(Url::from_file_path(Path::new(path)))?;
+        Ok((source, fragment))
+    }
+
+    /// A source for a target that failed to resolve (e.g. a missing file),
+    /// kept so its error page can be shown and retried with a reload.
+    pub fn unresolved(input: &str) -> Source {
+        let url = Url::parse(input)
+            .ok()
+            .filter(Url::is_hierarchical)
+            .unwrap_or_else(|| Url::from_file_path(Path::new(input)));
+        Source { url: url.without_fragment(), kind: None }
+    }
+
+    /// Resolve an absolute URL, e.g. a link target. Its fragment is split off
+    /// and returned.
+    pub fn from_url(mut url: Url) -> Result<(Source, Option<String>), String> {
+        let fragment = url.fragment.take();
+        match url.scheme.as_str() {
+            "http" | "https" => {}
+            "file" => {
+                let path = url.to_file_path().unwrap_or_default();
+                if path.is_dir() {
+                    let index = path.join("index.html");
+                    if index.is_file() {
+                        url = Url::from_file_path(&index);
+                    } else if !url.path.ends_with('/') {
+                        // Listing entries are relative to the directory itself.
+                        url.path.push('/');
+                    }
+                } else if !path.is_file() {
+                    return Err(format!("'{}' is not a file or directory", path.display()));
+                }
+            }
+            other => return Err(format!("unsupported URL scheme '{other}:'")),
+        }
+        Ok((Source { url, kind: None }, fragment))
+    }
+
+    /// The local file backing this document, if any.
+    pub fn path(&self) -> Option<PathBuf> {
+        self.url.to_file_path()
+    }
+
+    /// Files whose modification should trigger a reload of `page`.
+    pub fn dependencies(&self, page: &Page) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self.path().into_iter().collect();
+        for asset in &page.assets {
+            if !paths.contains(asset) {
+                paths.push(asset.clone());
+            }
+        }
+        paths
+    }
+
+    /// The document's `<title>`, or else its file name (or host, for a
+    /// remote site root).
+    pub fn title(&self, nodes: &[Node]) -> String {
+        if let Some(title) = content::find_text(nodes, "title") {
+            return title;
+        }
+        let name = self.url.path.trim_end_matches('/').rsplit('/').next().unwrap_or_default();
+        match percent_decode_str(name).decode_utf8_lossy() {
+            name if !name.is_empty() => name.into_owned(),
+            _ if !self.url.host.is_empty() => self.url.host.clone(),
+            _ => self.to_string(),
+        }
+    }
+
+    /// The icon named by the first `<link rel="icon">`, as an absolute URL.
+    pub fn icon(&self, nodes: &[Node]) -> Option<Url> {
+        fn find(nodes: &[Node]) -> Option<&str> {
+            nodes.iter().find_map(|node| match node {
+                Node::Element { tag, attrs, .. }
+                    if tag == "link"
+                        && attrs.get("rel").is_some_and(|rel| rel.split_whitespace().any(|r| r.eq_ignore_ascii_case("icon"))) =>
+                {
+                    attrs.get("href").map(String::as_str)
+                }
+                Node::Element { children, .. } => find(children),
+                Node::Text(_) => None,
+            })
+        }
+        self.url.join(find(nodes)?).ok()
+    }
+
+    /// Read or fetch the document and turn it into a DOM tree: HTML is
+    /// parsed, plain text and images are wrapped in a synthesized document,
+    /// directories are listed, EPUB books are flattened into one document,
+    /// and HTTP errors without an HTML body become an
+    /// error page.
+    ///
+    /// If an HTTP request was redirected, `url` is updated to the final
+    /// location so relative links resolve correctly.
+    pub fn load(&mut self) -> Result<Vec<Node>, String> {
+        self.load_with_stats(&mut Stats::default())
+    }
+
+    /// Like [`Source::load`], adding the body's size and the time spent
+    /// reading and parsing it to `stats`.
+    pub fn load_with_stats(&mut self, stats: &mut Stats) -> Result<Vec<Node>, String> {
+        self.load_progressively(stats, &mut |_| {})
+    }
+
+    /// Like [`Source::load_with_stats`]; while a remote document downloads,
+    /// `preview` is given the part of it received so far, parsed, now and
+    /// then.
+    fn load_progressively(&mut self, stats: &mut Stats, preview: &mut dyn FnMut(Vec<Node>)) -> Result<Vec<Node>, String> {
+        let started = Instant::now();
+        let read = self.read(preview);
+        stats.read += started.elapsed();
+        let (body, content_type, path) = match read? {
+            Body::Document(nodes) => return Ok(nodes),
+            Body::Bytes { body, content_type, path } => (body, content_type, path),
+        };
+
+        stats.bytes += body.len();
+        let started = Instant::now();
+        let nodes = self.parse_body(&body, content_type.as_deref(), path.as_deref());
+        stats.parse += started.elapsed();
+        Ok(nodes)
+    }
+
+    /// Read or fetch the body, or build the whole document where there is
+    /// no body to parse. `preview` follows a download as in
+    /// [`Source::load_progressively`].
+    fn read(&mut self, preview: &mut dyn FnMut(Vec<Node>)) -> Result<Body, String> {
+        let path = self.path();
+        match &path {
+            Some(path) if path.is_dir() => content::directory_document(path).map(Body::Document),
+            Some(path) if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("epub")) => epub::load(path).map(Body::Document),
+            Some(path) => {
+                let body = std::fs::read(path).map_err(|e| format!("reading {}: {e}", path.display()))?;
+                // `page.html.gz`: decompress, then classify as `page.html`.
+                if body.starts_with(content::GZIP_MAGIC) {
+                    let body = content::gunzip(&body).map_err(|e| format!("{}: {e}", path.display()))?;
+                    return Ok(Body::Bytes { body, content_type: None, path: Some(path.with_extension("")) });
+                }
+                Ok(Body::Bytes { body, content_type: None, path: Some(path.clone()) })
+            }
+            None => {
+                let started = Instant::now();
+                let (mut next_preview, mut previewed) = (FIRST_PREVIEW, 0);
+                let (final_url, response) = net::fetch_progressively(&self.url, &mut |raw| {
+                    if started.elapsed() < next_preview || raw.len() == previewed {
+                        return;
+                    }
+                    next_preview = started.elapsed() + PREVIEW_INTERVAL;
+                    previewed = raw.len();
+                    let partial = Response::partial(raw).filter(Response::is_success);
+                    if let Some(nodes) = partial.and_then(|r| self.parse_prefix(&r.body, r.header("content-type"))) {
+                        preview(nodes);
+                    }
+                })?;
+                self.url = final_url;
+                let content_type = response.header("content-type").map(str::to_string);
+                let kind = content::classify(content_type.as_deref(), None, &response.body);
+                if !response.is_success() && (kind != ContentKind::Html || response.body.is_empty()) {
+                    let page = content::error_document(response.status, &response.reason, &self.url.to_string());
+                    return Ok(Body::Document(page));
+                }
+                Ok(Body::Bytes { body: response.body, content_type, path: None })
+            }
+        }
+    }
+
+    /// The document's decoded text as served, before any parsing (for
+    /// `--dump-tokens`). Directories and archives have none.
+    pub fn text(&mut self) -> Result<String, String> {
+        let (body, content_type) = match self.path() {
+            Some(path) => {
+                let body = std::fs::read(&path).map_err(|e| format!("reading {}: {e}", path.display()))?;
+                if body.starts_with(content::GZIP_MAGIC) {
+                    (content::gunzip(&body).map_err(|e| format!("{}: {e}", path.display()))?, None)
+                } else {
+                    (body, None)
+                }
+            }
+            None => {
+                let (final_url, response) = net::fetch(&self.url)?;
+                self.url = final_url;
+                let content_type = response.header("content-type").map(str::to_string);
+                (response.body, content_type)
+            }
+        };
+        let charset = content_type.as_deref().and_then(content::charset);
+        Ok(content::decode(&body, charset.as_deref()))
+    }
+
+    /// Like [`Source::load`], but a failure is reported on stderr and
+    /// becomes an error page, so the window always has something to show.
+    pub fn load_or_error(&mut self) -> Vec<Node> {
+        self.load_or_error_with_stats(&mut Stats::default())
+    }
+
+    /// [`Source::load_or_error`], recording into `stats` as
+    /// [`Source::load_with_stats`] does.
+    pub fn load_or_error_with_stats(&mut self, stats: &mut Stats) -> Vec<Node> {
+        self.load_or_error_progressively(stats, &mut |_| {})
+    }
+
+    /// [`Source::load_or_error_with_stats`], with previews of a download in
+    /// progress as [`Source::load_progressively`] gives them.
+    pub fn load_or_error_progressively(&mut self, stats: &mut Stats, preview: &mut dyn FnMut(Vec<Node>)) -> Vec<Node> {
+        self.load_progressively(stats, preview).unwrap_or_else(|e| {
+            eprintln!("radium: {e}");
+            content::failure_document(&e, &self.to_string(), self.path().is_none())
+        })
+    }
+
+    /// Turn a body obtained elsewhere (from an embedding application) into
+    /// a DOM, classified by `kind`, the URL's extension, or sniffing.
+    pub fn parse(&self, body: &[u8]) -> Vec<Node> {
+        self.parse_body(body, None, Some(Path::new(&self.url.path)))
+    }
+
+    /// Parse the start of a body still arriving, cut where markup or a line
+    /// ends so nothing half-received shows. Images have no preview.
+    fn parse_prefix(&self, body: &[u8], content_type: Option<&str>) -> Option<Vec<Node>> {
+        let kind = self.kind.unwrap_or_else(|| content::classify(content_type, None, body));
+        let cut = match kind {
+            ContentKind::Html => b'<',
+            ContentKind::Text | ContentKind::Markdown => b'\n',
+            ContentKind::Image => return None,
+        };
+        let end = body.iter().rposition(|&b| b == cut)?;
+        Some(self.parse_body(&body[..end], content_type, None))
+    }
+
+    /// Turn a (decompressed) body into a DOM according to its content kind.
+    fn parse_body(&self, body: &[u8], content_type: Option<&str>, path: Option<&Path>) -> Vec<Node> {
+        let charset = content_type.and_then(content::charset);
+        let kind = self.kind.unwrap_or_else(|| content::classify(content_type, path, body));
+        match kind {
+            ContentKind::Html => {
+                let tokens = parser::tokenize(&content::decode(body, charset.as_deref()));
+                parser::dom::build_tree(tokens)
+            }
+            ContentKind::Text => content::text_document(&content::decode(body, charset.as_deref())),
+            ContentKind::Markdown => markdown::to_document(&content::decode(body, charset.as_deref())),
+            ContentKind::Image => content::image_document(&self.url.to_string()),
+        }
+    }
+}
+
+/// How long a download runs before the part received so far is shown, and
+/// then how often that is brought up to date.
+const FIRST_PREVIEW: Duration = Duration::from_millis(300);
+const PREVIEW_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What [`Source::read`] produced.
+enum Body {
+    /// A document built without parsing: a directory listing, a flattened
+    /// archive, an HTTP error page.
+    Document(Vec<Node>),
+    /// A body to parse, with what classifies it.
+    Bytes { body: Vec<u8>, content_type: Option<String>, path: Option<PathBuf> },
+}
+
+/// Local documents display as their path, remote ones as their URL.
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path() {
+            Some(path) => write!(f, "{}", path.display()),
+            None => write!(f, "{}", self.url),
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use crate::parser::dom::Node;
+
+use super::content::element;
+
+/// Tags that never belong to an article's text.
+const BOILERPLATE_TAGS: &[&str] = &[
+    "nav", "aside", "form", "script", "style", "noscript", "iframe", "button", "input", "select", "textarea",
+];
+
+/// `class`/`id` words marking navigation, ads and other page furniture.
+const BOILERPLATE_WORDS: &[&str] = &[
+    "ad", "ads", "advert", "advertisement", "banner", "breadcrumb", "breadcrumbs", "comment", "comments",
+    "cookie", "footer", "masthead", "menu", "nav", "navbar", "newsletter", "popup", "promo", "related",
+    "share", "sidebar", "social", "sponsor", "subscribe", "widget",
+];
+
+/// Reduce a document to its main article, readability-style.
+///
+/// The article is the `<article>` or `<main>` element when the page has one;
+/// otherwise the element whose paragraphs hold the most text. Navigation,
+/// sidebars, footers, forms and elements whose `class`/`id` name such things
+/// are dropped. The page `<title>` heads the result unless the article has
+/// its own `<h1>`.
+pub fn extract(nodes: &[Node]) -> Vec<Node> {
+    let candidate = find_tagged(nodes, &["article", "main"]).or_else(|| best_scored(nodes));
+    let mut body = match candidate {
+        Some(Node::Element { children, .. }) => clean(children, true),
+        _ => clean(nodes, false),
+    };
+
+    if !contains_tag(&body, "h1") {
+        if let Some(title) = find_tagged(nodes, &["title"]).map(text_of).filter(|t| !t.is_empty()) {
+            body.insert(0, element("h1", &[], vec![Node::Text(title)]));
+        }
+    }
+    vec![element("article", &[], body)]
+}
+
+// ── Candidate selection ───────────────────────────────────────────────────────
+
+/// The first element with one of `tags`, preferring earlier tags; several
+/// matches of the same tag are decided by text length.
+fn find_tagged<'a>(nodes: &'a [Node], tags: &[&str]) -> Option<&'a Node> {
+    tags.iter().find_map(|tag| {
+        let mut found = Vec::new();
+        collect(nodes, &mut |node| matches!(node, Node::Element { tag: t, .. } if t == tag), &mut found);
+        found.into_iter().max_by_key(|node| text_of(node).len())
+    })
+}
+
+/// The element whose `<p>` children (and, at half weight, grandchildren)
+/// carry the most text. Boilerplate subtrees do not compete.
+fn best_scored(nodes: &[Node]) -> Option<&Node> {
+    let mut best = None;
+    score(nodes, &mut best);
+    best.map(|(_, node)| node)
+}
+
+fn score<'a>(nodes: &'a [Node], best: &mut Option<(usize, &'a Node)>) {
+    for node in nodes {
+        let Node::Element { children, .. } = node else { continue };
+        if is_boilerplate(node) {
+            continue;
+        }
+        let direct: usize = children.iter().filter(|c| is_tag(c, "p")).map(|p| text_of(p).len()).sum();
+        let nested: usize = children.iter()
+            .filter_map(|c| match c {
+                Node::Element { children, .. } if !is_boilerplate(c) => Some(children),
+                _ => None,
+            })
+            .flat_map(|grandchildren| grandchildren.iter().filter(|g| is_tag(g, "p")))
+            .map(|p| text_of(p).len())
+            .sum();
+        let total = direct + nested / 2;
+        if total > 0 && best.is_none_or(|(s, _)| total > s) {
+            *best = Some((total, node));
+        }
+        score(children, best);
+    }
+}
+
+// ── Cleaning ──────────────────────────────────────────────────────────────────
+
+/// Copy `nodes` without boilerplate. Inside an article, its own `<header>`
+/// and `<footer>` (byline, date) are kept; at page level they are dropped.
+fn clean(nodes: &[Node], in_article: bool) -> Vec<Node> {
+    nodes.iter()
+        .filter(|node| !is_boilerplate(node))
+        .filter(|node| in_article || !(is_tag(node, "header") || is_tag(node, "footer")))
+        .map(|node| match node {
+            Node::Element { tag, attrs, children } => Node::Element {
+                tag: tag.clone(),
+                attrs: attrs.clone(),
+                children: clean(children, in_article || tag == "article"),
+            },
+            Node::Text(text) => Node::Text(text.clone()),
+        })
+        .collect()
+}
+
+fn is_boilerplate(node: &Node) -> bool {
+    let Node::Element { tag, attrs, .. } = node else { return false };
+    if BOILERPLATE_TAGS.contains(&tag.as_str()) || tag == "title" || tag == "head" {
+        return true;
+    }
+    if attrs.get("role").is_some_and(|r| matches!(r.as_str(), "navigation" | "complementary" | "banner")) {
+        return true;
+    }
+    ["class", "id"].iter()
+        .filter_map(|name| attrs.get(*name))
+        .flat_map(|value| value.split(|c: char| !c.is_ascii_alphanumeric()))
+        .any(|word| BOILERPLATE_WORDS.contains(&word.to_ascii_lowercase().as_str()))
+}
+
+// ── Tree helpers ──────────────────────────────────────────────────────────────
+
+fn is_tag(node: &Node, name: &str) -> bool {
+    matches!(node, Node::Element { tag, .. } if tag == name)
+}
+
+fn contains_tag(nodes: &[Node], name: &str) -> bool {
+    nodes.iter().any(|node| match node {
+        Node::Element { tag, children, .. } => tag == name || contains_tag(children, name),
+        Node::Text(_) => false,
+    })
+}
+
+fn collect<'a>(nodes: &'a [Node], pred: &mut dyn FnMut(&Node) -> bool, out: &mut Vec<&'a Node>) {
+    for node in nodes {
+        if pred(node) {
+            out.push(node);
+        }
+        if let Node::Element { children, .. } = node {
+            collect(children, pred, out);
+        }
+    }
+}
+
+/// All text under `node`, whitespace-joined.
+fn text_of(node: &Node) -> String {
+    fn walk(node: &Node, out: &mut Vec<String>) {
+        match node {
+            Node::Text(text) if !text.trim().is_empty() => out.push(text.trim().to_string()),
+            Node::Text(_) => {}
+            Node::Element { children, .. } => children.iter().for_each(|c| walk(c, out)),
+        }
+    }
+    let mut parts = Vec::new();
+    walk(node, &mut parts);
+    parts.join(" ")
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use percent_encoding::percent_decode_str;
+
+use crate::net::{self, Url};
+use crate::parser::dom::{self, Node};
+
+use super::Source;
+
+/// A document ready to be written to disk: HTML with asset references
+/// pointing into `assets/`, and the assets still to be copied there.
+pub struct Snapshot {
+    html: String,
+    /// Remote or local URL → file name under `assets/`.
+    assets: Vec<(Url, String)>,
+}
+
+impl Snapshot {
+    /// Serialize `nodes` (loaded from `base`). Images and stylesheets become
+    /// local copies; every other link is made absolute so it still resolves
+    /// from the saved location.
+    pub fn new(nodes: &[Node], base: &Url) -> Snapshot {
+        let mut assets: Vec<(Url, String)> = Vec::new();
+        let mut names: HashMap<String, String> = HashMap::new();
+
+        let html = dom::to_html(nodes, &mut |tag, attr, value| {
+            let is_asset = matches!((tag, attr), ("img", "src") | ("link", "href"));
+            if !matches!(attr, "src" | "href") || value.starts_with('#') {
+                return None;
+            }
+            let url = base.join(value).ok()?;
+            if !is_asset || !matches!(url.scheme.as_str(), "file" | "http" | "https") {
+                return Some(url.to_string());
+            }
+            let key = url.without_fragment().to_string();
+            let name = names.entry(key).or_insert_with(|| {
+                let name = asset_name(&url, assets.len());
+                assets.push((url.without_fragment(), name.clone()));
+                name
+            });
+            Some(format!("assets/{name}"))
+        });
+
+        Snapshot { html: format!("<!DOCTYPE html>\n{html}\n"), assets }
+    }
+
+    /// Write `index.html` and `assets/` into `dir`, fetching assets as needed.
+    /// Assets that cannot be fetched are reported and skipped.
+    pub fn write(&self, dir: &Path) -> Result<(), String> {
+        let mut html = self.html.clone();
+        let assets_dir = dir.join("assets");
+        fs::create_dir_all(&assets_dir).map_err(|e| format!("creating {}: {e}", assets_dir.display()))?;
+        for (url, name) in &self.assets {
+            let body = match url.to_file_path() {
+                Some(path) => fs::read(&path).map_err(|e| format!("reading {}: {e}", path.display())),
+                None => net::fetch(url).and_then(|(_, response)| match response.is_success() {
+                    true => Ok(response.body),
+                    false => Err(format!("{url}: HTTP {}", response.status)),
+                }),
+            };
+            match body {
+                Ok(body) => fs::write(assets_dir.join(name), body)
+                    .map_err(|e| format!("writing {name}: {e}"))?,
+                Err(e) => {
+                    // Point back at the original rather than at nothing.
+                    eprintln!("radium: skipping asset: {e}");
+                    html = html.replace(&format!("\"assets/{name}\""), &format!("\"{url}\""));
+                }
+            }
+        }
+        let index = dir.join("index.html");
+        fs::write(&index, html).map_err(|e| format!("writing {}: {e}", index.display()))
+    }
+}
+
+/// A fresh folder for `source` in the user's downloads directory (or the
+/// working directory if there is none): `<name>`, `<name>-2`, ...
+pub fn target_dir(source: &Source) -> PathBuf {
+    let root = std::env::var_os("XDG_DOWNLOAD_DIR")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Downloads")))
+        .filter(|dir| dir.is_dir())
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    let segments: Vec<&str> = source.url.path.split('/').filter(|s| !s.is_empty()).collect();
+    let stem = match segments.last().map(|s| s.split('.').next().unwrap_or(s)) {
+        // `dir/index.html` is better named after `dir`.
+        Some("index") | None => segments.iter().rev().nth(1).copied().unwrap_or(&source.url.host),
+        Some(stem) => stem,
+    };
+    let stem = sanitize(&percent_decode_str(stem).decode_utf8_lossy());
+    let stem = if stem.is_empty() { "page".to_string() } else { stem };
+
+    (1..)
+        .map(|n| root.join(if n == 1 { stem.clone() } else { format!("{stem}-{n}") }))
+        .find(|dir| !dir.exists())
+        .unwrap_or_else(|| root.join(stem))
+}
+
+/// `<index>-<last path segment>`, safe as a file name.
+fn asset_name(url: &Url, index: usize) -> String {
+    let last = url.path.rsplit('/').next().unwrap_or_default();
+    let last = sanitize(&percent_decode_str(last).decode_utf8_lossy());
+    if last.is_empty() { format!("{index}") } else { format!("{index}-{last}") }
+}
+
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .collect::<String>()
+        .trim_start_matches('.')
+        .to_string()
+}
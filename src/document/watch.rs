@@ -0,0 +1,41 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// How often watched files are checked for modification.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The set of files being watched. Replace its contents to change what is
+/// watched (e.g. after a reload discovers new assets).
+pub type WatchList = Arc<Mutex<Vec<PathBuf>>>;
+
+/// Poll the files in `paths` on a background thread and call `on_change`
+/// whenever one is modified, created or removed.
+///
+/// Polling modification times keeps this dependency-free and behaves the
+/// same on every platform; at 4Hz the cost is negligible.
+pub fn spawn(paths: WatchList, on_change: impl Fn() + Send + 'static) {
+    thread::spawn(move || {
+        let mut seen: Vec<(PathBuf, Option<SystemTime>)> = Vec::new();
+        loop {
+            let current: Vec<(PathBuf, Option<SystemTime>)> = paths.lock().unwrap()
+                .iter()
+                .map(|p| (p.clone(), modified(p)))
+                .collect();
+
+            // Only compare like with like: a changed list just re-baselines.
+            let same_files = seen.len() == current.len()
+                && seen.iter().zip(&current).all(|(a, b)| a.0 == b.0);
+            if same_files && seen != current {
+                on_change();
+            }
+            seen = current;
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
@@ -0,0 +1,91 @@
+use std::io::Read;
+
+use flate2::read::DeflateDecoder;
+
+/// A ZIP archive held in memory. Only what EPUB needs is supported: stored
+/// and deflated entries, no ZIP64, no encryption.
+pub struct Archive {
+    data: Vec<u8>,
+    entries: Vec<Entry>,
+}
+
+struct Entry {
+    name: String,
+    method: u16,
+    compressed_size: usize,
+    local_offset: usize,
+}
+
+const END_OF_CENTRAL_DIR: u32 = 0x0605_4b50;
+const CENTRAL_DIR_ENTRY: u32 = 0x0201_4b50;
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+
+impl Archive {
+    pub fn new(data: Vec<u8>) -> Result<Archive, String> {
+        // The end record is at least 22 bytes and may be followed by a comment.
+        let eocd = (0..data.len().saturating_sub(21))
+            .rev()
+            .find(|&i| u32_at(&data, i) == Some(END_OF_CENTRAL_DIR))
+            .ok_or("not a zip archive")?;
+        let count = u16_at(&data, eocd + 10).ok_or("truncated zip")? as usize;
+        let mut at = u32_at(&data, eocd + 16).ok_or("truncated zip")? as usize;
+
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            if u32_at(&data, at) != Some(CENTRAL_DIR_ENTRY) {
+                return Err("corrupt zip central directory".into());
+            }
+            let field = |offset: usize| u16_at(&data, at + offset).ok_or("truncated zip");
+            let method = field(10)?;
+            let compressed_size = u32_at(&data, at + 20).ok_or("truncated zip")? as usize;
+            let (name_len, extra_len, comment_len) = (field(28)? as usize, field(30)? as usize, field(32)? as usize);
+            let local_offset = u32_at(&data, at + 42).ok_or("truncated zip")? as usize;
+            let name = data.get(at + 46..at + 46 + name_len).ok_or("truncated zip")?;
+            entries.push(Entry {
+                name: String::from_utf8_lossy(name).into_owned(),
+                method,
+                compressed_size,
+                local_offset,
+            });
+            at += 46 + name_len + extra_len + comment_len;
+        }
+        Ok(Archive { data, entries })
+    }
+
+    /// Names of every entry, in archive order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|e| e.name.as_str())
+    }
+
+    /// The decompressed contents of entry `name`.
+    pub fn read(&self, name: &str) -> Result<Vec<u8>, String> {
+        let entry = self.entries.iter().find(|e| e.name == name).ok_or_else(|| format!("{name}: not in archive"))?;
+        let at = entry.local_offset;
+        if u32_at(&self.data, at) != Some(LOCAL_HEADER) {
+            return Err(format!("{name}: corrupt local header"));
+        }
+        // Local name/extra lengths may differ from the central directory's.
+        let name_len = u16_at(&self.data, at + 26).unwrap_or(0) as usize;
+        let extra_len = u16_at(&self.data, at + 28).unwrap_or(0) as usize;
+        let start = at + 30 + name_len + extra_len;
+        let raw = self.data.get(start..start + entry.compressed_size).ok_or_else(|| format!("{name}: truncated"))?;
+
+        match entry.method {
+            0 => Ok(raw.to_vec()),
+            8 => {
+                let mut out = Vec::new();
+                DeflateDecoder::new(raw).read_to_end(&mut out).map_err(|e| format!("{name}: {e}"))?;
+                Ok(out)
+            }
+            other => Err(format!("{name}: unsupported compression method {other}")),
+        }
+    }
+}
+
+fn u16_at(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
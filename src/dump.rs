@@ -0,0 +1,445 @@
+//! Plain-text renderings of the pipeline's intermediate representations,
+//! behind `--dump-tokens`, `--dump-dom` and `--dump-layout`, and of the
+//! document itself for `--dump-text`. Output is deterministic (attributes
+//! sorted, fixed float precision) so it can be diffed against golden files.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::css;
+use crate::layout::{self, ButtonKind, HitKind, Page, PaintCmd, ToggleKind};
+use crate::net::Url;
+use crate::parser::dom::Node;
+use crate::parser::{self, Token};
+
+/// One token per line: `doctype`, `open <tag> [attrs] [/]`, `close <tag>`,
+/// `text "<content>"` or `comment "<content>"`.
+pub fn tokens(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    for token in tokens {
+        match token {
+            Token::Doctype => out.push_str("doctype"),
+            Token::OpenTag { name, attrs, self_closing } => {
+                out.push_str("open ");
+                out.push_str(name);
+                write_attrs(attrs, &mut out);
+                if *self_closing {
+                    out.push_str(" /");
+                }
+            }
+            Token::CloseTag(name) => {
+                let _ = write!(out, "close {name}");
+            }
+            Token::Text(text) => {
+                let _ = write!(out, "text {text:?}");
+            }
+            Token::Comment(text) => {
+                let _ = write!(out, "comment {text:?}");
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// The tree, one node per line, children indented by two spaces.
+pub fn dom(nodes: &[Node]) -> String {
+    fn walk(nodes: &[Node], depth: usize, out: &mut String) {
+        for node in nodes {
+            out.push_str(&"  ".repeat(depth));
+            match node {
+                Node::Element { tag, attrs, children } => {
+                    out.push_str(tag);
+                    write_attrs(attrs, out);
+                    out.push('\n');
+                    walk(children, depth + 1, out);
+                }
+                Node::Text(text) => {
+                    let _ = writeln!(out, "{text:?}");
+                }
+            }
+        }
+    }
+    let mut out = String::new();
+    walk(nodes, 0, &mut out);
+    out
+}
+
+/// Every paint box (`x y width height` then what it draws), followed by the
+/// interactive regions and the fragment anchors.
+///
+/// This is radium's display-list snapshot format, and it is kept stable so
+/// tests and external tools can rely on it. One item per line, coordinates
+/// in logical pixels with one decimal, strings quoted and escaped as by
+/// Rust's `{:?}`:
+///
+/// ```text
+/// box <x> <y> <w> <h> text <size>px #rrggbb[ bold][ italic][ monospace][ underline] "<content>"
+/// box <x> <y> <w> <h> rect #rrggbb
+/// box <x> <y> <w> <h> hline #rrggbb
+/// box <x> <y> <w> <h> image <width>x<height>
+/// link <x> <y> <w> <h> "<href>"
+/// title <x> <y> <w> <h> "<text>"
+/// input|textarea <x> <y> <w> <h> "<name>"
+/// checkbox|radio <x> <y> <w> <h>[ checked] "<name>"
+/// button <x> <y> <w> <h> submit|reset|button "<label>"
+/// select <x> <y> <w> <h> <chosen>/<options> "<name>"
+/// label <x> <y> <w> <h> -> <index of the control's region>
+/// anchor <x> <y> <w> <h> "<name>"
+/// ```
+///
+/// Colours that are not opaque are written `#rrggbbaa`.
+///
+/// Boxes and regions are in paint order, anchors sorted by name.
+pub fn layout(page: &Page) -> String {
+    let mut out = String::new();
+    for b in &page.boxes {
+        let _ = write!(out, "box {:.1} {:.1} {:.1} {:.1} ", b.x, b.y, b.width, b.height);
+        let _ = match &b.cmd {
+            PaintCmd::Text { content, font_size, bold, italic, monospace, color, underline } => {
+                let style: String = [(*bold, " bold"), (*italic, " italic"), (*monospace, " monospace"), (*underline, " underline")]
+                    .iter()
+                    .filter(|(on, _)| *on)
+                    .map(|(_, name)| *name)
+                    .collect();
+                writeln!(out, "text {font_size}px {}{style} {content:?}", css::hex(*color))
+            }
+            PaintCmd::FillRect { color } => writeln!(out, "rect {}", css::hex(*color)),
+            PaintCmd::HLine { color } => writeln!(out, "hline {}", css::hex(*color)),
+            PaintCmd::Image { img_width, img_height, .. } => writeln!(out, "image {img_width}x{img_height}"),
+        };
+    }
+    let empty = String::new();
+    for region in &page.regions {
+        let r = region.rect;
+        let (kind, value) = match &region.kind {
+            HitKind::Link(href) => ("link", href),
+            HitKind::Title(title) => ("title", title),
+            HitKind::Input(i) => {
+                let input = &page.inputs[*i];
+                let kind = if input.multiline { "textarea" } else { "input" };
+                (kind, input.name.as_ref().unwrap_or(&empty))
+            }
+            HitKind::Toggle(i) => {
+                let toggle = &page.toggles[*i];
+                let kind = match toggle.kind {
+                    ToggleKind::Checkbox => "checkbox",
+                    ToggleKind::Radio => "radio",
+                };
+                let checked = if toggle.checked { " checked" } else { "" };
+                let name = toggle.name.as_ref().unwrap_or(&empty);
+                let _ = writeln!(out, "{kind} {:.1} {:.1} {:.1} {:.1}{checked} {name:?}", r.x, r.y, r.width, r.height);
+                continue;
+            }
+            HitKind::Label(control) => {
+                let _ = writeln!(out, "label {:.1} {:.1} {:.1} {:.1} -> {control}", r.x, r.y, r.width, r.height);
+                continue;
+            }
+            HitKind::Button(i) => {
+                let button = &page.buttons[*i];
+                let kind = match button.kind {
+                    ButtonKind::Submit => "submit",
+                    ButtonKind::Reset => "reset",
+                    ButtonKind::Button => "button",
+                };
+                let _ = writeln!(out, "button {:.1} {:.1} {:.1} {:.1} {kind} {:?}", r.x, r.y, r.width, r.height, button.label);
+                continue;
+            }
+            HitKind::Select(i) => {
+                let select = &page.selects[*i];
+                let chosen = select.selected.map_or("-".to_string(), |option| (option + 1).to_string());
+                let name = select.name.as_ref().unwrap_or(&empty);
+                let count = select.options.len();
+                let _ = writeln!(out, "select {:.1} {:.1} {:.1} {:.1} {chosen}/{count} {name:?}", r.x, r.y, r.width, r.height);
+                continue;
+            }
+        };
+        let _ = writeln!(out, "{kind} {:.1} {:.1} {:.1} {:.1} {value:?}", r.x, r.y, r.width, r.height);
+    }
+    let mut anchors: Vec<_> = page.anchors.iter().collect();
+    anchors.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, r) in anchors {
+        let _ = writeln!(out, "anchor {:.1} {:.1} {:.1} {:.1} {name:?}", r.x, r.y, r.width, r.height);
+    }
+    out
+}
+
+/// The document as readable plain text, like `w3m -dump`: blocks separated
+/// by blank lines, `h1`/`h2` underlined with `=`/`-`, list items behind
+/// their markers, images as `[alt]`, and each link followed by its target
+/// (resolved against `base`) in brackets. Lines are not wrapped, and
+/// preformatted text keeps its own.
+pub fn text(nodes: &[Node], base: &Url) -> String {
+    let mut writer = TextWriter { base, out: String::new(), line: String::new(), indent: 0, marker: None, gap: false, spaced: false, lists: 0, langs: Vec::new(), quotes: 0, counters: layout::outline_numbering().then(Vec::new), pre: false };
+    writer.children(nodes);
+    writer.flush();
+    writer.out
+}
+
+/// Columns a list indents its items by; the marker sits right-aligned in them.
+const LIST_INDENT: usize = 4;
+/// Columns a `<blockquote>` or a `<dd>` indents its lines by.
+const BLOCK_INDENT: usize = 4;
+/// Width of the line an `<hr>` becomes.
+const RULE_WIDTH: usize = 40;
+
+/// Builds [`text`] output line by line.
+struct TextWriter<'a> {
+    base: &'a Url,
+    out: String,
+    /// Words of the line being built, separated by single spaces.
+    line: String,
+    /// Column the current block's lines start at.
+    indent: usize,
+    /// List marker for the gutter of the next line written.
+    marker: Option<String>,
+    /// Put a blank line before the next line (a block boundary was crossed).
+    gap: bool,
+    /// Put a space before the next text: whitespace ended the text before
+    /// it, or starts it.
+    spaced: bool,
+    /// How many lists deep the walk is.
+    lists: usize,
+    /// `lang` of each enclosing element that has one, innermost last.
+    langs: Vec<String>,
+    /// How many `<q>` elements deep the walk is.
+    quotes: usize,
+    /// The numbers of the ordered list items the walk is in, as layout
+    /// keeps them for outline numbering.
+    counters: Option<Vec<String>>,
+    /// Inside a `<pre>`: text is written as it is, line by line.
+    pre: bool,
+}
+
+impl TextWriter<'_> {
+    fn children(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            match node {
+                Node::Text(text) if self.pre => {
+                    for (i, segment) in text.split('\n').enumerate() {
+                        if i > 0 {
+                            let line = std::mem::take(&mut self.line);
+                            self.write_line(&line);
+                        }
+                        self.line.push_str(segment);
+                    }
+                }
+                Node::Text(text) => self.text(text),
+                Node::Element { tag, attrs, children } => {
+                    let lang = attrs.get("lang").map(|lang| lang.trim().to_ascii_lowercase());
+                    if let Some(lang) = lang.clone() {
+                        self.langs.push(lang);
+                    }
+                    self.element(tag, attrs, children);
+                    if lang.is_some() {
+                        self.langs.pop();
+                    }
+                }
+            }
+        }
+    }
+
+    /// The same block/inline split as layout: containers, headings,
+    /// paragraphs, lists, tables and rules are blocks; anything else flows
+    /// inline.
+    fn element(&mut self, tag: &str, attrs: &HashMap<String, String>, children: &[Node]) {
+        match tag {
+            "head" | "title" | "script" | "style" | "meta" | "link" => {}
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.block();
+                self.children(children);
+                let width = self.line.chars().count();
+                self.flush();
+                let underline = match tag {
+                    "h1" => Some('='),
+                    "h2" => Some('-'),
+                    _ => None,
+                };
+                if let Some(c) = underline.filter(|_| width > 0) {
+                    self.write_line(&c.to_string().repeat(width));
+                }
+                self.block();
+            }
+            "html" | "body" | "div" | "section" | "article" | "main" | "header" | "footer" | "p" | "dl" => {
+                self.block();
+                self.children(children);
+                self.block();
+            }
+            "blockquote" => {
+                self.block();
+                self.indent += BLOCK_INDENT;
+                self.children(children);
+                self.block();
+                self.indent -= BLOCK_INDENT;
+            }
+            // Terms and their descriptions on lines of their own, without
+            // blank lines between them.
+            "dt" => {
+                self.flush();
+                self.children(children);
+                self.flush();
+            }
+            "dd" => {
+                self.flush();
+                self.indent += BLOCK_INDENT;
+                self.children(children);
+                self.flush();
+                self.indent -= BLOCK_INDENT;
+            }
+            "pre" => {
+                self.block();
+                self.pre = true;
+                self.children(children);
+                self.pre = false;
+                self.block();
+            }
+            "table" => {
+                self.block();
+                self.children(children);
+                self.block();
+            }
+            // Cells are apart however they were written.
+            "td" | "th" => {
+                self.spaced = true;
+                self.children(children);
+                self.spaced = true;
+            }
+            // One line per row (and caption), its cells in order.
+            "tr" | "caption" => {
+                self.flush();
+                self.children(children);
+                self.flush();
+            }
+            "ul" | "ol" => {
+                // Nested lists continue their item; only the outermost is a separate block.
+                if self.lists == 0 { self.block() } else { self.flush() }
+                self.list(tag, attrs, children);
+                if self.lists == 0 { self.block() } else { self.flush() }
+            }
+            "br" => self.flush(),
+            "q" => {
+                let (open, close) = layout::quote_marks(self.langs.last().map(String::as_str), self.quotes);
+                self.run(open);
+                self.quotes += 1;
+                self.children(children);
+                self.quotes -= 1;
+                self.run(close);
+            }
+            "hr" => {
+                self.block();
+                self.write_line(&"-".repeat(RULE_WIDTH));
+                self.block();
+            }
+            "img" => {
+                if let Some(alt) = attrs.get("alt").map(|alt| alt.trim()).filter(|alt| !alt.is_empty()) {
+                    self.word(&format!("[{alt}]"));
+                }
+            }
+            "a" => {
+                self.children(children);
+                if let Some(href) = attrs.get("href").map(|href| href.trim()).filter(|href| !href.is_empty()) {
+                    // In-page links stay as written; the rest are made absolute.
+                    let target = match self.base.join(href) {
+                        Ok(url) if !href.starts_with('#') => url.to_string(),
+                        _ => href.to_string(),
+                    };
+                    self.word(&format!("[{target}]"));
+                }
+            }
+            _ => self.children(children),
+        }
+    }
+
+    fn list(&mut self, tag: &str, attrs: &HashMap<String, String>, items: &[Node]) {
+        self.lists += 1;
+        self.indent += LIST_INDENT;
+        let mut markers = layout::ListMarkers::new(tag, attrs, items, self.lists, self.counters.as_deref().unwrap_or_default());
+        for item in items {
+            let Node::Element { tag: item_tag, children, .. } = item else { continue };
+            if item_tag != "li" {
+                continue;
+            }
+            self.flush();
+            let (marker, number) = markers.next_marker();
+            self.marker = Some(marker);
+            let counted = match (&mut self.counters, number) {
+                (Some(counters), Some(number)) => {
+                    counters.push(number);
+                    true
+                }
+                _ => false,
+            };
+            self.children(children);
+            if counted {
+                self.counters.as_mut().map(Vec::pop);
+            }
+            self.flush();
+            // An empty item leaves its marker unused.
+            self.marker = None;
+        }
+        self.indent -= LIST_INDENT;
+        self.lists -= 1;
+    }
+
+    /// Add a text node's words to the line. Whitespace at either end is the
+    /// space between them and what is beside them, as in layout.
+    fn text(&mut self, text: &str) {
+        let space = |c: char| c.is_ascii_whitespace();
+        self.spaced |= text.starts_with(space);
+        let words = parser::trim_whitespace(text);
+        if !words.is_empty() {
+            self.run(words);
+            self.spaced = text.ends_with(space);
+        }
+    }
+
+    /// Add `word` to the line apart from what comes before it.
+    fn word(&mut self, word: &str) {
+        self.spaced = true;
+        self.run(word);
+    }
+
+    /// Add `text` to the line, after a space if one is due.
+    fn run(&mut self, text: &str) {
+        if std::mem::take(&mut self.spaced) && !self.line.is_empty() {
+            self.line.push(' ');
+        }
+        self.line.push_str(text);
+    }
+
+    /// End the current block: the next line starts after a blank one.
+    fn block(&mut self) {
+        self.flush();
+        self.gap = true;
+    }
+
+    /// Write out the line being built, if any.
+    fn flush(&mut self) {
+        if !self.line.is_empty() {
+            let line = std::mem::take(&mut self.line);
+            self.write_line(&line);
+        }
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if std::mem::take(&mut self.gap) && !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        match self.marker.take() {
+            Some(marker) => {
+                let _ = write!(self.out, "{marker:>width$} ", width = self.indent.saturating_sub(1));
+            }
+            None => self.out.push_str(&" ".repeat(self.indent)),
+        }
+        self.out.push_str(line);
+        self.out.push('\n');
+    }
+}
+
+fn write_attrs(attrs: &HashMap<String, String>, out: &mut String) {
+    let mut names: Vec<&String> = attrs.keys().collect();
+    names.sort();
+    for name in names {
+        let _ = write!(out, " {name}={:?}", attrs[name]);
+    }
+}
@@ -0,0 +1,57 @@
+use std::fmt;
+use std::io;
+
+/// Why rendering (or the window shell around it) failed.
+#[derive(Debug)]
+pub enum Error {
+    /// No usable font was found, or a font file could not be parsed.
+    Font(String),
+    /// The windowing system failed: event loop, window or drawing surface.
+    Window(String),
+    /// A pixel buffer is smaller than the area painted into it.
+    Buffer { len: usize, width: u32, height: u32 },
+    /// Encoding an image failed.
+    Encode(String),
+    Io(io::Error),
+}
+
+/// `Result` with [`Error`].
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Font(e) => write!(f, "font: {e}"),
+            Error::Window(e) => write!(f, "window: {e}"),
+            Error::Buffer { len, width, height } => {
+                write!(f, "buffer of {len} pixels is smaller than {width}×{height}")
+            }
+            Error::Encode(e) => write!(f, "encoding image: {e}"),
+            Error::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<image::ImageError> for Error {
+    fn from(e: image::ImageError) -> Self {
+        match e {
+            image::ImageError::IoError(e) => Error::Io(e),
+            e => Error::Encode(e.to_string()),
+        }
+    }
+}
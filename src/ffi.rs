@@ -0,0 +1,194 @@
+//! C API (`--features ffi`), for embedding radium as an HTML preview in
+//! non-Rust applications. The declarations are in `include/radium.h`.
+//!
+//! Functions return [`RADIUM_OK`] or an error code; the message for the last
+//! error on the calling thread is available from [`radium_last_error`].
+//! Fonts are found as by the `radium` binary, once per process.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::sync::OnceLock;
+
+use crate::layout::{Estimate, PaintCmd};
+use crate::{Error, FontSet, Viewport};
+
+pub const RADIUM_OK: c_int = 0;
+/// A null pointer, HTML that is not UTF-8, or a zero size.
+pub const RADIUM_ERROR_ARGUMENT: c_int = 1;
+/// No usable font was found.
+pub const RADIUM_ERROR_FONT: c_int = 2;
+/// Any other rendering failure.
+pub const RADIUM_ERROR_RENDER: c_int = 3;
+
+pub const RADIUM_PAINT_TEXT: u32 = 0;
+pub const RADIUM_PAINT_RECT: u32 = 1;
+pub const RADIUM_PAINT_HLINE: u32 = 2;
+pub const RADIUM_PAINT_IMAGE: u32 = 3;
+
+pub const RADIUM_TEXT_BOLD: u32 = 1;
+pub const RADIUM_TEXT_ITALIC: u32 = 2;
+pub const RADIUM_TEXT_UNDERLINE: u32 = 4;
+pub const RADIUM_TEXT_MONOSPACE: u32 = 8;
+
+/// One entry of a display list, as passed to a [`RadiumPaintCallback`].
+/// Coordinates are logical pixels from the top-left of the document;
+/// pointers are only valid during the callback.
+#[repr(C)]
+pub struct RadiumPaintCmd {
+    /// `RADIUM_PAINT_*`.
+    pub kind: u32,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// `0xRRGGBB`; unused for images.
+    pub color: u32,
+    /// Text only: size in logical pixels and `RADIUM_TEXT_*` flags.
+    pub font_size: f32,
+    pub flags: u32,
+    /// Text only: UTF-8, not NUL-terminated.
+    pub text: *const u8,
+    pub text_len: usize,
+    /// Images only: RGBA8 pixels, `image_width × image_height × 4` bytes.
+    pub image: *const u8,
+    pub image_width: u32,
+    pub image_height: u32,
+}
+
+pub type RadiumPaintCallback = extern "C" fn(cmd: *const RadiumPaintCmd, user_data: *mut c_void);
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+static FONTS: OnceLock<Result<FontSet, String>> = OnceLock::new();
+
+/// Render `html` into `out_rgba`, which must hold `width × height × 4`
+/// bytes (RGBA8, row-major). Relative URLs resolve against the working
+/// directory; remote images are left as placeholders.
+///
+/// # Safety
+///
+/// `html` must be a NUL-terminated string and `out_rgba` must point to at
+/// least `width × height × 4` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn radium_render_html(html: *const c_char, width: u32, height: u32, out_rgba: *mut u8) -> c_int {
+    let html = match html_arg(html) {
+        Ok(html) => html,
+        Err(code) => return code,
+    };
+    if out_rgba.is_null() || width == 0 || height == 0 {
+        return fail(RADIUM_ERROR_ARGUMENT, "out_rgba is null or the size is zero");
+    }
+    let fonts = match fonts() {
+        Ok(fonts) => fonts,
+        Err(code) => return code,
+    };
+    let viewport = Viewport::new(width, height);
+    let list = crate::layout(&crate::parse(html), viewport, fonts);
+    // SAFETY: the caller guarantees the buffer's size.
+    let buffer = unsafe { std::slice::from_raw_parts_mut(out_rgba, width as usize * height as usize * 4) };
+    match crate::render_rgba(&list, buffer, viewport, fonts) {
+        Ok(()) => RADIUM_OK,
+        Err(e) => fail(RADIUM_ERROR_RENDER, &e.to_string()),
+    }
+}
+
+/// Lay `html` out `width` logical pixels wide and call `callback` with each
+/// paint command in order, for applications that draw with their own
+/// graphics stack. Needs no fonts: text is wrapped by an estimate of its
+/// width ([`Estimate`]).
+///
+/// # Safety
+///
+/// `html` must be a NUL-terminated string. `user_data` is passed through to
+/// `callback` untouched.
+#[no_mangle]
+pub unsafe extern "C" fn radium_paint_html(
+    html: *const c_char,
+    width: u32,
+    callback: Option<RadiumPaintCallback>,
+    user_data: *mut c_void,
+) -> c_int {
+    let html = match html_arg(html) {
+        Ok(html) => html,
+        Err(code) => return code,
+    };
+    let Some(callback) = callback.filter(|_| width > 0) else {
+        return fail(RADIUM_ERROR_ARGUMENT, "callback is null or the width is zero");
+    };
+    let list = crate::layout(&crate::parse(html), Viewport::new(width, 1), &Estimate);
+    for b in &list.boxes {
+        let mut cmd = RadiumPaintCmd {
+            kind: RADIUM_PAINT_RECT,
+            x: b.x,
+            y: b.y,
+            width: b.width,
+            height: b.height,
+            color: 0,
+            font_size: 0.0,
+            flags: 0,
+            text: std::ptr::null(),
+            text_len: 0,
+            image: std::ptr::null(),
+            image_width: 0,
+            image_height: 0,
+        };
+        match &b.cmd {
+            PaintCmd::Text { content, font_size, bold, italic, monospace, color, underline } => {
+                cmd.kind = RADIUM_PAINT_TEXT;
+                cmd.color = *color;
+                cmd.font_size = *font_size;
+                cmd.flags = [(*bold, RADIUM_TEXT_BOLD), (*italic, RADIUM_TEXT_ITALIC), (*underline, RADIUM_TEXT_UNDERLINE), (*monospace, RADIUM_TEXT_MONOSPACE)]
+                    .iter()
+                    .filter(|(on, _)| *on)
+                    .fold(0, |flags, (_, flag)| flags | flag);
+                cmd.text = content.as_ptr();
+                cmd.text_len = content.len();
+            }
+            PaintCmd::FillRect { color } => cmd.color = *color,
+            PaintCmd::HLine { color } => {
+                cmd.kind = RADIUM_PAINT_HLINE;
+                cmd.color = *color;
+            }
+            PaintCmd::Image { data, img_width, img_height } => {
+                cmd.kind = RADIUM_PAINT_IMAGE;
+                cmd.image = data.as_ptr();
+                cmd.image_width = *img_width;
+                cmd.image_height = *img_height;
+            }
+        }
+        callback(&cmd, user_data);
+    }
+    RADIUM_OK
+}
+
+/// The message for the last error on this thread, or null. Valid until the
+/// next radium call on the same thread.
+#[no_mangle]
+pub extern "C" fn radium_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(std::ptr::null(), |e| e.as_ptr()))
+}
+
+/// Borrow the `html` argument as UTF-8.
+unsafe fn html_arg<'a>(html: *const c_char) -> Result<&'a str, c_int> {
+    if html.is_null() {
+        return Err(fail(RADIUM_ERROR_ARGUMENT, "html is null"));
+    }
+    // SAFETY: the caller guarantees a NUL-terminated string.
+    unsafe { CStr::from_ptr(html) }
+        .to_str()
+        .map_err(|e| fail(RADIUM_ERROR_ARGUMENT, &format!("html is not UTF-8: {e}")))
+}
+
+fn fonts() -> Result<&'static FontSet, c_int> {
+    let fonts = FONTS.get_or_init(|| FontSet::load(&Default::default()).map_err(|e: Error| e.to_string()));
+    fonts.as_ref().map_err(|e| fail(RADIUM_ERROR_FONT, e))
+}
+
+/// Record `message` as the last error and return `code`.
+fn fail(code: c_int, message: &str) -> c_int {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+    code
+}
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, RwLock};
+
+use super::{ComputedStyle, LayoutBox};
+use crate::net::Url;
+use crate::parser::dom::Node;
+
+/// An element handed to a custom element handler.
+pub struct CustomElement<'a> {
+    /// Lowercase tag name.
+    pub tag: &'a str,
+    pub attrs: &'a HashMap<String, String>,
+    pub children: &'a [Node],
+    /// Width available to the element, in logical pixels.
+    pub width: f32,
+    /// Style inherited from its ancestors.
+    pub style: ComputedStyle,
+    /// Resolves relative URLs in its attributes.
+    pub base: &'a Url,
+}
+
+/// What a handler laid an element out as.
+#[derive(Debug, Default)]
+pub struct CustomLayout {
+    /// Paint boxes, positioned relative to the element's top-left corner.
+    pub boxes: Vec<LayoutBox>,
+    /// Vertical space the element takes up.
+    pub height: f32,
+}
+
+/// Lays out one element, or returns `None` to have it laid out as though
+/// no handler were registered.
+pub type ElementHandler = dyn Fn(&CustomElement) -> Option<CustomLayout> + Send + Sync;
+
+/// Registered handlers by tag name.
+pub(super) type Handlers = HashMap<String, Arc<ElementHandler>>;
+
+static HANDLERS: LazyLock<RwLock<Arc<Handlers>>> = LazyLock::new(Default::default);
+
+/// Lay out every `tag` element with `handler` from now on, in place of
+/// radium's own layout for that tag (including built-in tags). Replaces any
+/// handler registered for `tag` before.
+pub fn register_element(
+    tag: &str,
+    handler: impl Fn(&CustomElement) -> Option<CustomLayout> + Send + Sync + 'static,
+) {
+    update(|handlers| {
+        handlers.insert(tag.to_ascii_lowercase(), Arc::new(handler));
+    });
+}
+
+/// Go back to radium's own layout for `tag`. Returns whether a handler was
+/// registered for it.
+pub fn unregister_element(tag: &str) -> bool {
+    let mut removed = false;
+    update(|handlers| removed = handlers.remove(&tag.to_ascii_lowercase()).is_some());
+    removed
+}
+
+/// The handlers registered now. A layout takes this once, so registering
+/// during a layout affects only later ones.
+pub(super) fn handlers() -> Arc<Handlers> {
+    HANDLERS.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+fn update(f: impl FnOnce(&mut Handlers)) {
+    let mut handlers = HANDLERS.write().unwrap_or_else(|e| e.into_inner());
+    f(Arc::make_mut(&mut handlers));
+}
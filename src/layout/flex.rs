@@ -0,0 +1,255 @@
+use super::{content_widths, element_style, is_hidden, layout_node, parser, Ctx, Style};
+use crate::css;
+use crate::parser::dom::Node;
+
+/// The flex container properties: `flex-direction`, `justify-content` and
+/// `align-items`.
+#[derive(Clone, Copy, Default)]
+pub(super) struct Container {
+    direction: Direction,
+    justify: Justify,
+    align: Align,
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+enum Direction {
+    #[default]
+    Row,
+    RowReverse,
+    Column,
+    ColumnReverse,
+}
+
+/// Where items go along the main axis when they leave room to spare.
+#[derive(Clone, Copy, Default)]
+enum Justify {
+    #[default]
+    Start,
+    End,
+    Center,
+    SpaceBetween,
+    SpaceAround,
+    SpaceEvenly,
+}
+
+/// Where items go across the main axis.
+#[derive(Clone, Copy, Default, PartialEq)]
+enum Align {
+    #[default]
+    Stretch,
+    Start,
+    End,
+    Center,
+}
+
+/// The flex item properties: `flex-grow`, `flex-shrink` and `flex-basis`.
+#[derive(Clone, Copy)]
+pub(super) struct Item {
+    grow: f32,
+    shrink: f32,
+    /// `None` for `auto`: the item's content decides.
+    basis: Option<f32>,
+}
+
+impl Default for Item {
+    fn default() -> Self {
+        Item { grow: 0.0, shrink: 1.0, basis: None }
+    }
+}
+
+impl Container {
+    /// Apply `flex-direction`, `justify-content` or `align-items`; values
+    /// other than these keywords are ignored.
+    pub(super) fn set(&mut self, name: &str, keyword: Option<&str>) {
+        match (name, keyword) {
+            ("flex-direction", Some("row")) => self.direction = Direction::Row,
+            ("flex-direction", Some("row-reverse")) => self.direction = Direction::RowReverse,
+            ("flex-direction", Some("column")) => self.direction = Direction::Column,
+            ("flex-direction", Some("column-reverse")) => self.direction = Direction::ColumnReverse,
+            ("justify-content", Some("flex-start" | "start" | "left" | "normal")) => self.justify = Justify::Start,
+            ("justify-content", Some("flex-end" | "end" | "right")) => self.justify = Justify::End,
+            ("justify-content", Some("center")) => self.justify = Justify::Center,
+            ("justify-content", Some("space-between")) => self.justify = Justify::SpaceBetween,
+            ("justify-content", Some("space-around")) => self.justify = Justify::SpaceAround,
+            ("justify-content", Some("space-evenly")) => self.justify = Justify::SpaceEvenly,
+            ("align-items", Some("stretch" | "normal")) => self.align = Align::Stretch,
+            ("align-items", Some("flex-start" | "start" | "self-start" | "baseline")) => self.align = Align::Start,
+            ("align-items", Some("flex-end" | "end" | "self-end")) => self.align = Align::End,
+            ("align-items", Some("center")) => self.align = Align::Center,
+            _ => {}
+        }
+    }
+}
+
+impl Item {
+    /// Apply `flex`, `flex-grow`, `flex-shrink` or `flex-basis` (whose
+    /// percentages are of `width`); invalid values are ignored.
+    pub(super) fn set(&mut self, name: &str, parts: &[&[css::Token]], font_size: f32, width: f32) {
+        let number = |part: &[css::Token]| match part {
+            [css::Token::Number(n)] if *n >= 0.0 => Some(*n),
+            _ => None,
+        };
+        let basis = |part: &[css::Token]| match (css::keyword(part).as_deref(), part) {
+            (Some("auto" | "content"), _) => Some(None),
+            (_, [token]) => css::length(token, font_size, width).filter(|&basis| basis >= 0.0).map(Some),
+            _ => None,
+        };
+        match (name, parts) {
+            ("flex-grow", [part]) => self.grow = number(part).unwrap_or(self.grow),
+            ("flex-shrink", [part]) => self.shrink = number(part).unwrap_or(self.shrink),
+            ("flex-basis", [part]) => self.basis = basis(part).unwrap_or(self.basis),
+            ("flex", [part]) if css::keyword(part).is_some_and(|k| k == "none") => *self = Item { grow: 0.0, shrink: 0.0, basis: None },
+            ("flex", [part]) if css::keyword(part).is_some_and(|k| k == "auto") => *self = Item { grow: 1.0, shrink: 1.0, basis: None },
+            ("flex", [part]) if css::keyword(part).is_some_and(|k| k == "initial") => *self = Item::default(),
+            ("flex", _) if !parts.is_empty() => {
+                // `<grow> [<shrink>] [<basis>]` in that order, or a basis
+                // alone; a grow factor without a basis makes it 0.
+                let mut item = Item { grow: 1.0, shrink: 1.0, basis: Some(0.0) };
+                let mut parts = parts.iter().peekable();
+                if let Some(grow) = parts.peek().and_then(|part| number(part)) {
+                    item.grow = grow;
+                    parts.next();
+                    if let Some(shrink) = parts.peek().and_then(|part| number(part)) {
+                        item.shrink = shrink;
+                        parts.next();
+                    }
+                }
+                if let Some(part) = parts.next() {
+                    let Some(basis) = basis(part) else { return };
+                    item.basis = basis;
+                }
+                if parts.next().is_none() {
+                    *self = item;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Lay out the children of a flex container, `style` being its own, and
+/// return the bottom of its content. Every element child and every run of
+/// text directly in it is an item, laid out as a block.
+///
+/// In a row, items start as wide as their `flex-basis` or else their text
+/// on one line, then grow into the room left by their `flex-grow` or shrink
+/// to fit by their `flex-shrink` (no narrower than their longest word);
+/// `justify-content` places them in what room remains. The row is as tall
+/// as its tallest item, found by laying each out once beforehand, which
+/// `align-items` places the others in. Rows do not wrap.
+///
+/// A column stacks its items like blocks; `align-items` either stretches
+/// them across it or shrinks them to their text and places them.
+pub(super) fn layout_flex(children: &[Node], ctx: &mut Ctx, y: f32, style: &Style) -> f32 {
+    ctx.style_children(style);
+    let Container { direction, justify, align } = style.flex;
+    let mut items: Vec<(usize, &Node, Style)> = children.iter().enumerate()
+        .filter_map(|(i, child)| {
+            let item = match child {
+                Node::Text(text) if parser::trim_whitespace(text).is_empty() => return None,
+                Node::Text(_) => style.inherited(),
+                Node::Element { tag, .. } if is_hidden(tag) => return None,
+                Node::Element { tag, attrs, .. } => element_style(tag, attrs, style, ctx),
+            };
+            Some((i, child, item))
+        })
+        .collect();
+    if direction == Direction::ColumnReverse {
+        items.reverse();
+    }
+    let available = ctx.width - style.indent - style.right;
+    // The style an item is laid out in: its container's, narrowed to the
+    // `width` of the item starting `x` into it.
+    let column = |x: f32, width: f32| Style { indent: style.indent + x, right: style.right + available - x - width, ..style.clone() };
+
+    if matches!(direction, Direction::Column | Direction::ColumnReverse) {
+        let mut y = y;
+        for (i, child, item) in &items {
+            let width = match align {
+                Align::Stretch => available,
+                _ => (content_widths(std::slice::from_ref(child), item, ctx).1 + item.horizontal_edges()).min(available),
+            };
+            let x = match align {
+                Align::Stretch | Align::Start => 0.0,
+                Align::Center => (available - width) / 2.0,
+                Align::End => available - width,
+            };
+            ctx.path.push(*i);
+            y = layout_node(child, ctx, y, &column(x, width));
+            y = ctx.end_line(y);
+            ctx.path.pop();
+        }
+        return y;
+    }
+
+    let mut sizes = Vec::new();
+    let mut mins = Vec::new();
+    for (_, child, item) in &items {
+        let (min, max) = content_widths(std::slice::from_ref(child), item, ctx);
+        sizes.push(item.item.basis.unwrap_or(max) + item.horizontal_edges());
+        mins.push(min + item.horizontal_edges());
+    }
+    let mut free = available - sizes.iter().sum::<f32>();
+    let grow: f32 = items.iter().map(|(_, _, item)| item.item.grow).sum();
+    let shrink: f32 = items.iter().zip(&sizes).map(|((_, _, item), size)| item.item.shrink * size).sum();
+    if free > 0.0 && grow > 0.0 {
+        for ((_, _, item), size) in items.iter().zip(&mut sizes) {
+            *size += free * item.item.grow / grow;
+        }
+        free = 0.0;
+    } else if free < 0.0 && shrink > 0.0 {
+        for (((_, _, item), size), min) in items.iter().zip(&mut sizes).zip(&mins) {
+            *size = (*size + free * item.item.shrink * *size / shrink).max(*min);
+        }
+        free = available - sizes.iter().sum::<f32>();
+    }
+    let free = free.max(0.0);
+    let count = items.len() as f32;
+    let (lead, gap) = match justify {
+        Justify::Start => (0.0, 0.0),
+        Justify::End => (free, 0.0),
+        Justify::Center => (free / 2.0, 0.0),
+        Justify::SpaceBetween if items.len() > 1 => (0.0, free / (count - 1.0)),
+        Justify::SpaceBetween => (0.0, 0.0),
+        Justify::SpaceAround => (free / count / 2.0, free / count),
+        Justify::SpaceEvenly => (free / (count + 1.0), free / (count + 1.0)),
+    };
+    let mut lefts = Vec::new();
+    let mut x = lead;
+    for size in &sizes {
+        // A reversed row runs from the right.
+        lefts.push(if direction == Direction::RowReverse { available - x - size } else { x });
+        x += size + gap;
+    }
+
+    // How tall each item is, and so the row; not needed to put every item
+    // at the top, nor while this row is itself only being measured.
+    let heights: Vec<f32> = if align == Align::Start || ctx.measuring {
+        Vec::new()
+    } else {
+        items.iter().zip(lefts.iter().zip(&sizes))
+            .map(|((_, child, _), (&x, &width))| {
+                let mut scratch = ctx.scratch();
+                let end = layout_node(child, &mut scratch, y, &column(x, width));
+                scratch.end_line(end) - y
+            })
+            .collect()
+    };
+    let line = heights.iter().copied().fold(0.0, f32::max);
+    let mut bottom = y + line;
+    for (n, ((i, child, _), (&x, &width))) in items.iter().zip(lefts.iter().zip(&sizes)).enumerate() {
+        let height = heights.get(n).copied().unwrap_or(line);
+        let top = match align {
+            Align::Stretch | Align::Start => y,
+            Align::Center => y + (line - height) / 2.0,
+            Align::End => y + line - height,
+        };
+        ctx.fill = (align == Align::Stretch && !heights.is_empty()).then_some(line);
+        ctx.path.push(*i);
+        let end = layout_node(child, ctx, top, &column(x, width));
+        ctx.path.pop();
+        ctx.fill = None;
+        bottom = bottom.max(ctx.end_line(end));
+    }
+    bottom
+}
@@ -0,0 +1,115 @@
+use super::{Ctx, Rect, Style};
+
+/// The side of the column a float keeps to.
+#[derive(Clone, Copy, PartialEq)]
+pub(super) enum Side {
+    Left,
+    Right,
+}
+
+/// Which floats an element with `clear` goes below.
+#[derive(Clone, Copy, PartialEq)]
+pub(super) enum Clear {
+    Left,
+    Right,
+    Both,
+}
+
+/// A floated element's margin box, which lines beside it are shortened to
+/// flow around.
+pub(super) struct Float {
+    pub(super) side: Side,
+    pub(super) rect: Rect,
+}
+
+impl Side {
+    /// `float: left | right | none`, as `Some(None)` for `none`.
+    pub(super) fn parse(keyword: Option<&str>) -> Option<Option<Side>> {
+        match keyword? {
+            "left" => Some(Some(Side::Left)),
+            "right" => Some(Some(Side::Right)),
+            "none" => Some(None),
+            _ => None,
+        }
+    }
+}
+
+impl Clear {
+    /// `clear: left | right | both | none`, as `Some(None)` for `none`.
+    pub(super) fn parse(keyword: Option<&str>) -> Option<Option<Clear>> {
+        match keyword? {
+            "left" => Some(Some(Clear::Left)),
+            "right" => Some(Some(Clear::Right)),
+            "both" => Some(Some(Clear::Both)),
+            "none" => Some(None),
+            _ => None,
+        }
+    }
+}
+
+impl Ctx<'_> {
+    /// Left and right edges of a line from `top` to `bottom` in a column
+    /// with `style`: the column's, narrowed by the floats in it beside the
+    /// line.
+    pub(super) fn line_edges(&self, top: f32, bottom: f32, style: &Style) -> (f32, f32) {
+        let (column_left, column_right) = (self.pad + style.indent, self.pad + self.width - style.right);
+        let (mut left, mut right) = (column_left, column_right);
+        for Float { side, rect } in self.floats_in(column_left, column_right) {
+            if rect.y < bottom && rect.y + rect.height > top {
+                match side {
+                    Side::Left => left = left.max(rect.x + rect.width),
+                    Side::Right => right = right.min(rect.x),
+                }
+            }
+        }
+        (left, right)
+    }
+
+    /// Where a float `width` wide (margins included) goes, at `y` or below:
+    /// against the floats on its side, or below the floats beside it if it
+    /// does not fit between them. Returns its left edge and top.
+    pub(super) fn place_float(&self, side: Side, width: f32, y: f32, style: &Style) -> (f32, f32) {
+        let mut y = y;
+        loop {
+            let (left, right) = self.line_edges(y, y + 1.0, style);
+            let x = match side {
+                Side::Left => left,
+                Side::Right => right - width,
+            };
+            // The next float beside it to end, if it does not fit.
+            let below = if width > right - left { self.float_end_below(y, style) } else { None };
+            match below {
+                Some(bottom) => y = bottom,
+                _ => return (x, y),
+            }
+        }
+    }
+
+    /// The first bottom below `y` of the floats in the column of `style`.
+    pub(super) fn float_end_below(&self, y: f32, style: &Style) -> Option<f32> {
+        self.floats_in(self.pad + style.indent, self.pad + self.width - style.right)
+            .map(|float| float.rect.y + float.rect.height)
+            .filter(|&bottom| bottom > y)
+            .reduce(f32::min)
+    }
+
+    /// `y`, or below the floats in the column of `style` that `clear` names
+    /// if they reach further.
+    pub(super) fn clear(&self, clear: Option<Clear>, y: f32, style: &Style) -> f32 {
+        let Some(clear) = clear else { return y };
+        self.floats_in(self.pad + style.indent, self.pad + self.width - style.right)
+            .filter(|float| match clear {
+                Clear::Left => float.side == Side::Left,
+                Clear::Right => float.side == Side::Right,
+                Clear::Both => true,
+            })
+            .map(|float| float.rect.y + float.rect.height)
+            .fold(y, f32::max)
+    }
+
+    /// The floats overlapping a column from `left` to `right`; those of
+    /// other columns (a neighbouring table cell, say) leave it alone.
+    fn floats_in(&self, left: f32, right: f32) -> impl Iterator<Item = &Float> {
+        self.floats.iter().filter(move |float| float.rect.x < right && float.rect.x + float.rect.width > left)
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, RwLock};
+
+/// Letters a hyphenated word keeps before its first hyphen and after its
+/// last, as TeX does for English.
+const LEFT_MIN: usize = 2;
+const RIGHT_MIN: usize = 3;
+
+pub(crate) type Languages = HashMap<String, Arc<Hyphenator>>;
+
+static LANGUAGES: LazyLock<RwLock<Arc<Languages>>> = LazyLock::new(Default::default);
+
+/// Hyphenate text in language `lang` (as a `lang` attribute names it: `en`
+/// for all of its regions, `en-gb` for one) by `patterns` from now on, or
+/// (`None`) no longer. `patterns` are Liang's, as TeX's hyphenation pattern
+/// files (`hyph-en-us.pat.txt`) write them: whitespace-separated letters
+/// with the digits between them that allow (odd) or forbid (even) a hyphen
+/// there, `.` marking the start or end of a word, and `%` comments. Words
+/// written with hyphens (`ta-ble`) are exceptions, hyphenated only there.
+pub fn set_hyphenation(lang: &str, patterns: Option<&str>) {
+    let mut languages = LANGUAGES.write().unwrap_or_else(|e| e.into_inner());
+    let lang = lang.trim().to_ascii_lowercase();
+    match patterns {
+        Some(patterns) => Arc::make_mut(&mut languages).insert(lang, Arc::new(Hyphenator::new(patterns))),
+        None => Arc::make_mut(&mut languages).remove(&lang),
+    };
+}
+
+/// The hyphenation patterns of each language now. A layout takes them
+/// once, as it does the element handlers.
+pub(crate) fn languages() -> Arc<Languages> {
+    LANGUAGES.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// The hyphenator for (lower-cased) `lang`, or failing that for its
+/// language without the region.
+pub(crate) fn for_lang<'a>(languages: &'a Languages, lang: &str) -> Option<&'a Hyphenator> {
+    languages.get(lang)
+        .or_else(|| languages.get(lang.split('-').next()?))
+        .map(Arc::as_ref)
+}
+
+/// One language's hyphenation patterns and exceptions.
+pub(crate) struct Hyphenator {
+    /// The letters of each pattern, and the digit before, between and
+    /// after them (0 where none is written).
+    patterns: HashMap<String, Vec<u8>>,
+    /// Each exception's letters, and the positions of its hyphens in them.
+    exceptions: HashMap<String, Vec<usize>>,
+    /// Letters in the longest pattern.
+    longest: usize,
+}
+
+impl Hyphenator {
+    fn new(source: &str) -> Self {
+        let mut hyphenator = Hyphenator { patterns: HashMap::new(), exceptions: HashMap::new(), longest: 0 };
+        let words = source.lines().flat_map(|line| line.split('%').next().unwrap_or_default().split_whitespace());
+        for word in words {
+            if word.contains('-') {
+                let mut letters = String::new();
+                let mut points = Vec::new();
+                for ch in word.chars() {
+                    if ch == '-' {
+                        points.push(letters.chars().count());
+                    } else {
+                        letters.push(ch.to_lowercase().next().unwrap_or(ch));
+                    }
+                }
+                hyphenator.exceptions.insert(letters, points);
+                continue;
+            }
+            let mut letters = String::new();
+            let mut values = vec![0];
+            for ch in word.chars() {
+                match ch.to_digit(10) {
+                    Some(digit) => {
+                        let last = values.len() - 1;
+                        values[last] = digit as u8;
+                    }
+                    None => {
+                        letters.push(ch);
+                        values.push(0);
+                    }
+                }
+            }
+            hyphenator.longest = hyphenator.longest.max(values.len() - 1);
+            hyphenator.patterns.insert(letters, values);
+        }
+        hyphenator
+    }
+
+    /// Where `word` may be hyphenated: the indices of the letters a hyphen
+    /// may go before.
+    pub(crate) fn points(&self, word: &[char]) -> Vec<usize> {
+        let lower: Vec<char> = word.iter().map(|ch| ch.to_lowercase().next().unwrap_or(*ch)).collect();
+        if let Some(points) = self.exceptions.get(&lower.iter().collect::<String>()) {
+            return points.clone();
+        }
+        if lower.len() < LEFT_MIN + RIGHT_MIN {
+            return Vec::new();
+        }
+        let dotted: Vec<char> = std::iter::once('.').chain(lower.iter().copied()).chain(std::iter::once('.')).collect();
+        // `values[i]` goes before `dotted[i]`; each pattern matching the
+        // word raises those under it to its own digits.
+        let mut values = vec![0; dotted.len() + 1];
+        for start in 0..dotted.len() {
+            for end in start + 1..=(start + self.longest).min(dotted.len()) {
+                let Some(pattern) = self.patterns.get(&dotted[start..end].iter().collect::<String>()) else { continue };
+                for (value, &digit) in values[start..].iter_mut().zip(pattern) {
+                    *value = (*value).max(digit);
+                }
+            }
+        }
+        (LEFT_MIN..=lower.len() - RIGHT_MIN).filter(|&i| values[i + 1] % 2 == 1).collect()
+    }
+}
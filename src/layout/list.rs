@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::parser::dom::Node;
+
+static OUTLINE_NUMBERING: AtomicBool = AtomicBool::new(false);
+
+/// Number the items of ordered lists nested in ordered list items after
+/// the items they are in (`1.`, `1.1.`, `1.1.1.`) from now on, or (`false`,
+/// the default) on their own from 1 as browsers do.
+pub fn set_outline_numbering(on: bool) {
+    OUTLINE_NUMBERING.store(on, Ordering::Relaxed);
+}
+
+/// Whether ordered lists are numbered as an outline now. A layout takes
+/// this once, as it does the element handlers.
+pub(crate) fn outline_numbering() -> bool {
+    OUTLINE_NUMBERING.load(Ordering::Relaxed)
+}
+
+/// The numerals an ordered list's `type` attribute asks for.
+#[derive(Clone, Copy, PartialEq)]
+enum Numerals {
+    Decimal,
+    LowerAlpha,
+    UpperAlpha,
+    LowerRoman,
+    UpperRoman,
+}
+
+/// The markers of one list's items, in order: a bullet for its nesting
+/// depth in a `ul`, and in an `ol` a number counting from `start`, up or
+/// (`reversed`) down, in the numerals of its `type`.
+pub(crate) struct ListMarkers {
+    depth: usize,
+    /// `None` for an unordered list.
+    numerals: Option<Numerals>,
+    next: i64,
+    step: i64,
+    /// The numbers of the ordered list items the list is in, each followed
+    /// by a dot, with outline numbering.
+    prefix: String,
+}
+
+impl ListMarkers {
+    /// The markers for the items of the `list_tag` list with `attrs` and
+    /// children `items`, nested `depth` lists deep (1 for the outermost).
+    /// `outer` holds the numbers of the ordered list items it is in,
+    /// outermost first, to number an ordered list after them.
+    pub(crate) fn new(list_tag: &str, attrs: &HashMap<String, String>, items: &[Node], depth: usize, outer: &[String]) -> Self {
+        if list_tag != "ol" {
+            return ListMarkers { depth, numerals: None, next: 1, step: 1, prefix: String::new() };
+        }
+        let numerals = match attrs.get("type").map(|t| t.trim()) {
+            Some("a") => Numerals::LowerAlpha,
+            Some("A") => Numerals::UpperAlpha,
+            Some("i") => Numerals::LowerRoman,
+            Some("I") => Numerals::UpperRoman,
+            _ => Numerals::Decimal,
+        };
+        let reversed = attrs.contains_key("reversed");
+        // A reversed list counts down to 1 unless told where to start.
+        let count = items.iter().filter(|item| matches!(item, Node::Element { tag, .. } if tag == "li")).count();
+        let start = attrs.get("start")
+            .and_then(|start| start.trim().parse().ok())
+            .unwrap_or(if reversed { count as i64 } else { 1 });
+        let prefix = outer.iter().map(|number| format!("{number}.")).collect();
+        ListMarkers { depth, numerals: Some(numerals), next: start, step: if reversed { -1 } else { 1 }, prefix }
+    }
+
+    /// The next item's marker, and in an ordered list its number, which
+    /// the ordered lists inside the item are numbered after.
+    pub(crate) fn next_marker(&mut self) -> (String, Option<String>) {
+        let Some(numerals) = self.numerals else {
+            // Different bullet symbol per nesting depth.
+            let bullet = match self.depth {
+                1 => "•",
+                2 => "◦",
+                _ => "▪",
+            };
+            return (bullet.to_string(), None);
+        };
+        let number = format_number(self.next, numerals);
+        self.next = self.next.saturating_add(self.step);
+        (format!("{}{number}.", self.prefix), Some(number))
+    }
+}
+
+/// `number` in `numerals`, or in decimal where they have no way to write it
+/// (zero and negative numbers, and roman numerals past 3999).
+fn format_number(number: i64, numerals: Numerals) -> String {
+    match numerals {
+        Numerals::LowerAlpha if number > 0 => alphabetic(number),
+        Numerals::UpperAlpha if number > 0 => alphabetic(number).to_uppercase(),
+        Numerals::LowerRoman if (1..4000).contains(&number) => roman(number),
+        Numerals::UpperRoman if (1..4000).contains(&number) => roman(number).to_uppercase(),
+        _ => number.to_string(),
+    }
+}
+
+/// `a` to `z`, then `aa`, `ab`, … as a spreadsheet names its columns.
+fn alphabetic(mut number: i64) -> String {
+    let mut letters = Vec::new();
+    while number > 0 {
+        number -= 1;
+        letters.push(b'a' + (number % 26) as u8);
+        number /= 26;
+    }
+    letters.iter().rev().map(|&b| b as char).collect()
+}
+
+/// Lowercase roman numerals, subtractive (`iv`, `xc`), for 1 to 3999.
+fn roman(mut number: i64) -> String {
+    const NUMERALS: [(i64, &str); 13] = [
+        (1000, "m"), (900, "cm"), (500, "d"), (400, "cd"), (100, "c"), (90, "xc"),
+        (50, "l"), (40, "xl"), (10, "x"), (9, "ix"), (5, "v"), (4, "iv"), (1, "i"),
+    ];
+    let mut out = String::new();
+    for (value, numeral) in NUMERALS {
+        while number >= value {
+            out.push_str(numeral);
+            number -= value;
+        }
+    }
+    out
+}
@@ -1,7 +1,31 @@
+mod custom;
+mod flex;
+mod float;
+mod hyphenate;
+mod list;
+mod position;
+#[cfg(feature = "serde")]
+mod serialize;
+mod table;
+mod text;
+
+pub use custom::{register_element, unregister_element, CustomElement, CustomLayout, ElementHandler};
+pub use text::{Estimate, TextIndex, TextMeasurer, SOFT_HYPHEN, WORD_JOINER, ZERO_WIDTH_SPACE};
+pub use hyphenate::set_hyphenation;
+pub use list::set_outline_numbering;
+pub(crate) use list::{outline_numbering, ListMarkers};
+
 use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::Instant;
 
-use crate::parser::dom::Node;
+use image::RgbaImage;
+
+use crate::css;
+use crate::net::Url;
+use crate::parser::{self, dom::Node};
 
 // ── Public types ─────────────────────────────────────────────────────────────
 
@@ -14,6 +38,9 @@ pub struct LayoutBox {
     pub cmd: PaintCmd,
 }
 
+/// What a box paints. Colours are `0xRRGGBB` with the colour's
+/// transparency in the top byte: 0 (as in plain `0xRRGGBB`) is opaque, 255
+/// invisible (see [`css::alpha`]).
 #[derive(Debug)]
 pub enum PaintCmd {
     Text {
@@ -21,6 +48,8 @@ pub enum PaintCmd {
         font_size: f32,
         bold: bool,
         italic: bool,
+        /// Drawn in the monospace face rather than the proportional one.
+        monospace: bool,
         color: u32,
         underline: bool,
     },
@@ -31,13 +60,574 @@ pub enum PaintCmd {
         color: u32,
     },
     Image {
-        /// Raw RGBA8 pixel data.
-        data: Vec<u8>,
+        /// Decoded pixels, shared with the document's image cache so
+        /// relayout does not copy them.
+        data: Arc<RgbaImage>,
         img_width: u32,
         img_height: u32,
     },
 }
 
+/// Result of laying out a document: paint boxes plus interactive regions.
+#[derive(Debug, Default)]
+pub struct Page {
+    pub boxes: Vec<LayoutBox>,
+    /// Hit-testable areas, innermost element first.
+    pub regions: Vec<HitRegion>,
+    /// Element extents keyed by `id` (and `<a name>`), for fragment navigation.
+    pub anchors: HashMap<String, Rect>,
+    /// Box index ranges of paragraph-like blocks (p, headings, list items).
+    pub blocks: Vec<Range<usize>>,
+    /// Local files the layout read (or tried to read), e.g. image sources.
+    pub assets: Vec<PathBuf>,
+    /// Images missing from the `RemoteImages` passed to layout; each was
+    /// laid out as a placeholder box.
+    pub pending_images: Vec<PendingImage>,
+    /// Headings in document order, for the outline.
+    pub headings: Vec<Heading>,
+    /// Text inputs in document order, indexed by [`HitKind::Input`].
+    pub inputs: Vec<InputField>,
+    /// Checkboxes and radio buttons in document order, indexed by
+    /// [`HitKind::Toggle`].
+    pub toggles: Vec<Toggle>,
+    /// Buttons in document order, indexed by [`HitKind::Button`].
+    pub buttons: Vec<Button>,
+    /// Dropdowns in document order, indexed by [`HitKind::Select`].
+    pub selects: Vec<Select>,
+    /// `<form>` elements in document order.
+    pub forms: Vec<Form>,
+    /// The `tabindex` of each link or control that has a valid one, by
+    /// index into `regions`.
+    pub tab_indices: HashMap<usize, i32>,
+    /// Index into `regions` of the first link or control with `autofocus`.
+    pub autofocus: Option<usize>,
+    /// Only the top of the document: layout ran out of time (see
+    /// [`layout_until`]), or the rest had not been downloaded yet.
+    pub partial: bool,
+    /// Vertical extents of `boxes`, for [`Page::visible`].
+    extents: Extents,
+    /// Every form control in document order, which is not always the
+    /// order layout placed them in.
+    controls: Vec<HitKind>,
+}
+
+/// Where a page's boxes lie vertically. Layout emits boxes roughly top to
+/// bottom, so both lists are sorted and can be binary-searched, without
+/// reordering the boxes (their order is their paint order).
+#[derive(Debug, Default)]
+struct Extents {
+    /// Lowest bottom edge among the boxes up to and including each one.
+    reach: Vec<f32>,
+    /// Highest top edge among each box and those after it.
+    low: Vec<f32>,
+}
+
+impl Extents {
+    fn new(boxes: &[LayoutBox]) -> Self {
+        let reach = boxes.iter()
+            .scan(f32::NEG_INFINITY, |reach, b| {
+                *reach = reach.max(b.y + b.height);
+                Some(*reach)
+            })
+            .collect();
+        let mut low: Vec<f32> = boxes.iter().rev()
+            .scan(f32::INFINITY, |low, b| {
+                *low = low.min(b.y);
+                Some(*low)
+            })
+            .collect();
+        low.reverse();
+        Extents { reach, low }
+    }
+}
+
+/// A heading, as listed in the document outline.
+#[derive(Debug, Clone)]
+pub struct Heading {
+    /// 1 for `<h1>`, 2 for `<h2>`, ...
+    pub level: u8,
+    pub text: String,
+    /// Its `id`, if it has one: the key of its entry in `Page::anchors`.
+    pub id: Option<String>,
+    pub rect: Rect,
+}
+
+/// An editable text field: single-line (`<input type=text>`) or
+/// multi-line (`<textarea>`).
+#[derive(Debug, Clone)]
+pub struct InputField {
+    /// The field's border box.
+    pub rect: Rect,
+    /// Index into `Page::boxes` of the first of `rows` text boxes, one per
+    /// line shown, showing its value (or its placeholder while the value is
+    /// empty).
+    pub text_box: usize,
+    pub value: String,
+    pub placeholder: String,
+    /// Its `name` attribute.
+    pub name: Option<String>,
+    /// Colour of its value; the placeholder is grey.
+    pub color: u32,
+    /// Whether it is a `<textarea>`, whose value may hold line breaks.
+    pub multiline: bool,
+    /// `type=password`: its value is shown as one bullet per character.
+    pub masked: bool,
+    /// Lines shown at once.
+    pub rows: usize,
+    /// Characters per line before the value wraps; `None` for no wrapping.
+    pub cols: Option<usize>,
+    /// `wrap=hard`: the value is submitted with line breaks where it wraps.
+    pub hard_wrap: bool,
+    /// Index into [`InputField::lines`] of the first line shown.
+    pub scroll: usize,
+    /// Index into `Page::forms` of the form it is in.
+    pub form: Option<usize>,
+    /// `disabled`: greyed out, and cannot be focused, edited or submitted.
+    pub disabled: bool,
+    /// `readonly`: can be focused and its text selected, but not edited.
+    pub readonly: bool,
+}
+
+/// A checkbox or radio button (`<input type=checkbox|radio>`).
+#[derive(Debug, Clone)]
+pub struct Toggle {
+    pub kind: ToggleKind,
+    pub rect: Rect,
+    /// Index into `Page::boxes` of the mark drawn while it is checked.
+    pub mark_box: usize,
+    pub checked: bool,
+    /// Its `name` attribute; radio buttons sharing one form a group.
+    pub name: Option<String>,
+    /// Its `value` attribute, `"on"` by default.
+    pub value: String,
+    /// Index into `Page::forms` of the form it is in.
+    pub form: Option<usize>,
+    /// `disabled`: greyed out, and cannot be focused, toggled or submitted.
+    pub disabled: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToggleKind {
+    Checkbox,
+    Radio,
+}
+
+/// A `<button>`, or an `<input type=submit|reset|button>`.
+#[derive(Debug, Clone)]
+pub struct Button {
+    pub kind: ButtonKind,
+    pub rect: Rect,
+    /// Index into `Page::boxes` of its face, recoloured by
+    /// [`Page::set_button_state`].
+    pub face_box: usize,
+    /// The text shown on it.
+    pub label: String,
+    pub name: Option<String>,
+    pub value: String,
+    /// Index into `Page::forms` of the form it is in.
+    pub form: Option<usize>,
+    /// `disabled`: greyed out, and cannot be focused or pressed.
+    pub disabled: bool,
+}
+
+/// What a button does when pressed, from its `type` attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonKind {
+    /// Submits its form (the default for `<button>`).
+    Submit,
+    /// Resets its form's controls.
+    Reset,
+    /// Nothing by itself.
+    Button,
+}
+
+/// How a button is drawn: its face darkens under the pointer, and more so
+/// while held down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonState {
+    Normal,
+    Hover,
+    Pressed,
+}
+
+/// A `<select>` dropdown, showing its chosen option while collapsed.
+#[derive(Debug, Clone)]
+pub struct Select {
+    pub rect: Rect,
+    /// Index into `Page::boxes` of the text box showing the chosen option.
+    pub text_box: usize,
+    /// Its `<option>`s, those in `<optgroup>`s included, in order.
+    pub options: Vec<SelectOption>,
+    /// Index into `options` of the chosen one (`None` without options).
+    pub selected: Option<usize>,
+    pub name: Option<String>,
+    /// Index into `Page::forms` of the form it is in.
+    pub form: Option<usize>,
+    /// `disabled`: greyed out, and cannot be focused, opened or submitted.
+    pub disabled: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct SelectOption {
+    pub label: String,
+    /// Its `value` attribute, or else its label.
+    pub value: String,
+}
+
+/// A `<form>`: where its controls' values go when it is submitted.
+#[derive(Debug, Clone)]
+pub struct Form {
+    /// Its `action` attribute, unresolved; empty for the document itself.
+    pub action: String,
+    /// Its `method` attribute in lower case, `"get"` by default.
+    pub method: String,
+}
+
+/// Where an element ended up: the back-reference from layout to the DOM
+/// (see [`layout_elements`]).
+#[derive(Debug)]
+pub struct ElementBox {
+    /// Child indices leading from the laid-out nodes to the element (see
+    /// [`node_at`](crate::parser::dom::node_at)).
+    pub path: Vec<usize>,
+    /// Bounding rectangle of everything the element painted.
+    pub rect: Rect,
+    /// Its paint boxes, descendants' included, as indices into `Page::boxes`
+    /// of the same layout.
+    pub boxes: Range<usize>,
+    /// Style its content was laid out with.
+    pub style: ComputedStyle,
+}
+
+/// The style properties layout resolves for an element.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComputedStyle {
+    pub font_size: f32,
+    pub bold: bool,
+    pub italic: bool,
+    pub color: u32,
+    pub underline: bool,
+    /// Left indent relative to the content column (list nesting).
+    pub indent: f32,
+    /// Margin, padding and border widths: top, right, bottom and left.
+    pub margin: [f32; 4],
+    pub padding: [f32; 4],
+    pub border: [f32; 4],
+}
+
+/// Decoded images available to layout, keyed by absolute URL (without
+/// fragment). Layout never decodes images itself, local or remote; those
+/// missing here become [`PendingImage`]s.
+pub type RemoteImages = HashMap<String, Arc<RgbaImage>>;
+
+/// An image laid out as a placeholder because its pixels were not in the
+/// `RemoteImages` passed to layout.
+#[derive(Debug, Clone)]
+pub struct PendingImage {
+    pub url: Url,
+    /// Index into `Page::boxes` of the placeholder.
+    pub box_index: usize,
+    /// `loading="lazy"`: fetch it only once it is scrolled near.
+    pub lazy: bool,
+}
+
+/// An axis-aligned rectangle in document coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+/// A rectangle tied to an element's interactive behaviour.
+#[derive(Debug)]
+pub struct HitRegion {
+    pub rect: Rect,
+    pub kind: HitKind,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum HitKind {
+    /// Tooltip text from a `title` attribute.
+    Title(String),
+    /// Link target from an `<a href>`.
+    Link(String),
+    /// A text input, as an index into `Page::inputs`.
+    Input(usize),
+    /// A checkbox or radio button, as an index into `Page::toggles`.
+    Toggle(usize),
+    /// A button, as an index into `Page::buttons`.
+    Button(usize),
+    /// A dropdown, as an index into `Page::selects`.
+    Select(usize),
+    /// A `<label>`, as an index into `Page::regions` of the control it
+    /// labels.
+    Label(usize),
+}
+
+impl Page {
+    /// Whether the element of a region of kind `kind` can be used: it is
+    /// not a `disabled` control.
+    pub fn is_enabled(&self, kind: &HitKind) -> bool {
+        match *kind {
+            HitKind::Input(i) => !self.inputs[i].disabled,
+            HitKind::Toggle(i) => !self.toggles[i].disabled,
+            HitKind::Button(i) => !self.buttons[i].disabled,
+            HitKind::Select(i) => !self.selects[i].disabled,
+            HitKind::Label(control) => self.is_enabled(&self.regions[control].kind),
+            HitKind::Title(_) | HitKind::Link(_) => true,
+        }
+    }
+}
+
+impl HitKind {
+    /// Whether this is a form control's region.
+    pub fn is_control(&self) -> bool {
+        matches!(self, HitKind::Input(_) | HitKind::Toggle(_) | HitKind::Button(_) | HitKind::Select(_))
+    }
+}
+
+impl Page {
+    /// Height of the document: the bottom of its lowest box plus the page
+    /// margin.
+    pub fn height(&self) -> f32 {
+        self.bottom() + PAGE_PAD
+    }
+
+    /// Bottom edge of the lowest box, or 0 on an empty page.
+    pub fn bottom(&self) -> f32 {
+        match self.extents.reach.last() {
+            Some(&reach) if self.extents.reach.len() == self.boxes.len() => reach.max(0.0),
+            _ => self.boxes.iter().map(|b| b.y + b.height).fold(0.0, f32::max),
+        }
+    }
+
+    /// Indices of the boxes that may overlap the band from `top` to
+    /// `bottom`: none before the range or after it does, though some inside
+    /// it may not either.
+    pub fn visible(&self, top: f32, bottom: f32) -> Range<usize> {
+        let Extents { reach, low } = &self.extents;
+        if reach.len() != self.boxes.len() {
+            return 0..self.boxes.len();
+        }
+        let start = reach.partition_point(|&r| r < top);
+        let end = low.partition_point(|&l| l <= bottom);
+        start..end.max(start)
+    }
+
+    /// Innermost region at document point `(x, y)` matching `pred`.
+    pub fn region_at(&self, x: f32, y: f32, pred: impl Fn(&HitKind) -> bool) -> Option<&HitRegion> {
+        self.regions.iter().find(|r| pred(&r.kind) && r.rect.contains(x, y))
+    }
+
+    /// Change the value of text input `input`, updating the text boxes that
+    /// show it in place (no relayout needed: the field keeps its size).
+    pub fn set_input_value(&mut self, input: usize, value: String) {
+        let field = &mut self.inputs[input];
+        field.value = value;
+        field.scroll = field.scroll.min(field.max_scroll());
+        refresh_field(&mut self.boxes, field);
+    }
+
+    /// Scroll text input `input` to show its lines from `line` on (as far as
+    /// there are lines to show).
+    pub fn scroll_input(&mut self, input: usize, line: usize) {
+        let field = &mut self.inputs[input];
+        field.scroll = line.min(field.max_scroll());
+        refresh_field(&mut self.boxes, field);
+    }
+}
+
+impl Page {
+    /// Check or uncheck `toggle`, redrawing its mark in place. Checking a
+    /// radio button unchecks the others in its group.
+    pub fn set_checked(&mut self, toggle: usize, checked: bool) {
+        set_checked(&mut self.boxes, &mut self.toggles, toggle, checked);
+    }
+
+    /// Draw `button` in `state`, recolouring its face in place.
+    pub fn set_button_state(&mut self, button: usize, state: ButtonState) {
+        let face = self.buttons[button].face_box;
+        if let PaintCmd::FillRect { color } = &mut self.boxes[face].cmd {
+            *color = match state {
+                ButtonState::Normal => BUTTON_FACE_COLOR,
+                ButtonState::Hover => 0xE5E5E5,
+                ButtonState::Pressed => 0xD0D0D0,
+            };
+        }
+    }
+
+    /// Choose `option` of dropdown `select`, showing its label in place.
+    pub fn set_selected(&mut self, select: usize, option: usize) {
+        let select = &mut self.selects[select];
+        select.selected = Some(option);
+        if let PaintCmd::Text { content, .. } = &mut self.boxes[select.text_box].cmd {
+            content.clone_from(&select.options[option].label);
+        }
+    }
+
+    /// `(name, value)` of every named text input and dropdown and every
+    /// checked named checkbox or radio button, in document order: what
+    /// submitting a form of all the page's controls would send.
+    pub fn form_values(&self) -> Vec<(String, String)> {
+        self.values(|_| true, None)
+    }
+
+    /// What submitting `form` with `submitter` (an index into
+    /// `Page::buttons`, if a button did it) sends: the
+    /// [`form_values`](Page::form_values) of its controls, and the
+    /// submitter's `name` and `value` if it is named.
+    pub fn form_data(&self, form: usize, submitter: Option<usize>) -> Vec<(String, String)> {
+        self.values(|f| f == Some(form), submitter)
+    }
+
+    /// The values of the controls in forms matching `in_form`, and of
+    /// button `submitter`.
+    fn values(&self, in_form: impl Fn(Option<usize>) -> bool, submitter: Option<usize>) -> Vec<(String, String)> {
+        let mut values = Vec::new();
+        for kind in &self.controls {
+            let (name, value) = match *kind {
+                HitKind::Input(i) => {
+                    let input = &self.inputs[i];
+                    if input.disabled || !in_form(input.form) {
+                        continue;
+                    }
+                    (&input.name, input.submitted_value())
+                }
+                HitKind::Toggle(i) => {
+                    let toggle = &self.toggles[i];
+                    if !toggle.checked || toggle.disabled || !in_form(toggle.form) {
+                        continue;
+                    }
+                    (&toggle.name, toggle.value.clone())
+                }
+                HitKind::Select(i) => {
+                    let select = &self.selects[i];
+                    let Some(option) = select.selected.filter(|_| !select.disabled && in_form(select.form)) else { continue };
+                    (&select.name, select.options[option].value.clone())
+                }
+                HitKind::Button(i) if submitter == Some(i) => (&self.buttons[i].name, self.buttons[i].value.clone()),
+                _ => continue,
+            };
+            if let Some(name) = name {
+                values.push((name.clone(), value));
+            }
+        }
+        values
+    }
+}
+
+/// [`Page::set_checked`], also used while the page is being laid out.
+fn set_checked(boxes: &mut [LayoutBox], toggles: &mut [Toggle], toggle: usize, checked: bool) {
+    if checked && toggles[toggle].kind == ToggleKind::Radio {
+        if let Some(name) = toggles[toggle].name.clone() {
+            for i in 0..toggles.len() {
+                let other = &toggles[i];
+                let grouped = other.kind == ToggleKind::Radio && other.name.as_ref() == Some(&name) && other.form == toggles[toggle].form;
+                if i != toggle && grouped {
+                    set_checked(boxes, toggles, i, false);
+                }
+            }
+        }
+    }
+    let toggle = &mut toggles[toggle];
+    toggle.checked = checked;
+    if let PaintCmd::FillRect { color } = &mut boxes[toggle.mark_box].cmd {
+        *color = match (checked, toggle.disabled) {
+            (true, false) => CHECK_COLOR,
+            (true, true) => DISABLED_CHECK_COLOR,
+            (false, false) => 0xFFFFFF,
+            (false, true) => DISABLED_FACE_COLOR,
+        };
+    }
+}
+
+impl InputField {
+    /// Its value broken into the lines it shows, as character ranges
+    /// without the line breaks: at each newline, and (with `cols`) at the
+    /// last break opportunity (after a space, soft hyphen or zero-width
+    /// space, or between ideographs) that
+    /// keeps a line within `cols` columns, or at `cols` if there is none.
+    /// Wide characters take two columns.
+    pub fn lines(&self) -> Vec<Range<usize>> {
+        let mut lines = Vec::new();
+        let mut base = 0;
+        for text in self.value.split('\n') {
+            let chars: Vec<char> = text.chars().collect();
+            let mut from = 0;
+            while let Some(cols) = self.cols.filter(|&cols| cols > 0) {
+                let mut width = 0;
+                let mut limit = from;
+                while limit < chars.len() && width + text::columns(chars[limit]) <= cols {
+                    width += text::columns(chars[limit]);
+                    limit += 1;
+                }
+                // A character wider than the whole line still gets one.
+                let limit = limit.max(from + 1);
+                if limit >= chars.len() {
+                    break;
+                }
+                let at = (from + 1..=limit).rev().find(|&i| text::can_break_between(chars[i - 1], chars[i])).unwrap_or(limit);
+                lines.push(base + from..base + at);
+                from = at;
+            }
+            lines.push(base + from..base + chars.len());
+            base += chars.len() + 1;
+        }
+        lines
+    }
+
+    /// The value as a form would submit it: with `hard_wrap`, a line break
+    /// wherever it wraps.
+    pub fn submitted_value(&self) -> String {
+        if !self.hard_wrap {
+            return self.value.clone();
+        }
+        let chars: Vec<char> = self.value.chars().collect();
+        let lines: Vec<String> = self.lines().into_iter().map(|line| chars[line].iter().collect()).collect();
+        lines.join("\n")
+    }
+
+    /// The furthest `scroll` can go: the last line at the bottom.
+    fn max_scroll(&self) -> usize {
+        self.lines().len().saturating_sub(self.rows)
+    }
+}
+
+/// Show `field`'s lines from its `scroll` on in its text boxes, or its
+/// placeholder in grey while it is empty.
+fn refresh_field(boxes: &mut [LayoutBox], field: &InputField) {
+    let chars: Vec<char> = field.value.chars().collect();
+    let lines = field.lines();
+    for row in 0..field.rows {
+        let (shown, shown_color) = match lines.get(field.scroll + row) {
+            _ if field.value.is_empty() && row == 0 => (field.placeholder.clone(), PLACEHOLDER_COLOR),
+            Some(line) if field.masked => (MASK.to_string().repeat(line.len()), field.color),
+            Some(line) => {
+                let mut shown: String = chars[line.clone()].iter().collect();
+                // A line that wraps at a soft hyphen ends in a visible one.
+                if shown.ends_with(SOFT_HYPHEN) && lines.get(field.scroll + row + 1).is_some_and(|next| next.start == line.end) {
+                    shown.pop();
+                    shown.push('-');
+                }
+                (shown, field.color)
+            }
+            None => (String::new(), field.color),
+        };
+        if let PaintCmd::Text { content, color, .. } = &mut boxes[field.text_box + row].cmd {
+            *content = shown;
+            *color = shown_color;
+        }
+    }
+}
+
 // ── Internal style state ──────────────────────────────────────────────────────
 
 #[derive(Clone)]
@@ -47,24 +637,1045 @@ struct Style {
     italic: bool,
     color: u32,
     underline: bool,
-    /// Extra left indent relative to the page margin (for list nesting).
+    /// Extra left indent relative to the page margin (for list nesting,
+    /// margins and padding).
     indent: f32,
+    /// Extra right inset relative to the page margin (margins and padding).
+    right: f32,
+    /// How many lists deep the content is.
+    lists: usize,
+    /// Where lines sit between the edges of their column.
+    text_align: TextAlign,
+    /// Drawn in the monospace face.
+    monospace: bool,
+    white_space: WhiteSpace,
+    tab_size: TabSize,
+    /// The element's own margins, padding and borders: top, right, bottom
+    /// and left. Unlike the rest, these are not inherited.
+    margin: [f32; 4],
+    padding: [f32; 4],
+    border: [Border; 4],
+    /// `display`, if given; otherwise the tag decides.
+    display: Option<Display>,
+    /// How the element lays out its items if it is a flex container, and
+    /// how it grows and shrinks if it is a flex item.
+    flex: flex::Container,
+    item: flex::Item,
+    /// Painted behind the element's border box.
+    background: Option<u32>,
+    float: Option<float::Side>,
+    clear: Option<float::Clear>,
+    position: position::Position,
+    /// `top`, `right`, `bottom` and `left`; `None` for `auto`.
+    offsets: [Option<f32>; 4],
+    /// Content past the element's box is hidden (`overflow` other than
+    /// `visible`).
+    overflow_hidden: bool,
+    /// `text-overflow: ellipsis`: with the overflow hidden, lines too long
+    /// for the element end in "…" where they are cut.
+    ellipsis: bool,
+}
+
+/// `text-align`.
+#[derive(Clone, Copy, Default, PartialEq)]
+enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    /// Every line but a paragraph's last stretched to both edges.
+    Justify,
+}
+
+impl TextAlign {
+    /// `left`, `center`, `right` or `justify`; `start` and `end` as `left`
+    /// and `right`, text being laid out left to right.
+    fn parse(keyword: Option<&str>) -> Option<TextAlign> {
+        match keyword? {
+            "left" | "start" => Some(TextAlign::Left),
+            "center" => Some(TextAlign::Center),
+            "right" | "end" => Some(TextAlign::Right),
+            "justify" => Some(TextAlign::Justify),
+            _ => None,
+        }
+    }
+}
+
+/// `white-space`: whether the line breaks and runs of spaces in text are
+/// kept, which the parser only does inside `<pre>`.
+#[derive(Clone, Copy, Default, PartialEq)]
+enum WhiteSpace {
+    #[default]
+    Normal,
+    /// Kept, and lines only break where the text does.
+    Pre,
+    /// Kept, and lines also wrap.
+    PreWrap,
+    /// Collapsed, but lines never wrap.
+    NoWrap,
+}
+
+/// `tab-size`: how far apart the tab stops of preformatted text are.
+#[derive(Clone, Copy, PartialEq)]
+enum TabSize {
+    /// This many spaces of the text's font.
+    Spaces(f32),
+    /// This many pixels.
+    Length(f32),
+}
+
+/// The `display` values layout tells apart.
+#[derive(Clone, Copy, PartialEq)]
+enum Display {
+    Block,
+    Inline,
+    Flex,
+}
+
+/// One side of an element's border.
+#[derive(Clone, Copy)]
+struct Border {
+    width: f32,
+    /// Whether `border-style` draws it; any style but `none` and `hidden`
+    /// is drawn solid.
+    drawn: bool,
+    /// `None` for `currentColor`.
+    color: Option<u32>,
+}
+
+impl Default for Border {
+    fn default() -> Self {
+        Border { width: 3.0, drawn: false, color: None }
+    }
 }
 
 impl Default for Style {
     fn default() -> Self {
-        Style { font_size: 16.0, bold: false, italic: false, color: 0x000000, underline: false, indent: 0.0 }
+        Style {
+            font_size: 16.0,
+            bold: false,
+            italic: false,
+            color: 0x000000,
+            underline: false,
+            indent: 0.0,
+            right: 0.0,
+            lists: 0,
+            text_align: TextAlign::Left,
+            monospace: false,
+            white_space: WhiteSpace::Normal,
+            tab_size: TabSize::Spaces(8.0),
+            margin: [0.0; 4],
+            padding: [0.0; 4],
+            border: [Border::default(); 4],
+            display: None,
+            flex: flex::Container::default(),
+            item: flex::Item::default(),
+            background: None,
+            float: None,
+            clear: None,
+            position: position::Position::Static,
+            offsets: [None; 4],
+            overflow_hidden: false,
+            ellipsis: false,
+        }
     }
 }
 
-struct Ctx {
+impl From<&Style> for ComputedStyle {
+    fn from(style: &Style) -> Self {
+        let Style { font_size, bold, italic, color, underline, indent, margin, padding, .. } = *style;
+        ComputedStyle { font_size, bold, italic, color, underline, indent, margin, padding, border: style.border_widths() }
+    }
+}
+
+/// The defaults every document starts from, as a browser's default style
+/// sheet gives them.
+const USER_AGENT_CSS: &str = "
+    h1 { font-size: 32px; font-weight: bold; margin: 24px 0 16px }
+    h2 { font-size: 24px; font-weight: bold; margin: 20px 0 12px }
+    h3 { font-size: 20px; font-weight: bold; margin: 16px 0 8px }
+    h4 { font-size: 16px; font-weight: bold; margin: 16px 0 8px }
+    h5 { font-size: 14px; font-weight: bold; margin: 12px 0 6px }
+    h6 { font-size: 12px; font-weight: bold; margin: 12px 0 6px }
+    p { margin: 0 0 16px }
+    ul, ol { margin: 8px 0; padding-left: 24px }
+    li { margin-bottom: 4px }
+    dl { margin: 0 0 16px }
+    dt { font-weight: bold }
+    dd { margin: 0 0 8px 40px }
+    hr { margin: 8px 0 }
+    td, th { padding: 2px 4px; border: 1px solid #808080 }
+    th { font-weight: bold }
+    blockquote { margin: 0 0 16px 24px; padding-left: 12px; border-left: 3px solid #CCCCCC; color: #555555 }
+    pre { font-family: monospace; white-space: pre; background-color: #F4F4F4; padding: 8px; margin: 0 0 16px }
+    code, kbd, samp, tt { font-family: monospace }
+    small { font-size: smaller }
+    strong { font-weight: bold }
+    em { font-style: italic }
+    a { color: #0000EE; text-decoration: underline }
+";
+
+static USER_AGENT_SHEET: LazyLock<css::Stylesheet> = LazyLock::new(|| css::Stylesheet::parse(USER_AGENT_CSS));
+
+static USER_SHEET: LazyLock<RwLock<Arc<css::Stylesheet>>> = LazyLock::new(Default::default);
+
+/// Style the documents laid out from now on with `css` as the reader's
+/// style sheet, over radium's defaults and under the document's own rules
+/// (but over them where it says `!important`), or (`None`) without one.
+pub fn set_user_stylesheet(css: Option<&str>) {
+    *USER_SHEET.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(css.map(css::Stylesheet::parse).unwrap_or_default());
+}
+
+impl Style {
+    /// Apply the `declarations` of an element whose parent has style
+    /// `parent` and content `width` wide, in cascade order (so the last of
+    /// each property wins). Declarations with a value layout does not
+    /// understand are ignored.
+    fn apply(&mut self, declarations: &[&css::Declaration], parent: &Style, width: f32) {
+        for css::Declaration { name, value, .. } in declarations {
+            let parts = css::components(value);
+            let keyword = css::keyword(value);
+            match name.as_str() {
+                "color" => self.color = css::color(value).unwrap_or(self.color),
+                "font-size" => {
+                    let size = match keyword.as_deref() {
+                        Some("xx-small") => Some(9.0),
+                        Some("x-small") => Some(10.0),
+                        Some("small") => Some(13.0),
+                        Some("medium") => Some(16.0),
+                        Some("large") => Some(18.0),
+                        Some("x-large") => Some(24.0),
+                        Some("xx-large") => Some(32.0),
+                        Some("xxx-large") => Some(48.0),
+                        Some("larger") => Some(parent.font_size * 1.2),
+                        Some("smaller") => Some(parent.font_size / 1.2),
+                        _ => match value.as_slice() {
+                            [length] => css::length(length, parent.font_size, parent.font_size),
+                            _ => None,
+                        },
+                    };
+                    self.font_size = size.filter(|&size| size >= 0.0).unwrap_or(self.font_size);
+                }
+                "font-weight" => {
+                    self.bold = match (keyword.as_deref(), value.as_slice()) {
+                        (Some("bold" | "bolder"), _) => true,
+                        (Some("normal" | "lighter"), _) => false,
+                        (_, [css::Token::Number(weight)]) => *weight >= 600.0,
+                        _ => self.bold,
+                    }
+                }
+                "font-style" => {
+                    self.italic = match parts.first().and_then(|part| css::keyword(part)).as_deref() {
+                        Some("italic" | "oblique") => true,
+                        Some("normal") => false,
+                        _ => self.italic,
+                    }
+                }
+                "font-family" => {
+                    // The first family decides, whichever fonts are installed.
+                    let family: Vec<String> = value.iter()
+                        .take_while(|token| **token != css::Token::Comma)
+                        .filter_map(|token| match token {
+                            css::Token::Ident(word) | css::Token::String(word) => Some(word.to_ascii_lowercase()),
+                            _ => None,
+                        })
+                        .collect();
+                    let family = family.join(" ");
+                    if !family.is_empty() {
+                        self.monospace = family == "monospace" || ["mono", "courier", "consolas", "menlo", "monaco"].iter().any(|name| family.contains(name));
+                    }
+                }
+                "white-space" => {
+                    self.white_space = match keyword.as_deref() {
+                        Some("normal" | "pre-line") => WhiteSpace::Normal,
+                        Some("nowrap") => WhiteSpace::NoWrap,
+                        Some("pre") => WhiteSpace::Pre,
+                        Some("pre-wrap" | "break-spaces") => WhiteSpace::PreWrap,
+                        _ => self.white_space,
+                    }
+                }
+                "tab-size" => {
+                    self.tab_size = match value.as_slice() {
+                        [css::Token::Number(spaces)] if *spaces >= 0.0 => TabSize::Spaces(*spaces),
+                        [length @ css::Token::Dimension(..)] => match css::length(length, self.font_size, width) {
+                            Some(length) if length >= 0.0 => TabSize::Length(length),
+                            _ => continue,
+                        },
+                        _ => continue,
+                    }
+                }
+                "background-color" | "background" => {
+                    // Of the `background` shorthand, only a colour is drawn.
+                    if let Some(color) = parts.iter().find_map(|part| css::color(part)) {
+                        self.background = Some(color).filter(|&color| css::alpha(color) > 0);
+                    } else if name == "background" && keyword.as_deref() == Some("none") {
+                        self.background = None;
+                    }
+                }
+                "text-decoration" | "text-decoration-line" => {
+                    let lines: Vec<String> = parts.iter().filter_map(|part| css::keyword(part)).collect();
+                    if lines.iter().any(|line| ["none", "underline", "overline", "line-through"].contains(&line.as_str())) {
+                        self.underline = lines.iter().any(|line| line == "underline");
+                    }
+                }
+                "margin" | "padding" => {
+                    let lengths: Option<Vec<f32>> = parts.iter().map(|part| self.edge_length(name, part, width)).collect();
+                    let Some(lengths) = lengths.as_deref().and_then(sides) else { continue };
+                    for (side, length) in lengths.into_iter().enumerate() {
+                        self.set_edge(name, side, length);
+                    }
+                }
+                "border" => {
+                    if let Some(border) = border(&parts, self.font_size) {
+                        self.border = [border; 4];
+                    }
+                }
+                "display" => {
+                    self.display = match keyword.as_deref() {
+                        Some("block" | "list-item") => Some(Display::Block),
+                        Some("inline" | "inline-block") => Some(Display::Inline),
+                        Some("flex" | "inline-flex") => Some(Display::Flex),
+                        _ => self.display,
+                    }
+                }
+                "text-align" => self.text_align = TextAlign::parse(keyword.as_deref()).unwrap_or(self.text_align),
+                "position" => self.position = position::Position::parse(keyword.as_deref()).unwrap_or(self.position),
+                "top" | "right" | "bottom" | "left" => {
+                    let side = ["top", "right", "bottom", "left"].iter().position(|side| side == name).unwrap_or(0);
+                    self.offsets[side] = match (keyword.as_deref(), parts.as_slice()) {
+                        (Some("auto"), _) => None,
+                        (_, [[token]]) => match css::length(token, self.font_size, width) {
+                            Some(offset) => Some(offset),
+                            None => continue,
+                        },
+                        _ => continue,
+                    };
+                }
+                "float" => self.float = float::Side::parse(keyword.as_deref()).unwrap_or(self.float),
+                "overflow" | "overflow-x" => {
+                    self.overflow_hidden = match keyword.as_deref() {
+                        Some("visible") => false,
+                        Some("hidden" | "clip" | "scroll" | "auto") => true,
+                        _ => self.overflow_hidden,
+                    }
+                }
+                "text-overflow" => {
+                    self.ellipsis = match keyword.as_deref() {
+                        Some("ellipsis") => true,
+                        Some("clip") => false,
+                        _ => self.ellipsis,
+                    }
+                }
+                "clear" => self.clear = float::Clear::parse(keyword.as_deref()).unwrap_or(self.clear),
+                "flex-direction" | "justify-content" | "align-items" => self.flex.set(name, keyword.as_deref()),
+                "flex" | "flex-grow" | "flex-shrink" | "flex-basis" => self.item.set(name, &parts, self.font_size, width),
+                "border-width" | "border-style" | "border-color" => {
+                    let Some(values) = sides(&parts) else { continue };
+                    let mut borders = self.border;
+                    let property = &name["border-".len()..];
+                    if values.iter().zip(&mut borders).all(|(value, border)| border.set(property, value, self.font_size)) {
+                        self.border = borders;
+                    }
+                }
+                _ => {
+                    // `margin-top`, `border-left`, `border-right-color`, ...
+                    let mut words = name.split('-');
+                    let (Some(edge), Some(side)) = (words.next(), words.next()) else { continue };
+                    let Some(side) = ["top", "right", "bottom", "left"].iter().position(|s| *s == side) else { continue };
+                    match (edge, words.next(), words.next()) {
+                        ("margin" | "padding", None, _) => {
+                            if let Some(length) = match value.as_slice() {
+                                [part] => self.edge_length(edge, std::slice::from_ref(part), width),
+                                _ => None,
+                            } {
+                                self.set_edge(edge, side, length);
+                            }
+                        }
+                        ("border", None, _) => {
+                            if let Some(border) = border(&parts, self.font_size) {
+                                self.border[side] = border;
+                            }
+                        }
+                        ("border", Some(property), None) => {
+                            let font_size = self.font_size;
+                            self.border[side].set(property, value, font_size);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    /// A margin or padding length; `auto` margins count as 0.
+    fn edge_length(&self, edge: &str, part: &[css::Token], width: f32) -> Option<f32> {
+        match part {
+            [css::Token::Ident(auto)] if auto.eq_ignore_ascii_case("auto") && edge == "margin" => Some(0.0),
+            [token] => css::length(token, self.font_size, width),
+            _ => None,
+        }
+    }
+
+    /// Set `side` (0 = top, clockwise) of `edge` (`margin` or `padding`);
+    /// padding cannot be negative.
+    fn set_edge(&mut self, edge: &str, side: usize, length: f32) {
+        match edge {
+            "margin" => self.margin[side] = length,
+            "padding" if length >= 0.0 => self.padding[side] = length,
+            _ => {}
+        }
+    }
+
+    /// The style an element's children start from: this one without the
+    /// properties that are not inherited.
+    fn inherited(&self) -> Style {
+        let Style { font_size, bold, italic, color, underline, indent, right, lists, text_align, monospace, white_space, tab_size, .. } = *self;
+        Style { font_size, bold, italic, color, underline, indent, right, lists, text_align, monospace, white_space, tab_size, ..Style::default() }
+    }
+
+    /// Advance width of `ch` in this style's face.
+    fn advance(&self, measurer: &dyn TextMeasurer, ch: char) -> f32 {
+        if self.monospace {
+            measurer.monospace_advance(ch, self.bold, self.italic, self.font_size)
+        } else {
+            measurer.advance(ch, self.bold, self.italic, self.font_size)
+        }
+    }
+
+    /// How far text in this style's face reaches above and below its
+    /// baseline on a line.
+    fn extents(&self, measurer: &dyn TextMeasurer) -> (f32, f32) {
+        let (bold, italic, monospace, size) = (self.bold, self.italic, self.monospace, self.font_size);
+        (measurer.ascent(bold, italic, monospace, size), measurer.descent(bold, italic, monospace, size))
+    }
+
+    /// How much wider its margins, borders and padding make an element
+    /// than its content.
+    fn horizontal_edges(&self) -> f32 {
+        let [_, br, _, bl] = self.border_widths();
+        self.margin[1] + br + self.padding[1] + self.padding[3] + bl + self.margin[3]
+    }
+
+    /// How wide each border is drawn: 0 for those without a style.
+    fn border_widths(&self) -> [f32; 4] {
+        self.border.map(|border| if border.drawn { border.width } else { 0.0 })
+    }
+}
+
+impl Border {
+    /// Set the `property` (`width`, `style` or `color`) of this side to
+    /// `value`, if valid; returns whether it was.
+    fn set(&mut self, property: &str, value: &[css::Token], font_size: f32) -> bool {
+        match property {
+            "width" => border_width(value, font_size).map(|width| self.width = width),
+            "style" => border_style(value).map(|drawn| self.drawn = drawn),
+            "color" => border_color(value).map(|color| self.color = color),
+            _ => None,
+        }
+        .is_some()
+    }
+}
+
+/// Expand one to four values to the four sides: all sides; vertical,
+/// horizontal; top, horizontal, bottom; or each side clockwise from the top.
+fn sides<T: Copy>(values: &[T]) -> Option<[T; 4]> {
+    match *values {
+        [all] => Some([all; 4]),
+        [vertical, horizontal] => Some([vertical, horizontal, vertical, horizontal]),
+        [top, horizontal, bottom] => Some([top, horizontal, bottom, horizontal]),
+        [top, right, bottom, left] => Some([top, right, bottom, left]),
+        _ => None,
+    }
+}
+
+/// A `border` shorthand: a width, a style and a colour in any order, each
+/// optional. Those left out are reset.
+fn border(parts: &[&[css::Token]], font_size: f32) -> Option<Border> {
+    let mut border = Border::default();
+    let (mut width, mut style, mut color) = (false, false, false);
+    for part in parts {
+        if let (false, Some(w)) = (width, border_width(part, font_size)) {
+            (border.width, width) = (w, true);
+        } else if let (false, Some(drawn)) = (style, border_style(part)) {
+            (border.drawn, style) = (drawn, true);
+        } else if let (false, Some(c)) = (color, border_color(part)) {
+            (border.color, color) = (c, true);
+        } else {
+            return None;
+        }
+    }
+    (!parts.is_empty()).then_some(border)
+}
+
+/// `thin`, `medium`, `thick` or a non-negative length.
+fn border_width(value: &[css::Token], font_size: f32) -> Option<f32> {
+    match (css::keyword(value).as_deref(), value) {
+        (Some("thin"), _) => Some(1.0),
+        (Some("medium"), _) => Some(3.0),
+        (Some("thick"), _) => Some(5.0),
+        (_, [css::Token::Percentage(_)]) => None,
+        (_, [token]) => css::length(token, font_size, 0.0).filter(|&width| width >= 0.0),
+        _ => None,
+    }
+}
+
+/// Whether a `border-style` draws the border.
+fn border_style(value: &[css::Token]) -> Option<bool> {
+    match css::keyword(value)?.as_str() {
+        "none" | "hidden" => Some(false),
+        "solid" | "dotted" | "dashed" | "double" | "groove" | "ridge" | "inset" | "outset" => Some(true),
+        _ => None,
+    }
+}
+
+/// A border colour; `Some(None)` for `currentColor`.
+fn border_color(value: &[css::Token]) -> Option<Option<u32>> {
+    match css::keyword(value).as_deref() {
+        Some("currentcolor") => Some(None),
+        _ => css::color(value).map(Some),
+    }
+}
+
+/// The computed style of an element with `attrs` whose parent has style
+/// `parent`: what it inherits, overridden by the cascaded declarations of
+/// the default style sheet, the document's style sheets and its `style`
+/// attribute.
+fn element_style(tag: &str, attrs: &HashMap<String, String>, parent: &Style, ctx: &Ctx) -> Style {
+    let mut style = parent.inherited();
+    // `align` is a presentational hint, which style sheets override.
+    let align = attrs.get("align").map(|align| align.trim().to_ascii_lowercase());
+    match tag {
+        "img" => style.float = float::Side::parse(align.as_deref()).flatten(),
+        "p" | "div" | "td" | "th" | "caption" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            style.text_align = TextAlign::parse(align.as_deref()).unwrap_or(style.text_align);
+        }
+        _ => {}
+    }
+    let inline = attrs.get("style").map(|declarations| css::parse_declarations(declarations)).unwrap_or_default();
+    let sheets = [
+        (css::Origin::UserAgent, &*USER_AGENT_SHEET),
+        (css::Origin::User, &*ctx.user_styles),
+        (css::Origin::Author, &*ctx.styles),
+    ];
+    let declarations = css::cascade(&sheets, &inline, tag, attrs);
+    if declarations.is_empty() {
+        return style;
+    }
+    style.apply(&declarations, parent, ctx.width - parent.indent - parent.right);
+    style
+}
+
+/// The rules of the `<style>` elements and linked style sheets under
+/// `nodes`, in document order. Linked sheets are read from disk if local
+/// (and noted in `assets`); remote ones are not fetched yet.
+fn style_sheet(nodes: &[Node], base: &Url, assets: &mut Vec<PathBuf>) -> css::Stylesheet {
+    let mut sheet = css::Stylesheet::default();
+    for node in nodes {
+        match node {
+            Node::Element { tag, children, .. } if tag == "style" => sheet.extend(css::Stylesheet::parse(&raw_text(children))),
+            Node::Element { tag, attrs, .. } if tag == "link" && is_stylesheet_link(attrs) => {
+                let Some(href) = attrs.get("href").map(|href| href.trim()).filter(|href| !href.is_empty()) else { continue };
+                let url = match base.join(href) {
+                    Ok(url) => url,
+                    Err(e) => {
+                        eprintln!("radium: bad stylesheet href '{href}': {e}");
+                        continue;
+                    }
+                };
+                let Some(path) = url.to_file_path() else { continue };
+                match std::fs::read(&path) {
+                    Ok(bytes) => sheet.extend(css::Stylesheet::parse(&String::from_utf8_lossy(&bytes))),
+                    Err(e) => eprintln!("radium: failed to load stylesheet {url}: {e}"),
+                }
+                assets.push(path);
+            }
+            Node::Element { children, .. } => sheet.extend(style_sheet(children, base, assets)),
+            Node::Text(_) => {}
+        }
+    }
+    sheet
+}
+
+/// Whether a `<link>` with `attrs` links a style sheet to apply: its `rel`
+/// includes `stylesheet` but not `alternate`.
+fn is_stylesheet_link(attrs: &HashMap<String, String>) -> bool {
+    let rel = attrs.get("rel").map(|rel| rel.to_ascii_lowercase()).unwrap_or_default();
+    let mut types = rel.split_ascii_whitespace();
+    types.clone().any(|t| t == "stylesheet") && !types.any(|t| t == "alternate")
+}
+
+struct Ctx<'a> {
     pad: f32,
     width: f32,
-    /// Full viewport width — used for full-bleed heading backgrounds.
+    /// Full viewport width, which the column is centred in and absolutely
+    /// positioned elements are placed in by default.
     viewport_width: f32,
-    /// Base directory for resolving relative paths (e.g. image src).
-    base_dir: PathBuf,
+    /// Document URL that relative references (e.g. image src) resolve against.
+    base: Url,
+    images: RemoteImages,
     boxes: Vec<LayoutBox>,
+    regions: Vec<HitRegion>,
+    anchors: HashMap<String, Rect>,
+    blocks: Vec<Range<usize>>,
+    assets: Vec<PathBuf>,
+    pending_images: Vec<PendingImage>,
+    headings: Vec<Heading>,
+    inputs: Vec<InputField>,
+    toggles: Vec<Toggle>,
+    buttons: Vec<Button>,
+    selects: Vec<Select>,
+    forms: Vec<Form>,
+    /// Index into `forms` of the innermost `<form>` being laid out.
+    form: Option<usize>,
+    /// Index into `regions` of each control with an `id`, by id.
+    control_ids: HashMap<String, usize>,
+    /// Each form control laid out so far, with its path (see
+    /// [`ElementBox::path`]), which orders them as the document does where
+    /// layout does not (in a reversed flex column).
+    controls: Vec<(Vec<usize>, HitKind)>,
+    /// Extent and `for` of each `<label for>`, resolved once every control
+    /// is laid out.
+    labels: Vec<(Rect, String)>,
+    tab_indices: HashMap<usize, i32>,
+    autofocus: Option<usize>,
+    /// `lang` of each element being laid out that has one, innermost last,
+    /// lower-cased.
+    langs: Vec<String>,
+    /// How many `<q>` elements deep layout is.
+    quotes: usize,
+    /// When to stop laying out nodes, for [`layout_until`].
+    deadline: Option<Instant>,
+    /// Nodes laid out so far; the deadline is checked every
+    /// `DEADLINE_CHECK_INTERVAL` of them.
+    nodes_seen: usize,
+    /// The deadline passed: every node from here on is skipped.
+    partial: bool,
+    /// Only recorded for `layout_elements`; on large documents the
+    /// allocations would slow every layout noticeably.
+    elements: Option<Vec<ElementBox>>,
+    /// Child indices down to the node being laid out.
+    path: Vec<usize>,
+    /// For each element being laid out, innermost last, the style its
+    /// children were given (set by the first `layout_children` call).
+    child_styles: Vec<Option<Style>>,
+    /// Custom element handlers, as registered when this layout started.
+    handlers: Arc<custom::Handlers>,
+    /// Measures text to break it into lines.
+    measurer: &'a dyn TextMeasurer,
+    /// The line inline content is being laid out on, until a block or a
+    /// `<br>` ends it.
+    line: Option<OpenLine>,
+    /// Put a space between the last run and the next: whitespace ended the
+    /// text of one or started the other.
+    spaced: bool,
+    /// Where the boxes of the innermost element being laid out begin. A run
+    /// only extends one of those, so each element keeps its own extent.
+    element_start: usize,
+    /// The rules of the document's `<style>` elements and style sheets.
+    styles: Arc<css::Stylesheet>,
+    /// The reader's style sheet, as set when the layout began.
+    user_styles: Arc<css::Stylesheet>,
+    /// The hyphenation patterns of each language, as set when the layout
+    /// began.
+    hyphenation: Arc<hyphenate::Languages>,
+    /// The floats laid out so far, which lines flow around.
+    floats: Vec<float::Float>,
+    /// The containing blocks absolutely positioned elements are placed in,
+    /// the page's first and the innermost last.
+    containing: Vec<position::Containing>,
+    /// The numbers of the ordered list items being laid out, outermost
+    /// first, when ordered lists are numbered as an outline.
+    counters: Option<Vec<String>>,
+    /// Laying out only to measure (see [`Ctx::scratch`]): flex rows skip
+    /// measuring their items again.
+    measuring: bool,
+    /// Height, margins included, the next element must take up at least
+    /// (a flex item stretched to its row).
+    fill: Option<f32>,
+}
+
+/// A line of inline content being filled left to right, one run of text
+/// (in one style) after another.
+struct OpenLine {
+    top: f32,
+    /// Where the next run goes.
+    x: f32,
+    /// Its right edge.
+    right: f32,
+    align: TextAlign,
+    /// What had been laid out when the line started.
+    start: position::Marks,
+    /// How far the runs on it reach above and below the baseline they
+    /// share, at most: the baseline is `ascent` below its top, and its
+    /// bottom `descent` below that.
+    ascent: f32,
+    descent: f32,
+    /// What the floats and absolutely positioned elements laid out while
+    /// the line was open added, which is not on it.
+    out_of_flow: Vec<(position::Marks, position::Marks)>,
+    /// The last run on it was `white-space: nowrap`, so one after it that
+    /// is too does not wrap before it either.
+    nowrap: bool,
+}
+
+impl OpenLine {
+    fn bottom(&self) -> f32 {
+        self.top + self.ascent + self.descent
+    }
+}
+
+impl Ctx<'_> {
+    /// Whether the deadline has passed, so the node about to be laid out
+    /// (and every one after it) is skipped.
+    fn out_of_time(&mut self) -> bool {
+        let Some(deadline) = self.deadline else { return false };
+        self.nodes_seen += 1;
+        if !self.partial && self.nodes_seen.is_multiple_of(DEADLINE_CHECK_INTERVAL) && Instant::now() >= deadline {
+            self.partial = true;
+        }
+        self.partial
+    }
+
+    /// Bounding rectangle of every box emitted since `first`.
+    fn extent_since(&self, first: usize) -> Option<Rect> {
+        let emitted = &self.boxes[first..];
+        if emitted.is_empty() {
+            return None;
+        }
+        let x0 = emitted.iter().map(|b| b.x).fold(f32::INFINITY, f32::min);
+        let y0 = emitted.iter().map(|b| b.y).fold(f32::INFINITY, f32::min);
+        let x1 = emitted.iter().map(|b| b.x + b.width).fold(f32::NEG_INFINITY, f32::max);
+        let y1 = emitted.iter().map(|b| b.y + b.height).fold(f32::NEG_INFINITY, f32::max);
+        Some(Rect { x: x0, y: y0, width: x1 - x0, height: y1 - y0 })
+    }
+
+    /// Note that the innermost element being laid out gives its children
+    /// `style`, unless it already gave them another.
+    fn style_children(&mut self, style: &Style) {
+        if let Some(slot @ None) = self.child_styles.last_mut() {
+            *slot = Some(style.clone());
+        }
+    }
+
+    /// Lay out `text` as a run of inline content in `style`: after the runs
+    /// on the open line (or on a new one at `y`), wrapping onto new lines as
+    /// needed. Returns the bottom of the line it ends on.
+    fn layout_run(&mut self, text: &str, y: f32, style: &Style) -> f32 {
+        let measurer = self.measurer;
+        let advance = |ch| style.advance(measurer, ch);
+        let spaced = std::mem::take(&mut self.spaced);
+        let height = line_height(style.font_size);
+        let mut breaker = text::LineBreaker::new(text, advance);
+        if let Some(hyphenator) = self.langs.last().and_then(|lang| hyphenate::for_lang(&self.hyphenation, lang)) {
+            breaker.hyphenate(hyphenator);
+        }
+        let mut y = y;
+        for i in 0.. {
+            let (x, width, fresh) = match &self.line {
+                // The first line goes on after the runs on the open line.
+                Some(line) if i == 0 => {
+                    let x = if spaced { line.x + advance(' ') } else { line.x };
+                    let (_, right) = self.line_edges(line.top, line.top + height, style);
+                    (x, right - x, false)
+                }
+                _ => {
+                    if i > 0 {
+                        if breaker.is_done() {
+                            break;
+                        }
+                        y = self.wrap_line(y);
+                    }
+                    // Beside floats too close together for its first word,
+                    // a line goes below them.
+                    let (mut left, mut right) = self.line_edges(y, y + height, style);
+                    while right - left < breaker.first_word_width() {
+                        let Some(below) = self.float_end_below(y, style) else { break };
+                        y = below;
+                        (left, right) = self.line_edges(y, y + height, style);
+                    }
+                    (left, right - left, true)
+                }
+            };
+            // Preformatted text only breaks where its line breaks are, and
+            // unwrapped text goes whole on the line it starts or the next
+            // (unless it goes on from unwrapped text).
+            let after_nowrap = !fresh && self.line.as_ref().is_some_and(|line| line.nowrap);
+            let room = match style.white_space {
+                WhiteSpace::Pre => f32::INFINITY,
+                WhiteSpace::NoWrap if fresh || after_nowrap || breaker.rest_width() <= width => f32::INFINITY,
+                WhiteSpace::NoWrap => 0.0,
+                _ => width,
+            };
+            let Some(line) = breaker.next_line(room, fresh) else { break };
+            if line.text.is_empty() {
+                continue;
+            }
+            let start = self.marks();
+            let (ascent, descent) = style.extents(measurer);
+            let open = self.line.get_or_insert(OpenLine {
+                top: y,
+                x,
+                right: x + width,
+                align: style.text_align,
+                start,
+                ascent,
+                descent,
+                out_of_flow: Vec::new(),
+                nowrap: false,
+            });
+            open.x = x + line.width;
+            open.nowrap = style.white_space == WhiteSpace::NoWrap;
+            open.ascent = open.ascent.max(ascent);
+            open.descent = open.descent.max(descent);
+            let (baseline, first) = (open.top + open.ascent, open.start.boxes);
+            let out_of_flow: Vec<_> = open.out_of_flow.iter().map(|(from, to)| from.boxes..to.boxes).collect();
+            let on_line = |i: &usize| *i >= first && !out_of_flow.iter().any(|boxes| boxes.contains(i));
+            // A run joined to one in the same style extends its box.
+            if let Some(LayoutBox { width, cmd: PaintCmd::Text { content, .. }, .. }) = self.boxes.len().checked_sub(1)
+                .filter(|last| on_line(last) && *last >= self.element_start)
+                .map(|last| &mut self.boxes[last])
+                .filter(|b| !spaced && i == 0 && b.x + b.width == x && same_style(&b.cmd, style))
+            {
+                content.push_str(&line.text);
+                *width += line.width;
+                continue;
+            }
+            // Justified text is laid out a word to a box, so the spaces
+            // between them can be stretched.
+            let words = if style.text_align == TextAlign::Justify { line.text.split(' ').collect() } else { vec![line.text.as_str()] };
+            let mut word_x = x;
+            for word in words {
+                let width = word.chars().map(advance).sum();
+                self.boxes.push(LayoutBox {
+                    x: word_x,
+                    y: baseline - ascent,
+                    width,
+                    height: line_height(style.font_size),
+                    cmd: PaintCmd::Text {
+                        content: word.to_string(),
+                        font_size: style.font_size,
+                        bold: style.bold,
+                        italic: style.italic,
+                        monospace: style.monospace,
+                        color: style.color,
+                        underline: style.underline,
+                    },
+                });
+                word_x += width + advance(' ');
+            }
+            // Every run on the line sits on the baseline, which moves down
+            // as runs reaching higher join it.
+            for (_, b) in self.boxes.iter_mut().enumerate().skip(first).filter(|(i, _)| on_line(i)) {
+                if let PaintCmd::Text { font_size, bold, italic, monospace, .. } = b.cmd {
+                    b.y = baseline - measurer.ascent(bold, italic, monospace, font_size);
+                }
+            }
+        }
+        self.line.as_ref().map_or(y, OpenLine::bottom)
+    }
+
+    /// Cut each line of text laid out since box `first` that runs past
+    /// `right` short, to end there in an ellipsis. Boxes past the cut are
+    /// emptied rather than removed, as the other outputs refer to boxes by
+    /// index.
+    fn cut_overflow(&mut self, first: usize, right: f32) {
+        let measurer = self.measurer;
+        let texts: Vec<usize> = (first..self.boxes.len()).filter(|&i| matches!(self.boxes[i].cmd, PaintCmd::Text { .. })).collect();
+        // A new line starts where the text goes back to the left.
+        let mut lines = Vec::new();
+        let mut start = 0;
+        for end in 1..=texts.len() {
+            if end == texts.len() || self.boxes[texts[end]].x < self.boxes[texts[end - 1]].x {
+                lines.push(&texts[start..end]);
+                start = end;
+            }
+        }
+        for line in lines {
+            let Some(at) = line.iter().position(|&i| self.boxes[i].x + self.boxes[i].width > right + 0.01) else { continue };
+            for &i in &line[at + 1..] {
+                empty_text(&mut self.boxes[i]);
+            }
+            // The ellipsis goes on the box cut, or one before it if that
+            // starts too close to the edge to hold even the ellipsis.
+            for cut in (0..=at).rev() {
+                let b = &mut self.boxes[line[cut]];
+                let PaintCmd::Text { content, font_size, bold, italic, monospace, .. } = &mut b.cmd else { continue };
+                let advance = |ch| if *monospace {
+                    measurer.monospace_advance(ch, *bold, *italic, *font_size)
+                } else {
+                    measurer.advance(ch, *bold, *italic, *font_size)
+                };
+                let ellipsis = advance('…');
+                if b.x + ellipsis > right && cut > 0 {
+                    empty_text(b);
+                    continue;
+                }
+                let mut kept = String::new();
+                let mut width = 0.0;
+                for ch in content.chars() {
+                    if b.x + width + advance(ch) + ellipsis > right {
+                        break;
+                    }
+                    kept.push(ch);
+                    width += advance(ch);
+                }
+                while kept.ends_with(' ') {
+                    kept.pop();
+                    width -= advance(' ');
+                }
+                kept.push('…');
+                *content = kept;
+                b.width = width + ellipsis;
+                break;
+            }
+        }
+    }
+
+    /// Lay out `text` in `style` with its whitespace kept: each line break in
+    /// it ends a line, and each tab advances to the next tab stop.
+    fn layout_preformatted(&mut self, text: &str, y: f32, style: &Style) -> f32 {
+        let space = style.advance(self.measurer, ' ');
+        let tab = match style.tab_size {
+            TabSize::Spaces(spaces) => spaces,
+            TabSize::Length(length) => length / space,
+        };
+        let mut y = y;
+        for (i, segment) in text.split('\n').enumerate() {
+            if i > 0 {
+                // An empty line is as tall as one of the text.
+                y = if self.line.is_some() { self.end_line(y) } else { y + line_height(style.font_size) };
+            }
+            if segment.is_empty() {
+                continue;
+            }
+            // Columns count in spaces from the left of the column, so tabs
+            // line up across the runs of a line.
+            let left = self.pad + style.indent;
+            let mut column = self.line.as_ref().map_or(0, |line| ((line.x - left) / space).round() as usize);
+            let mut expanded = String::new();
+            for ch in segment.chars() {
+                if ch == '\t' {
+                    // A stop between two columns is rounded to the nearer;
+                    // a `tab-size` of 0 drops tabs.
+                    let stop = if tab > 0.0 { ((column as f32 / tab).floor() + 1.0) * tab } else { column as f32 };
+                    let stop = (stop.round() as usize).max(column);
+                    expanded.extend(std::iter::repeat_n(' ', stop - column));
+                    column = stop;
+                } else {
+                    expanded.push(ch);
+                    column += 1;
+                }
+            }
+            y = self.layout_run(&expanded, y, style);
+        }
+        y
+    }
+
+    /// End the open line, if there is one. Returns where what follows goes:
+    /// `y`, or below that line if it reaches further.
+    fn end_line(&mut self, y: f32) -> f32 {
+        self.close_line(y, false)
+    }
+
+    /// End the open line because the text on it wraps: as [`Ctx::end_line`],
+    /// but a justified line is stretched to its edges.
+    fn wrap_line(&mut self, y: f32) -> f32 {
+        self.close_line(y, true)
+    }
+
+    fn close_line(&mut self, y: f32, wrapped: bool) -> f32 {
+        self.spaced = false;
+        let Some(line) = self.line.take() else { return y };
+        self.align_line(&line, wrapped);
+        y.max(line.bottom())
+    }
+
+    /// Move what is on `line` to where its `text-align` puts it in the room
+    /// left at its end: all of it right, or half of it, or shared between
+    /// the spaces on a justified line that `wrapped`.
+    fn align_line(&mut self, line: &OpenLine, wrapped: bool) {
+        let free = line.right - line.x;
+        if free <= 0.0 || line.align == TextAlign::Left || (line.align == TextAlign::Justify && !wrapped) {
+            return;
+        }
+        // The stretches laid out while the line was open that are on it.
+        let end = self.marks();
+        let starts = std::iter::once(line.start).chain(line.out_of_flow.iter().map(|&(_, to)| to));
+        let ends = line.out_of_flow.iter().map(|&(from, _)| from).chain(std::iter::once(end));
+        let on_line: Vec<_> = starts.zip(ends).collect();
+        let offset: Box<dyn Fn(f32) -> (f32, f32)> = match line.align {
+            TextAlign::Center => Box::new(move |_| (free / 2.0, 0.0)),
+            TextAlign::Right => Box::new(move |_| (free, 0.0)),
+            _ => {
+                // Where each space on the line ends, from the gaps between its
+                // boxes; whatever starts past one moves by a share of `free`.
+                let mut boxes: Vec<_> = on_line.iter().flat_map(|(from, to)| &self.boxes[from.boxes..to.boxes]).map(|b| (b.x, b.x + b.width)).collect();
+                boxes.sort_by(|a, b| a.0.total_cmp(&b.0));
+                let spaces: Vec<f32> = boxes.windows(2).filter(|pair| pair[1].0 > pair[0].1 + 0.01).map(|pair| pair[1].0).collect();
+                if spaces.is_empty() {
+                    return;
+                }
+                let share = free / spaces.len() as f32;
+                Box::new(move |x| (spaces.iter().filter(|&&space| space <= x).count() as f32 * share, 0.0))
+            }
+        };
+        for (from, to) in on_line {
+            self.translate_by(from, to, &offset);
+        }
+    }
+
+    /// Paint the borders `style` gives the border box `rect`, each side in
+    /// its own colour.
+    fn draw_borders(&mut self, rect: Rect, style: &Style) {
+        let [top, right, bottom, left] = style.border_widths();
+        let Rect { x, y, width, height } = rect;
+        let sides = [
+            (x, y, width, top),
+            (x + width - right, y + top, right, height - top - bottom),
+            (x, y + height - bottom, width, bottom),
+            (x, y + top, left, height - top - bottom),
+        ];
+        for ((x, y, width, height), border) in sides.into_iter().zip(style.border) {
+            if width > 0.0 && height > 0.0 {
+                let color = border.color.unwrap_or(style.color);
+                self.boxes.push(LayoutBox { x, y, width, height, cmd: PaintCmd::FillRect { color } });
+            }
+        }
+    }
+
+    /// Record the element at `path` whose boxes start at `first`, if it
+    /// painted anything, and return its extent. `style` stands in for an
+    /// element without children.
+    fn record_element(&mut self, first: usize, style: &Style) -> Option<Rect> {
+        let child_style = self.child_styles.pop().flatten();
+        let rect = self.extent_since(first)?;
+        if let Some(elements) = &mut self.elements {
+            elements.push(ElementBox {
+                path: self.path.clone(),
+                rect,
+                boxes: first..self.boxes.len(),
+                style: child_style.as_ref().unwrap_or(style).into(),
+            });
+        }
+        Some(rect)
+    }
+
+    /// Give an element that painted `rect` the tooltip of its `title`.
+    fn record_title(&mut self, attrs: &HashMap<String, String>, rect: Rect) {
+        if let Some(title) = attrs.get("title").filter(|t| !t.trim().is_empty()) {
+            self.regions.push(HitRegion { rect, kind: HitKind::Title(title.trim().to_string()) });
+        }
+    }
+
+    /// Record the anchor an element's `id` (or an `<a>`'s `name`) names: the
+    /// element's `extent`, or `fallback` if it painted nothing.
+    fn record_anchor(&mut self, tag: &str, attrs: &HashMap<String, String>, extent: Option<Rect>, fallback: Rect) {
+        // Empty targets (e.g. `<a id="x"></a>`) still anchor at their position.
+        let name = attrs.get("id").or(if tag == "a" { attrs.get("name") } else { None });
+        if let Some(name) = name.filter(|n| !n.is_empty()) {
+            self.anchors.entry(name.clone()).or_insert(extent.unwrap_or(fallback));
+        }
+    }
 }
 
 // ── Entry point ───────────────────────────────────────────────────────────────
@@ -72,20 +1683,220 @@ struct Ctx {
 const PAGE_PAD: f32 = 16.0;
 /// Width of the gutter reserved for list markers (bullet / number).
 const MARKER_INDENT: f32 = 24.0;
+/// Side of the square drawn in place of an image that has not arrived and
+/// gives no `width`/`height`.
+const IMAGE_PLACEHOLDER_SIZE: f32 = 32.0;
+/// Padding between a text input's border and its text.
+const INPUT_PAD: f32 = 4.0;
+/// Width a text input gives each of the characters in its `size`.
+const INPUT_CHAR_WIDTH: f32 = 8.0;
+/// Text inputs show this many characters unless `size` says otherwise.
+const INPUT_SIZE: usize = 20;
+/// Size of a `<textarea>` without `cols` / `rows`.
+const TEXTAREA_COLS: usize = 20;
+const TEXTAREA_ROWS: usize = 2;
+const INPUT_BORDER_COLOR: u32 = 0x767676;
+const PLACEHOLDER_COLOR: u32 = 0x757575;
+/// Shown for each character of a password field's value.
+const MASK: char = '•';
+/// Side of a checkbox or radio button.
+const TOGGLE_SIZE: f32 = 16.0;
+/// Fill of the mark in a checked checkbox or radio button.
+const CHECK_COLOR: u32 = 0x1A73E8;
+/// Colours of a `disabled` control.
+const DISABLED_BORDER_COLOR: u32 = 0xC6C6C6;
+const DISABLED_FACE_COLOR: u32 = 0xF4F4F4;
+const DISABLED_TEXT_COLOR: u32 = 0x8F8F8F;
+const DISABLED_CHECK_COLOR: u32 = 0xA8A8A8;
+/// Padding between a button's border and its label, left and right.
+const BUTTON_PAD: f32 = 12.0;
+const BUTTON_FACE_COLOR: u32 = 0xEFEFEF;
+/// Width of the arrow at the right end of a dropdown.
+const SELECT_ARROW_WIDTH: f32 = 20.0;
+/// Nodes [`layout_until`] lays out between looks at the clock.
+const DEADLINE_CHECK_INTERVAL: usize = 256;
+
+/// Lay out `nodes`; `base` resolves relative URLs, `images` supplies
+/// remote images fetched so far and `measurer` measures text (with the
+/// fonts it will be painted in) to wrap it.
+pub fn layout(nodes: &[Node], viewport_width: f32, base: &Url, images: &RemoteImages, measurer: &dyn TextMeasurer) -> Page {
+    layout_column(nodes, viewport_width, viewport_width - PAGE_PAD * 2.0, base, images, measurer)
+}
 
-pub fn layout(nodes: &[Node], viewport_width: f32, base_dir: &Path) -> Vec<LayoutBox> {
+/// Like [`layout`], but content is confined to a centred column at most
+/// `column_width` wide (reader mode's readable measure).
+pub fn layout_column(
+    nodes: &[Node],
+    viewport_width: f32,
+    column_width: f32,
+    base: &Url,
+    images: &RemoteImages,
+    measurer: &dyn TextMeasurer,
+) -> Page {
+    page(run(nodes, viewport_width, column_width, base, images, measurer, false, None))
+}
+
+/// Like [`layout_column`] (with `f32::INFINITY` for [`layout`]'s full
+/// width), but stops taking on nodes once `deadline` passes, so the top of a
+/// huge document can be shown while the rest is laid out. The result is
+/// then [`Page::partial`]; its boxes are not necessarily a prefix of the
+/// full layout's.
+pub fn layout_until(
+    nodes: &[Node],
+    viewport_width: f32,
+    column_width: f32,
+    base: &Url,
+    images: &RemoteImages,
+    measurer: &dyn TextMeasurer,
+    deadline: Instant,
+) -> Page {
+    page(run(nodes, viewport_width, column_width, base, images, measurer, false, Some(deadline)))
+}
+
+fn page(mut ctx: Ctx) -> Page {
+    let extents = Extents::new(&ctx.boxes);
+    // Paths compare in document order.
+    ctx.controls.sort_by(|a, b| a.0.cmp(&b.0));
+    let controls = ctx.controls.into_iter().map(|(_, kind)| kind).collect();
+    Page {
+        boxes: ctx.boxes,
+        regions: ctx.regions,
+        anchors: ctx.anchors,
+        blocks: ctx.blocks,
+        assets: ctx.assets,
+        pending_images: ctx.pending_images,
+        headings: ctx.headings,
+        inputs: ctx.inputs,
+        toggles: ctx.toggles,
+        buttons: ctx.buttons,
+        selects: ctx.selects,
+        forms: ctx.forms,
+        tab_indices: ctx.tab_indices,
+        autofocus: ctx.autofocus,
+        partial: ctx.partial,
+        extents,
+        controls,
+    }
+}
+
+/// Lay out `nodes` as [`layout_column`] does (with `f32::INFINITY` for
+/// [`layout`]'s full width) and return where each element that painted
+/// something ended up, children before parents.
+pub fn layout_elements(
+    nodes: &[Node],
+    viewport_width: f32,
+    column_width: f32,
+    base: &Url,
+    images: &RemoteImages,
+    measurer: &dyn TextMeasurer,
+) -> Vec<ElementBox> {
+    run(nodes, viewport_width, column_width, base, images, measurer, true, None).elements.unwrap_or_default()
+}
+
+/// Index into `elements` of the innermost element at document point `(x, y)`.
+pub fn element_at(elements: &[ElementBox], x: f32, y: f32) -> Option<usize> {
+    elements.iter().enumerate()
+        .filter(|(_, e)| e.rect.contains(x, y))
+        .max_by_key(|(_, e)| e.path.len())
+        .map(|(i, _)| i)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run<'a>(
+    nodes: &[Node],
+    viewport_width: f32,
+    column_width: f32,
+    base: &Url,
+    images: &RemoteImages,
+    measurer: &'a dyn TextMeasurer,
+    record_elements: bool,
+    deadline: Option<Instant>,
+) -> Ctx<'a> {
+    let width = column_width.min(viewport_width - PAGE_PAD * 2.0);
+    let mut assets = Vec::new();
+    let styles = Arc::new(style_sheet(nodes, base, &mut assets));
     let mut ctx = Ctx {
-        pad: PAGE_PAD,
-        width: viewport_width - PAGE_PAD * 2.0,
-        viewport_width,
-        base_dir: base_dir.to_path_buf(),
-        boxes: Vec::new(),
+        deadline,
+        elements: record_elements.then(Vec::new),
+        assets,
+        ..Ctx::new(width, viewport_width, base.clone(), images.clone(), measurer, styles)
     };
-    let mut y = PAGE_PAD;
-    for node in nodes {
-        y = layout_node(node, &mut ctx, y, &Style::default());
+    let y = layout_children(nodes, &mut ctx, PAGE_PAD, &Style::default());
+    let y = ctx.end_line(y);
+    ctx.close_containing_block(y);
+    for (rect, id) in std::mem::take(&mut ctx.labels) {
+        if let Some(&control) = ctx.control_ids.get(&id) {
+            ctx.regions.push(HitRegion { rect, kind: HitKind::Label(control) });
+        }
+    }
+    ctx
+}
+
+impl<'a> Ctx<'a> {
+    /// An empty layout of a `width` column centred in the viewport.
+    fn new(width: f32, viewport_width: f32, base: Url, images: RemoteImages, measurer: &'a dyn TextMeasurer, styles: Arc<css::Stylesheet>) -> Self {
+        Ctx {
+            pad: (viewport_width - width) / 2.0,
+            width,
+            viewport_width,
+            base,
+            images,
+            boxes: Vec::new(),
+            regions: Vec::new(),
+            anchors: HashMap::new(),
+            blocks: Vec::new(),
+            assets: Vec::new(),
+            pending_images: Vec::new(),
+            headings: Vec::new(),
+            inputs: Vec::new(),
+            toggles: Vec::new(),
+            buttons: Vec::new(),
+            selects: Vec::new(),
+            forms: Vec::new(),
+            form: None,
+            control_ids: HashMap::new(),
+            controls: Vec::new(),
+            labels: Vec::new(),
+            tab_indices: HashMap::new(),
+            autofocus: None,
+            langs: Vec::new(),
+            quotes: 0,
+            deadline: None,
+            nodes_seen: 0,
+            partial: false,
+            elements: None,
+            path: Vec::new(),
+            child_styles: Vec::new(),
+            handlers: custom::handlers(),
+            measurer,
+            line: None,
+            spaced: false,
+            element_start: 0,
+            styles,
+            user_styles: USER_SHEET.read().unwrap_or_else(|e| e.into_inner()).clone(),
+            hyphenation: hyphenate::languages(),
+            floats: Vec::new(),
+            containing: vec![position::Containing::new(0.0, viewport_width, 0.0)],
+            counters: outline_numbering().then(Vec::new),
+            measuring: false,
+            fill: None,
+        }
+    }
+
+    /// A layout of the same column to lay content out in only to see how
+    /// tall it is, leaving this one untouched.
+    fn scratch(&self) -> Ctx<'a> {
+        Ctx {
+            langs: self.langs.clone(),
+            quotes: self.quotes,
+            handlers: self.handlers.clone(),
+            user_styles: self.user_styles.clone(),
+            hyphenation: self.hyphenation.clone(),
+            counters: self.counters.clone(),
+            measuring: true,
+            ..Ctx::new(self.width, self.viewport_width, self.base.clone(), self.images.clone(), self.measurer, self.styles.clone())
+        }
     }
-    ctx.boxes
 }
 
 // ── Layout helpers ────────────────────────────────────────────────────────────
@@ -95,227 +1906,855 @@ fn line_height(font_size: f32) -> f32 {
 }
 
 fn layout_node(node: &Node, ctx: &mut Ctx, y: f32, style: &Style) -> f32 {
+    if ctx.out_of_time() {
+        return y;
+    }
     match node {
+        Node::Text(content) if matches!(style.white_space, WhiteSpace::Pre | WhiteSpace::PreWrap) => ctx.layout_preformatted(content, y, style),
         Node::Text(content) => {
-            let text = content.trim();
+            // Whitespace at either end is the space between this text and
+            // the runs beside it; a line drops it at its start and end.
+            let space = |c: char| c.is_ascii_whitespace();
+            ctx.spaced |= content.starts_with(space);
+            let text = parser::trim_whitespace(content);
             if text.is_empty() {
                 return y;
             }
-            let h = line_height(style.font_size);
-            ctx.boxes.push(LayoutBox {
-                x: ctx.pad + style.indent,
-                y,
-                width: ctx.width - style.indent,
-                height: h,
-                cmd: PaintCmd::Text {
-                    content: text.to_string(),
-                    font_size: style.font_size,
-                    bold: style.bold,
-                    italic: style.italic,
-                    color: style.color,
-                    underline: style.underline,
-                },
+            let y = ctx.layout_run(text, y, style);
+            ctx.spaced = content.ends_with(space);
+            y
+        }
+        Node::Element { tag, attrs, children } => {
+            // Blocks end the line before them and the one their content ends
+            // on, and are set off by their margins, borders and padding.
+            let mut own = element_style(tag, attrs, style, ctx);
+            // Flex items are blocks whatever their display.
+            let block = style.display == Some(Display::Flex)
+                || own.display.map_or(is_block(tag) || ctx.handlers.contains_key(tag.as_str()), |display| display != Display::Inline);
+            let fill = ctx.fill.take();
+            // Floats and absolutely positioned elements are blocks out of the
+            // flow: the line they interrupt stays open, and they take up no
+            // room in it.
+            let before = y;
+            let absolute = own.position == position::Position::Absolute;
+            let open = (absolute || own.float.is_some()).then(|| (ctx.line.take(), std::mem::take(&mut ctx.spaced)));
+            let below_line = match &open {
+                Some((Some(line), _)) => line.bottom(),
+                _ => y,
+            };
+            let (mut float, mut pinned) = (None, None);
+            let y = if absolute {
+                // Placed by its offsets in its containing block, or else
+                // where it would have gone in the flow.
+                let containing = ctx.containing_block();
+                let (containing_left, containing_right, containing_top) = (containing.left, containing.right, containing.top);
+                let [top, right, bottom, left] = own.offsets;
+                let width = match (left, right) {
+                    (Some(left), Some(right)) => containing_right - containing_left - left - right,
+                    _ => shrink_to_fit(tag, attrs, children, &own, containing_right - containing_left, ctx),
+                };
+                let x = match (left, right) {
+                    (Some(left), _) => containing_left + left,
+                    (None, Some(right)) => containing_right - right - width,
+                    (None, None) => ctx.pad + style.indent,
+                };
+                own.indent = x - ctx.pad;
+                own.right = ctx.pad + ctx.width - x - width;
+                pinned = bottom.filter(|_| top.is_none());
+                top.map_or(below_line, |top| containing_top + top)
+            } else if let Some(side) = own.float {
+                // A float is a block to one side of the column, beside the
+                // lines after it.
+                let width = shrink_to_fit(tag, attrs, children, &own, ctx.width - style.indent - style.right, ctx);
+                let (x, top) = ctx.place_float(side, width, ctx.clear(own.clear, below_line, style), style);
+                own.indent = x - ctx.pad;
+                own.right = ctx.pad + ctx.width - x - width;
+                float = Some((side, Rect { x, y: top, width, height: 0.0 }));
+                top
+            } else {
+                y
+            };
+            let block = block || open.is_some();
+            let [mt, mr, mb, ml] = own.margin;
+            let [bt, br, bb, bl] = own.border_widths();
+            let [pt, pr, pb, pl] = own.padding;
+            // Left and right edges of the border box.
+            let (left, right) = (ctx.pad + own.indent + ml, ctx.pad + ctx.width - own.right - mr);
+            let above = if block {
+                own.indent += ml + bl + pl;
+                own.right += mr + br + pr;
+                mt + bt + pt
+            } else {
+                0.0
+            };
+            let y = if block { ctx.end_line(y) } else { y };
+            let y = if block { ctx.clear(own.clear, y, style) } else { y };
+            let (first, first_region) = (ctx.boxes.len(), ctx.regions.len());
+            let marks = ctx.marks();
+            let positioned = own.position != position::Position::Static;
+            if positioned {
+                // Its padding box contains the absolutely positioned elements
+                // inside it; an inline element's is taken as its column.
+                let (left, right) = if block { (left + bl, right - br) } else { (ctx.pad + own.indent, ctx.pad + ctx.width - own.right) };
+                ctx.containing.push(position::Containing::new(left, right, y + mt + bt));
+            }
+            ctx.child_styles.push(None);
+            let lang = attrs.get("lang").map(|lang| lang.trim().to_ascii_lowercase());
+            if let Some(lang) = lang.clone() {
+                ctx.langs.push(lang);
+            }
+            // The background goes behind the element's content, and is as
+            // tall as the element once that is laid out.
+            let background = own.background.filter(|_| block).map(|color| {
+                ctx.boxes.push(LayoutBox { x: left, y: y + mt, width: right - left, height: 0.0, cmd: PaintCmd::FillRect { color } });
+                ctx.boxes.len() - 1
             });
-            y + h
+            let outer = std::mem::replace(&mut ctx.element_start, ctx.boxes.len());
+            let y_end = layout_element(tag, attrs, children, ctx, y + above, &own);
+            ctx.element_start = outer;
+            let (y_end, padding_bottom) = if block {
+                let bottom = (ctx.end_line(y_end) + pb + bb).max(fill.map_or(f32::MIN, |fill| y + fill - mb));
+                if let Some(background) = background {
+                    ctx.boxes[background].height = bottom - y - mt;
+                }
+                ctx.draw_borders(Rect { x: left, y: y + mt, width: right - left, height: bottom - y - mt }, &own);
+                if own.overflow_hidden && own.ellipsis {
+                    ctx.cut_overflow(first, ctx.pad + ctx.width - own.right);
+                }
+                (bottom + mb, bottom - bb)
+            } else {
+                (y_end, y_end)
+            };
+            if positioned {
+                ctx.close_containing_block(padding_bottom);
+            }
+            if own.position == position::Position::Relative {
+                // Moved from where the flow put it, which it still takes up.
+                let [top, right, bottom, left] = own.offsets;
+                let dx = left.or(right.map(|right| -right)).unwrap_or(0.0);
+                let dy = top.or(bottom.map(|bottom| -bottom)).unwrap_or(0.0);
+                let to = ctx.marks();
+                ctx.translate(marks, to, dx, dy);
+            }
+            if let Some((side, rect)) = float {
+                ctx.floats.push(float::Float { side, rect: Rect { height: y_end - rect.y, ..rect } });
+            }
+            if let Some((mut line, spaced)) = open {
+                if let Some(line) = &mut line {
+                    line.out_of_flow.push((marks, ctx.marks()));
+                }
+                (ctx.line, ctx.spaced) = (line, spaced);
+            }
+            if lang.is_some() {
+                ctx.langs.pop();
+            }
+            let extent = ctx.record_element(first, &own);
+            // The control an element laid out, or the first one inside it.
+            let control = (first_region..ctx.regions.len()).find(|&i| ctx.regions[i].kind.is_control());
+            // The region the element itself takes focus as.
+            let mut own = control.filter(|_| matches!(tag.as_str(), "input" | "textarea" | "select" | "button"));
+
+            if let (Some(id), Some(control)) = (attrs.get("id"), own) {
+                ctx.control_ids.entry(id.trim().to_string()).or_insert(control);
+            }
+
+            if let Some(rect) = extent {
+                if let Some(level) = heading_level(tag) {
+                    ctx.headings.push(Heading { level, text: text_content(children), id: attrs.get("id").cloned(), rect });
+                }
+                ctx.record_title(attrs, rect);
+                if tag == "a" {
+                    if let Some(href) = attrs.get("href") {
+                        own = Some(ctx.regions.len());
+                        ctx.regions.push(HitRegion { rect, kind: HitKind::Link(href.trim().to_string()) });
+                    }
+                }
+                if tag == "label" {
+                    match (attrs.get("for"), control) {
+                        (Some(id), _) => ctx.labels.push((rect, id.trim().to_string())),
+                        (None, Some(control)) => ctx.regions.push(HitRegion { rect, kind: HitKind::Label(control) }),
+                        (None, None) => {}
+                    }
+                }
+            }
+
+            if let Some(region) = own {
+                if let Some(index) = attrs.get("tabindex").and_then(|t| t.trim().parse().ok()) {
+                    ctx.tab_indices.insert(region, index);
+                }
+                if attrs.contains_key("autofocus") {
+                    ctx.autofocus.get_or_insert(region);
+                }
+            }
+
+            let fallback = Rect { x: ctx.pad + style.indent, y, width: ctx.width - style.indent, height: y_end - y };
+            ctx.record_anchor(tag, attrs, extent, fallback);
+            if let Some(bottom) = pinned {
+                ctx.pin(marks, y, y_end - y, bottom);
+            }
+            if absolute || float.is_some() {
+                before
+            } else {
+                y_end
+            }
         }
-        Node::Element { tag, attrs, children } => layout_element(tag, attrs, children, ctx, y, style),
     }
 }
 
 fn layout_element(tag: &str, attrs: &HashMap<String, String>, children: &[Node], ctx: &mut Ctx, y: f32, style: &Style) -> f32 {
+    if !ctx.handlers.is_empty() {
+        if let Some(y_end) = layout_custom(tag, attrs, children, ctx, y, style) {
+            return y_end;
+        }
+    }
+
     match tag {
         // ── Skip entirely ──────────────────────────────────────────────────
-        "head" | "title" | "script" | "style" | "meta" | "link" => y,
+        _ if is_hidden(tag) => y,
 
         // ── Transparent containers ─────────────────────────────────────────
-        "html" | "body" | "div" | "section" | "article" | "main" | "header" | "footer" => {
-            layout_children(children, ctx, y, style)
+        "html" | "body" | "div" | "section" | "article" | "main" | "header" | "footer" | "blockquote" | "dl" | "dt" | "dd" => {
+            layout_contents(children, ctx, y, style)
         }
 
         // ── Headings ───────────────────────────────────────────────────────
-        "h1" => heading(children, ctx, y, style, 32.0, 24.0, 16.0, None, None),
-        "h2" => heading(children, ctx, y, style, 24.0, 20.0, 12.0, None, None),
-        "h3" => heading(children, ctx, y, style, 20.0, 16.0,  8.0, None, None),
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => block(children, ctx, y, style),
 
         // ── Paragraph ─────────────────────────────────────────────────────
-        "p" => block(children, ctx, y, style, 0.0, 16.0, style.clone()),
+        "p" | "pre" => block(children, ctx, y, style),
+
+        // ── Tables ─────────────────────────────────────────────────────────
+        "table" => table::layout_table(children, ctx, y, style),
 
         // ── Lists ──────────────────────────────────────────────────────────
-        "ul" | "ol" => {
-            let inner = Style { indent: style.indent + MARKER_INDENT, ..style.clone() };
-            let y = y + 8.0;
-            let y = layout_list(tag, children, ctx, y, &inner);
-            y + 8.0
-        }
+        "ul" | "ol" => layout_list(tag, attrs, children, ctx, y, &Style { lists: style.lists + 1, ..style.clone() }),
 
-        // ── Inline elements (v1: treat as block, pass style through) ───────
-        "strong" => layout_children(children, ctx, y, &Style { bold: true, ..style.clone() }),
-        "em"     => layout_children(children, ctx, y, &Style { italic: true, ..style.clone() }),
-        "a"    => layout_children(children, ctx, y, &Style { color: 0x0000EE, underline: true, ..style.clone() }),
-        "span" => layout_children(children, ctx, y, style),
+        // ── Inline elements: runs on the line around them ──────────────────
+        "strong" | "em" | "a" | "span" => layout_contents(children, ctx, y, style),
+        "q" => layout_quote(children, ctx, y, style),
 
         // ── Void ──────────────────────────────────────────────────────────
+        "br" if ctx.line.is_some() => ctx.end_line(y),
         "br" => y + line_height(style.font_size),
+        // A break opportunity, as between any two runs.
+        "wbr" => y,
         "hr" => {
-            let mid = y + 8.0;
             ctx.boxes.push(LayoutBox {
                 x: ctx.pad,
-                y: mid,
+                y,
                 width: ctx.width,
                 height: 1.0,
                 cmd: PaintCmd::HLine { color: 0xAAAAAA },
             });
-            mid + 1.0 + 8.0
+            y + 1.0
         }
 
         // ── Image ─────────────────────────────────────────────────────────
-        "img" => layout_img(attrs, ctx, y),
+        "img" => layout_img(attrs, ctx, y, style),
+
+        // ── Form controls ─────────────────────────────────────────────────
+        "form" => {
+            ctx.forms.push(Form {
+                action: attrs.get("action").map(|a| a.trim().to_string()).unwrap_or_default(),
+                method: attrs.get("method").map_or("get".into(), |m| m.trim().to_ascii_lowercase()),
+            });
+            let outer = ctx.form.replace(ctx.forms.len() - 1);
+            let y = layout_contents(children, ctx, y, style);
+            ctx.form = outer;
+            y
+        }
+        "input" if is_text_input(attrs) => layout_input(attrs, ctx, y, style),
+        "input" => match attrs.get("type").map(|t| t.trim().to_ascii_lowercase()).as_deref() {
+            Some("checkbox") => layout_toggle(ToggleKind::Checkbox, attrs, ctx, y, style),
+            Some("radio") => layout_toggle(ToggleKind::Radio, attrs, ctx, y, style),
+            Some("submit") => layout_button(ButtonKind::Submit, attrs.get("value").map_or("Submit", |v| v.trim()), attrs, ctx, y, style),
+            Some("reset") => layout_button(ButtonKind::Reset, attrs.get("value").map_or("Reset", |v| v.trim()), attrs, ctx, y, style),
+            Some("button") => layout_button(ButtonKind::Button, attrs.get("value").map_or("", |v| v.trim()), attrs, ctx, y, style),
+            _ => y,
+        },
+        "select" => layout_select(attrs, children, ctx, y, style),
+        "textarea" => layout_textarea(attrs, children, ctx, y, style),
+        "button" => {
+            let kind = match attrs.get("type").map(|t| t.trim().to_ascii_lowercase()).as_deref() {
+                Some("reset") => ButtonKind::Reset,
+                Some("button") => ButtonKind::Button,
+                _ => ButtonKind::Submit,
+            };
+            layout_button(kind, &text_content(children), attrs, ctx, y, style)
+        }
 
         // ── Unknown: transparent ───────────────────────────────────────────
-        _ => layout_children(children, ctx, y, style),
+        _ => layout_contents(children, ctx, y, style),
     }
 }
 
-fn layout_img(attrs: &HashMap<String, String>, ctx: &mut Ctx, y: f32) -> f32 {
+/// Make text box `b` paint nothing.
+fn empty_text(b: &mut LayoutBox) {
+    if let PaintCmd::Text { content, .. } = &mut b.cmd {
+        content.clear();
+        b.width = 0.0;
+    }
+}
+
+/// Whether text box `cmd` paints in `style`.
+fn same_style(cmd: &PaintCmd, style: &Style) -> bool {
+    matches!(*cmd, PaintCmd::Text { font_size, bold, italic, monospace, color, underline, .. }
+        if (font_size, bold, italic, monospace, color, underline) == (style.font_size, style.bold, style.italic, style.monospace, style.color, style.underline))
+}
+
+/// Whether `tag` is never laid out.
+fn is_hidden(tag: &str) -> bool {
+    matches!(tag, "head" | "title" | "script" | "style" | "meta" | "link")
+}
+
+/// Whether `tag` starts and ends lines of its own; everything else (text
+/// elements, unknown tags) flows inline.
+fn is_block(tag: &str) -> bool {
+    matches!(
+        tag,
+        "html" | "body" | "div" | "section" | "article" | "main" | "header" | "footer" | "blockquote" | "p" | "pre" | "h1"
+            | "h2" | "h3" | "h4" | "h5" | "h6" | "ul" | "ol" | "li" | "dl" | "dt" | "dd" | "table" | "caption" | "hr" | "img" | "form" | "input" | "select" | "textarea" | "button"
+    )
+}
+
+/// Lay the element out with the handler registered for `tag`, if there is
+/// one and it takes the element.
+fn layout_custom(tag: &str, attrs: &HashMap<String, String>, children: &[Node], ctx: &mut Ctx, y: f32, style: &Style) -> Option<f32> {
+    let handler = ctx.handlers.get(tag)?.clone();
+    let element = CustomElement {
+        tag,
+        attrs,
+        children,
+        width: ctx.width - style.indent,
+        style: style.into(),
+        base: &ctx.base,
+    };
+    let laid_out = handler(&element)?;
+    let x = ctx.pad + style.indent;
+    ctx.boxes.extend(laid_out.boxes.into_iter().map(|b| LayoutBox { x: x + b.x, y: y + b.y, ..b }));
+    Some(y + laid_out.height.max(0.0))
+}
+
+fn layout_img(attrs: &HashMap<String, String>, ctx: &mut Ctx, y: f32, style: &Style) -> f32 {
     let src = match attrs.get("src") {
         Some(s) => s,
         None => return y,
     };
 
-    let path = ctx.base_dir.join(src);
-    let img = match image::open(&path) {
-        Ok(img) => img,
+    let url = match ctx.base.join(src) {
+        Ok(url) => url,
         Err(e) => {
-            eprintln!("radium: failed to load image {}: {e}", path.display());
+            eprintln!("radium: bad image src '{src}': {e}");
             return y;
         }
     };
 
-    let rgba = img.to_rgba8();
-    let (img_w, img_h) = rgba.dimensions();
-    let data = rgba.into_raw();
-
-    // Scale down proportionally if wider than the content area.
-    let display_w = ctx.width.min(img_w as f32);
-    let scale = display_w / img_w as f32;
-    let display_h = img_h as f32 * scale;
+    if let Some(path) = url.to_file_path() {
+        ctx.assets.push(path);
+    }
+    let image = ctx.images.get(&url.without_fragment().to_string()).cloned();
+    let (display_w, display_h) = image_size(attrs, image.as_ref().map(|img| img.dimensions()), ctx.width - style.indent - style.right);
+    let cmd = match image {
+        Some(data) => PaintCmd::Image { img_width: data.width(), img_height: data.height(), data },
+        None => {
+            // Not decoded yet: reserve a placeholder the image replaces later.
+            let lazy = attrs.get("loading").is_some_and(|l| l.trim().eq_ignore_ascii_case("lazy"));
+            ctx.pending_images.push(PendingImage { url, box_index: ctx.boxes.len(), lazy });
+            PaintCmd::FillRect { color: 0xE8E8E8 }
+        }
+    };
 
     ctx.boxes.push(LayoutBox {
-        x: ctx.pad,
+        x: ctx.pad + style.indent,
         y,
         width: display_w,
         height: display_h,
-        cmd: PaintCmd::Image { data, img_width: img_w, img_height: img_h },
+        cmd,
     });
 
     y + display_h + 8.0
 }
 
-/// Lay out a block element with top/bottom margins.
-fn block(children: &[Node], ctx: &mut Ctx, y: f32, _parent: &Style, mt: f32, mb: f32, style: Style) -> f32 {
-    let y = layout_children(children, ctx, y + mt, &style);
-    y + mb
+/// Display size of an image with the given attributes and `natural` size
+/// (if decoded), at most `max_width` wide. `width` and `height` fix the box
+/// before the pixels arrive, so it does not move when they do; a missing one
+/// follows the aspect ratio, or else the placeholder size.
+fn image_size(attrs: &HashMap<String, String>, natural: Option<(u32, u32)>, max_width: f32) -> (f32, f32) {
+    let dimension = |name: &str| {
+        attrs.get(name)
+            .and_then(|v| v.trim().trim_end_matches("px").parse::<f32>().ok())
+            .filter(|v| v.is_finite() && *v > 0.0)
+    };
+    let natural = natural.filter(|&(w, h)| w > 0 && h > 0).map(|(w, h)| (w as f32, h as f32));
+    let (w, h) = match (dimension("width"), dimension("height"), natural) {
+        (Some(w), Some(h), _) => (w, h),
+        (Some(w), None, Some((nw, nh))) => (w, w * nh / nw),
+        (None, Some(h), Some((nw, nh))) => (h * nw / nh, h),
+        (Some(w), None, None) => (w, IMAGE_PLACEHOLDER_SIZE),
+        (None, Some(h), None) => (IMAGE_PLACEHOLDER_SIZE, h),
+        (None, None, natural) => natural.unwrap_or((IMAGE_PLACEHOLDER_SIZE, IMAGE_PLACEHOLDER_SIZE)),
+    };
+    // Scale down proportionally if wider than the content area.
+    let scale = (max_width / w).min(1.0);
+    (w * scale, h * scale)
 }
 
-/// Layout a heading with optional full-bleed background and bottom border.
-fn heading(
-    children: &[Node],
-    ctx: &mut Ctx,
-    y: f32,
-    parent_style: &Style,
-    font_size: f32,
-    mt: f32,
-    mb: f32,
-    bg: Option<u32>,
-    border: Option<u32>,
-) -> f32 {
-    let style = Style { font_size, bold: true, ..parent_style.clone() };
-    let top = y + mt;
-
-    // Emit background BEFORE children so it appears behind the text.
-    if let Some(color) = bg {
-        let lh = line_height(font_size);
+/// Whether an `<input>` is a single-line text field (other types are not
+/// supported yet and lay out as nothing).
+fn is_text_input(attrs: &HashMap<String, String>) -> bool {
+    let kind = attrs.get("type").map(|t| t.trim().to_ascii_lowercase());
+    matches!(kind.as_deref(), None | Some("" | "text" | "search" | "email" | "url" | "tel" | "password"))
+}
+
+/// A bordered box `size` characters wide holding the value (or placeholder)
+/// on one line.
+fn layout_input(attrs: &HashMap<String, String>, ctx: &mut Ctx, y: f32, style: &Style) -> f32 {
+    let size = attrs.get("size").and_then(|s| s.trim().parse().ok()).filter(|&n: &usize| n > 0).unwrap_or(INPUT_SIZE);
+    let field = InputField {
+        rect: field_rect(ctx, y, style, size, 1),
+        text_box: 0,
+        value: attrs.get("value").cloned().unwrap_or_default(),
+        placeholder: attrs.get("placeholder").map(|p| p.trim().to_string()).unwrap_or_default(),
+        name: attrs.get("name").cloned(),
+        color: if attrs.contains_key("disabled") { DISABLED_TEXT_COLOR } else { style.color },
+        multiline: false,
+        masked: attrs.get("type").is_some_and(|t| t.trim().eq_ignore_ascii_case("password")),
+        rows: 1,
+        cols: None,
+        hard_wrap: false,
+        scroll: 0,
+        form: ctx.form,
+        disabled: attrs.contains_key("disabled"),
+        readonly: attrs.contains_key("readonly"),
+    };
+    push_field(ctx, style, field)
+}
+
+/// A bordered box `cols` characters wide and `rows` lines tall holding its
+/// text, wrapped at `cols` unless `wrap=off`.
+fn layout_textarea(attrs: &HashMap<String, String>, children: &[Node], ctx: &mut Ctx, y: f32, style: &Style) -> f32 {
+    let number = |name: &str, default: usize| attrs.get(name).and_then(|n| n.trim().parse().ok()).filter(|&n: &usize| n > 0).unwrap_or(default);
+    let (cols, rows) = (number("cols", TEXTAREA_COLS), number("rows", TEXTAREA_ROWS));
+    let wrap = attrs.get("wrap").map(|w| w.trim().to_ascii_lowercase());
+
+    let mut value = raw_text(children).replace("\r\n", "\n");
+    // A line break right after the start tag is not part of the text.
+    if value.starts_with('\n') {
+        value.remove(0);
+    }
+    let field = InputField {
+        rect: field_rect(ctx, y, style, cols, rows),
+        text_box: 0,
+        value,
+        placeholder: attrs.get("placeholder").map(|p| p.trim().to_string()).unwrap_or_default(),
+        name: attrs.get("name").cloned(),
+        color: if attrs.contains_key("disabled") { DISABLED_TEXT_COLOR } else { style.color },
+        multiline: true,
+        masked: false,
+        rows,
+        cols: (wrap.as_deref() != Some("off")).then_some(cols),
+        hard_wrap: wrap.as_deref() == Some("hard"),
+        scroll: 0,
+        form: ctx.form,
+        disabled: attrs.contains_key("disabled"),
+        readonly: attrs.contains_key("readonly"),
+    };
+    push_field(ctx, style, field)
+}
+
+/// The border box of a text field `chars` characters wide and `rows` lines tall.
+fn field_rect(ctx: &Ctx, y: f32, style: &Style, chars: usize, rows: usize) -> Rect {
+    let inset = 1.0 + INPUT_PAD;
+    Rect {
+        x: ctx.pad + style.indent,
+        y,
+        width: (chars as f32 * INPUT_CHAR_WIDTH + inset * 2.0).min(ctx.width - style.indent),
+        height: line_height(style.font_size) * rows as f32 + inset * 2.0,
+    }
+}
+
+/// Emit `field`'s border, background and a text box per row, and record it.
+fn push_field(ctx: &mut Ctx, style: &Style, mut field: InputField) -> f32 {
+    let rect = field.rect;
+    let inset = 1.0 + INPUT_PAD;
+    push_frame(ctx, rect, 0xFFFFFF, field.disabled);
+    field.text_box = ctx.boxes.len();
+    for row in 0..field.rows {
         ctx.boxes.push(LayoutBox {
-            x: 0.0,
-            y: top - 6.0,
-            width: ctx.viewport_width,
-            height: lh + 12.0,
-            cmd: PaintCmd::FillRect { color },
+            x: rect.x + inset,
+            y: rect.y + inset + row as f32 * line_height(style.font_size),
+            width: rect.width - inset * 2.0,
+            height: line_height(style.font_size),
+            cmd: PaintCmd::Text { content: String::new(), font_size: style.font_size, bold: false, italic: false, monospace: false, color: style.color, underline: false },
         });
     }
+    refresh_field(&mut ctx.boxes, &field);
+
+    ctx.regions.push(HitRegion { rect, kind: HitKind::Input(ctx.inputs.len()) });
+    ctx.controls.push((ctx.path.clone(), HitKind::Input(ctx.inputs.len())));
+    ctx.inputs.push(field);
+    rect.y + rect.height
+}
+
+/// Push a control's one-pixel border and its `face` inside it, both greyed
+/// out if `disabled`, and return the index of the face box.
+fn push_frame(ctx: &mut Ctx, rect: Rect, face: u32, disabled: bool) -> usize {
+    let (border, face) = if disabled { (DISABLED_BORDER_COLOR, DISABLED_FACE_COLOR) } else { (INPUT_BORDER_COLOR, face) };
+    ctx.boxes.push(LayoutBox { x: rect.x, y: rect.y, width: rect.width, height: rect.height, cmd: PaintCmd::FillRect { color: border } });
+    ctx.boxes.push(LayoutBox {
+        x: rect.x + 1.0,
+        y: rect.y + 1.0,
+        width: rect.width - 2.0,
+        height: rect.height - 2.0,
+        cmd: PaintCmd::FillRect { color: face },
+    });
+    ctx.boxes.len() - 1
+}
+
+/// A square box on its own line, with a mark inset in it (smaller for a
+/// radio button) that is coloured only while it is checked.
+fn layout_toggle(kind: ToggleKind, attrs: &HashMap<String, String>, ctx: &mut Ctx, y: f32, style: &Style) -> f32 {
+    let line = line_height(style.font_size);
+    let rect = Rect { x: ctx.pad + style.indent, y: y + ((line - TOGGLE_SIZE) / 2.0).max(0.0), width: TOGGLE_SIZE, height: TOGGLE_SIZE };
+    let inset = match kind {
+        ToggleKind::Checkbox => 3.0,
+        ToggleKind::Radio => 4.0,
+    };
+    let disabled = attrs.contains_key("disabled");
+
+    push_frame(ctx, rect, 0xFFFFFF, disabled);
+    let mark_box = ctx.boxes.len();
+    ctx.boxes.push(LayoutBox {
+        x: rect.x + inset,
+        y: rect.y + inset,
+        width: rect.width - inset * 2.0,
+        height: rect.height - inset * 2.0,
+        cmd: PaintCmd::FillRect { color: if disabled { DISABLED_FACE_COLOR } else { 0xFFFFFF } },
+    });
+
+    let index = ctx.toggles.len();
+    ctx.toggles.push(Toggle {
+        kind,
+        rect,
+        mark_box,
+        checked: false,
+        name: attrs.get("name").cloned(),
+        value: attrs.get("value").cloned().unwrap_or_else(|| "on".into()),
+        form: ctx.form,
+        disabled,
+    });
+    ctx.regions.push(HitRegion { rect, kind: HitKind::Toggle(index) });
+    ctx.controls.push((ctx.path.clone(), HitKind::Toggle(index)));
+    // A later checked radio button wins over earlier ones in its group.
+    if attrs.contains_key("checked") {
+        set_checked(&mut ctx.boxes, &mut ctx.toggles, index, true);
+    }
+    y + line.max(TOGGLE_SIZE)
+}
 
-    let y = layout_children(children, ctx, top, &style);
+/// A bordered box around `label` on one line, as wide as the label needs.
+fn layout_button(kind: ButtonKind, label: &str, attrs: &HashMap<String, String>, ctx: &mut Ctx, y: f32, style: &Style) -> f32 {
+    let (inset_x, inset_y) = (1.0 + BUTTON_PAD, 1.0 + INPUT_PAD);
+    let rect = Rect {
+        x: ctx.pad + style.indent,
+        y,
+        width: (label.chars().count() as f32 * INPUT_CHAR_WIDTH + inset_x * 2.0).min(ctx.width - style.indent),
+        height: line_height(style.font_size) + inset_y * 2.0,
+    };
 
-    // Emit bottom border AFTER children.
-    if let Some(color) = border {
+    let disabled = attrs.contains_key("disabled");
+    let face_box = push_frame(ctx, rect, BUTTON_FACE_COLOR, disabled);
+    if !label.is_empty() {
         ctx.boxes.push(LayoutBox {
-            x: ctx.pad,
-            y: y + 4.0,
-            width: ctx.width,
-            height: 1.0,
-            cmd: PaintCmd::HLine { color },
+            x: rect.x + inset_x,
+            y: rect.y + inset_y,
+            width: rect.width - inset_x * 2.0,
+            height: line_height(style.font_size),
+            cmd: PaintCmd::Text {
+                content: label.to_string(),
+                font_size: style.font_size,
+                bold: false,
+                italic: false,
+                monospace: false,
+                color: if disabled { DISABLED_TEXT_COLOR } else { style.color },
+                underline: false,
+            },
         });
-        return y + 5.0 + mb; // 4px gap + 1px line
     }
 
-    y + mb
+    ctx.regions.push(HitRegion { rect, kind: HitKind::Button(ctx.buttons.len()) });
+    ctx.controls.push((ctx.path.clone(), HitKind::Button(ctx.buttons.len())));
+    ctx.buttons.push(Button {
+        kind,
+        rect,
+        face_box,
+        label: label.to_string(),
+        name: attrs.get("name").cloned(),
+        value: attrs.get("value").cloned().unwrap_or_default(),
+        form: ctx.form,
+        disabled,
+    });
+    y + rect.height
+}
+
+/// A bordered box as wide as its longest option, showing the chosen one
+/// next to a down arrow. The options themselves are not laid out.
+fn layout_select(attrs: &HashMap<String, String>, children: &[Node], ctx: &mut Ctx, y: f32, style: &Style) -> f32 {
+    fn collect(nodes: &[Node], options: &mut Vec<(SelectOption, bool)>) {
+        for node in nodes {
+            match node {
+                Node::Element { tag, attrs, children } if tag == "option" => {
+                    let label = text_content(children);
+                    let value = attrs.get("value").cloned().unwrap_or_else(|| label.clone());
+                    options.push((SelectOption { label, value }, attrs.contains_key("selected")));
+                }
+                Node::Element { tag, children, .. } if tag == "optgroup" => collect(children, options),
+                _ => {}
+            }
+        }
+    }
+    let mut options = Vec::new();
+    collect(children, &mut options);
+    // The last option marked `selected` wins; without one, the first.
+    let selected = options.iter().rposition(|(_, selected)| *selected).or((!options.is_empty()).then_some(0));
+    let options: Vec<SelectOption> = options.into_iter().map(|(option, _)| option).collect();
+
+    let inset = 1.0 + INPUT_PAD;
+    let longest = options.iter().map(|o| o.label.chars().count()).max().unwrap_or(0);
+    let rect = Rect {
+        x: ctx.pad + style.indent,
+        y,
+        width: (longest as f32 * INPUT_CHAR_WIDTH + inset * 2.0 + SELECT_ARROW_WIDTH).min(ctx.width - style.indent),
+        height: line_height(style.font_size) + inset * 2.0,
+    };
+    let disabled = attrs.contains_key("disabled");
+    let text = |content: String, x: f32, width: f32, color: u32| LayoutBox {
+        x,
+        y: rect.y + inset,
+        width,
+        height: line_height(style.font_size),
+        cmd: PaintCmd::Text { content, font_size: style.font_size, bold: false, italic: false, monospace: false, color, underline: false },
+    };
+
+    push_frame(ctx, rect, 0xFFFFFF, disabled);
+    let text_box = ctx.boxes.len();
+    let label = selected.map(|i| options[i].label.clone()).unwrap_or_default();
+    let (color, arrow_color) = if disabled { (DISABLED_TEXT_COLOR, DISABLED_TEXT_COLOR) } else { (style.color, 0x555555) };
+    ctx.boxes.push(text(label, rect.x + inset, (rect.width - inset * 2.0 - SELECT_ARROW_WIDTH).max(0.0), color));
+    let arrow_x = rect.x + rect.width - 1.0 - SELECT_ARROW_WIDTH;
+    ctx.boxes.push(text("▾".into(), arrow_x + INPUT_PAD, SELECT_ARROW_WIDTH - INPUT_PAD, arrow_color));
+
+    ctx.regions.push(HitRegion { rect, kind: HitKind::Select(ctx.selects.len()) });
+    ctx.controls.push((ctx.path.clone(), HitKind::Select(ctx.selects.len())));
+    ctx.selects.push(Select { rect, text_box, options, selected, name: attrs.get("name").cloned(), form: ctx.form, disabled });
+    y + rect.height
+}
+
+/// Lay out a paragraph-like block.
+fn block(children: &[Node], ctx: &mut Ctx, y: f32, style: &Style) -> f32 {
+    let first = ctx.boxes.len();
+    let y = layout_children(children, ctx, y, style);
+    ctx.blocks.push(first..ctx.boxes.len());
+    y
+}
+
+/// Outline level of a heading tag.
+fn heading_level(tag: &str) -> Option<u8> {
+    match tag {
+        "h1" => Some(1),
+        "h2" => Some(2),
+        "h3" => Some(3),
+        "h4" => Some(4),
+        "h5" => Some(5),
+        "h6" => Some(6),
+        _ => None,
+    }
+}
+
+/// The text under `nodes` as written, whitespace included.
+fn raw_text(nodes: &[Node]) -> String {
+    nodes.iter()
+        .map(|node| match node {
+            Node::Text(text) => text.clone(),
+            Node::Element { children, .. } => raw_text(children),
+        })
+        .collect()
+}
+
+/// How wide an element in `style` sized to its content is, margins
+/// included: its text on one line (or its image), but no wider than
+/// `available`.
+fn shrink_to_fit(tag: &str, attrs: &HashMap<String, String>, children: &[Node], style: &Style, available: f32, ctx: &Ctx) -> f32 {
+    let content = if tag == "img" {
+        let natural = attrs.get("src")
+            .and_then(|src| ctx.base.join(src).ok())
+            .and_then(|url| ctx.images.get(&url.without_fragment().to_string()))
+            .map(|image| image.dimensions());
+        image_size(attrs, natural, available).0
+    } else {
+        content_widths(children, style, ctx).1
+    };
+    (content + style.horizontal_edges()).min(available)
+}
+
+/// How narrow and how wide `nodes` can be laid out in `style`: their
+/// longest word, and their text on one line.
+fn content_widths(nodes: &[Node], style: &Style, ctx: &Ctx) -> (f32, f32) {
+    let text = text_content(nodes);
+    let measure = |text: &str| text.chars().map(|ch| style.advance(ctx.measurer, ch)).sum::<f32>();
+    (text.split_whitespace().map(measure).fold(0.0, f32::max), measure(&text))
+}
+
+/// The text under `nodes`, one space between text nodes.
+fn text_content(nodes: &[Node]) -> String {
+    fn collect<'a>(nodes: &'a [Node], words: &mut Vec<&'a str>) {
+        for node in nodes {
+            match node {
+                Node::Text(text) if !parser::trim_whitespace(text).is_empty() => words.push(parser::trim_whitespace(text)),
+                Node::Text(_) => {}
+                Node::Element { children, .. } => collect(children, words),
+            }
+        }
+    }
+    let mut words = Vec::new();
+    collect(nodes, &mut words);
+    words.join(" ")
+}
+
+/// Lay out an element's children: as flex items if it is a flex container
+/// (`style` being its own), as blocks and runs otherwise.
+fn layout_contents(children: &[Node], ctx: &mut Ctx, y: f32, style: &Style) -> f32 {
+    if style.display == Some(Display::Flex) {
+        flex::layout_flex(children, ctx, y, style)
+    } else {
+        layout_children(children, ctx, y, style)
+    }
 }
 
 fn layout_children(children: &[Node], ctx: &mut Ctx, y: f32, style: &Style) -> f32 {
+    ctx.style_children(style);
     let mut y = y;
-    for child in children {
+    for (i, child) in children.iter().enumerate() {
+        ctx.path.push(i);
         y = layout_node(child, ctx, y, style);
+        ctx.path.pop();
     }
     y
 }
 
-fn layout_list(list_tag: &str, children: &[Node], ctx: &mut Ctx, y: f32, style: &Style) -> f32 {
-    let mut y = y;
-    let mut counter = 1usize;
+/// Quotation marks around the content of a `<q>`, as runs joined to the
+/// text inside.
+fn layout_quote(children: &[Node], ctx: &mut Ctx, y: f32, style: &Style) -> f32 {
+    let (open, close) = quote_marks(ctx.langs.last().map(String::as_str), ctx.quotes);
+    let y = ctx.layout_run(open, y, style);
+    ctx.quotes += 1;
+    let y = layout_children(children, ctx, y, style);
+    ctx.quotes -= 1;
+    ctx.layout_run(close, y, style)
+}
+
+/// Opening and closing quotation marks for a quote nested `depth` quotes
+/// deep (0 = outermost) in language `lang` (a BCP 47 tag, lower-cased);
+/// English ones when it is unknown. Quotes deeper than the second level
+/// use the second level's marks.
+pub(crate) fn quote_marks(lang: Option<&str>, depth: usize) -> (&'static str, &'static str) {
+    let lang = lang.unwrap_or("en");
+    let primary = lang.split(['-', '_']).next().unwrap_or(lang);
+    let [outer, inner] = match primary {
+        "fr" => [("«", "»"), ("“", "”")],
+        "de" | "cs" | "sk" | "sl" | "lt" | "is" => [("„", "“"), ("‚", "‘")],
+        "ru" | "uk" | "be" => [("«", "»"), ("„", "“")],
+        "es" | "it" | "pt" | "ca" | "el" | "nb" | "no" | "nn" => [("«", "»"), ("“", "”")],
+        "pl" | "hu" | "ro" => [("„", "”"), ("«", "»")],
+        "nl" => [("“", "”"), ("‘", "’")],
+        "sv" | "fi" => [("”", "”"), ("’", "’")],
+        "da" => [("»", "«"), ("›", "‹")],
+        "ja" => [("「", "」"), ("『", "』")],
+        "zh" if lang.contains("hant") || lang.ends_with("-tw") || lang.ends_with("-hk") => [("「", "」"), ("『", "』")],
+        _ => [("“", "”"), ("‘", "’")],
+    };
+    if depth == 0 { outer } else { inner }
+}
 
-    // Nesting depth: how many MARKER_INDENT levels deep are we?
-    let depth = (style.indent / MARKER_INDENT).round() as usize;
+fn layout_list(list_tag: &str, attrs: &HashMap<String, String>, children: &[Node], ctx: &mut Ctx, y: f32, style: &Style) -> f32 {
+    ctx.style_children(style);
+    let mut y = y;
+    let mut markers = ListMarkers::new(list_tag, attrs, children, style.lists, ctx.counters.as_deref().unwrap_or_default());
 
-    for child in children {
-        let Node::Element { tag, children: li_children, .. } = child else { continue };
+    for (i, child) in children.iter().enumerate() {
+        let Node::Element { tag, attrs, children: li_children } = child else { continue };
         if tag != "li" { continue }
 
-        let marker = if list_tag == "ol" {
-            format!("{}.", counter)
-        } else {
-            // Different bullet symbol per nesting depth.
-            match depth {
-                1 => "•",
-                2 => "◦",
-                _ => "▪",
-            }
-            .to_string()
-        };
-        counter += 1;
+        let (marker, number) = markers.next_marker();
+
+        let mut item = element_style(tag, attrs, style, ctx);
+        let [mt, mr, mb, ml] = item.margin;
+        let [bt, br, bb, bl] = item.border_widths();
+        let [pt, pr, pb, pl] = item.padding;
+        y += mt;
+        item.indent += ml;
+        item.right += mr;
+        let (left, right) = (ctx.pad + item.indent, ctx.pad + ctx.width - item.right);
 
         // Marker sits in the MARKER_INDENT gutter to the left of content.
-        let marker_x = ctx.pad + style.indent - MARKER_INDENT;
-        let h = line_height(style.font_size);
+        let marker_x = ctx.pad + item.indent - MARKER_INDENT;
+        let h = line_height(item.font_size);
         ctx.boxes.push(LayoutBox {
             x: marker_x,
-            y,
+            y: y + bt + pt,
             width: MARKER_INDENT,
             height: h,
             cmd: PaintCmd::Text {
                 content: marker,
-                font_size: style.font_size,
-                bold: style.bold,
-                italic: style.italic,
+                font_size: item.font_size,
+                bold: item.bold,
+                italic: item.italic,
+                monospace: item.monospace,
                 // Markers are slightly muted.
                 color: 0x555555,
                 underline: false,
             },
         });
+        item.indent += bl + pl;
+        item.right += br + pr;
 
         // Layout the li's children (text nodes, inline elements, nested lists).
-        let after = layout_children(li_children, ctx, y, style);
-        // Advance by at least one line height, then add inter-item gap.
-        y = after.max(y + h) + 4.0;
+        let first = ctx.boxes.len();
+        ctx.path.push(i);
+        ctx.child_styles.push(None);
+        let counted = match (&mut ctx.counters, number) {
+            (Some(counters), Some(number)) => {
+                counters.push(number);
+                true
+            }
+            _ => false,
+        };
+        let lang = attrs.get("lang").map(|lang| lang.trim().to_ascii_lowercase());
+        if let Some(lang) = lang.clone() {
+            ctx.langs.push(lang);
+        }
+        let outer = std::mem::replace(&mut ctx.element_start, first);
+        let after = layout_children(li_children, ctx, y + bt + pt, &item);
+        ctx.element_start = outer;
+        if lang.is_some() {
+            ctx.langs.pop();
+        }
+        if counted {
+            ctx.counters.as_mut().map(Vec::pop);
+        }
+        // Advance by at least one line height.
+        let bottom = ctx.end_line(after).max(y + bt + pt + h) + pb + bb;
+        let rect = Rect { x: left, y, width: right - left, height: bottom - y };
+        ctx.draw_borders(rect, &item);
+        // Items skip `layout_node`, so they record what it would for them.
+        let extent = ctx.record_element(first, &item);
+        if let Some(extent) = extent {
+            ctx.record_title(attrs, extent);
+        }
+        ctx.record_anchor(tag, attrs, extent, rect);
+        ctx.path.pop();
+        ctx.blocks.push(first..ctx.boxes.len());
+        y = bottom + mb;
     }
     y
 }
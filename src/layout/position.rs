@@ -0,0 +1,143 @@
+use super::{Ctx, Rect};
+
+/// How an element is positioned: `static` in the flow, `relative` to where
+/// the flow put it, or `absolute` in its containing block.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub(super) enum Position {
+    #[default]
+    Static,
+    Relative,
+    Absolute,
+}
+
+impl Position {
+    /// `position: static | relative | absolute`, with `sticky` taken as
+    /// `relative` and `fixed` as `absolute` (the page scrolls as a whole).
+    pub(super) fn parse(keyword: Option<&str>) -> Option<Position> {
+        match keyword? {
+            "static" => Some(Position::Static),
+            "relative" | "sticky" => Some(Position::Relative),
+            "absolute" | "fixed" => Some(Position::Absolute),
+            _ => None,
+        }
+    }
+}
+
+/// The padding box of a positioned element being laid out (or the page),
+/// which the absolutely positioned elements inside it are placed in.
+pub(super) struct Containing {
+    pub(super) left: f32,
+    pub(super) right: f32,
+    pub(super) top: f32,
+    /// Those placed by `bottom`, which wait for its bottom to be known.
+    pinned: Vec<Pinned>,
+}
+
+/// An absolutely positioned element laid out at `top`, to be moved up or
+/// down to end `bottom` above the bottom of its containing block.
+struct Pinned {
+    from: Marks,
+    to: Marks,
+    top: f32,
+    /// Margin box height.
+    height: f32,
+    bottom: f32,
+}
+
+/// How much of each of a layout's outputs there is at some point, so what
+/// an element added can be told apart and moved.
+#[derive(Clone, Copy)]
+pub(super) struct Marks {
+    pub(super) boxes: usize,
+    regions: usize,
+    headings: usize,
+    inputs: usize,
+    toggles: usize,
+    buttons: usize,
+    selects: usize,
+    labels: usize,
+    floats: usize,
+    elements: usize,
+}
+
+impl Containing {
+    pub(super) fn new(left: f32, right: f32, top: f32) -> Self {
+        Containing { left, right, top, pinned: Vec::new() }
+    }
+}
+
+impl Ctx<'_> {
+    pub(super) fn marks(&self) -> Marks {
+        Marks {
+            boxes: self.boxes.len(),
+            regions: self.regions.len(),
+            headings: self.headings.len(),
+            inputs: self.inputs.len(),
+            toggles: self.toggles.len(),
+            buttons: self.buttons.len(),
+            selects: self.selects.len(),
+            labels: self.labels.len(),
+            floats: self.floats.len(),
+            elements: self.elements.as_ref().map_or(0, Vec::len),
+        }
+    }
+
+    /// Move everything laid out between `from` and `to` by `dx`, `dy`.
+    /// Anchors stay where they were recorded.
+    pub(super) fn translate(&mut self, from: Marks, to: Marks, dx: f32, dy: f32) {
+        if dx != 0.0 || dy != 0.0 {
+            self.translate_by(from, to, |_| (dx, dy));
+        }
+    }
+
+    /// Move everything laid out between `from` and `to` by what `offset`
+    /// gives for its left edge.
+    pub(super) fn translate_by(&mut self, from: Marks, to: Marks, offset: impl Fn(f32) -> (f32, f32)) {
+        let moved = |rect: &mut Rect| {
+            let (dx, dy) = offset(rect.x);
+            rect.x += dx;
+            rect.y += dy;
+        };
+        for b in &mut self.boxes[from.boxes..to.boxes] {
+            let (dx, dy) = offset(b.x);
+            b.x += dx;
+            b.y += dy;
+        }
+        self.regions[from.regions..to.regions].iter_mut().for_each(|region| moved(&mut region.rect));
+        self.headings[from.headings..to.headings].iter_mut().for_each(|heading| moved(&mut heading.rect));
+        self.inputs[from.inputs..to.inputs].iter_mut().for_each(|input| moved(&mut input.rect));
+        self.toggles[from.toggles..to.toggles].iter_mut().for_each(|toggle| moved(&mut toggle.rect));
+        self.buttons[from.buttons..to.buttons].iter_mut().for_each(|button| moved(&mut button.rect));
+        self.selects[from.selects..to.selects].iter_mut().for_each(|select| moved(&mut select.rect));
+        self.labels[from.labels..to.labels].iter_mut().for_each(|(rect, _)| moved(rect));
+        self.floats[from.floats..to.floats].iter_mut().for_each(|float| moved(&mut float.rect));
+        if let Some(elements) = &mut self.elements {
+            elements[from.elements..to.elements].iter_mut().for_each(|element| moved(&mut element.rect));
+        }
+    }
+
+    /// The containing block absolutely positioned elements are placed in
+    /// now: the innermost positioned element's padding box, or the page's.
+    pub(super) fn containing_block(&self) -> &Containing {
+        self.containing.last().expect("the page is always a containing block")
+    }
+
+    /// Move an absolutely positioned element laid out at `top`, `height`
+    /// tall, once the bottom of its containing block is known.
+    pub(super) fn pin(&mut self, from: Marks, top: f32, height: f32, bottom: f32) {
+        let to = self.marks();
+        if let Some(containing) = self.containing.last_mut() {
+            containing.pinned.push(Pinned { from, to, top, height, bottom });
+        }
+    }
+
+    /// Finish the innermost containing block, whose padding box ends at
+    /// `bottom`: move the elements pinned to its bottom into place.
+    pub(super) fn close_containing_block(&mut self, bottom: f32) {
+        let Some(containing) = self.containing.pop() else { return };
+        for pinned in containing.pinned {
+            let dy = bottom - pinned.bottom - pinned.height - pinned.top;
+            self.translate(pinned.from, pinned.to, 0.0, dy);
+        }
+    }
+}
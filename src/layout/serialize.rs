@@ -0,0 +1,225 @@
+//! `serde::Serialize` for display lists (`--features serde`), so external
+//! tools can consume them as JSON, CBOR, etc.
+//!
+//! Field names match the Rust types. Anchors are emitted sorted by name, and
+//! `Page`'s bookkeeping fields (`blocks`, `assets`, `pending_images`,
+//! `tab_indices`, `autofocus`, `partial`) are left out.
+
+use std::collections::BTreeMap;
+
+use serde::ser::{SerializeStruct, SerializeStructVariant};
+use serde::{Serialize, Serializer};
+
+use super::{
+    Button, ButtonKind, Form, HitKind, HitRegion, InputField, LayoutBox, Page, PaintCmd, Rect, Select, SelectOption,
+    Toggle, ToggleKind,
+};
+
+impl Serialize for Page {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let anchors: BTreeMap<&String, &Rect> = self.anchors.iter().collect();
+        let mut s = serializer.serialize_struct("Page", 8)?;
+        s.serialize_field("boxes", &self.boxes)?;
+        s.serialize_field("regions", &self.regions)?;
+        s.serialize_field("anchors", &anchors)?;
+        s.serialize_field("inputs", &self.inputs)?;
+        s.serialize_field("toggles", &self.toggles)?;
+        s.serialize_field("buttons", &self.buttons)?;
+        s.serialize_field("selects", &self.selects)?;
+        s.serialize_field("forms", &self.forms)?;
+        s.end()
+    }
+}
+
+impl Serialize for LayoutBox {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("LayoutBox", 5)?;
+        s.serialize_field("x", &self.x)?;
+        s.serialize_field("y", &self.y)?;
+        s.serialize_field("width", &self.width)?;
+        s.serialize_field("height", &self.height)?;
+        s.serialize_field("cmd", &self.cmd)?;
+        s.end()
+    }
+}
+
+impl Serialize for PaintCmd {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            PaintCmd::Text { content, font_size, bold, italic, monospace, color, underline } => {
+                let mut s = serializer.serialize_struct_variant("PaintCmd", 0, "Text", 7)?;
+                s.serialize_field("content", content)?;
+                s.serialize_field("font_size", font_size)?;
+                s.serialize_field("bold", bold)?;
+                s.serialize_field("italic", italic)?;
+                s.serialize_field("monospace", monospace)?;
+                s.serialize_field("color", color)?;
+                s.serialize_field("underline", underline)?;
+                s.end()
+            }
+            PaintCmd::FillRect { color } => {
+                let mut s = serializer.serialize_struct_variant("PaintCmd", 1, "FillRect", 1)?;
+                s.serialize_field("color", color)?;
+                s.end()
+            }
+            PaintCmd::HLine { color } => {
+                let mut s = serializer.serialize_struct_variant("PaintCmd", 2, "HLine", 1)?;
+                s.serialize_field("color", color)?;
+                s.end()
+            }
+            PaintCmd::Image { data, img_width, img_height } => {
+                let mut s = serializer.serialize_struct_variant("PaintCmd", 3, "Image", 3)?;
+                s.serialize_field("data", &Bytes(data.as_raw()))?;
+                s.serialize_field("img_width", img_width)?;
+                s.serialize_field("img_height", img_height)?;
+                s.end()
+            }
+        }
+    }
+}
+
+impl Serialize for Rect {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Rect", 4)?;
+        s.serialize_field("x", &self.x)?;
+        s.serialize_field("y", &self.y)?;
+        s.serialize_field("width", &self.width)?;
+        s.serialize_field("height", &self.height)?;
+        s.end()
+    }
+}
+
+impl Serialize for HitRegion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("HitRegion", 2)?;
+        s.serialize_field("rect", &self.rect)?;
+        s.serialize_field("kind", &self.kind)?;
+        s.end()
+    }
+}
+
+impl Serialize for HitKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            HitKind::Link(href) => serializer.serialize_newtype_variant("HitKind", 0, "Link", href),
+            HitKind::Title(title) => serializer.serialize_newtype_variant("HitKind", 1, "Title", title),
+            HitKind::Input(input) => serializer.serialize_newtype_variant("HitKind", 2, "Input", input),
+            HitKind::Toggle(toggle) => serializer.serialize_newtype_variant("HitKind", 3, "Toggle", toggle),
+            HitKind::Button(button) => serializer.serialize_newtype_variant("HitKind", 4, "Button", button),
+            HitKind::Select(select) => serializer.serialize_newtype_variant("HitKind", 5, "Select", select),
+            HitKind::Label(control) => serializer.serialize_newtype_variant("HitKind", 6, "Label", control),
+        }
+    }
+}
+
+impl Serialize for InputField {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("InputField", 15)?;
+        s.serialize_field("rect", &self.rect)?;
+        s.serialize_field("text_box", &self.text_box)?;
+        s.serialize_field("value", &self.value)?;
+        s.serialize_field("placeholder", &self.placeholder)?;
+        s.serialize_field("name", &self.name)?;
+        s.serialize_field("color", &self.color)?;
+        s.serialize_field("multiline", &self.multiline)?;
+        s.serialize_field("masked", &self.masked)?;
+        s.serialize_field("rows", &self.rows)?;
+        s.serialize_field("cols", &self.cols)?;
+        s.serialize_field("hard_wrap", &self.hard_wrap)?;
+        s.serialize_field("scroll", &self.scroll)?;
+        s.serialize_field("form", &self.form)?;
+        s.serialize_field("disabled", &self.disabled)?;
+        s.serialize_field("readonly", &self.readonly)?;
+        s.end()
+    }
+}
+
+impl Serialize for Toggle {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Toggle", 8)?;
+        s.serialize_field("kind", &self.kind)?;
+        s.serialize_field("rect", &self.rect)?;
+        s.serialize_field("mark_box", &self.mark_box)?;
+        s.serialize_field("checked", &self.checked)?;
+        s.serialize_field("name", &self.name)?;
+        s.serialize_field("value", &self.value)?;
+        s.serialize_field("form", &self.form)?;
+        s.serialize_field("disabled", &self.disabled)?;
+        s.end()
+    }
+}
+
+impl Serialize for ToggleKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ToggleKind::Checkbox => serializer.serialize_unit_variant("ToggleKind", 0, "Checkbox"),
+            ToggleKind::Radio => serializer.serialize_unit_variant("ToggleKind", 1, "Radio"),
+        }
+    }
+}
+
+impl Serialize for Button {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Button", 8)?;
+        s.serialize_field("kind", &self.kind)?;
+        s.serialize_field("rect", &self.rect)?;
+        s.serialize_field("face_box", &self.face_box)?;
+        s.serialize_field("label", &self.label)?;
+        s.serialize_field("name", &self.name)?;
+        s.serialize_field("value", &self.value)?;
+        s.serialize_field("form", &self.form)?;
+        s.serialize_field("disabled", &self.disabled)?;
+        s.end()
+    }
+}
+
+impl Serialize for ButtonKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ButtonKind::Submit => serializer.serialize_unit_variant("ButtonKind", 0, "Submit"),
+            ButtonKind::Reset => serializer.serialize_unit_variant("ButtonKind", 1, "Reset"),
+            ButtonKind::Button => serializer.serialize_unit_variant("ButtonKind", 2, "Button"),
+        }
+    }
+}
+
+impl Serialize for Select {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Select", 7)?;
+        s.serialize_field("rect", &self.rect)?;
+        s.serialize_field("text_box", &self.text_box)?;
+        s.serialize_field("options", &self.options)?;
+        s.serialize_field("selected", &self.selected)?;
+        s.serialize_field("name", &self.name)?;
+        s.serialize_field("form", &self.form)?;
+        s.serialize_field("disabled", &self.disabled)?;
+        s.end()
+    }
+}
+
+impl Serialize for SelectOption {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("SelectOption", 2)?;
+        s.serialize_field("label", &self.label)?;
+        s.serialize_field("value", &self.value)?;
+        s.end()
+    }
+}
+
+impl Serialize for Form {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Form", 2)?;
+        s.serialize_field("action", &self.action)?;
+        s.serialize_field("method", &self.method)?;
+        s.end()
+    }
+}
+
+/// Image pixels as a byte string rather than a sequence of numbers.
+struct Bytes<'a>(&'a [u8]);
+
+impl Serialize for Bytes<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0)
+    }
+}
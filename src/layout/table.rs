@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+
+use super::{content_widths, element_style, layout_children, layout_node, Ctx, Rect, Style};
+use crate::parser::dom::Node;
+
+/// Most columns a cell may span, as in browsers.
+const MAX_COLSPAN: usize = 1000;
+
+/// A `<tr>`.
+struct Row<'a> {
+    /// Child indices from the table down to the row.
+    path: Vec<usize>,
+    attrs: &'a HashMap<String, String>,
+    children: &'a [Node],
+}
+
+/// A `<td>` or `<th>` and where it sits in the grid.
+struct Cell<'a> {
+    /// Child indices from the table down to the cell.
+    path: Vec<usize>,
+    children: &'a [Node],
+    row: usize,
+    column: usize,
+    rows: usize,
+    columns: usize,
+    style: Style,
+    /// Narrowest and widest the cell can be laid out, borders and padding
+    /// included: its longest word, and its text on one line.
+    min: f32,
+    max: f32,
+}
+
+/// Lay out a `<table>`: its caption, then its rows (directly inside it or
+/// in `thead`, `tbody` and `tfoot`) as a grid of cells whose columns are as
+/// wide as their content asks for, shrunk towards its longest words when
+/// that does not fit. Each cell's borders are drawn around its row's full
+/// height.
+pub(super) fn layout_table(children: &[Node], ctx: &mut Ctx, y: f32, style: &Style) -> f32 {
+    ctx.style_children(style);
+    let mut y = y;
+    for (i, child) in children.iter().enumerate() {
+        if matches!(child, Node::Element { tag, .. } if tag == "caption") {
+            ctx.path.push(i);
+            y = layout_node(child, ctx, y, style);
+            ctx.path.pop();
+        }
+    }
+
+    let rows = rows(children);
+    let mut cells = Vec::new();
+    // Which columns of each row earlier cells already take.
+    let mut taken: Vec<Vec<bool>> = vec![Vec::new(); rows.len()];
+    for (row, Row { path, attrs, children: row_children }) in rows.iter().enumerate() {
+        let row_style = element_style("tr", attrs, style, ctx);
+        for (i, child) in row_children.iter().enumerate() {
+            let Node::Element { tag, attrs, children } = child else { continue };
+            if tag != "td" && tag != "th" {
+                continue;
+            }
+            let column = (0..).find(|&c| !taken[row].get(c).copied().unwrap_or(false)).unwrap_or(0);
+            let columns = span(attrs, "colspan").unwrap_or(1).clamp(1, MAX_COLSPAN);
+            // `rowspan="0"` spans the rest of the table.
+            let rows_left = rows.len() - row;
+            let rows = span(attrs, "rowspan").map_or(1, |n| if n == 0 { rows_left } else { n.min(rows_left) });
+            for taken in &mut taken[row..row + rows] {
+                if taken.len() < column + columns {
+                    taken.resize(column + columns, false);
+                }
+                taken[column..column + columns].fill(true);
+            }
+            let style = element_style(tag, attrs, &row_style, ctx);
+            let (min, max) = content_widths(children, &style, ctx);
+            let [_, br, _, bl] = style.border_widths();
+            let edges = bl + style.padding[3] + style.padding[1] + br;
+            let (min, max) = (min + edges, max + edges);
+            let path = [path.as_slice(), &[i]].concat();
+            cells.push(Cell { path, children, row, column, rows, columns, style, min, max });
+        }
+    }
+
+    let widths = column_widths(&cells, ctx.width - style.indent - style.right);
+    let mut lefts = vec![ctx.pad + style.indent];
+    for width in &widths {
+        lefts.push(lefts[lefts.len() - 1] + width);
+    }
+
+    // Lay the cells out row by row; a row is as tall as its tallest cell,
+    // and the last row a cell spans grows to fit it if the rows together
+    // are not tall enough.
+    let mut tops = vec![y];
+    let mut heights = vec![0.0; cells.len()];
+    for row in 0..rows.len() {
+        let top = tops[row];
+        let mut bottom = top;
+        for (cell, height) in cells.iter_mut().zip(&mut heights).filter(|(cell, _)| cell.row == row) {
+            let [bt, br, bb, bl] = cell.style.border_widths();
+            let [pt, pr, pb, pl] = cell.style.padding;
+            let (left, right) = (lefts[cell.column], lefts[cell.column + cell.columns]);
+            cell.style.indent = left - ctx.pad + bl + pl;
+            cell.style.right = ctx.pad + ctx.width - right + br + pr;
+            let first = ctx.boxes.len();
+            ctx.path.extend(&cell.path);
+            ctx.child_styles.push(None);
+            let end = layout_children(cell.children, ctx, top + bt + pt, &cell.style);
+            let end = ctx.end_line(end) + pb + bb;
+            ctx.record_element(first, &cell.style);
+            ctx.path.truncate(ctx.path.len() - cell.path.len());
+            ctx.blocks.push(first..ctx.boxes.len());
+            *height = end - top;
+            if cell.rows == 1 {
+                bottom = f32::max(bottom, end);
+            }
+        }
+        for (cell, height) in cells.iter().zip(&heights).filter(|(cell, _)| cell.rows > 1 && cell.row + cell.rows == row + 1) {
+            bottom = bottom.max(tops[cell.row] + height);
+        }
+        tops.push(bottom);
+        for cell in cells.iter().filter(|cell| cell.row + cell.rows == row + 1) {
+            let (left, right) = (lefts[cell.column], lefts[cell.column + cell.columns]);
+            let (top, bottom) = (tops[cell.row], tops[row + 1]);
+            ctx.draw_borders(Rect { x: left, y: top, width: right - left, height: bottom - top }, &cell.style);
+        }
+    }
+    tops[rows.len()]
+}
+
+/// The table's rows, in order.
+fn rows(children: &[Node]) -> Vec<Row<'_>> {
+    let mut rows = Vec::new();
+    for (i, child) in children.iter().enumerate() {
+        let Node::Element { tag, attrs, children } = child else { continue };
+        match tag.as_str() {
+            "tr" => rows.push(Row { path: vec![i], attrs, children }),
+            "thead" | "tbody" | "tfoot" => {
+                for (j, row) in children.iter().enumerate() {
+                    if let Node::Element { tag, attrs, children } = row {
+                        if tag == "tr" {
+                            rows.push(Row { path: vec![i, j], attrs, children });
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    rows
+}
+
+/// A `colspan` or `rowspan`.
+fn span(attrs: &HashMap<String, String>, name: &str) -> Option<usize> {
+    attrs.get(name)?.trim().parse().ok()
+}
+
+/// Widths of the grid's columns: as wide as their widest cell's content on
+/// one line if the table fits in `available` that way; as narrow as their
+/// longest words if even that does not fit; otherwise in between, each
+/// column giving up the same share of the room its content would take
+/// beyond its longest word. Cells spanning several columns widen them
+/// evenly where those are too narrow for them.
+fn column_widths(cells: &[Cell], available: f32) -> Vec<f32> {
+    let count = cells.iter().map(|cell| cell.column + cell.columns).max().unwrap_or(0);
+    let (mut min, mut max) = (vec![0.0f32; count], vec![0.0f32; count]);
+    let mut by_span: Vec<&Cell> = cells.iter().collect();
+    by_span.sort_by_key(|cell| cell.columns);
+    for cell in by_span {
+        let columns = cell.column..cell.column + cell.columns;
+        for (widths, wanted) in [(&mut min, cell.min), (&mut max, cell.max)] {
+            let short = wanted - widths[columns.clone()].iter().sum::<f32>();
+            if short > 0.0 {
+                widths[columns.clone()].iter_mut().for_each(|width| *width += short / cell.columns as f32);
+            }
+        }
+    }
+    for (max, min) in max.iter_mut().zip(&min) {
+        *max = max.max(*min);
+    }
+    let (min_total, max_total) = (min.iter().sum::<f32>(), max.iter().sum::<f32>());
+    if max_total <= available {
+        max
+    } else if min_total >= available {
+        min
+    } else {
+        let share = (available - min_total) / (max_total - min_total);
+        min.iter().zip(&max).map(|(min, max)| min + (max - min) * share).collect()
+    }
+}
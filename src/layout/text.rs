@@ -0,0 +1,271 @@
+use super::hyphenate::Hyphenator;
+use super::{LayoutBox, Page, PaintCmd};
+
+/// Plain text of a page with a back-reference from every character to the
+/// text box it was laid out in.
+pub struct TextIndex {
+    /// Text of every box, joined by single spaces unless the boxes touch
+    /// (runs laid out without whitespace between them).
+    pub chars: Vec<char>,
+    /// `(box index, index of the box's first char in chars)`, in document order.
+    spans: Vec<(usize, usize)>,
+}
+
+impl TextIndex {
+    pub fn new(page: &Page) -> Self {
+        let mut chars = Vec::new();
+        let mut spans = Vec::new();
+        let mut last: Option<&LayoutBox> = None;
+        for (i, b) in page.boxes.iter().enumerate() {
+            let PaintCmd::Text { content, .. } = &b.cmd else { continue };
+            let touches = last.is_some_and(|last| {
+                (last.x + last.width - b.x).abs() < 0.01 && last.y < b.y + b.height && b.y < last.y + last.height
+            });
+            if !chars.is_empty() && !touches {
+                chars.push(' ');
+            }
+            last = Some(b);
+            spans.push((i, chars.len()));
+            chars.extend(content.chars());
+        }
+        TextIndex { chars, spans }
+    }
+
+    /// Box index and char offset within that box for position `at` in `chars`.
+    /// A joining space maps to the end of the box before it.
+    pub fn locate(&self, at: usize) -> Option<(usize, usize)> {
+        let span = self.spans.partition_point(|&(_, start)| start <= at).checked_sub(1)?;
+        let (box_index, start) = self.spans[span];
+        Some((box_index, at - start))
+    }
+}
+
+/// Measures text for layout, in the units of `font_size`. The renderer's
+/// [`FontSet`](crate::renderer::FontSet) implements it with the advances its
+/// faces memoize for painting, so both stages measure alike and share one
+/// cache.
+pub trait TextMeasurer {
+    /// Advance width of `ch` in the given face.
+    fn advance(&self, ch: char, bold: bool, italic: bool, font_size: f32) -> f32;
+
+    /// Advance width of `ch` in the given monospace face; by default, as in
+    /// the proportional one.
+    fn monospace_advance(&self, ch: char, bold: bool, italic: bool, font_size: f32) -> f32 {
+        self.advance(ch, bold, italic, font_size)
+    }
+
+    /// How far below the top of a line of text in the given face its
+    /// baseline is: the face's ascent, which painting draws the glyphs of a
+    /// box that far below its top by. By default, four fifths of an em.
+    fn ascent(&self, _bold: bool, _italic: bool, _monospace: bool, font_size: f32) -> f32 {
+        font_size * 0.8
+    }
+
+    /// How far a line of text in the given face reaches below its baseline:
+    /// the rest of its 1.4 em height, descenders and leading.
+    fn descent(&self, bold: bool, italic: bool, monospace: bool, font_size: f32) -> f32 {
+        font_size * 1.4 - self.ascent(bold, italic, monospace, font_size)
+    }
+
+    /// Total advance width of `text`.
+    fn width(&self, text: &str, bold: bool, italic: bool, font_size: f32) -> f32 {
+        text.chars().map(|ch| self.advance(ch, bold, italic, font_size)).sum()
+    }
+}
+
+/// Measures text without fonts: half an em for each character, a whole em
+/// for a wide one, nothing for the invisible ones. For laying out where no
+/// fonts are loaded (the paint-command FFI, `--dump-layout`) and in tests,
+/// which then do not depend on the fonts installed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Estimate;
+
+impl TextMeasurer for Estimate {
+    fn advance(&self, ch: char, _bold: bool, _italic: bool, font_size: f32) -> f32 {
+        match ch {
+            SOFT_HYPHEN | ZERO_WIDTH_SPACE | WORD_JOINER | '\u{FEFF}' => 0.0,
+            _ if is_wide(ch) => font_size,
+            _ => font_size / 2.0,
+        }
+    }
+}
+
+/// One line of text as a [`LineBreaker`] broke it.
+pub struct Line {
+    pub text: String,
+    /// Advance width of `text`.
+    pub width: f32,
+}
+
+/// Breaks text (whitespace already collapsed) into lines one at a time,
+/// each as wide as the caller has room for, at the last break opportunity
+/// (see [`can_break_between`]) that keeps it within that width; a word
+/// wider than a line by itself is cut where it overflows. The space a line
+/// breaks at is dropped, and a line that breaks at a soft hyphen, or
+/// inside a word where it was [hyphenated](LineBreaker::hyphenate), ends in
+/// a visible hyphen.
+pub struct LineBreaker<F> {
+    chars: Vec<char>,
+    advances: Vec<f32>,
+    /// Whether a hyphen may go before each character; empty without
+    /// hyphenation.
+    hyphens: Vec<bool>,
+    /// Where the next line starts, in `chars`.
+    start: usize,
+    advance: F,
+}
+
+impl<F: Fn(char) -> f32> LineBreaker<F> {
+    /// Break `text`, `advance` giving the advance width of a character.
+    pub fn new(text: &str, advance: F) -> Self {
+        let chars: Vec<char> = text.chars().collect();
+        let advances = chars.iter().map(|&ch| advance(ch)).collect();
+        LineBreaker { chars, advances, hyphens: Vec::new(), start: 0, advance }
+    }
+
+    /// Let lines also break inside words, where `hyphenator` finds they
+    /// can be hyphenated.
+    pub fn hyphenate(&mut self, hyphenator: &Hyphenator) {
+        self.hyphens = vec![false; self.chars.len()];
+        let mut start = 0;
+        while start < self.chars.len() {
+            let len = self.chars[start..].iter().take_while(|&&ch| ch.is_alphabetic() && !is_wide(ch)).count();
+            for point in hyphenator.points(&self.chars[start..start + len]) {
+                self.hyphens[start + point] = true;
+            }
+            start += len.max(1);
+        }
+    }
+
+    /// Whether a line from `start` can be hyphenated before character `at`
+    /// and still, hyphen and all, be at most `width` wide.
+    fn hyphen_fits(&self, start: usize, at: usize, width: f32) -> bool {
+        self.hyphens.get(at) == Some(&true) && self.advances[start..at].iter().sum::<f32>() + (self.advance)('-') <= width
+    }
+
+    /// Whether every line has been taken.
+    pub fn is_done(&self) -> bool {
+        self.start >= self.chars.len()
+    }
+
+    /// Advance width of the next line's first word: up to its first break
+    /// opportunity.
+    pub fn first_word_width(&self) -> f32 {
+        let chars = &self.chars;
+        let end = (self.start + 1..chars.len())
+            .find(|&i| chars[i] == ' ' || can_break_between(chars[i - 1], chars[i]))
+            .unwrap_or(chars.len());
+        self.advances[self.start..end].iter().sum()
+    }
+
+    /// How wide the rest of the text is on one line.
+    pub fn rest_width(&self) -> f32 {
+        self.advances[self.start..].iter().sum()
+    }
+
+    /// The next line, at most `width` wide. A `fresh` line (of its own)
+    /// takes at least one character, however narrow; one that continues a
+    /// line other text already started is empty if not even the first word
+    /// fits, so the text starts on a new line instead.
+    pub fn next_line(&mut self, width: f32, fresh: bool) -> Option<Line> {
+        let (chars, advances, start) = (&self.chars, &self.advances, self.start);
+        if self.is_done() {
+            return None;
+        }
+        let (mut end, mut used) = if fresh { (start + 1, advances[start]) } else { (start, 0.0) };
+        while end < chars.len() && used + advances[end] <= width {
+            used += advances[end];
+            end += 1;
+        }
+        let (at, hyphenated) = if end == chars.len() || chars[end] == ' ' {
+            (end, false)
+        } else {
+            let found = (start + 1..=end).rev().find_map(|i| {
+                if can_break_between(chars[i - 1], chars[i]) {
+                    Some((i, false))
+                } else {
+                    self.hyphen_fits(start, i, width).then_some((i, true))
+                }
+            });
+            found.unwrap_or((if fresh { end } else { start }, false))
+        };
+        let mut line: String = chars[start..at].iter().collect();
+        let mut line_width: f32 = advances[start..at].iter().sum();
+        // Only the last line keeps its trailing spaces (in preformatted
+        // text, where a run may end in some).
+        while at < chars.len() && line.ends_with(' ') {
+            line.pop();
+            line_width -= (self.advance)(' ');
+        }
+        if at < chars.len() && line.ends_with(SOFT_HYPHEN) {
+            line.pop();
+            line.push('-');
+            line_width += (self.advance)('-');
+        }
+        if hyphenated {
+            line.push('-');
+            line_width += (self.advance)('-');
+        }
+        self.start = at;
+        while self.chars.get(self.start) == Some(&' ') {
+            self.start += 1;
+        }
+        Some(Line { text: line, width: line_width })
+    }
+}
+
+/// U+00AD, a break opportunity inside a word that is invisible unless a
+/// line breaks at it, where it shows as a hyphen.
+pub const SOFT_HYPHEN: char = '\u{AD}';
+
+/// U+200B, a break opportunity inside a word that takes no space.
+pub const ZERO_WIDTH_SPACE: char = '\u{200B}';
+
+/// U+2060, which keeps the characters on either side on one line and takes
+/// no space.
+pub const WORD_JOINER: char = '\u{2060}';
+
+/// Whether `ch` forbids a line break on either side of it: the no-break
+/// space, figure space and narrow no-break space, the word joiner, and the
+/// zero-width no-break space.
+pub fn is_no_break(ch: char) -> bool {
+    matches!(ch, '\u{A0}' | '\u{2007}' | '\u{202F}' | WORD_JOINER | '\u{FEFF}')
+}
+
+/// Whether `ch` is East Asian Wide or Fullwidth (UAX #11): ideographs, kana,
+/// hangul, CJK punctuation and fullwidth forms, which take two columns.
+/// Ambiguous-width characters (Greek, Cyrillic, box drawing, ...) are not:
+/// outside a known East Asian context they take one, as UAX #11 advises.
+pub fn is_wide(ch: char) -> bool {
+    matches!(ch as u32,
+        0x1100..=0x115F         // Hangul Jamo initials
+        | 0x2E80..=0x303E       // CJK radicals, ideographic description, CJK symbols and punctuation
+        | 0x3041..=0x33FF       // Kana, bopomofo, Hangul compatibility jamo, CJK compatibility
+        | 0x3400..=0x4DBF       // CJK Extension A
+        | 0x4E00..=0x9FFF       // CJK Unified Ideographs
+        | 0xA000..=0xA4CF       // Yi
+        | 0xAC00..=0xD7A3       // Hangul syllables
+        | 0xF900..=0xFAFF       // CJK compatibility ideographs
+        | 0xFE30..=0xFE4F       // CJK compatibility forms
+        | 0xFF00..=0xFF60       // Fullwidth forms
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F     // Pictographs and emoticons
+        | 0x20000..=0x3FFFD)    // CJK Extensions B and on
+}
+
+/// Columns `ch` takes in a fixed-width field: two if it is wide, else one.
+pub fn columns(ch: char) -> usize {
+    if is_wide(ch) { 2 } else { 1 }
+}
+
+/// Whether a line may break between `before` and `after`: after a space, a
+/// soft hyphen or a zero-width space, and on either side of a wide character, except before closing
+/// punctuation and small kana that must not start a line. Never next to a
+/// no-break character.
+pub fn can_break_between(before: char, after: char) -> bool {
+    const NO_LINE_START: &str = "、。，．：；？！）」』】〕〉》〗〙〟ー々〻ぁぃぅぇぉっゃゅょゎァィゥェォッャュョヮヵヶ・･）］｝";
+    if is_no_break(before) || is_no_break(after) {
+        return false;
+    }
+    matches!(before, ' ' | SOFT_HYPHEN | ZERO_WIDTH_SPACE) || ((is_wide(before) || is_wide(after)) && !NO_LINE_START.contains(after))
+}
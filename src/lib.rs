@@ -0,0 +1,230 @@
+//! radium's rendering pipeline as a library.
+//!
+//! [`parse`] turns HTML into a [`Document`], [`layout()`] positions it for a
+//! [`Viewport`] as a [`DisplayList`], and [`render`] paints that into a
+//! pixel buffer (or [`paint`] onto any [`PaintBackend`]). The `radium` binary is a thin shell around these modules
+//! that adds windows, navigation and networking.
+
+pub mod config;
+pub mod control;
+pub mod css;
+pub mod document;
+mod error;
+pub mod dump;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod layout;
+pub mod net;
+pub mod parser;
+pub mod renderer;
+pub mod stats;
+#[cfg(target_arch = "wasm32")]
+pub mod web;
+
+use std::io::Write;
+
+use image::RgbaImage;
+
+use layout::{PendingImage, RemoteImages, TextMeasurer};
+use net::loader::{self, Kind, Resource};
+use net::Url;
+use parser::dom::Node;
+use renderer::backend::{PaintBackend, Raster};
+
+pub use error::{Error, Result};
+pub use renderer::{load_font_set, FontConfig, FontSet, Theme};
+
+/// A parsed document and the URL its relative references resolve against.
+#[derive(Debug)]
+pub struct Document {
+    pub nodes: Vec<Node>,
+    pub base: Url,
+}
+
+/// Paint boxes and interactive regions of a laid-out document.
+pub type DisplayList = layout::Page;
+
+/// A pixel buffer and how the document is placed in it.
+#[derive(Debug, Clone, Copy)]
+pub struct Viewport {
+    /// Buffer size in physical pixels.
+    pub width: u32,
+    pub height: u32,
+    /// Physical pixels per logical (CSS) pixel.
+    pub scale: f32,
+    /// Logical document offset shown at the top of the buffer.
+    pub scroll_y: f32,
+    pub theme: Theme,
+}
+
+impl Viewport {
+    /// An unscaled, unscrolled viewport of `width × height` pixels.
+    pub fn new(width: u32, height: u32) -> Viewport {
+        Viewport { width, height, scale: 1.0, scroll_y: 0.0, theme: Theme::default() }
+    }
+}
+
+/// Parse `html`. Relative URLs resolve against the working directory.
+pub fn parse(html: &str) -> Document {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let mut base = Url::from_file_path(&cwd);
+    base.path.push('/');
+    Document { nodes: parser::dom::build_tree(parser::tokenize(html)), base }
+}
+
+/// Lay `document` out at the viewport's logical width, wrapping its text as
+/// `fonts` (usually the [`FontSet`] it will be rendered with, or else a
+/// [`layout::Estimate`]) measure it. Local images are read from disk;
+/// remote ones are left as placeholders.
+pub fn layout(document: &Document, viewport: Viewport, fonts: &dyn TextMeasurer) -> DisplayList {
+    layout_loading_images(document, viewport.width as f32 / viewport.scale, false, fonts)
+}
+
+/// Paint `list` into `buffer` (`0RGB` pixels, row-major, `width × height`)
+/// over the theme's background. Fails if `buffer` is smaller than the
+/// viewport.
+pub fn render(list: &DisplayList, buffer: &mut [u32], viewport: Viewport, fonts: &FontSet) -> Result<()> {
+    let mut target = Raster::new(buffer, viewport.width, viewport.height)?;
+    paint(list, &mut target, viewport, fonts);
+    Ok(())
+}
+
+/// Like [`render`], but into `buffer` as RGBA8 bytes (`width × height × 4`,
+/// row-major, opaque), the layout of an `image::RgbaImage` or a canvas's
+/// `ImageData`.
+pub fn render_rgba(list: &DisplayList, buffer: &mut [u8], viewport: Viewport, fonts: &FontSet) -> Result<()> {
+    let Viewport { width, height, .. } = viewport;
+    let len = buffer.len() / 4;
+    if len < width as usize * height as usize {
+        return Err(Error::Buffer { len, width, height });
+    }
+    let mut target = Raster::blank(width, height, 0);
+    paint(list, &mut target, viewport, fonts);
+    buffer[..width as usize * height as usize * 4].copy_from_slice(&target.to_rgba());
+    Ok(())
+}
+
+/// Paint `list` onto any [`PaintBackend`], which sets the viewport's size.
+pub fn paint(list: &DisplayList, target: &mut impl PaintBackend, viewport: Viewport, fonts: &FontSet) {
+    let view = renderer::paint::Viewport {
+        scale: viewport.scale,
+        scroll_y: viewport.scroll_y,
+        top: 0.0,
+        left: 0.0,
+        scrollbar: false,
+        theme: viewport.theme,
+    };
+    renderer::paint::render_frame(target, &view, list, fonts, None, &[]);
+}
+
+// ── Headless rendering ────────────────────────────────────────────────────────
+
+/// Settings for [`render_to_rgba`] and [`screenshot`].
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// Physical pixels per logical pixel.
+    pub scale: f32,
+    /// Element id to scroll to the top of the image.
+    pub fragment: Option<String>,
+    pub theme: Theme,
+    pub fonts: FontConfig,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions { scale: 1.0, fragment: None, theme: Theme::default(), fonts: FontConfig::default() }
+    }
+}
+
+/// Render `html` into a `width × height` image without opening a window.
+/// Relative URLs resolve against the working directory.
+pub fn render_to_rgba(html: &str, width: u32, height: u32, options: &RenderOptions) -> Result<RgbaImage> {
+    screenshot(&parse(html), width, height, options)
+}
+
+/// Render `document` into a `width × height` image without opening a window.
+/// Unlike [`layout()`], remote images are fetched (synchronously) first.
+pub fn screenshot(document: &Document, width: u32, height: u32, options: &RenderOptions) -> Result<RgbaImage> {
+    let fonts = FontSet::load(&options.fonts)?;
+    let list = headless_layout(document, width, options, &fonts);
+    let scroll_y = options.fragment.as_ref()
+        .and_then(|f| list.anchors.get(f))
+        .map_or(0.0, |rect| rect.y);
+    let viewport = Viewport { width, height, scale: options.scale, scroll_y, theme: options.theme };
+    let mut target = Raster::blank(width, height, 0);
+    paint(&list, &mut target, viewport, &fonts);
+    let rgba = target.to_rgba();
+    let len = rgba.len() / 4;
+    RgbaImage::from_raw(width, height, rgba).ok_or(Error::Buffer { len, width, height })
+}
+
+/// Rows rasterized at a time by [`full_page_png`].
+const STRIP_HEIGHT: u32 = 512;
+
+/// Render the entire height of `document`, `width` pixels wide, as a PNG
+/// written to `out`. The page is rasterized and encoded in strips, so memory
+/// use stays bounded however long the document is. `options.fragment` is
+/// ignored.
+pub fn full_page_png(document: &Document, width: u32, options: &RenderOptions, out: impl Write) -> Result<()> {
+    let fonts = FontSet::load(&options.fonts)?;
+    let list = headless_layout(document, width, options, &fonts);
+    let height = ((list.height() * options.scale).ceil() as u32).max(1);
+
+    let mut encoder = png::Encoder::new(out, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut header = encoder.write_header().map_err(encode_error)?;
+    let mut writer = header.stream_writer().map_err(encode_error)?;
+
+    for top in (0..height).step_by(STRIP_HEIGHT as usize) {
+        let rows = STRIP_HEIGHT.min(height - top);
+        let mut strip = Raster::blank(width, rows, 0);
+        let viewport = Viewport {
+            width,
+            height: rows,
+            scale: options.scale,
+            scroll_y: top as f32 / options.scale,
+            theme: options.theme,
+        };
+        paint(&list, &mut strip, viewport, &fonts);
+        writer.write_all(&strip.to_rgb())?;
+    }
+    writer.finish().and_then(|()| header.finish()).map_err(encode_error)
+}
+
+fn encode_error(e: png::EncodingError) -> Error {
+    match e {
+        png::EncodingError::IoError(e) => Error::Io(e),
+        e => Error::Encode(e.to_string()),
+    }
+}
+
+/// Lay `document` out for a `width`-pixel image, fetching remote images
+/// synchronously so none is left as a placeholder.
+fn headless_layout(document: &Document, width: u32, options: &RenderOptions, fonts: &dyn TextMeasurer) -> DisplayList {
+    layout_loading_images(document, width as f32 / options.scale, true, fonts)
+}
+
+/// Lay `document` out `width` logical pixels wide, reading the images the
+/// layout is missing (only local ones, unless `remote`) and laying it out
+/// again with them.
+fn layout_loading_images(document: &Document, width: f32, remote: bool, fonts: &dyn TextMeasurer) -> DisplayList {
+    let mut images = RemoteImages::new();
+    let list = layout::layout(&document.nodes, width, &document.base, &images, fonts);
+    let missing: Vec<_> = list.pending_images.iter()
+        .filter(|pending| remote || pending.url.to_file_path().is_some())
+        .collect();
+    if missing.is_empty() {
+        return list;
+    }
+    for PendingImage { url, .. } in missing {
+        match loader::load(url, Kind::Image) {
+            Ok(Resource::Image(image)) => {
+                images.insert(url.without_fragment().to_string(), image);
+            }
+            Ok(Resource::Bytes(_)) => {}
+            Err(e) => eprintln!("radium: failed to load image {url}: {e}"),
+        }
+    }
+    layout::layout(&document.nodes, width, &document.base, &images, fonts)
+}
@@ -1,39 +1,101 @@
-mod parser;
-mod layout;
-mod renderer;
+mod cli;
 
-use std::env;
-use std::path::Path;
+use std::fs::File;
+use std::io::{self, BufWriter};
+
+use radium::document::{ContentKind, Source};
+use radium::layout::{self, Estimate};
+use radium::{config, control, dump, parser, renderer, Document, Error, RenderOptions, Viewport};
+
+use cli::Dump;
+
+/// Image size for `--screenshot` without `--width`/`--height`, matching a new window.
+const SCREENSHOT_SIZE: (u32, u32) = (800, 600);
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        eprintln!("Usage: radium <directory>");
-        std::process::exit(1);
-    }
+    let mut defaults = renderer::Options::default();
+    config::load(&mut defaults);
+    let cli::Args { target, mut options, markdown, screenshot, full_page, dump, control } = cli::parse(defaults);
+    layout::set_user_stylesheet(options.user_stylesheet.as_deref());
 
-    let dir = Path::new(&args[1]);
+    // A trailing `#fragment` selects the element to scroll to on open.
+    // A target that cannot be resolved still opens, showing an error page.
+    let source = target.map(|target| {
+        let (mut source, fragment) =
+            Source::resolve(&target).unwrap_or_else(|_| (Source::unresolved(&target), None));
+        options.fragment = fragment;
+        if markdown {
+            source.kind = Some(ContentKind::Markdown);
+        }
+        source
+    });
 
-    if !dir.is_dir() {
-        eprintln!("Error: '{}' is not a directory", dir.display());
-        std::process::exit(1);
+    if control {
+        if let Err(e) = control::run(io::stdin().lock(), io::stdout().lock(), &options, source) {
+            eprintln!("radium: {e}");
+            std::process::exit(1);
+        }
+        return;
     }
+    let mut source = source.expect("cli::parse requires a document without --control");
 
-    let html_path = dir.join("index.html");
-
-    if !html_path.exists() {
-        eprintln!("Error: no index.html found in '{}'", dir.display());
-        std::process::exit(1);
+    if let Some(Dump::Tokens) = dump {
+        match source.text() {
+            Ok(text) => print!("{}", dump::tokens(&parser::tokenize(&text))),
+            Err(e) => {
+                eprintln!("radium: {e}");
+                std::process::exit(1);
+            }
+        }
+        return;
     }
 
-    let html = std::fs::read_to_string(&html_path).unwrap_or_else(|e| {
-        eprintln!("Error reading {}: {e}", html_path.display());
-        std::process::exit(1);
-    });
+    let nodes = match &mut options.stats {
+        Some(stats) => source.load_or_error_with_stats(stats),
+        None => source.load_or_error(),
+    };
+
+    match dump {
+        Some(Dump::Dom) => return print!("{}", dump::dom(&nodes)),
+        Some(Dump::Layout) => {
+            let document = Document { nodes, base: source.url };
+            // Estimated text widths keep the output the same whatever fonts are installed.
+            let page = radium::layout(&document, Viewport::new(renderer::LAYOUT_WIDTH as u32, 1), &Estimate);
+            return print!("{}", dump::layout(&page));
+        }
+        Some(Dump::Text) => return print!("{}", dump::text(&nodes, &source.url)),
+        _ => {}
+    }
 
-    let tokens = parser::tokenize(&html);
-    let nodes = parser::dom::build_tree(tokens);
-    let boxes = layout::layout(&nodes, 800.0, dir);
+    if let Some(out) = screenshot {
+        let document = Document { nodes, base: source.url };
+        // Sizes are logical, like a window's; zoom narrows the layout.
+        let scale = options.scale.unwrap_or(1.0);
+        let (width, height) = options.size.unwrap_or(SCREENSHOT_SIZE);
+        let (width, height) = ((width as f32 * scale).round() as u32, (height as f32 * scale).round() as u32);
+        let render_options = RenderOptions {
+            scale: scale * options.zoom.unwrap_or(1.0),
+            fragment: options.fragment,
+            theme: options.theme,
+            fonts: options.fonts,
+        };
+        let written = if full_page {
+            File::create(&out)
+                .map_err(Error::from)
+                .and_then(|file| radium::full_page_png(&document, width, &render_options, BufWriter::new(file)))
+        } else {
+            radium::screenshot(&document, width, height, &render_options)
+                .and_then(|image| image.save(&out).map_err(Error::from))
+        };
+        if let Err(e) = written {
+            eprintln!("radium: {}: {e}", out.display());
+            std::process::exit(1);
+        }
+        return;
+    }
 
-    renderer::run(format!("radium — {}", dir.display()), boxes);
+    if let Err(e) = renderer::run(source, nodes, options) {
+        eprintln!("radium: {e}");
+        std::process::exit(1);
+    }
 }
@@ -0,0 +1,125 @@
+use std::fs;
+use std::path::PathBuf;
+
+use super::http::{self, Response};
+use super::Url;
+
+/// GET `url` through the on-disk cache.
+///
+/// A cached entry is revalidated with `If-None-Match` / `If-Modified-Since`;
+/// a `304 Not Modified` answer is served from disk. If the request fails
+/// outright (offline, DNS, timeout) a cached entry is served as is.
+/// `progress` follows the request as [`http::get`] describes.
+pub fn get(url: &Url, progress: &mut dyn FnMut(&[u8])) -> Result<Response, String> {
+    let Some(dir) = cache_dir() else { return http::get(url, &[], progress) };
+    let entry = Entry::new(&dir, url);
+    let cached = entry.read();
+
+    let mut validators = Vec::new();
+    if let Some(cached) = &cached {
+        if let Some(etag) = cached.header("etag") {
+            validators.push(("If-None-Match", etag.to_string()));
+        }
+        if let Some(date) = cached.header("last-modified") {
+            validators.push(("If-Modified-Since", date.to_string()));
+        }
+    }
+
+    match (http::get(url, &validators, progress), cached) {
+        (Ok(response), Some(cached)) if response.status == 304 => Ok(cached),
+        (Ok(response), _) => {
+            if is_storable(&response) {
+                if let Err(e) = entry.write(&response) {
+                    eprintln!("radium: caching {url}: {e}");
+                }
+            }
+            Ok(response)
+        }
+        (Err(e), Some(cached)) => {
+            eprintln!("radium: {e}; using cached copy of {url}");
+            Ok(cached)
+        }
+        (Err(e), None) => Err(e),
+    }
+}
+
+/// Successful responses and redirects are kept unless the server says not to.
+fn is_storable(response: &Response) -> bool {
+    let cacheable_status = matches!(response.status, 200 | 301 | 302 | 307 | 308);
+    let no_store = response.header("cache-control").is_some_and(|v| v.to_ascii_lowercase().contains("no-store"));
+    cacheable_status && !no_store
+}
+
+/// `$XDG_CACHE_HOME/radium`, or the platform's usual cache location.
+fn cache_dir() -> Option<PathBuf> {
+    let base = if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Caches"))
+    } else if cfg!(windows) {
+        std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_CACHE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+    };
+    Some(base?.join("radium"))
+}
+
+// ── Entries ───────────────────────────────────────────────────────────────────
+
+/// One cached response: `<key>.meta` holds the URL, status line and headers,
+/// `<key>.body` the decoded body.
+struct Entry {
+    url: String,
+    meta: PathBuf,
+    body: PathBuf,
+}
+
+impl Entry {
+    fn new(dir: &std::path::Path, url: &Url) -> Entry {
+        let url = url.without_fragment().to_string();
+        let key = format!("{:016x}", fnv1a(url.as_bytes()));
+        Entry { meta: dir.join(format!("{key}.meta")), body: dir.join(format!("{key}.body")), url }
+    }
+
+    fn read(&self) -> Option<Response> {
+        let meta = fs::read_to_string(&self.meta).ok()?;
+        let mut lines = meta.lines();
+        // Guard against hash collisions.
+        if lines.next()? != self.url {
+            return None;
+        }
+        let (status, reason) = lines.next()?.split_once(' ')?;
+        let headers = lines
+            .filter_map(|line| line.split_once(": "))
+            .map(|(n, v)| (n.to_string(), v.to_string()))
+            .collect();
+        Some(Response {
+            status: status.parse().ok()?,
+            reason: reason.to_string(),
+            headers,
+            body: fs::read(&self.body).ok()?,
+        })
+    }
+
+    fn write(&self, response: &Response) -> std::io::Result<()> {
+        if let Some(dir) = self.meta.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut meta = format!("{}\n{} {}\n", self.url, response.status, response.reason);
+        for (name, value) in &response.headers {
+            // The body is stored decoded, so these no longer describe it.
+            if !matches!(name.as_str(), "content-encoding" | "transfer-encoding" | "content-length") {
+                meta.push_str(&format!("{name}: {value}\n"));
+            }
+        }
+        // Body first: an entry only counts once its meta file exists.
+        fs::write(&self.body, &response.body)?;
+        fs::write(&self.meta, meta)
+    }
+}
+
+/// 64-bit FNV-1a, stable across builds (unlike `DefaultHasher`).
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3))
+}
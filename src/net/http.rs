@@ -0,0 +1,220 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+use flate2::read::{GzDecoder, ZlibDecoder, DeflateDecoder};
+
+use super::url::Url;
+
+const USER_AGENT: &str = concat!("radium/", env!("CARGO_PKG_VERSION"));
+const TIMEOUT: Duration = Duration::from_secs(15);
+/// How often a request's progress is reported while nothing arrives.
+const PROGRESS_TICK: Duration = Duration::from_millis(100);
+
+/// A decoded HTTP response.
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    /// Reason phrase from the status line, e.g. `Not Found`.
+    pub reason: String,
+    /// Header names are lower-cased.
+    pub headers: Vec<(String, String)>,
+    /// Body with transfer and content encodings removed.
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    /// The response as far as `raw`, the start of one still arriving, goes:
+    /// `None` until its headers are in, then with as much of the body as can
+    /// be decoded so far.
+    pub fn partial(raw: &[u8]) -> Option<Response> {
+        parse_response(raw, false).ok()
+    }
+}
+
+/// Issue a single GET request (no redirect handling) with extra `headers`.
+/// `progress` is given the raw response received so far (see
+/// [`Response::partial`]) each time more of it arrives.
+pub fn get(url: &Url, headers: &[(&str, String)], progress: &mut dyn FnMut(&[u8])) -> Result<Response, String> {
+    let raw = match url.scheme.as_str() {
+        "http" => get_plain(url, headers, progress)?,
+        "https" => get_tls(url, headers, progress)?,
+        other => return Err(format!("unsupported scheme '{other}'")),
+    };
+    parse_response(&raw, true).map_err(|e| format!("{url}: {e}"))
+}
+
+/// Read `reader` to the end, passing what has been read to `progress` after
+/// every read, and every `PROGRESS_TICK` while none comes in. The reading
+/// happens on a thread of its own, so a stalled response still ticks.
+fn read_progressively(mut reader: impl Read + Send + 'static, progress: &mut dyn FnMut(&[u8])) -> std::io::Result<Vec<u8>> {
+    let (sender, chunks) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            let read = match reader.read(&mut buf) {
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                read => read.map(|n| buf[..n].to_vec()),
+            };
+            let done = !matches!(&read, Ok(chunk) if !chunk.is_empty());
+            if sender.send(read).is_err() || done {
+                return;
+            }
+        }
+    });
+
+    let mut raw = Vec::new();
+    loop {
+        match chunks.recv_timeout(PROGRESS_TICK) {
+            Ok(Ok(chunk)) if chunk.is_empty() => return Ok(raw),
+            Ok(Ok(chunk)) => raw.extend_from_slice(&chunk),
+            Ok(Err(e)) => return Err(e),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Ok(raw),
+        }
+        progress(&raw);
+    }
+}
+
+/// Plain HTTP/1.1 over a TCP socket.
+fn get_plain(url: &Url, headers: &[(&str, String)], progress: &mut dyn FnMut(&[u8])) -> Result<Vec<u8>, String> {
+    let addr = (url.host.as_str(), url.port_or_default());
+    let mut stream = TcpStream::connect(addr).map_err(|e| format!("connecting to {}: {e}", url.host))?;
+    stream.set_read_timeout(Some(TIMEOUT)).ok();
+
+    let host = match url.port {
+        Some(port) => format!("{}:{port}", url.host),
+        None => url.host.clone(),
+    };
+    let mut request = format!(
+        "GET {} HTTP/1.1\r\nHost: {host}\r\nUser-Agent: {USER_AGENT}\r\nAccept: text/html, */*\r\n\
+         Accept-Encoding: gzip, deflate\r\nConnection: close\r\n",
+        url.request_target(),
+    );
+    for (name, value) in headers {
+        request.push_str(&format!("{name}: {value}\r\n"));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).map_err(|e| format!("sending request to {}: {e}", url.host))?;
+
+    read_progressively(stream, progress).map_err(|e| format!("reading from {}: {e}", url.host))
+}
+
+/// HTTPS is delegated to the system `curl`, asked for the raw, undecoded
+/// response so both schemes share the same parser.
+fn get_tls(url: &Url, headers: &[(&str, String)], progress: &mut dyn FnMut(&[u8])) -> Result<Vec<u8>, String> {
+    let mut curl = Command::new("curl");
+    curl.args(["-sS", "-i", "--raw", "--http1.1", "--max-time", &TIMEOUT.as_secs().to_string()])
+        .args(["-A", USER_AGENT, "-H", "Accept: text/html, */*", "-H", "Accept-Encoding: gzip, deflate"]);
+    for (name, value) in headers {
+        curl.arg("-H").arg(format!("{name}: {value}"));
+    }
+    let mut child = curl
+        .arg(url.without_fragment().to_string())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("running curl for HTTPS: {e}"))?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let raw = read_progressively(stdout, progress);
+    let output = child.wait_with_output().map_err(|e| format!("running curl for HTTPS: {e}"))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    raw.map_err(|e| format!("reading from curl: {e}"))
+}
+
+// ── Response parsing ──────────────────────────────────────────────────────────
+
+/// Parse a raw response; unless `complete`, one cut short anywhere after
+/// its headers, whose body is decoded as far as it goes.
+fn parse_response(raw: &[u8], complete: bool) -> Result<Response, String> {
+    let mut rest = raw;
+    loop {
+        let end = find(rest, b"\r\n\r\n").ok_or("truncated response headers")?;
+        let head = String::from_utf8_lossy(&rest[..end]).into_owned();
+        rest = &rest[end + 4..];
+
+        let mut lines = head.split("\r\n");
+        let status_line = lines.next().unwrap_or_default();
+        let mut parts = status_line.splitn(3, ' ');
+        let status: u16 = parts
+            .nth(1)
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| format!("malformed status line '{status_line}'"))?;
+        let reason = parts.next().unwrap_or_default().trim().to_string();
+
+        // Interim 1xx responses precede the real one.
+        if (100..200).contains(&status) {
+            continue;
+        }
+
+        let headers: Vec<(String, String)> = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(n, v)| (n.trim().to_ascii_lowercase(), v.trim().to_string()))
+            .collect();
+        let mut response = Response { status, reason, headers, body: Vec::new() };
+
+        let chunked = response.header("transfer-encoding").is_some_and(|v| v.eq_ignore_ascii_case("chunked"));
+        let body = if chunked { dechunk(rest, complete)? } else { rest.to_vec() };
+        response.body = match response.header("content-encoding").map(str::to_ascii_lowercase).as_deref() {
+            Some("gzip" | "x-gzip") => decode(GzDecoder::new(&body[..]), complete)?,
+            Some("deflate") => decode(ZlibDecoder::new(&body[..]), complete)
+                .or_else(|_| decode(DeflateDecoder::new(&body[..]), complete))?,
+            _ => body,
+        };
+        return Ok(response);
+    }
+}
+
+/// Remove chunked transfer encoding; unless `complete`, from as many chunks
+/// as `data` holds.
+fn dechunk(mut data: &[u8], complete: bool) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    loop {
+        let Some(line_end) = find(data, b"\r\n") else {
+            return if complete { Err("truncated chunk header".into()) } else { Ok(out) };
+        };
+        let size_field = String::from_utf8_lossy(&data[..line_end]);
+        let size_hex = size_field.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size_hex, 16).map_err(|_| format!("bad chunk size '{size_hex}'"))?;
+        data = &data[line_end + 2..];
+        if size == 0 {
+            return Ok(out);
+        }
+        let Some(chunk) = data.get(..size) else {
+            if complete {
+                return Err("truncated chunk".into());
+            }
+            out.extend_from_slice(data);
+            return Ok(out);
+        };
+        out.extend_from_slice(chunk);
+        data = data.get(size + 2..).unwrap_or_default();
+    }
+}
+
+/// Decompress `reader`; unless `complete`, as far as it goes.
+fn decode(mut reader: impl Read, complete: bool) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    match reader.read_to_end(&mut out) {
+        Ok(_) => Ok(out),
+        // What was decoded before the input ran out is in `out`.
+        Err(_) if !complete => Ok(out),
+        Err(e) => Err(format!("decompressing body: {e}")),
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
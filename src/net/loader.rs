@@ -0,0 +1,155 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+use image::RgbaImage;
+
+use super::{fetch, Url};
+
+/// Number of concurrent fetches.
+const WORKERS: usize = 4;
+
+/// What a subresource is for. Declaration order is fetch priority: anything
+/// that can change layout wholesale goes before images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Kind {
+    #[allow(dead_code)] // requested once external stylesheets are supported
+    Stylesheet,
+    #[allow(dead_code)] // requested once web fonts are supported
+    Font,
+    Image,
+}
+
+/// A fetched and decoded subresource.
+#[derive(Debug, Clone)]
+pub enum Resource {
+    Image(Arc<RgbaImage>),
+    /// Undecoded body (stylesheets, fonts).
+    #[allow(dead_code)]
+    Bytes(Arc<[u8]>),
+}
+
+/// Outcome of one request, delivered to every interested party.
+#[derive(Debug)]
+pub struct Loaded {
+    pub url: Url,
+    pub kind: Kind,
+    pub result: Result<Resource, String>,
+}
+
+/// A shared pool of worker threads fetching `file:` and `http(s):`
+/// subresources off the main thread.
+///
+/// Requests for a URL already queued or in flight are dropped, so the same
+/// image used by several windows is only fetched once; the single result is
+/// handed to `deliver`, which is expected to broadcast it.
+#[derive(Clone)]
+pub struct Loader {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    wake: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    /// Ordered by (kind, arrival).
+    queue: BinaryHeap<Reverse<(Kind, u64, String)>>,
+    /// URLs queued or being fetched.
+    pending: HashSet<String>,
+    next_seq: u64,
+}
+
+impl Loader {
+    /// Start the worker threads. `deliver` runs on a worker for every
+    /// finished request.
+    pub fn new(deliver: impl Fn(Loaded) + Send + Sync + 'static) -> Loader {
+        Loader::with_supplier(|_, _| None, deliver)
+    }
+
+    /// Like [`Loader::new`], but `supply` is asked for each body first, and
+    /// only URLs it returns `None` for are read or fetched.
+    pub fn with_supplier(
+        supply: impl Fn(&Url, Kind) -> Option<Vec<u8>> + Send + Sync + 'static,
+        deliver: impl Fn(Loaded) + Send + Sync + 'static,
+    ) -> Loader {
+        let shared = Arc::new(Shared { state: Mutex::new(State::default()), wake: Condvar::new() });
+        let supply = Arc::new(supply);
+        let deliver = Arc::new(deliver);
+        for _ in 0..WORKERS {
+            let shared = shared.clone();
+            let supply = supply.clone();
+            let deliver = deliver.clone();
+            thread::spawn(move || loop {
+                let (kind, url) = shared.next();
+                // A panic (say, in an image decoder) fails the request, not the worker.
+                let result = panic::catch_unwind(AssertUnwindSafe(|| match supply(&url, kind) {
+                    Some(body) => decode(&url, kind, body),
+                    None => load(&url, kind),
+                }))
+                .unwrap_or_else(|_| Err(format!("loading {url} panicked")));
+                shared.state.lock().unwrap().pending.remove(&url.to_string());
+                deliver(Loaded { url, kind, result });
+            });
+        }
+        Loader { shared }
+    }
+
+    /// Queue `url` unless it is already queued or in flight.
+    pub fn request(&self, url: &Url, kind: Kind) {
+        let key = url.without_fragment().to_string();
+        let mut state = self.shared.state.lock().unwrap();
+        if !state.pending.insert(key.clone()) {
+            return;
+        }
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        state.queue.push(Reverse((kind, seq, key)));
+        self.shared.wake.notify_one();
+    }
+}
+
+impl Shared {
+    /// Block until a request is available and take the most urgent one.
+    fn next(&self) -> (Kind, Url) {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(Reverse((kind, _, key))) = state.queue.pop() {
+                // Keys were produced by `Url::to_string`, so they re-parse.
+                if let Ok(url) = Url::parse(&key) {
+                    return (kind, url);
+                }
+                state.pending.remove(&key);
+                continue;
+            }
+            state = self.wake.wait(state).unwrap();
+        }
+    }
+}
+
+/// Read or fetch `url` and decode it according to `kind`, on the calling
+/// thread (the workers' job, also used directly by headless rendering).
+pub fn load(url: &Url, kind: Kind) -> Result<Resource, String> {
+    let body = match url.to_file_path() {
+        Some(path) => std::fs::read(&path).map_err(|e| format!("reading {}: {e}", path.display()))?,
+        None => match fetch(url)? {
+            (_, response) if response.is_success() => response.body,
+            (_, response) => return Err(format!("HTTP {} {}", response.status, response.reason)),
+        },
+    };
+    decode(url, kind, body)
+}
+
+/// Decode the body of `url` according to `kind`.
+fn decode(url: &Url, kind: Kind, body: Vec<u8>) -> Result<Resource, String> {
+    match kind {
+        Kind::Image => image::load_from_memory(&body)
+            .map(|img| Resource::Image(Arc::new(img.to_rgba8())))
+            .map_err(|e| format!("decoding {url}: {e}")),
+        Kind::Stylesheet | Kind::Font => Ok(Resource::Bytes(body.into())),
+    }
+}
@@ -0,0 +1,36 @@
+mod cache;
+mod http;
+pub mod loader;
+mod url;
+
+pub use http::Response;
+pub use url::{form_urlencoded, Url};
+
+const MAX_REDIRECTS: usize = 8;
+
+/// Fetch `url` over HTTP(S) through the disk cache, following up to
+/// `MAX_REDIRECTS` redirects.
+///
+/// Returns the final URL (for resolving relative links) with the response,
+/// whatever its status; check `Response::is_success`.
+pub fn fetch(url: &Url) -> Result<(Url, Response), String> {
+    fetch_progressively(url, &mut |_| {})
+}
+
+/// [`fetch`], passing each response's raw bytes received so far to
+/// `progress` as they arrive (see [`Response::partial`]).
+pub fn fetch_progressively(url: &Url, progress: &mut dyn FnMut(&[u8])) -> Result<(Url, Response), String> {
+    let mut url = url.without_fragment();
+    for _ in 0..=MAX_REDIRECTS {
+        let response = cache::get(&url, progress)?;
+        let location = match response.status {
+            301 | 302 | 303 | 307 | 308 => response.header("location"),
+            _ => None,
+        };
+        match location {
+            Some(location) => url = url.join(location)?.without_fragment(),
+            None => return Ok((url, response)),
+        }
+    }
+    Err(format!("{url}: too many redirects"))
+}
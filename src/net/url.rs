@@ -0,0 +1,242 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS, NON_ALPHANUMERIC};
+
+/// Characters escaped when turning a file path into a URL path.
+const PATH_ESCAPE: &AsciiSet = &CONTROLS.add(b' ').add(b'"').add(b'#').add(b'%').add(b'?').add(b'<').add(b'>');
+/// Characters escaped in `application/x-www-form-urlencoded` names and values.
+const FORM_ESCAPE: &AsciiSet = &NON_ALPHANUMERIC.remove(b'*').remove(b'-').remove(b'.').remove(b'_');
+
+/// A parsed absolute URL.
+///
+/// Hierarchical schemes (`http`, `https`, `file`) have an authority and a
+/// `/`-rooted path; anything else (`mailto:`, `tel:`) is kept opaque in `path`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Url {
+    /// Lower-cased scheme without the trailing `:`.
+    pub scheme: String,
+    /// Lower-cased host; empty for `file` URLs and opaque schemes.
+    pub host: String,
+    pub port: Option<u16>,
+    /// Percent-encoded path. Always starts with `/` for hierarchical URLs.
+    pub path: String,
+    pub query: Option<String>,
+    pub fragment: Option<String>,
+}
+
+impl Url {
+    /// Parse an absolute URL such as `https://example.com:8080/a/b?q#frag`.
+    pub fn parse(input: &str) -> Result<Url, String> {
+        let input = input.trim();
+        let (scheme, rest) = split_scheme(input).ok_or_else(|| format!("not an absolute URL: {input}"))?;
+        let scheme = scheme.to_ascii_lowercase();
+
+        let (rest, fragment) = match rest.split_once('#') {
+            Some((r, f)) => (r, Some(f.to_string())),
+            None => (rest, None),
+        };
+        let (rest, query) = match rest.split_once('?') {
+            Some((r, q)) => (r, Some(q.to_string())),
+            None => (rest, None),
+        };
+
+        let Some(rest) = rest.strip_prefix("//") else {
+            // Opaque: `mailto:someone@example.com`.
+            return Ok(Url { scheme, host: String::new(), port: None, path: rest.to_string(), query, fragment });
+        };
+
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        // Drop any `user:password@` prefix.
+        let authority = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((h, p)) if !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit()) => {
+                let port = p.parse().map_err(|_| format!("invalid port in {input}"))?;
+                (h, Some(port))
+            }
+            _ => (authority.trim_end_matches(':'), None),
+        };
+
+        if host.is_empty() && scheme != "file" {
+            return Err(format!("missing host in {input}"));
+        }
+
+        Ok(Url {
+            scheme,
+            host: host.to_ascii_lowercase(),
+            port,
+            path: remove_dot_segments(path),
+            query,
+            fragment,
+        })
+    }
+
+    /// A `file://` URL for `path`, made absolute against the working directory.
+    pub fn from_file_path(path: &Path) -> Url {
+        let absolute = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            std::env::current_dir().map(|cwd| cwd.join(path)).unwrap_or_else(|_| path.to_path_buf())
+        };
+        let mut raw = absolute.to_string_lossy().replace('\\', "/");
+        if !raw.starts_with('/') {
+            raw.insert(0, '/'); // Windows drive paths: /C:/...
+        }
+        let path = utf8_percent_encode(&raw, PATH_ESCAPE).to_string();
+        Url { scheme: "file".into(), host: String::new(), port: None, path: remove_dot_segments(&path), query: None, fragment: None }
+    }
+
+    /// The local path of a `file:` URL.
+    pub fn to_file_path(&self) -> Option<PathBuf> {
+        if self.scheme != "file" {
+            return None;
+        }
+        let decoded = percent_decode_str(&self.path).decode_utf8_lossy().into_owned();
+        // `/C:/dir` → `C:/dir` on Windows.
+        let is_drive = decoded.len() > 2 && decoded.as_bytes()[2] == b':';
+        Some(PathBuf::from(if cfg!(windows) && is_drive { &decoded[1..] } else { &decoded[..] }))
+    }
+
+    /// Whether the URL has an authority and hierarchical path.
+    pub fn is_hierarchical(&self) -> bool {
+        matches!(self.scheme.as_str(), "http" | "https" | "file")
+    }
+
+    /// The port to connect to, falling back to the scheme's default.
+    pub fn port_or_default(&self) -> u16 {
+        self.port.unwrap_or(if self.scheme == "https" { 443 } else { 80 })
+    }
+
+    /// Path plus query, as sent in an HTTP request line.
+    pub fn request_target(&self) -> String {
+        match &self.query {
+            Some(q) => format!("{}?{q}", self.path),
+            None => self.path.clone(),
+        }
+    }
+
+    /// The same URL without its fragment.
+    pub fn without_fragment(&self) -> Url {
+        Url { fragment: None, ..self.clone() }
+    }
+
+    /// Resolve `reference` (absolute, scheme-relative, root-relative or
+    /// relative) against this URL, per RFC 3986 §5.2.
+    pub fn join(&self, reference: &str) -> Result<Url, String> {
+        let reference = reference.trim();
+        match split_scheme(reference) {
+            // `http:g` is relative to an `http:` URL, as browsers (and the
+            // non-strict reading of RFC 3986 §5.2.2) take it.
+            Some((scheme, rest)) if self.is_hierarchical() && scheme.eq_ignore_ascii_case(&self.scheme) && !rest.starts_with("//") => {
+                return self.join(rest);
+            }
+            Some(_) => return Url::parse(reference),
+            None => {}
+        }
+        if !self.is_hierarchical() {
+            return Err(format!("cannot resolve '{reference}' against {self}"));
+        }
+        if let Some(rest) = reference.strip_prefix("//") {
+            return Url::parse(&format!("{}://{rest}", self.scheme));
+        }
+
+        let (rest, fragment) = match reference.split_once('#') {
+            Some((r, f)) => (r, Some(f.to_string())),
+            None => (reference, None),
+        };
+        let (path, query) = match rest.split_once('?') {
+            Some((p, q)) => (p, Some(q.to_string())),
+            None => (rest, None),
+        };
+
+        let mut url = self.clone();
+        url.fragment = fragment;
+        if path.is_empty() {
+            if query.is_some() {
+                url.query = query;
+            }
+            return Ok(url);
+        }
+
+        url.query = query;
+        url.path = if path.starts_with('/') {
+            remove_dot_segments(path)
+        } else {
+            let dir = &self.path[..self.path.rfind('/').map_or(0, |i| i + 1)];
+            remove_dot_segments(&format!("{dir}{path}"))
+        };
+        Ok(url)
+    }
+}
+
+impl fmt::Display for Url {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Other schemes with an authority (`ftp://host/...`) keep it too.
+        if self.is_hierarchical() || !self.host.is_empty() {
+            write!(f, "{}://{}", self.scheme, self.host)?;
+            if let Some(port) = self.port {
+                write!(f, ":{port}")?;
+            }
+        } else {
+            write!(f, "{}:", self.scheme)?;
+        }
+        f.write_str(&self.path)?;
+        if let Some(q) = &self.query {
+            write!(f, "?{q}")?;
+        }
+        if let Some(frag) = &self.fragment {
+            write!(f, "#{frag}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Encode form data as `application/x-www-form-urlencoded`, the query string
+/// of a GET submission: `name=value` pairs joined by `&`, with spaces as `+`
+/// and line breaks as CRLF.
+pub fn form_urlencoded(pairs: &[(String, String)]) -> String {
+    let encode = |text: &str| {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n").replace('\n', "\r\n");
+        // `%` is escaped too, so no `%20` but a space's survives this.
+        utf8_percent_encode(&text, FORM_ESCAPE).to_string().replace("%20", "+")
+    };
+    let pairs: Vec<String> = pairs.iter().map(|(name, value)| format!("{}={}", encode(name), encode(value))).collect();
+    pairs.join("&")
+}
+
+/// Split `scheme:rest` if `input` starts with a syntactically valid scheme.
+fn split_scheme(input: &str) -> Option<(&str, &str)> {
+    let (scheme, rest) = input.split_once(':')?;
+    let mut chars = scheme.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    // A single letter is a Windows drive (`C:\...`), not a scheme.
+    (valid && scheme.len() > 1).then_some((scheme, rest))
+}
+
+/// Collapse `.` and `..` segments of an absolute path.
+fn remove_dot_segments(path: &str) -> String {
+    let mut out: Vec<&str> = Vec::new();
+    let segments: Vec<&str> = path.split('/').skip(1).collect();
+    for (i, seg) in segments.iter().enumerate() {
+        let last = i + 1 == segments.len();
+        match *seg {
+            "." => {
+                if last {
+                    out.push("");
+                }
+            }
+            ".." => {
+                out.pop();
+                if last {
+                    out.push("");
+                }
+            }
+            s => out.push(s),
+        }
+    }
+    format!("/{}", out.join("/"))
+}
@@ -29,7 +29,21 @@ struct Partial {
     children: Vec<Node>,
 }
 
+/// Deepest element nesting kept by [`build_tree`]. Layout, serialization and
+/// even dropping a tree recurse per level, so unbounded nesting would
+/// overflow the stack.
+pub const MAX_DEPTH: usize = 512;
+
+/// Parse raw bytes into a tree, replacing invalid UTF-8 with U+FFFD. Any
+/// input is accepted without panicking.
+pub fn build_tree_lossy(input: &[u8]) -> Vec<Node> {
+    build_tree(super::tokenize_bytes(input))
+}
+
 /// Convert a flat token stream into a tree of `Node`s.
+///
+/// Elements opened more than [`MAX_DEPTH`] levels deep are dropped; their
+/// contents go to the deepest element kept.
 pub fn build_tree(tokens: Vec<Token>) -> Vec<Node> {
     let mut stack: Vec<Partial> = vec![Partial {
         tag: String::new(),
@@ -39,17 +53,18 @@ pub fn build_tree(tokens: Vec<Token>) -> Vec<Node> {
 
     for token in tokens {
         match token {
-            Token::Doctype => {}
+            Token::Doctype | Token::Comment(_) => {}
             Token::OpenTag { name, attrs, self_closing } => {
                 if self_closing || is_void(&name) {
                     let node = Node::Element { tag: name, attrs, children: vec![] };
                     stack.last_mut().unwrap().children.push(node);
-                } else {
+                } else if stack.len() <= MAX_DEPTH {
                     stack.push(Partial { tag: name, attrs, children: Vec::new() });
                 }
             }
             Token::CloseTag(name) => {
-                let pos = stack.iter().rposition(|p| p.tag == name);
+                // Never match the synthetic root, even for a nameless `</>`.
+                let pos = stack.iter().rposition(|p| p.tag == name).filter(|&pos| pos > 0);
                 if let Some(pos) = pos {
                     while stack.len() > pos + 1 {
                         let partial = stack.pop().unwrap();
@@ -75,3 +90,72 @@ pub fn build_tree(tokens: Vec<Token>) -> Vec<Node> {
 
     stack.pop().unwrap().children
 }
+
+// ── Serialization ─────────────────────────────────────────────────────────────
+
+/// The node reached by following child indices `path` down from `nodes`
+/// (e.g. [`ElementBox::path`](crate::layout::ElementBox::path)).
+pub fn node_at<'a>(nodes: &'a [Node], path: &[usize]) -> Option<&'a Node> {
+    let (&first, rest) = path.split_first()?;
+    rest.iter().try_fold(nodes.get(first)?, |node, &i| match node {
+        Node::Element { children, .. } => children.get(i),
+        Node::Text(_) => None,
+    })
+}
+
+/// Serialize a tree back to HTML.
+///
+/// `rewrite(tag, attr, value)` may return a replacement for an attribute
+/// value (e.g. to relocate links); `None` keeps the original. Attributes are
+/// written in name order so output is deterministic.
+pub fn to_html(nodes: &[Node], rewrite: &mut dyn FnMut(&str, &str, &str) -> Option<String>) -> String {
+    let mut out = String::new();
+    write_nodes(nodes, "", rewrite, &mut out);
+    out
+}
+
+fn write_nodes(nodes: &[Node], parent: &str, rewrite: &mut dyn FnMut(&str, &str, &str) -> Option<String>, out: &mut String) {
+    for node in nodes {
+        match node {
+            // Script and style bodies are raw text; escaping would corrupt them.
+            Node::Text(text) if matches!(parent, "script" | "style") => out.push_str(text),
+            Node::Text(text) => escape(text, false, out),
+            Node::Element { tag, attrs, children } => {
+                out.push('<');
+                out.push_str(tag);
+                let mut names: Vec<&String> = attrs.keys().collect();
+                names.sort();
+                for name in names {
+                    let value = rewrite(tag, name, &attrs[name]).unwrap_or_else(|| attrs[name].clone());
+                    out.push(' ');
+                    out.push_str(name);
+                    out.push_str("=\"");
+                    escape(&value, true, out);
+                    out.push('"');
+                }
+                out.push('>');
+                if is_void(tag) {
+                    continue;
+                }
+                write_nodes(children, tag, rewrite, out);
+                out.push_str("</");
+                out.push_str(tag);
+                out.push('>');
+            }
+        }
+    }
+}
+
+/// Write `text` as it would be written in markup, escaping what would
+/// otherwise read as markup or a character reference.
+fn escape(text: &str, in_attr: bool, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' if !in_attr => out.push_str("&lt;"),
+            '>' if !in_attr => out.push_str("&gt;"),
+            '"' if in_attr => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
@@ -1,10 +1,11 @@
 pub mod dom;
+mod entities;
 
 use std::collections::HashMap;
 use std::iter::Peekable;
 use std::str::Chars;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Token {
     Doctype,
     OpenTag {
@@ -14,14 +15,20 @@ pub enum Token {
     },
     CloseTag(String),
     Text(String),
+    /// The text of a comment, `<!-- like this -->`, or of markup that is
+    /// not HTML (`<?xml ...?>`, `<![CDATA[...]]>`), which reads as one.
+    Comment(String),
 }
 
 pub fn tokenize(input: &str) -> Vec<Token> {
     let mut tokens = Vec::new();
     let mut chars = input.chars().peekable();
+    // How many `<pre>` elements deep the input is; their text keeps its
+    // whitespace.
+    let mut pre = 0usize;
 
     while chars.peek().is_some() {
-        if chars.peek() == Some(&'<') {
+        if opens_tag(&chars) {
             chars.next(); // consume '<'
 
             match chars.peek() {
@@ -31,36 +38,55 @@ pub fn tokenize(input: &str) -> Vec<Token> {
                     skip_until(&mut chars, '>');
                     chars.next(); // consume '>'
                     if !name.is_empty() {
-                        tokens.push(Token::CloseTag(name.to_lowercase()));
+                        let name = name.to_lowercase();
+                        if name == "pre" {
+                            pre = pre.saturating_sub(1);
+                        }
+                        tokens.push(Token::CloseTag(name));
                     }
                 }
                 Some(&'!') => {
                     chars.next();
-                    skip_until(&mut chars, '>');
-                    chars.next();
-                    tokens.push(Token::Doctype);
+                    tokens.push(read_declaration(&mut chars));
                 }
                 Some(&'?') => {
-                    skip_until(&mut chars, '>');
-                    chars.next();
+                    tokens.push(Token::Comment(read_bogus_comment(&mut chars)));
                 }
                 _ => {
                     let name = read_name(&mut chars);
-                    if name.is_empty() {
-                        skip_until(&mut chars, '>');
-                        chars.next();
-                        continue;
-                    }
                     let (attrs, self_closing) = parse_tag_body(&mut chars);
-                    tokens.push(Token::OpenTag {
-                        name: name.to_lowercase(),
-                        attrs,
-                        self_closing,
-                    });
+                    let name = name.to_lowercase();
+                    // A textarea's content is its initial value, whitespace
+                    // and all, and holds no tags.
+                    let raw = name == "textarea" && !self_closing;
+                    if name == "pre" && !self_closing {
+                        pre += 1;
+                    }
+                    tokens.push(Token::OpenTag { name, attrs, self_closing });
+                    if raw {
+                        let text = read_raw_text(&mut chars, "textarea");
+                        if !text.is_empty() {
+                            tokens.push(Token::Text(entities::decode(text, false)));
+                        }
+                    }
                 }
             }
         } else {
             let text = read_text(&mut chars);
+            // Script and style bodies are not HTML; references in them are code.
+            let code = matches!(tokens.last(), Some(Token::OpenTag { name, .. }) if name == "script" || name == "style");
+            let text = if code { text } else { entities::decode(text, false) };
+            if pre > 0 {
+                // As in browsers, a line break right after `<pre>` is not
+                // part of its text.
+                let text = text.replace("\r\n", "\n").replace('\r', "\n");
+                let opens_pre = matches!(tokens.last(), Some(Token::OpenTag { name, .. }) if name == "pre");
+                let text = if opens_pre { text.strip_prefix('\n').unwrap_or(&text) } else { &text };
+                if !text.is_empty() {
+                    tokens.push(Token::Text(text.to_string()));
+                }
+                continue;
+            }
             let collapsed = collapse_whitespace(&text);
             if !collapsed.is_empty() {
                 tokens.push(Token::Text(collapsed));
@@ -71,6 +97,19 @@ pub fn tokenize(input: &str) -> Vec<Token> {
     tokens
 }
 
+/// Tokenize raw bytes, replacing invalid UTF-8 with U+FFFD. Like
+/// [`tokenize`], this accepts any input without panicking.
+pub fn tokenize_bytes(input: &[u8]) -> Vec<Token> {
+    tokenize(&String::from_utf8_lossy(input))
+}
+
+/// Whether the input continues with markup: `<` followed by a letter, `/`,
+/// `!` or `?`. Any other `<` (as in `a < b`, or at the end) is text.
+fn opens_tag(chars: &Peekable<Chars<'_>>) -> bool {
+    let mut ahead = chars.clone();
+    ahead.next() == Some('<') && ahead.next().is_some_and(|c| c.is_ascii_alphabetic() || matches!(c, '/' | '!' | '?'))
+}
+
 fn read_name(chars: &mut Peekable<Chars<'_>>) -> String {
     let mut name = String::new();
     while let Some(&c) = chars.peek() {
@@ -127,7 +166,7 @@ fn parse_tag_body(chars: &mut Peekable<Chars<'_>>) -> (HashMap<String, String>,
                     while chars.peek().map(|c| c.is_whitespace()).unwrap_or(false) {
                         chars.next();
                     }
-                    let value = read_attr_value(chars);
+                    let value = entities::decode(read_attr_value(chars), true);
                     attrs.insert(name.to_lowercase(), value);
                 } else {
                     // Boolean attribute (no value).
@@ -183,7 +222,7 @@ fn read_attr_value(chars: &mut Peekable<Chars<'_>>) -> String {
 fn read_text(chars: &mut Peekable<Chars<'_>>) -> String {
     let mut text = String::new();
     while let Some(&c) = chars.peek() {
-        if c == '<' {
+        if c == '<' && opens_tag(chars) {
             break;
         }
         text.push(c);
@@ -192,6 +231,66 @@ fn read_text(chars: &mut Peekable<Chars<'_>>) -> String {
     text
 }
 
+/// Read text up to the `</tag` that ends it (in any case), or the end.
+fn read_raw_text(chars: &mut Peekable<Chars<'_>>, tag: &str) -> String {
+    let end = format!("</{tag}");
+    let mut text = String::new();
+    while let Some(&c) = chars.peek() {
+        if c == '<' && chars.clone().take(end.len()).collect::<String>().eq_ignore_ascii_case(&end) {
+            break;
+        }
+        text.push(c);
+        chars.next();
+    }
+    text
+}
+
+/// Read what follows `<!`: a comment, a doctype, or anything else as a
+/// bogus comment running to the next `>`.
+fn read_declaration(chars: &mut Peekable<Chars<'_>>) -> Token {
+    if advance_past(chars, "--") {
+        Token::Comment(read_comment(chars))
+    } else if advance_past(chars, "doctype") {
+        skip_until(chars, '>');
+        chars.next();
+        Token::Doctype
+    } else {
+        Token::Comment(read_bogus_comment(chars))
+    }
+}
+
+/// Read a comment's text after its `<!--`, through the `-->` that ends it
+/// (`--!>` too), or the end of the input. A `>` inside does not end it, but
+/// `<!-->` and `<!--->` are whole, empty comments.
+fn read_comment(chars: &mut Peekable<Chars<'_>>) -> String {
+    let mut text = String::new();
+    if advance_past(chars, ">") || advance_past(chars, "->") {
+        return text;
+    }
+    while !advance_past(chars, "-->") && !advance_past(chars, "--!>") {
+        match chars.next() {
+            Some(c) => text.push(c),
+            None => break,
+        }
+    }
+    text
+}
+
+/// Read up to and through the next `>`, as the text of a bogus comment.
+fn read_bogus_comment(chars: &mut Peekable<Chars<'_>>) -> String {
+    chars.by_ref().take_while(|&c| c != '>').collect()
+}
+
+/// If the input continues with `s` (ignoring ASCII case), consume it.
+fn advance_past(chars: &mut Peekable<Chars<'_>>, s: &str) -> bool {
+    let mut ahead = chars.clone();
+    let found = s.chars().all(|c| ahead.next().is_some_and(|next| next.eq_ignore_ascii_case(&c)));
+    if found {
+        *chars = ahead;
+    }
+    found
+}
+
 fn skip_until(chars: &mut Peekable<Chars<'_>>, stop: char) {
     while let Some(&c) = chars.peek() {
         if c == stop {
@@ -201,11 +300,21 @@ fn skip_until(chars: &mut Peekable<Chars<'_>>, stop: char) {
     }
 }
 
+/// `text` without leading and trailing HTML whitespace: ASCII spaces, tabs,
+/// line breaks and form feeds. No-break spaces (U+00A0 and the like) are
+/// content, and stay.
+pub fn trim_whitespace(text: &str) -> &str {
+    text.trim_matches(|c: char| c.is_ascii_whitespace())
+}
+
+/// `s` with each run of HTML whitespace as one space. Runs at the ends stay
+/// too: they are the spaces between a text node and the inline content
+/// beside it.
 fn collapse_whitespace(s: &str) -> String {
     let mut result = String::new();
-    let mut prev_ws = true; // trim leading whitespace
+    let mut prev_ws = false;
     for c in s.chars() {
-        if c.is_whitespace() {
+        if c.is_ascii_whitespace() {
             if !prev_ws {
                 result.push(' ');
                 prev_ws = true;
@@ -215,5 +324,5 @@ fn collapse_whitespace(s: &str) -> String {
             prev_ws = false;
         }
     }
-    result.trim_end().to_string()
+    result
 }
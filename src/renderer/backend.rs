@@ -0,0 +1,259 @@
+use super::font::Face;
+
+use crate::css;
+use crate::layout::Rect;
+use crate::Error;
+
+/// Drawing operations that `render_frame` and the overlays are written
+/// against, so the same painting code can target a window, an image or
+/// (later) the GPU.
+///
+/// Coordinates are physical pixels and may extend past the target's edges;
+/// colors are packed as in [`PaintCmd`](crate::layout::PaintCmd), `0xRRGGBB`
+/// with any transparency in the top byte.
+pub trait PaintBackend {
+    /// Target size in physical pixels.
+    fn size(&self) -> (u32, u32);
+
+    /// Blend a solid rectangle over the target with constant `alpha` (0–255),
+    /// on top of the color's own.
+    fn fill_rect(&mut self, rect: Rect, color: u32, alpha: u32);
+
+    /// Draw `text` in `font` at `font_size` pixels, its line box's top-left
+    /// corner at `(x, y)`.
+    fn glyph_run(&mut self, font: &Face, text: &str, x: f32, y: f32, font_size: f32, color: u32);
+
+    /// Draw an RGBA8 image of `width × height` pixels scaled to fill `rect`,
+    /// blended over what is already drawn by its alpha.
+    fn image(&mut self, rect: Rect, data: &[u8], width: u32, height: u32);
+
+    /// Restrict drawing to `rect` (within any enclosing clip) until the
+    /// matching [`pop_clip`](Self::pop_clip).
+    fn push_clip(&mut self, rect: Rect);
+
+    fn pop_clip(&mut self);
+
+    /// Outline `rect` with a border `thickness` pixels wide, drawn inside it.
+    fn stroke_rect(&mut self, rect: Rect, thickness: f32, color: u32) {
+        let Rect { x, y, width: w, height: h } = rect;
+        let t = thickness.min(w / 2.0).min(h / 2.0);
+        self.fill_rect(Rect { x, y, width: w, height: t }, color, 255);
+        self.fill_rect(Rect { x, y: y + h - t, width: w, height: t }, color, 255);
+        self.fill_rect(Rect { x, y: y + t, width: t, height: h - 2.0 * t }, color, 255);
+        self.fill_rect(Rect { x: x + w - t, y: y + t, width: t, height: h - 2.0 * t }, color, 255);
+    }
+}
+
+// ── CPU rasterizer ────────────────────────────────────────────────────────────
+
+/// Software rasterizer over a row-major `0RGB` pixel buffer.
+///
+/// Windows paint through `Raster<&mut [u32]>` over the softbuffer surface;
+/// headless rendering (screenshots, PNG export) uses an owned
+/// `Raster<Vec<u32>>` from [`Raster::blank`].
+pub struct Raster<P = Vec<u32>> {
+    pixels: P,
+    width: u32,
+    height: u32,
+    /// Clip stack as `(x0, y0, x1, y1)` pixel bounds, innermost last.
+    clips: Vec<(u32, u32, u32, u32)>,
+}
+
+impl<P: AsMut<[u32]> + AsRef<[u32]>> Raster<P> {
+    /// Paint into `pixels`, which must hold at least `width × height` pixels.
+    pub fn new(pixels: P, width: u32, height: u32) -> Result<Raster<P>, Error> {
+        let len = pixels.as_ref().len();
+        if len < width as usize * height as usize {
+            return Err(Error::Buffer { len, width, height });
+        }
+        Ok(Raster { pixels, width, height, clips: Vec::new() })
+    }
+
+    pub fn pixels(&self) -> &[u32] {
+        &self.pixels.as_ref()[..(self.width * self.height) as usize]
+    }
+
+    /// Fill the whole buffer, ignoring the clip.
+    pub fn clear(&mut self, color: u32) {
+        let len = (self.width * self.height) as usize;
+        self.pixels.as_mut()[..len].fill(color);
+    }
+
+    /// Current clip bounds.
+    fn bounds(&self) -> (u32, u32, u32, u32) {
+        self.clips.last().copied().unwrap_or((0, 0, self.width, self.height))
+    }
+
+    fn blend(&mut self, px: u32, py: u32, color: u32, alpha: u32) {
+        let idx = (py * self.width + px) as usize;
+        let pixels = self.pixels.as_mut();
+        pixels[idx] = alpha_blend(pixels[idx], color, alpha);
+    }
+}
+
+impl Raster<Vec<u32>> {
+    /// An owned `width × height` buffer filled with `color`.
+    pub fn blank(width: u32, height: u32, color: u32) -> Raster<Vec<u32>> {
+        let pixels = vec![color; width as usize * height as usize];
+        Raster { pixels, width, height, clips: Vec::new() }
+    }
+
+    /// The pixels as 8-bit RGB triples, e.g. for a PNG row stream.
+    pub fn to_rgb(&self) -> Vec<u8> {
+        self.pixels().iter().flat_map(|&p| [(p >> 16) as u8, (p >> 8) as u8, p as u8]).collect()
+    }
+
+    /// The pixels as opaque 8-bit RGBA.
+    pub fn to_rgba(&self) -> Vec<u8> {
+        self.pixels().iter().flat_map(|&p| [(p >> 16) as u8, (p >> 8) as u8, p as u8, 0xFF]).collect()
+    }
+}
+
+impl<P: AsMut<[u32]> + AsRef<[u32]>> PaintBackend for Raster<P> {
+    fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: u32, alpha: u32) {
+        let alpha = alpha * css::alpha(color) / 255;
+        if alpha == 0 {
+            return;
+        }
+        let (cx0, cy0, cx1, cy1) = self.bounds();
+        let x0 = (rect.x.max(0.0) as u32).max(cx0);
+        let y0 = (rect.y.max(0.0) as u32).max(cy0);
+        let x1 = ((rect.x + rect.width).max(0.0) as u32).min(cx1);
+        let y1 = ((rect.y + rect.height).max(0.0) as u32).min(cy1);
+        if x0 >= x1 {
+            return;
+        }
+        let width = self.width as usize;
+        let pixels = self.pixels.as_mut();
+        for row in y0..y1 {
+            let start = row as usize * width;
+            let span = &mut pixels[start + x0 as usize..start + x1 as usize];
+            if alpha >= 255 {
+                span.fill(color & 0xFF_FFFF);
+            } else {
+                span.iter_mut().for_each(|p| *p = alpha_blend(*p, color, alpha));
+            }
+        }
+    }
+
+    fn glyph_run(&mut self, font: &Face, text: &str, x: f32, y: f32, font_size: f32, color: u32) {
+        let (cx0, cy0, cx1, cy1) = self.bounds();
+        let opacity = css::alpha(color);
+        // Skip rasterizing lines wholly outside the clip (a line's glyphs
+        // stay within two font sizes of its top).
+        if opacity == 0 || y >= cy1 as f32 || y + font_size * 2.0 <= cy0 as f32 {
+            return;
+        }
+        let ascent = font
+            .horizontal_line_metrics(font_size)
+            .map(|m| m.ascent)
+            .unwrap_or(font_size * 0.8);
+
+        let baseline_y = y + ascent;
+        let mut cursor_x = x;
+
+        for ch in text.chars() {
+            if cursor_x >= cx1 as f32 {
+                break;
+            }
+            let (metrics, bitmap) = font.rasterize(ch, font_size);
+
+            let gx = (cursor_x + metrics.xmin as f32) as i32;
+            let gy = (baseline_y - metrics.ymin as f32 - metrics.height as f32) as i32;
+
+            for row in 0..metrics.height {
+                for col in 0..metrics.width {
+                    let alpha = bitmap[row * metrics.width + col] as u32 * opacity / 255;
+                    if alpha == 0 {
+                        continue;
+                    }
+                    let px = gx + col as i32;
+                    let py = gy + row as i32;
+                    if px < cx0 as i32 || py < cy0 as i32 || px >= cx1 as i32 || py >= cy1 as i32 {
+                        continue;
+                    }
+                    self.blend(px as u32, py as u32, color, alpha);
+                }
+            }
+
+            cursor_x += font.advance(ch, font_size);
+        }
+    }
+
+    /// Bilinear sampling, blended over what is already drawn.
+    fn image(&mut self, rect: Rect, data: &[u8], src_w: u32, src_h: u32) {
+        let (dst_w, dst_h) = (rect.width as u32, rect.height as u32);
+        if dst_w == 0 || dst_h == 0 || src_w == 0 || src_h == 0 {
+            return;
+        }
+        let (cx0, cy0, cx1, cy1) = self.bounds();
+        let dst_x = rect.x as i32;
+        let dst_y = rect.y as i32;
+        let (x0, x1) = (dst_x.max(cx0 as i32), (dst_x + dst_w as i32).min(cx1 as i32));
+        let (y0, y1) = (dst_y.max(cy0 as i32), (dst_y + dst_h as i32).min(cy1 as i32));
+        let (step_x, step_y) = (src_w as f32 / dst_w as f32, src_h as f32 / dst_h as f32);
+        let width = self.width;
+        let pixels = self.pixels.as_mut();
+
+        for py in y0..y1 {
+            // Sample at the centre of each destination pixel.
+            let sy = ((py - dst_y) as f32 + 0.5) * step_y - 0.5;
+            let row = (py as u32 * width) as usize;
+            for px in x0..x1 {
+                let sx = ((px - dst_x) as f32 + 0.5) * step_x - 0.5;
+                let [r, g, b, a] = bilinear(data, src_w, src_h, sx, sy);
+                let bg = &mut pixels[row + px as usize];
+                let keep = 1.0 - a / 255.0;
+                let channel = |fg: f32, shift: u32| (fg + (*bg >> shift & 0xFF) as f32 * keep).round().min(255.0) as u32;
+                *bg = (channel(r, 16) << 16) | (channel(g, 8) << 8) | channel(b, 0);
+            }
+        }
+    }
+
+    fn push_clip(&mut self, rect: Rect) {
+        let (cx0, cy0, cx1, cy1) = self.bounds();
+        let x0 = (rect.x.max(0.0) as u32).clamp(cx0, cx1);
+        let y0 = (rect.y.max(0.0) as u32).clamp(cy0, cy1);
+        let x1 = ((rect.x + rect.width).max(0.0) as u32).clamp(x0, cx1);
+        let y1 = ((rect.y + rect.height).max(0.0) as u32).clamp(y0, cy1);
+        self.clips.push((x0, y0, x1, y1));
+    }
+
+    fn pop_clip(&mut self) {
+        self.clips.pop();
+    }
+}
+
+/// The RGBA `data` of a `width × height` image at source point `(x, y)`,
+/// interpolated between the four nearest pixels (edges repeat), with the
+/// colour premultiplied by alpha so transparent pixels do not bleed into
+/// their neighbours.
+fn bilinear(data: &[u8], width: u32, height: u32, x: f32, y: f32) -> [f32; 4] {
+    let (x, y) = (x.clamp(0.0, (width - 1) as f32), y.clamp(0.0, (height - 1) as f32));
+    let (x0, y0) = (x as u32, y as u32);
+    let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+    let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+    let texel = |tx: u32, ty: u32| {
+        let i = ((ty * width + tx) * 4) as usize;
+        let a = data[i + 3] as f32;
+        [data[i] as f32 * a / 255.0, data[i + 1] as f32 * a / 255.0, data[i + 2] as f32 * a / 255.0, a]
+    };
+    let (p00, p10, p01, p11) = (texel(x0, y0), texel(x1, y0), texel(x0, y1), texel(x1, y1));
+    std::array::from_fn(|c| {
+        let top = p00[c] + (p10[c] - p00[c]) * fx;
+        let bottom = p01[c] + (p11[c] - p01[c]) * fx;
+        top + (bottom - top) * fy
+    })
+}
+
+fn alpha_blend(bg: u32, fg: u32, alpha: u32) -> u32 {
+    let ia = 255 - alpha;
+    let r = ((fg >> 16 & 0xFF) * alpha + (bg >> 16 & 0xFF) * ia) / 255;
+    let g = ((fg >>  8 & 0xFF) * alpha + (bg >>  8 & 0xFF) * ia) / 255;
+    let b = ((fg       & 0xFF) * alpha + (bg       & 0xFF) * ia) / 255;
+    (r << 16) | (g << 8) | b
+}
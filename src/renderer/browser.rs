@@ -0,0 +1,2192 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+use std::num::NonZeroU32;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use image::RgbaImage;
+
+use softbuffer::{Context, Surface};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::event::{ElementState, Ime, KeyEvent, Modifiers, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, EventLoopProxy};
+use winit::keyboard::{Key, NamedKey};
+use winit::window::{CursorIcon, Icon, Window, WindowId};
+
+use crate::document::save::{self, Snapshot};
+use crate::document::watch::{self, WatchList};
+use crate::document::Source;
+use crate::layout::{
+    self, Button, ButtonKind, ButtonState, ElementBox, Form, HitKind, Page, PaintCmd, Rect, RemoteImages, TextIndex,
+    ToggleKind,
+};
+use crate::net::loader::{Kind, Loaded, Loader, Resource};
+use crate::net::{self, Url};
+use crate::parser::dom::Node;
+use crate::stats::{self, Stats};
+use crate::Error;
+
+use super::clipboard;
+use super::crash::Report;
+use super::damage::{fingerprints, pixel_area, Damage, Retained};
+use super::dropdown::{Dropdown, MAX_ROWS};
+use super::embed::{Activation, Cursor, Embedder};
+use super::external;
+use super::field::{line_of, FieldEdit, Preedit};
+use super::focus::{is_focusable, Focus};
+use super::find::FindBar;
+use super::inspector::Inspector;
+use super::font::{prefix_width, FontSet};
+use super::keys::{Action, Binding, Keymap};
+use super::location::{complete_path, LocationBar};
+use super::outline::{self, Outline};
+use super::pipeline::{self, Job, Prepared, Ticket};
+use super::backend::{PaintBackend, Raster};
+use super::paint::{
+    draw_dropdown, draw_find_bar, draw_inspector, draw_loading, draw_location_bar, draw_outline, draw_preedit,
+    draw_tooltip, render_frame, Mark, Theme, Viewport,
+};
+use super::selection::{block_at, hit_test, select_all, selected_text, word_at, Selection, TextPos};
+use super::session::{Geometry, Session};
+use super::{window_error, Options, UserEvent, CHROME_HEIGHT, LAYOUT_WIDTH};
+
+// ── Browser state ─────────────────────────────────────────────────────────────
+
+/// How long the pointer must rest before a `title` tooltip appears.
+const TOOLTIP_DELAY: Duration = Duration::from_millis(600);
+/// How long a fragment target stays highlighted after navigation.
+const HIGHLIGHT_DURATION: Duration = Duration::from_millis(1500);
+/// Shortest time between two frames; redraws asked for sooner wait for it,
+/// and any number of them make one frame.
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+/// Maximum delay between clicks counted as a double/triple click.
+const MULTI_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+/// Layout time after which the top of a huge page is shown on its own
+/// while the rest is laid out.
+const LAYOUT_BUDGET: Duration = Duration::from_millis(100);
+/// Column width in reader mode: roughly 70 characters of body text.
+const READER_MEASURE: f32 = 600.0;
+/// Zoom factor applied per Ctrl+Plus / Ctrl+Minus, and its bounds.
+const ZOOM_STEP: f32 = 1.1;
+const MIN_ZOOM: f32 = 0.3;
+const MAX_ZOOM: f32 = 3.0;
+/// Default distance scrolled per arrow key press or wheel notch.
+const LINE_SCROLL: f32 = 40.0;
+
+/// One document window and all of its interaction state.
+pub struct Browser {
+    /// Window title: the document's `<title>` or file name.
+    title: String,
+    /// Window icon from `<link rel="icon">`, once decoded.
+    icon: Option<Icon>,
+    /// Remote icon being fetched, if any.
+    icon_url: Option<Url>,
+    source: Source,
+    /// Parsed document, kept so it can be laid out again when images arrive.
+    nodes: Arc<Vec<Node>>,
+    /// The extracted article while reader mode is on; laid out instead of `nodes`.
+    reader: Option<Arc<Vec<Node>>>,
+    /// Whether reader mode is on, or being turned on by the job in flight.
+    reader_mode: bool,
+    page: Page,
+    /// Remote images downloaded so far, by URL.
+    images: RemoteImages,
+    /// Remote image URLs already requested (including failed ones).
+    requested_images: HashSet<String>,
+    /// Requested remote images that have not arrived (or failed) yet.
+    images_in_flight: HashSet<String>,
+    /// An image arrived since the last layout; re-run it on the next tick
+    /// without a job in flight.
+    layout_dirty: bool,
+    /// Loading or layout running on a pipeline thread, if any.
+    job: Option<InFlight>,
+    loader: Loader,
+    /// Zoom and scroll state remembered between runs, shared by all windows.
+    session: Rc<RefCell<Session>>,
+    /// Magnification on top of the display's scale factor.
+    zoom: f32,
+    /// Scale factor from the command line, used instead of the display's.
+    scale: Option<f32>,
+    /// Window size from the command line, used instead of the remembered one.
+    size: Option<(u32, u32)>,
+    theme: Theme,
+    keys: Keymap,
+    /// Logical pixels scrolled per arrow key press or wheel notch.
+    line_scroll: f32,
+    fonts: Arc<FontSet>,
+    proxy: EventLoopProxy<UserEvent>,
+    window: Option<Arc<Window>>,
+    context: Option<Context<Arc<Window>>>,
+    surface: Option<Surface<Arc<Window>, Arc<Window>>>,
+    scroll_y: f32,
+    /// Pointer position in logical window coordinates.
+    cursor: Option<(f32, f32)>,
+    /// When the resting pointer should trigger a tooltip lookup.
+    hover_deadline: Option<Instant>,
+    tooltip: Option<Tooltip>,
+    /// Whether the pointer is currently over a link (drives the cursor icon).
+    over_link: bool,
+    /// Fragment target being flashed, and when the flash started.
+    highlight: Option<(Rect, Instant)>,
+    /// Fragment to scroll to once the window exists.
+    pending_fragment: Option<String>,
+    selection: Option<Selection>,
+    /// Whether a left-button drag is extending the selection.
+    dragging: bool,
+    /// Time and logical position of the previous left press.
+    last_click: Option<(Instant, (f32, f32))>,
+    /// 1 = single, 2 = double, 3 = triple click.
+    click_count: u32,
+    modifiers: Modifiers,
+    /// Open find-in-page bar, if any.
+    find: Option<FindBar>,
+    /// Open outline sidebar, if any.
+    outline: Option<Outline>,
+    /// Open inspector panel, if any; clicks pick elements while it is.
+    inspector: Option<Inspector>,
+    /// Open dropdown list, if any; it takes clicks and keys while it is.
+    dropdown: Option<Dropdown>,
+    /// Page text with box back-references, built on first search.
+    text_index: Option<TextIndex>,
+    /// The keyboard-focused element, drawn with a ring.
+    focus: Focus,
+    /// Index into `page.inputs` of the text input being edited. The part of
+    /// its selection in view is also `selection`, over its text boxes.
+    editing: Option<usize>,
+    /// Anchor and caret of the selection in the input being edited, as
+    /// character offsets into its value.
+    field_selection: (usize, usize),
+    /// Text an input method is composing for the input being edited.
+    preedit: Option<Preedit>,
+    /// When the caret last moved; it blinks in phase with this.
+    caret_moved: Instant,
+    /// Whether the caret is in the on phase of its blink.
+    caret_shown: bool,
+    /// Index into `page.buttons` of the button drawn hovered or pressed,
+    /// and which.
+    lit_button: Option<(usize, ButtonState)>,
+    /// Index into `page.buttons` of the button the left mouse button went
+    /// down on, while it is held.
+    pressed_button: Option<usize>,
+    /// Whether something changed on screen since the last frame; `tick`
+    /// turns it into a redraw once a frame interval has passed.
+    redraw_wanted: Cell<bool>,
+    /// When the last frame was painted.
+    last_frame: Option<Instant>,
+    /// Whether the window is entirely hidden by others.
+    occluded: bool,
+    /// Reload when the document or its assets change on disk.
+    watch: bool,
+    /// Files watched for live reload, when `watch` is on.
+    watch_list: Option<WatchList>,
+    location: LocationBar,
+    /// Pending requests for the application (new window, close).
+    requests: Vec<Request>,
+    embedder: Arc<dyn Embedder>,
+    /// Print statistics for every load (`--stats`).
+    collect_stats: bool,
+    /// Statistics of the current load, until they are printed once the page
+    /// has been painted with every image it asked for.
+    stats: Option<Stats>,
+    /// The last frame painted, to repaint only what changes.
+    retained: Retained<Scene>,
+}
+
+/// Everything a frame's pixels depend on besides the page's boxes (with the
+/// selection and marks over them), the caret and the focus ring.
+#[derive(PartialEq)]
+struct Scene {
+    size: (u32, u32),
+    view: (f32, f32, f32, f32),
+    theme: Theme,
+    location: Option<(String, Option<String>)>,
+    find: Option<(String, Option<usize>, usize)>,
+    outline: Option<f32>,
+    inspector: Option<(Vec<String>, Option<Rect>)>,
+    dropdown: Option<(usize, usize, usize)>,
+    highlight: Option<(Rect, u32)>,
+    tooltip: Option<(String, f32, f32)>,
+    loading: bool,
+    preedit: Option<(String, Option<(usize, usize)>)>,
+}
+
+/// A tooltip overlay anchored at a logical window position.
+struct Tooltip {
+    text: String,
+    x: f32,
+    y: f32,
+}
+
+/// Something a browser needs the application to do on its behalf.
+pub enum Request {
+    /// Open `source` in a new window, scrolled to `fragment`.
+    OpenWindow { source: Source, fragment: Option<String> },
+    /// Close this browser's window.
+    Close,
+}
+
+impl Browser {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        source: Source,
+        nodes: Vec<Node>,
+        options: Options,
+        fonts: Arc<FontSet>,
+        proxy: EventLoopProxy<UserEvent>,
+        loader: Loader,
+        session: Rc<RefCell<Session>>,
+        embedder: Arc<dyn Embedder>,
+    ) -> Self {
+        let zoom = options.zoom.unwrap_or(session.borrow().zoom).clamp(MIN_ZOOM, MAX_ZOOM);
+        let fragment = options.fragment.clone();
+        let mut browser = Browser {
+            title: String::new(),
+            icon: None,
+            icon_url: None,
+            source,
+            nodes: Arc::new(nodes),
+            reader: None,
+            reader_mode: false,
+            page: Page::default(),
+            images: RemoteImages::new(),
+            requested_images: HashSet::new(),
+            images_in_flight: HashSet::new(),
+            layout_dirty: false,
+            job: None,
+            loader,
+            session,
+            zoom,
+            scale: options.scale,
+            size: options.size,
+            theme: options.theme,
+            keys: options.keys,
+            line_scroll: options.scroll_speed.unwrap_or(LINE_SCROLL),
+            fonts,
+            proxy,
+            window: None,
+            context: None,
+            surface: None,
+            scroll_y: 0.0,
+            cursor: None,
+            hover_deadline: None,
+            tooltip: None,
+            over_link: false,
+            highlight: None,
+            pending_fragment: None,
+            selection: None,
+            dragging: false,
+            last_click: None,
+            click_count: 0,
+            modifiers: Modifiers::default(),
+            find: None,
+            outline: None,
+            inspector: None,
+            dropdown: None,
+            text_index: None,
+            focus: Focus::default(),
+            editing: None,
+            field_selection: (0, 0),
+            preedit: None,
+            caret_moved: Instant::now(),
+            caret_shown: false,
+            lit_button: None,
+            pressed_button: None,
+            redraw_wanted: Cell::new(false),
+            last_frame: None,
+            occluded: false,
+            watch: options.watch,
+            watch_list: None,
+            location: LocationBar::new(options.location_bar),
+            requests: Vec::new(),
+            embedder,
+            collect_stats: options.stats.is_some(),
+            stats: options.stats,
+            retained: Retained::new(),
+        };
+        browser.update_metadata();
+        browser.start_job(None, After::Arrive(fragment));
+        browser
+    }
+
+    /// Create the window (and the file watcher, with `--watch`), sized like
+    /// the last window closed unless a size was given; `restore_position`
+    /// puts it where that was.
+    pub fn attach(&mut self, event_loop: &ActiveEventLoop, restore_position: bool) -> Result<WindowId, Error> {
+        let geometry = self.session.borrow().window;
+        let (width, height) = self.size
+            .or(geometry.map(|g| (g.width, g.height)))
+            .unwrap_or((800, 600));
+        let mut attrs = Window::default_attributes()
+            .with_title(&self.title)
+            .with_window_icon(self.icon.clone())
+            .with_inner_size(winit::dpi::LogicalSize::new(width, height));
+        if let Some(g) = geometry.filter(|_| restore_position) {
+            attrs = attrs.with_position(winit::dpi::PhysicalPosition::new(g.x, g.y));
+        }
+
+        let window = Arc::new(event_loop.create_window(attrs).map_err(window_error)?);
+        let context = Context::new(window.clone()).map_err(window_error)?;
+        let surface = Surface::new(&context, window.clone()).map_err(window_error)?;
+        let id = window.id();
+
+        self.window = Some(window);
+        self.context = Some(context);
+        self.surface = Some(surface);
+
+        if self.watch {
+            let list: WatchList = Default::default();
+            *list.lock().unwrap() = self.source.dependencies(&self.page);
+            let proxy = self.proxy.clone();
+            watch::spawn(list.clone(), move || {
+                let _ = proxy.send_event(UserEvent::SourceChanged(id));
+            });
+            self.watch_list = Some(list);
+        }
+
+        // Otherwise the document is positioned once it has been laid out.
+        if self.job.is_none() {
+            let fragment = self.pending_fragment.take();
+            self.position(fragment);
+        }
+        Ok(id)
+    }
+
+    /// Ask the application to close this browser's window.
+    pub fn request_close(&mut self) {
+        self.requests.push(Request::Close);
+    }
+
+    /// Requests raised while handling events, for the application to act on.
+    pub fn take_requests(&mut self) -> Vec<Request> {
+        std::mem::take(&mut self.requests)
+    }
+
+    /// Run a keyboard shortcut's action.
+    fn perform(&mut self, action: Action) {
+        match action {
+            Action::Copy => self.copy_selection(),
+            Action::Cut => {
+                self.copy_selection();
+                if let Some(input) = self.editing.filter(|&input| !self.page.inputs[input].masked) {
+                    let mut edit = self.field_edit(input);
+                    edit.insert("");
+                    self.set_field(input, edit);
+                }
+            }
+            Action::Paste => self.paste(),
+            Action::Find => self.open_find(),
+            Action::Reload => self.reload(),
+            Action::Location => self.edit_location(),
+            Action::Save => self.save_page(),
+            Action::ZoomIn => self.set_zoom(self.zoom * ZOOM_STEP),
+            Action::ZoomOut => self.set_zoom(self.zoom / ZOOM_STEP),
+            Action::ZoomReset => self.set_zoom(1.0),
+            Action::NewWindow => self.open_window(None),
+            Action::CloseWindow => self.requests.push(Request::Close),
+            Action::SelectAll => match self.editing {
+                Some(input) => {
+                    let mut edit = self.field_edit(input);
+                    edit.select_all();
+                    self.set_field(input, edit);
+                }
+                None => {
+                    self.selection = select_all(&self.page);
+                    self.request_redraw();
+                }
+            },
+            Action::Reader => self.toggle_reader(),
+            Action::Inspector => self.toggle_inspector(),
+            Action::Outline => self.toggle_outline(),
+        }
+    }
+
+    /// Ask for a new window showing this document, scrolled to `fragment`.
+    fn open_window(&mut self, fragment: Option<String>) {
+        self.requests.push(Request::OpenWindow { source: self.source.clone(), fragment });
+    }
+
+    pub fn window_event(&mut self, event: WindowEvent) {
+        match event {
+            WindowEvent::CloseRequested => self.requests.push(Request::Close),
+
+            WindowEvent::CursorMoved { position, .. } => {
+                let scale = self.scale_factor();
+                self.cursor = Some((position.x as f32 / scale, position.y as f32 / scale));
+                self.dismiss_tooltip();
+                self.hover_deadline = Some(Instant::now() + TOOLTIP_DELAY);
+                self.update_cursor_icon();
+                self.update_buttons();
+                if self.dropdown.is_some() {
+                    self.hover_dropdown();
+                }
+                if self.dragging {
+                    self.extend_selection();
+                }
+            }
+
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor = None;
+                self.dismiss_tooltip();
+                self.update_cursor_icon();
+                self.update_buttons();
+            }
+
+            WindowEvent::MouseInput { state: ElementState::Pressed, button: MouseButton::Left, .. } => {
+                if self.dropdown.is_some() {
+                    return self.click_dropdown();
+                }
+                if self.cursor.is_some_and(|(_, cy)| cy < self.chrome_height()) {
+                    self.edit_location();
+                    return;
+                }
+                if self.cursor.is_some_and(|(cx, _)| cx < self.sidebar_width()) {
+                    return self.click_outline();
+                }
+                if self.inspector.is_some() {
+                    return self.inspect_at_cursor();
+                }
+                // A click focuses the control it lands on (once it is
+                // released, for checkboxes and labels) and nothing else.
+                if self.focus.clear() {
+                    self.request_redraw();
+                }
+                // Buttons take the press rather than starting a selection.
+                if let Some(button) = self.button_under_cursor() {
+                    self.focus_control(HitKind::Button(button));
+                    self.pressed_button = Some(button);
+                    self.update_buttons();
+                    return;
+                }
+                if let Some((_, select)) = self.select_under_cursor() {
+                    self.focus_control(HitKind::Select(select));
+                    self.open_dropdown(select);
+                    return;
+                }
+                self.start_selection();
+                self.click_input();
+            }
+
+            WindowEvent::MouseInput { state: ElementState::Released, button: MouseButton::Left, .. } => {
+                self.dragging = false;
+                if self.inspector.is_some() {
+                    return;
+                }
+                if let Some(button) = self.pressed_button.take() {
+                    self.update_buttons();
+                    if self.button_under_cursor() == Some(button) {
+                        self.press_button(button);
+                    }
+                    return;
+                }
+                // A plain click (no text selected) activates links; in a
+                // text input it leaves the caret.
+                if self.editing.is_none() && self.selection.is_none_or(|s| s.is_collapsed()) {
+                    self.selection = None;
+                    if self.click_count == 1 {
+                        if let Some(toggle) = self.toggle_under_cursor() {
+                            self.focus_control(HitKind::Toggle(toggle));
+                            self.flip(toggle);
+                        } else if let Some(href) = self.link_under_cursor().map(str::to_string) {
+                            self.activate_link(&href);
+                        } else if let Some(control) = self.label_under_cursor() {
+                            self.activate_label(control);
+                        }
+                    }
+                }
+            }
+
+            WindowEvent::MouseInput { state: ElementState::Released, button: MouseButton::Middle, .. } => {
+                if let Some(href) = self.link_under_cursor().map(str::to_string) {
+                    self.open_link_in_new_window(&href);
+                }
+            }
+
+            WindowEvent::MouseWheel { delta, .. } => {
+                let dy = match delta {
+                    // LineDelta: positive y = scroll up (content moves up = see further down).
+                    // We negate so that scroll_y increases when scrolling down.
+                    MouseScrollDelta::LineDelta(_, y) => -y * self.line_scroll,
+                    MouseScrollDelta::PixelDelta(pos) => -pos.y as f32,
+                };
+                if self.cursor.is_some_and(|(cx, _)| cx < self.sidebar_width()) {
+                    let height = self.viewport_height();
+                    if let Some(outline) = &mut self.outline {
+                        outline.scroll_by(&self.page.headings, height, dy);
+                    }
+                    self.request_redraw();
+                    return;
+                }
+                if self.scroll_input_under_cursor(dy) {
+                    return;
+                }
+                self.scroll_by(dy);
+            }
+
+            WindowEvent::ModifiersChanged(modifiers) => self.modifiers = modifiers,
+
+            WindowEvent::Ime(ime) => self.ime_event(ime),
+
+            WindowEvent::KeyboardInput { event, .. } if event.state == ElementState::Pressed => {
+                // While composing, the input method has the keyboard.
+                if self.preedit.is_some() {
+                    return;
+                }
+                let binding = Binding::from_key(&event.logical_key, self.modifiers.state());
+                let action = binding.as_ref().and_then(|b| self.keys.get(b));
+
+                // Ctrl (Cmd on macOS) shortcuts win over the location and find bars.
+                if binding.as_ref().is_some_and(|b| b.ctrl) {
+                    if let Some(action) = action {
+                        self.perform(action);
+                    }
+                    return;
+                }
+
+                if self.dropdown.is_some() {
+                    self.dropdown_key(&event);
+                    return;
+                }
+
+                if self.location.editing.is_some() && self.location_key(&event) {
+                    return;
+                }
+
+                if self.editing.is_some() && self.input_key(&event) {
+                    return;
+                }
+
+                if self.find.is_some() && self.find_key(&event) {
+                    return;
+                }
+
+                if let Some(action) = action {
+                    self.perform(action);
+                    return;
+                }
+
+                if self.focus_key(&event) {
+                    return;
+                }
+
+                let page = self.viewport_height() * 0.9;
+
+                let dy: Option<f32> = match &event.logical_key {
+                    Key::Named(NamedKey::ArrowDown)  => Some(self.line_scroll),
+                    Key::Named(NamedKey::ArrowUp)    => Some(-self.line_scroll),
+                    Key::Named(NamedKey::PageDown)
+                    | Key::Named(NamedKey::Space)    => Some(page),
+                    Key::Named(NamedKey::PageUp)     => Some(-page),
+                    Key::Named(NamedKey::Home)       => { self.scroll_by(-f32::INFINITY); None }
+                    Key::Named(NamedKey::End)        => { self.scroll_by(f32::INFINITY);  None }
+                    _ => None,
+                };
+                if let Some(d) = dy { self.scroll_by(d); }
+            }
+
+            WindowEvent::Resized(_) => self.request_redraw(),
+            WindowEvent::Occluded(occluded) => {
+                self.occluded = occluded;
+                self.request_redraw();
+            }
+            WindowEvent::RedrawRequested => {
+                if let Err(e) = self.redraw() {
+                    eprintln!("radium: {e}");
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Paint the document and any overlays into the window.
+    fn redraw(&mut self) -> Result<(), Error> {
+        let started = Instant::now();
+        self.redraw_wanted.set(false);
+        self.last_frame = Some(started);
+        let scale = self.scale_factor();
+        let size = match &self.window {
+            Some(w) => w.inner_size(),
+            None => return Ok(()),
+        };
+        let (Some(pw), Some(ph)) =
+            (NonZeroU32::new(size.width), NonZeroU32::new(size.height))
+        else {
+            return Ok(());
+        };
+
+        let marks = self.find_marks();
+        let inspected = self.inspector.as_ref().and_then(Inspector::selected_rect);
+        let inspector_lines = self.inspector.as_ref().map(|i| i.describe(self.shown_nodes()));
+        let loading = self.is_loading();
+        let caret = self.caret_rect().filter(|_| self.caret_shown && self.preedit.is_none());
+        let composing = self.preedit.as_ref().and(self.editing).zip(self.caret_at());
+        if self.editing.is_some() {
+            self.place_ime();
+        }
+        let chrome_h = self.chrome_height();
+        let view = Viewport {
+            scale,
+            scroll_y: self.scroll_y,
+            top: chrome_h,
+            left: self.sidebar_width(),
+            scrollbar: true,
+            theme: self.theme,
+        };
+        let caret_bar = caret.map(|rect| {
+            let (x, y) = view.to_screen(rect.x, rect.y);
+            Rect { x: x.floor(), y, width: scale.max(1.0).round(), height: rect.height * scale }
+        });
+        let ring_width = (2.0 * scale).max(1.0);
+        let ring = self.focus.current().map(|i| {
+            let rect = self.page.regions[i].rect;
+            let (x, y) = view.to_screen(rect.x - 2.0, rect.y - 2.0);
+            Rect { x, y, width: (rect.width + 4.0) * scale, height: (rect.height + 4.0) * scale }
+        });
+        // Fade the flash out linearly over its lifetime.
+        let highlight = self.highlight.map(|(rect, started)| {
+            let t = started.elapsed().as_secs_f32() / HIGHLIGHT_DURATION.as_secs_f32();
+            (rect, (96.0 * (1.0 - t).max(0.0)) as u32)
+        });
+
+        let scene = Scene {
+            size: (size.width, size.height),
+            view: (scale, view.scroll_y, view.top, view.left),
+            theme: self.theme,
+            location: self.location.is_visible().then(|| {
+                (self.location.editing.clone().unwrap_or_else(|| self.source.to_string()), self.location.error.clone())
+            }),
+            find: self.find.as_ref().map(|f| (f.query.clone(), f.current, f.matches.len())),
+            outline: self.outline.as_ref().map(|o| o.scroll),
+            inspector: inspector_lines.clone().map(|lines| (lines, inspected)),
+            dropdown: self.dropdown.as_ref().map(|d| (d.select, d.highlighted, d.first)),
+            highlight,
+            tooltip: self.tooltip.as_ref().map(|t| (t.text.clone(), t.x, t.y)),
+            loading,
+            preedit: self.preedit.as_ref().map(|p| (p.text.clone(), p.cursor)),
+        };
+        let boxes = fingerprints(&self.page, &view, ph.get(), self.selection.as_ref(), &marks);
+        let extras = caret_bar.into_iter()
+            .chain(ring.map(|r| Rect { x: r.x - ring_width, y: r.y - ring_width, width: r.width + 2.0 * ring_width, height: r.height + 2.0 * ring_width }))
+            .collect();
+        let damage = self.retained.update(scene, boxes, extras);
+
+        let Some(surface) = &mut self.surface else { return Ok(()) };
+        surface.resize(pw, ph).map_err(window_error)?;
+        let mut buffer = surface.buffer_mut().map_err(window_error)?;
+        // The buffer only still holds the last frame if its age is 1.
+        let damage = match damage {
+            Damage::Area(area) if buffer.age() == 1 => pixel_area(area, size.width, size.height),
+            Damage::None if buffer.age() == 1 => None,
+            _ => pixel_area(Rect { x: 0.0, y: 0.0, width: size.width as f32, height: size.height as f32 }, size.width, size.height),
+        };
+        let Some(area) = damage else {
+            buffer.present().map_err(window_error)?;
+            self.report_stats();
+            return Ok(());
+        };
+        let mut target = Raster::new(&mut buffer[..], size.width, size.height)?;
+        target.push_clip(Rect { x: area.x as f32, y: area.y as f32, width: area.width.get() as f32, height: area.height.get() as f32 });
+
+        render_frame(
+            &mut target,
+            &view,
+            &self.page,
+            &self.fonts,
+            self.selection.as_ref(),
+            &marks,
+        );
+
+        if let Some(bar) = caret_bar {
+            target.fill_rect(bar, self.theme.color(CARET_COLOR), 255);
+        }
+
+        if let (Some((input, (rect, font_size))), Some(preedit)) = (composing, &self.preedit) {
+            draw_preedit(&mut target, &view, &self.fonts, self.page.inputs[input].rect, rect, font_size, preedit);
+        }
+
+        if let Some(outline) = &self.outline {
+            let current = outline::current(&self.page.headings, self.scroll_y);
+            draw_outline(&mut target, scale, &self.fonts, chrome_h, &self.page.headings, current, outline.scroll);
+        }
+
+        if let Some((rect, alpha)) = highlight {
+            let (x, y) = view.to_screen(rect.x, rect.y);
+            let flash = Rect { x, y, width: rect.width * scale, height: rect.height * scale };
+            target.fill_rect(flash, 0xFFD700, alpha);
+        }
+
+        if let Some(ring) = ring {
+            target.stroke_rect(ring, ring_width, FOCUS_RING_COLOR);
+        }
+
+        if let Some(dropdown) = &self.dropdown {
+            draw_dropdown(&mut target, &view, &self.fonts, &self.page.selects[dropdown.select], dropdown);
+        }
+
+        if let Some(rect) = inspected {
+            let (x, y) = view.to_screen(rect.x, rect.y);
+            let outline = Rect { x, y, width: rect.width * scale, height: rect.height * scale };
+            target.fill_rect(outline, INSPECT_COLOR, 64);
+            target.stroke_rect(outline, scale.max(1.0), INSPECT_COLOR);
+        }
+
+        if self.location.is_visible() {
+            let editing = self.location.editing.as_deref();
+            let shown = self.source.to_string();
+            draw_location_bar(
+                &mut target, scale, &self.fonts, chrome_h,
+                editing.unwrap_or(&shown), editing.is_some(), self.location.error.as_deref(),
+            );
+        }
+
+        if let Some(find) = &self.find {
+            draw_find_bar(
+                &mut target, scale, &self.fonts, chrome_h,
+                &find.query, find.current, find.matches.len(),
+            );
+        }
+
+        if let Some(lines) = &inspector_lines {
+            draw_inspector(&mut target, scale, &self.fonts, lines);
+        }
+
+        if loading {
+            draw_loading(&mut target, scale, &self.fonts, chrome_h);
+        }
+
+        if let Some(tip) = &self.tooltip {
+            draw_tooltip(&mut target, scale, &self.fonts, &tip.text, tip.x, tip.y);
+        }
+
+        target.pop_clip();
+        buffer.present_with_damage(&[area]).map_err(window_error)?;
+        if let Some(stats) = &mut self.stats {
+            stats.paint += started.elapsed();
+        }
+        self.report_stats();
+        Ok(())
+    }
+
+    /// Run timers (tooltip delay, highlight animation, caret blink), ask for
+    /// a frame if one is wanted and due, and return when this browser next
+    /// needs to wake up.
+    pub fn tick(&mut self) -> Option<Instant> {
+        let now = Instant::now();
+
+        if self.layout_dirty && self.job.is_none() {
+            self.layout_dirty = false;
+            self.start_job(None, After::Relayout);
+        }
+
+        if self.hover_deadline.is_some_and(|d| now >= d) {
+            self.hover_deadline = None;
+            self.show_tooltip();
+        }
+
+        let mut wake = self.hover_deadline;
+        if let Some((_, started)) = self.highlight {
+            if now >= started + HIGHLIGHT_DURATION {
+                self.highlight = None;
+            } else {
+                let next = now + FRAME_INTERVAL;
+                wake = Some(wake.map_or(next, |w| w.min(next)));
+            }
+            self.request_redraw();
+        }
+
+        if self.editing.is_some() {
+            let phase = (now - self.caret_moved).as_millis() / CARET_BLINK.as_millis();
+            let shown = phase.is_multiple_of(2);
+            if shown != self.caret_shown {
+                self.caret_shown = shown;
+                self.request_redraw();
+            }
+            let next = self.caret_moved + CARET_BLINK * (phase as u32 + 1);
+            wake = Some(wake.map_or(next, |w| w.min(next)));
+        }
+
+        // Nothing shows while hidden: sleep until the window is uncovered,
+        // keeping any wanted redraw for then.
+        if self.occluded || self.window.as_ref().is_some_and(|w| w.is_minimized() == Some(true)) {
+            return None;
+        }
+        if self.redraw_wanted.get() {
+            match self.last_frame.map(|last| last + FRAME_INTERVAL).filter(|&next| next > now) {
+                Some(next) => wake = Some(wake.map_or(next, |w| w.min(next))),
+                None => {
+                    self.redraw_wanted.set(false);
+                    if let Some(w) = &self.window {
+                        w.request_redraw();
+                    }
+                }
+            }
+        }
+        wake
+    }
+}
+
+// ── Tooltip helpers ───────────────────────────────────────────────────────────
+
+impl Browser {
+    /// Physical pixels per logical pixel, zoom included.
+    fn scale_factor(&self) -> f32 {
+        let display = self.window.as_ref().map_or(1.0, |w| w.scale_factor() as f32);
+        self.scale.unwrap_or(display) * self.zoom
+    }
+
+    /// Logical height of chrome above the document area.
+    fn chrome_height(&self) -> f32 {
+        if self.location.is_visible() { CHROME_HEIGHT } else { 0.0 }
+    }
+
+    /// Logical width of the sidebar left of the document area.
+    fn sidebar_width(&self) -> f32 {
+        if self.outline.is_some() { outline::WIDTH } else { 0.0 }
+    }
+
+    /// The pointer position in document coordinates, unless it is over the sidebar.
+    fn doc_cursor(&self) -> Option<(f32, f32)> {
+        let (cx, cy) = self.cursor?;
+        let x = cx - self.sidebar_width();
+        (x >= 0.0).then(|| (x, cy - self.chrome_height() + self.scroll_y))
+    }
+
+    /// Ask for a frame; however many are asked for, `tick` draws one per
+    /// frame interval.
+    fn request_redraw(&self) {
+        self.redraw_wanted.set(true);
+    }
+
+    /// Show the `title` of the innermost element under the resting pointer.
+    fn show_tooltip(&mut self) {
+        let (Some((cx, cy)), Some((dx, dy))) = (self.cursor, self.doc_cursor()) else { return };
+        let region = self.page.region_at(dx, dy, |k| matches!(k, HitKind::Title(_)));
+        if let Some(HitKind::Title(text)) = region.map(|r| &r.kind) {
+            self.tooltip = Some(Tooltip { text: text.clone(), x: cx, y: cy });
+            self.request_redraw();
+        }
+    }
+
+    fn dismiss_tooltip(&mut self) {
+        self.hover_deadline = None;
+        if self.tooltip.take().is_some() {
+            self.request_redraw();
+        }
+    }
+}
+
+// ── Loading and layout ────────────────────────────────────────────────────────
+
+/// How to show the result of a pipeline job.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum After {
+    /// The same boxes in new places (images arrived, zoom changed): keep
+    /// selection, focus and find state.
+    Relayout,
+    /// A new page: reset everything that referred to the old boxes, keeping
+    /// the scroll position (clamped) or going back to the top.
+    Show { top: bool },
+    /// A different document: scroll to the fragment, or else to where it was
+    /// left last time.
+    Arrive(Option<String>),
+}
+
+impl After {
+    /// How to show a job's result when it also stands in for `older`, the
+    /// ask of a job it replaced: the more thorough of the two, this one if
+    /// they are alike.
+    fn or(self, older: After) -> After {
+        let rank = |after: &After| match after {
+            After::Relayout => 0,
+            After::Show { top: false } => 1,
+            After::Show { top: true } => 2,
+            After::Arrive(_) => 3,
+        };
+        if rank(&older) > rank(&self) { older } else { self }
+    }
+}
+
+/// A pipeline job this browser is waiting for.
+struct InFlight {
+    ticket: Ticket,
+    /// The document being read, if the job loads one.
+    loading: Option<Source>,
+    after: After,
+}
+
+impl Browser {
+    /// Re-read the source and re-run the pipeline, keeping the scroll
+    /// position (clamped to the new document height).
+    pub fn reload(&mut self) {
+        let source = self.job.as_ref().and_then(|job| job.loading.clone()).unwrap_or_else(|| self.source.clone());
+        self.start_job(Some(source), After::Show { top: false });
+    }
+
+    /// Replace whatever was showing with the diagnostic page for a panic.
+    /// Laid out here rather than on a pipeline thread, which may be what failed.
+    pub fn show_crash(&mut self, report: &Report) {
+        if let Some(job) = self.job.take() {
+            job.ticket.cancel();
+        }
+        let nodes = report.document(&self.source.to_string());
+        let page = layout::layout(&nodes, LAYOUT_WIDTH / self.zoom, &self.source.url, &self.images, &*self.fonts);
+        self.reader_mode = false;
+        let prepared = Prepared {
+            generation: 0,
+            source: self.source.clone(),
+            loaded: true,
+            nodes: Arc::new(nodes),
+            article: None,
+            page,
+            stats: Stats::default(),
+        };
+        self.install(prepared, After::Show { top: true });
+    }
+
+    /// Switch between the full document and just its main article.
+    fn toggle_reader(&mut self) {
+        self.reader_mode = !self.reader_mode;
+        self.start_job(None, After::Show { top: true });
+    }
+
+    /// Have a pipeline thread read `load` (or else take the current
+    /// document) and lay it out. A job already in flight is cancelled, and
+    /// folded into this one: a pending load still happens, and the result is
+    /// shown as the more thorough of the two asks.
+    fn start_job(&mut self, load: Option<Source>, after: After) {
+        let (load, after) = match self.job.take() {
+            Some(old) => {
+                old.ticket.cancel();
+                (load.or(old.loading), after.or(old.after))
+            }
+            None => (load, after),
+        };
+        let job = Job {
+            source: load.clone().unwrap_or_else(|| self.source.clone()),
+            nodes: if load.is_some() { None } else { Some(self.nodes.clone()) },
+            // Zooming in narrows the layout so the page still fits the window.
+            width: LAYOUT_WIDTH / self.zoom,
+            column: self.reader_mode.then_some(READER_MEASURE),
+            // A load reads local images afresh, in case they changed.
+            images: if load.is_some() {
+                self.images.iter().filter(|(url, _)| !url.starts_with("file:")).map(|(u, i)| (u.clone(), i.clone())).collect()
+            } else {
+                self.images.clone()
+            },
+            // A page shown afresh can show its top early; a relayout keeps
+            // the boxes it has until all of the new ones are ready.
+            budget: (after != After::Relayout).then_some(LAYOUT_BUDGET),
+            fonts: self.fonts.clone(),
+        };
+        let ticket = pipeline::spawn(job, self.embedder.clone(), self.proxy.clone());
+        self.job = Some(InFlight { ticket, loading: load, after });
+        self.request_redraw();
+    }
+
+    /// Whether `prepared` is the result this browser is waiting for.
+    pub fn expects(&self, prepared: &Prepared) -> bool {
+        self.job.as_ref().is_some_and(|job| job.ticket.generation() == prepared.generation)
+    }
+
+    /// Show the result of this browser's job.
+    pub fn prepared(&mut self, prepared: Prepared) {
+        if !prepared.page.partial {
+            if let Some(job) = self.job.take() {
+                self.install(prepared, job.after);
+            }
+            return;
+        }
+        // Just the top of the page, while the job lays out the rest. That
+        // comes as a new page too, kept where it is scrolled, unless the top
+        // fell short of where it was to be scrolled to.
+        let Some(after) = self.job.as_ref().map(|job| job.after.clone()) else { return };
+        self.install(prepared, after.clone());
+        let fell_short = match &after {
+            After::Arrive(Some(fragment)) => !self.page.anchors.contains_key(fragment),
+            After::Arrive(None) => self.session.borrow().scroll(&self.source.url).is_some_and(|offset| offset > self.scroll_y),
+            _ => false,
+        };
+        if let Some(job) = &mut self.job {
+            job.after = if fell_short { after } else { After::Show { top: false } };
+        }
+    }
+
+    /// Whether to show the loading badge: a job is in flight that the user
+    /// is waiting for (not just arriving images).
+    fn is_loading(&self) -> bool {
+        self.job.as_ref().is_some_and(|job| job.loading.is_some() || job.after != After::Relayout)
+    }
+
+    /// Swap in a laid-out document.
+    fn install(&mut self, prepared: Prepared, after: After) {
+        let Prepared { source, loaded, nodes, article, page, stats, .. } = prepared;
+        if loaded {
+            self.stats = self.collect_stats.then_some(stats);
+        } else if let Some(gathered) = &mut self.stats {
+            gathered.layout += stats.layout;
+            gathered.image_bytes = gathered.image_bytes.max(stats.image_bytes);
+        }
+        self.source = source;
+        self.nodes = nodes;
+        self.reader = article;
+        if loaded {
+            // Local images are read afresh on each load, in case they changed.
+            self.images.retain(|url, _| !url.starts_with("file:"));
+            self.requested_images.retain(|url| !url.starts_with("file:") || self.images_in_flight.contains(url));
+            self.update_metadata();
+        }
+
+        if after == After::Relayout {
+            // Regions and controls come one per element whatever the width,
+            // so focus and control indices stay valid. Text wraps into a
+            // different number of boxes, though, so anything that indexes
+            // boxes (the selection, the text index, find matches) is dropped
+            // or found again.
+            let mut page = page;
+            self.lit_button = None;
+            for (input, field) in self.page.inputs.iter().enumerate().take(page.inputs.len()) {
+                page.set_input_value(input, field.value.clone());
+                page.scroll_input(input, field.scroll);
+            }
+            for (toggle, old) in self.page.toggles.iter().enumerate().take(page.toggles.len()) {
+                page.set_checked(toggle, old.checked);
+            }
+            for (select, old) in self.page.selects.iter().enumerate().take(page.selects.len()) {
+                if let Some(option) = old.selected.filter(|&o| o < page.selects[select].options.len()) {
+                    page.set_selected(select, option);
+                }
+            }
+            self.page = page;
+            self.text_index = None;
+            self.selection = None;
+            self.dragging = false;
+            if let Some(input) = self.editing {
+                self.show_field_selection(input);
+            }
+            if self.find.is_some() {
+                self.refresh_matches();
+            }
+            self.dismiss_tooltip();
+            self.scroll_to(self.scroll_y);
+            self.update_cursor_icon();
+            self.update_buttons();
+            self.request_redraw();
+            return;
+        }
+
+        if let Some(list) = &self.watch_list {
+            *list.lock().unwrap() = self.source.dependencies(&page);
+        }
+        self.page = page;
+        self.text_index = None;
+        self.selection = None;
+        self.dragging = false;
+        self.focus.clear();
+        self.blur_input();
+        self.lit_button = None;
+        self.pressed_button = None;
+        self.dropdown = None;
+        self.highlight = None;
+        if self.inspector.is_some() {
+            self.inspector = Some(Inspector::new(self.layout_elements()));
+        }
+        self.dismiss_tooltip();
+        match after {
+            After::Arrive(fragment) => {
+                self.scroll_y = 0.0;
+                if self.window.is_some() {
+                    self.position(fragment);
+                } else {
+                    self.pending_fragment = fragment;
+                }
+            }
+            After::Show { top } => self.scroll_to(if top { 0.0 } else { self.scroll_y }),
+            After::Relayout => unreachable!(),
+        }
+        if let Some(region) = self.page.autofocus.filter(|&r| is_focusable(&self.page, &self.page.regions[r].kind)) {
+            self.focus_region(region);
+        }
+        if self.find.is_some() {
+            self.refresh_matches();
+        }
+        self.update_cursor_icon();
+        self.update_buttons();
+        self.fetch_images();
+        self.request_redraw();
+    }
+
+    /// Scroll a newly arrived document to `fragment`, or else to where it
+    /// was left last time.
+    fn position(&mut self, fragment: Option<String>) {
+        match fragment.filter(|f| !f.is_empty()) {
+            Some(fragment) => self.go_to_fragment(&fragment),
+            None => self.restore_scroll(),
+        }
+    }
+
+    /// Print the statistics of the current load once it has settled: laid
+    /// out, painted, and with no image still on its way.
+    fn report_stats(&mut self) {
+        if self.job.is_some() || !self.images_in_flight.is_empty() {
+            return;
+        }
+        if let Some(mut stats) = self.stats.take() {
+            stats.nodes = stats::count_nodes(&self.nodes);
+            stats.boxes = self.page.boxes.len();
+            println!("{}", stats.to_json(&self.source.url));
+        }
+    }
+
+    /// Where the page's layout put each element, for the inspector.
+    fn layout_elements(&self) -> Vec<ElementBox> {
+        let width = LAYOUT_WIDTH / self.zoom;
+        let column = if self.reader.is_some() { READER_MEASURE } else { f32::INFINITY };
+        layout::layout_elements(self.shown_nodes(), width, column, &self.source.url, &self.images, &*self.fonts)
+    }
+}
+
+// ── Zoom and session ──────────────────────────────────────────────────────────
+
+impl Browser {
+    /// Change the magnification, keeping the same part of the page in view.
+    fn set_zoom(&mut self, zoom: f32) {
+        self.zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        self.session.borrow_mut().zoom = self.zoom;
+        self.start_job(None, After::Relayout);
+    }
+
+    /// Scroll to where this document was left last time, if anywhere.
+    fn restore_scroll(&mut self) {
+        let offset = self.session.borrow().scroll(&self.source.url);
+        if let Some(offset) = offset {
+            self.scroll_to(offset);
+        }
+    }
+
+    /// Record the scroll offset and window geometry in the session, before
+    /// the window closes.
+    pub fn save_state(&self) {
+        let mut session = self.session.borrow_mut();
+        session.set_scroll(&self.source.url, self.scroll_y);
+        if let Some(w) = &self.window {
+            let size = w.inner_size().to_logical::<u32>(w.scale_factor());
+            let (x, y) = w.outer_position().map_or((0, 0), |p| (p.x, p.y));
+            session.window = Some(Geometry { x, y, width: size.width, height: size.height });
+        }
+    }
+}
+
+// ── Title and icon ────────────────────────────────────────────────────────────
+
+impl Browser {
+    /// Take the window title and icon from the current document.
+    fn update_metadata(&mut self) {
+        let title = self.source.title(&self.nodes);
+        let full = format!("radium — {title}");
+        if full != self.title {
+            self.title = full;
+            if let Some(w) = &self.window {
+                w.set_title(&self.title);
+            }
+            self.embedder.title_changed(&title);
+        }
+
+        self.icon_url = None;
+        let Some(url) = self.source.icon(&self.nodes).map(|url| url.without_fragment()) else {
+            return self.set_icon(None);
+        };
+        let key = url.to_string();
+        if let Some(path) = url.to_file_path() {
+            match image::open(&path) {
+                Ok(img) => self.set_icon(Some(&img.to_rgba8())),
+                Err(e) => eprintln!("radium: cannot load icon {}: {e}", path.display()),
+            }
+        } else if let Some(img) = self.images.get(&key).cloned() {
+            self.set_icon(Some(&img));
+        } else {
+            // Keep the previous icon until the new one arrives.
+            self.loader.request(&url, Kind::Image);
+            self.icon_url = Some(url);
+        }
+    }
+
+    fn set_icon(&mut self, image: Option<&RgbaImage>) {
+        let icon = image.and_then(|img| {
+            Icon::from_rgba(img.as_raw().clone(), img.width(), img.height())
+                .map_err(|e| eprintln!("radium: unusable icon: {e}"))
+                .ok()
+        });
+        if let Some(w) = &self.window {
+            w.set_window_icon(icon.clone());
+        }
+        self.icon = icon;
+    }
+}
+
+// ── Saving ────────────────────────────────────────────────────────────────────
+
+impl Browser {
+    /// Save the document and its images/stylesheets to a new folder. Assets
+    /// are fetched on a background thread; the outcome is reported on stderr.
+    fn save_page(&self) {
+        let snapshot = Snapshot::new(&self.nodes, &self.source.url);
+        let dir = save::target_dir(&self.source);
+        std::thread::spawn(move || match snapshot.write(&dir) {
+            Ok(()) => eprintln!("radium: saved page to {}", dir.display()),
+            Err(e) => eprintln!("radium: saving page failed: {e}"),
+        });
+    }
+}
+
+// ── Images ────────────────────────────────────────────────────────────────────
+
+impl Browser {
+    /// Ask the loader for every image the page is still showing a
+    /// placeholder for, except lazy ones not yet scrolled near.
+    fn fetch_images(&mut self) {
+        // Lazy images start loading a viewport ahead of being scrolled to.
+        let viewport = self.viewport_height();
+        let (near_top, near_bottom) = (self.scroll_y - viewport, self.scroll_y + 2.0 * viewport);
+        for pending in &self.page.pending_images {
+            let b = &self.page.boxes[pending.box_index];
+            if pending.lazy && (b.y + b.height < near_top || b.y > near_bottom) {
+                continue;
+            }
+            let key = pending.url.without_fragment().to_string();
+            if self.requested_images.insert(key.clone()) {
+                self.images_in_flight.insert(key);
+                self.loader.request(&pending.url, Kind::Image);
+            }
+        }
+    }
+
+    /// Take a finished subresource if this window asked for it. Layout is
+    /// re-run on the next tick, so a burst of arrivals costs one relayout.
+    pub fn resource_loaded(&mut self, loaded: &Loaded) {
+        if loaded.kind == Kind::Image && self.icon_url.as_ref() == Some(&loaded.url) {
+            self.icon_url = None;
+            match &loaded.result {
+                Ok(Resource::Image(image)) => self.set_icon(Some(image)),
+                Ok(Resource::Bytes(_)) => {}
+                Err(e) => eprintln!("radium: cannot load icon {}: {e}", loaded.url),
+            }
+        }
+        if loaded.kind != Kind::Image || !self.requested_images.contains(&loaded.url.to_string()) {
+            return;
+        }
+        self.images_in_flight.remove(&loaded.url.to_string());
+        if self.images_in_flight.is_empty() && self.stats.is_some() {
+            // Settled; the next paint reports, even if this image failed.
+            self.request_redraw();
+        }
+        match &loaded.result {
+            Ok(Resource::Image(image)) => {
+                self.images.insert(loaded.url.to_string(), image.clone());
+                self.layout_dirty = true;
+                self.request_redraw();
+            }
+            Ok(Resource::Bytes(_)) => {}
+            Err(e) => eprintln!("radium: failed to load image {}: {e}", loaded.url),
+        }
+    }
+}
+
+// ── Location bar ──────────────────────────────────────────────────────────────
+
+impl Browser {
+    /// Focus the location bar with the current document's path or URL.
+    fn edit_location(&mut self) {
+        if self.location.editing.is_none() {
+            self.location.editing = Some(self.source.to_string());
+            self.location.error = None;
+            // Showing the strip shifts the document down; keep it in range.
+            self.scroll_to(self.scroll_y);
+        }
+        self.request_redraw();
+    }
+
+    /// Route a key press to the location bar being edited.
+    /// Returns whether it was consumed.
+    fn location_key(&mut self, event: &KeyEvent) -> bool {
+        let Some(text) = &mut self.location.editing else { return false };
+        match &event.logical_key {
+            Key::Named(NamedKey::Escape) => {
+                self.location.editing = None;
+                self.location.error = None;
+                self.scroll_to(self.scroll_y);
+            }
+            Key::Named(NamedKey::Enter) => {
+                let input = text.clone();
+                self.open_location(&input);
+            }
+            Key::Named(NamedKey::Tab) => {
+                if let Some(completed) = complete_path(text) {
+                    *text = completed;
+                    self.location.error = None;
+                }
+            }
+            Key::Named(NamedKey::Backspace) => {
+                text.pop();
+                self.location.error = None;
+            }
+            _ => match event.text.as_deref().filter(|t| !t.chars().any(char::is_control)) {
+                Some(typed) => {
+                    text.push_str(typed);
+                    self.location.error = None;
+                }
+                None => return false,
+            },
+        }
+        self.request_redraw();
+        true
+    }
+
+    /// Open `input` (a path or URL with optional `#fragment`) in this window.
+    /// On failure the bar stays open showing the error.
+    fn open_location(&mut self, input: &str) {
+        let input = input.trim();
+
+        // A bare fragment navigates within the current document.
+        if let Some(fragment) = input.strip_prefix('#') {
+            self.location.editing = None;
+            self.go_to_fragment(fragment);
+            return;
+        }
+
+        // A target that does not resolve stays in the bar to be corrected.
+        match Source::resolve(input) {
+            Ok((source, fragment)) => {
+                self.location.editing = None;
+                self.location.error = None;
+                self.navigate(source, fragment);
+            }
+            Err(e) => self.location.error = Some(e),
+        }
+    }
+
+    /// Load `source` into this window and scroll to `fragment`. A load
+    /// failure shows an error page, which Ctrl+R retries.
+    fn navigate(&mut self, source: Source, fragment: Option<String>) {
+        self.session.borrow_mut().set_scroll(&self.source.url, self.scroll_y);
+        self.start_job(Some(source), After::Arrive(fragment));
+    }
+}
+
+// ── Selection helpers ─────────────────────────────────────────────────────────
+
+impl Browser {
+    /// Begin a selection at the pointer: caret on single click, word on
+    /// double click, paragraph on triple click.
+    fn start_selection(&mut self) {
+        let Some((cx, cy)) = self.cursor else { return };
+        let now = Instant::now();
+
+        self.click_count = match self.last_click {
+            Some((at, (lx, ly)))
+                if now - at < MULTI_CLICK_INTERVAL && (lx - cx).abs() < 4.0 && (ly - cy).abs() < 4.0 =>
+            {
+                self.click_count % 3 + 1
+            }
+            _ => 1,
+        };
+        self.last_click = Some((now, (cx, cy)));
+
+        let Some((dx, dy)) = self.doc_cursor() else { return };
+        let pos = hit_test(&self.page, &self.fonts, dx, dy);
+        self.selection = pos.map(|pos| match self.click_count {
+            1 => Selection::collapsed(pos),
+            2 => word_at(&self.page, pos),
+            _ => block_at(&self.page, pos),
+        });
+        self.dragging = self.click_count == 1 && pos.is_some();
+        self.request_redraw();
+    }
+
+    fn copy_selection(&self) {
+        let text = match self.editing {
+            // A password stays in its field.
+            Some(input) if self.page.inputs[input].masked => return,
+            Some(input) => self.field_edit(input).selected_text(),
+            None => match self.selection.filter(|s| !s.is_collapsed()) {
+                Some(sel) => selected_text(&self.page, &sel),
+                None => return,
+            },
+        };
+        if text.is_empty() {
+            return;
+        }
+        if let Err(e) = clipboard::set_text(&text) {
+            eprintln!("radium: copy failed: {e}");
+        }
+    }
+
+    /// Move the selection focus to the pointer while dragging.
+    fn extend_selection(&mut self) {
+        let Some((dx, dy)) = self.doc_cursor() else { return };
+        let Some(sel) = &mut self.selection else { return };
+        if let Some(pos) = hit_test(&self.page, &self.fonts, dx, dy) {
+            if pos != sel.focus {
+                sel.focus = pos;
+                self.request_redraw();
+            }
+        }
+        if let Some(range) = self.editing.and_then(|input| self.selection_in_field(input)) {
+            self.field_selection = range;
+        }
+    }
+}
+
+// ── Text inputs ───────────────────────────────────────────────────────────────
+
+/// How long the caret of a text input stays on, and then off.
+const CARET_BLINK: Duration = Duration::from_millis(530);
+const CARET_COLOR: u32 = 0x000000;
+
+impl Browser {
+    /// Edit the text input under the pointer, if any, or else stop editing.
+    fn click_input(&mut self) {
+        let Some((dx, dy)) = self.doc_cursor() else { return };
+        let region = self.page.regions.iter()
+            .position(|r| matches!(r.kind, HitKind::Input(_)) && self.page.is_enabled(&r.kind) && r.rect.contains(dx, dy));
+        match region.map(|r| (r, &self.page.regions[r].kind)) {
+            Some((r, HitKind::Input(_))) => self.focus_region(r),
+            _ => self.blur_input(),
+        }
+    }
+
+    /// Start editing text input `input`, keeping the selection if it is in
+    /// the input and otherwise putting the caret at the end.
+    fn focus_input(&mut self, input: usize) {
+        let end = self.page.inputs[input].value.chars().count();
+        if self.editing.replace(input) != Some(input) {
+            self.preedit = None;
+            if let Some(window) = &self.window {
+                window.set_ime_allowed(true);
+            }
+        }
+        self.field_selection = self.selection_in_field(input).unwrap_or((end, end));
+        let edit = self.field_edit(input);
+        self.set_field(input, edit);
+    }
+
+    fn blur_input(&mut self) {
+        if self.editing.take().is_some() {
+            self.preedit = None;
+            if let Some(window) = &self.window {
+                window.set_ime_allowed(false);
+            }
+            self.request_redraw();
+        }
+    }
+
+    /// Show what an input method is composing at the caret of the input
+    /// being edited, and type in what it commits.
+    fn ime_event(&mut self, ime: Ime) {
+        match ime {
+            Ime::Enabled => {}
+            Ime::Preedit(text, cursor) => {
+                self.preedit = (!text.is_empty() && self.editing.is_some()).then_some(Preedit { text, cursor });
+                self.request_redraw();
+            }
+            Ime::Commit(text) => {
+                self.preedit = None;
+                let Some(input) = self.editing else { return };
+                let text = if self.page.inputs[input].multiline { text } else { text.replace(['\n', '\r'], " ") };
+                let mut edit = self.field_edit(input);
+                edit.insert(&text);
+                self.set_field(input, edit);
+            }
+            Ime::Disabled => {
+                if self.preedit.take().is_some() {
+                    self.request_redraw();
+                }
+            }
+        }
+    }
+
+    /// Tell the input method where the caret is (on screen, as last drawn),
+    /// so it puts its candidate window beside it.
+    fn place_ime(&self) {
+        let (Some(window), Some((rect, _))) = (&self.window, self.caret_at()) else { return };
+        let scale = self.scale_factor();
+        let x = (rect.x + self.sidebar_width()) * scale;
+        let y = (rect.y - self.scroll_y + self.chrome_height()) * scale;
+        window.set_ime_cursor_area(PhysicalPosition::new(x, y), PhysicalSize::new(scale, rect.height * scale));
+    }
+
+    /// `selection` as anchor and caret offsets into the value of `input`,
+    /// if it lies within the input's text boxes.
+    fn selection_in_field(&self, input: usize) -> Option<(usize, usize)> {
+        let sel = self.selection?;
+        Some((self.field_offset(input, sel.anchor)?, self.field_offset(input, sel.focus)?))
+    }
+
+    /// Offset into the value of `input` of `pos`, if it is in one of the
+    /// input's text boxes.
+    fn field_offset(&self, input: usize, pos: TextPos) -> Option<usize> {
+        let field = &self.page.inputs[input];
+        let row = pos.box_index.checked_sub(field.text_box).filter(|&row| row < field.rows)?;
+        let lines = field.lines();
+        Some(match lines.get(field.scroll + row) {
+            Some(line) => line.start + pos.offset.min(line.len()),
+            None => field.value.chars().count(),
+        })
+    }
+
+    /// The value of `input` and the selection in it.
+    fn field_edit(&self, input: usize) -> FieldEdit {
+        let field = &self.page.inputs[input];
+        let end = field.value.chars().count();
+        let (anchor, caret) = self.field_selection;
+        FieldEdit { value: field.value.clone(), anchor: anchor.min(end), caret: caret.min(end) }
+    }
+
+    /// Apply an edit of `input`: show its value, scrolled to the caret, and
+    /// the part of its selection in view as the page's. A read-only input
+    /// takes no edit that changes its value.
+    fn set_field(&mut self, input: usize, edit: FieldEdit) {
+        if edit.value != self.page.inputs[input].value {
+            if self.page.inputs[input].readonly {
+                return;
+            }
+            self.page.set_input_value(input, edit.value.clone());
+            self.text_index = None;
+        }
+        self.field_selection = (edit.anchor, edit.caret);
+        let field = &self.page.inputs[input];
+        let caret_line = line_of(&field.lines(), edit.caret);
+        let scroll = if caret_line < field.scroll {
+            caret_line
+        } else if caret_line >= field.scroll + field.rows {
+            caret_line + 1 - field.rows
+        } else {
+            field.scroll
+        };
+        if scroll != field.scroll {
+            self.page.scroll_input(input, scroll);
+            self.text_index = None;
+        }
+        self.show_field_selection(input);
+        // Restart the blink so the caret is visible while it moves.
+        self.caret_moved = Instant::now();
+        self.caret_shown = true;
+        self.request_redraw();
+    }
+
+    /// Make `selection` the part of the selection in `input` that is in view.
+    fn show_field_selection(&mut self, input: usize) {
+        let field = &self.page.inputs[input];
+        let lines = field.lines();
+        let pos = |offset: usize| {
+            let line = line_of(&lines, offset);
+            let (row, offset) = match line.checked_sub(field.scroll) {
+                None => (0, 0),
+                Some(row) if row >= field.rows => (field.rows - 1, lines.get(field.scroll + field.rows - 1).map_or(0, |l| l.len())),
+                Some(row) => (row, offset - lines[line].start),
+            };
+            TextPos { box_index: field.text_box + row, offset }
+        };
+        let (anchor, caret) = self.field_selection;
+        self.selection = Some(Selection { anchor: pos(anchor), focus: pos(caret) });
+    }
+
+    /// Scroll the text area under the pointer by `dy` pixels' worth of
+    /// lines, if it has more lines than it shows. Returns whether it did.
+    fn scroll_input_under_cursor(&mut self, dy: f32) -> bool {
+        let Some((x, y)) = self.doc_cursor() else { return false };
+        let Some(input) = self.page.region_at(x, y, |k| matches!(k, HitKind::Input(_))).and_then(|r| match r.kind {
+            HitKind::Input(input) => Some(input),
+            _ => None,
+        }) else {
+            return false;
+        };
+        let field = &self.page.inputs[input];
+        if field.lines().len() <= field.rows {
+            return false;
+        }
+        let line_height = self.page.boxes[field.text_box].height;
+        let lines = (dy / line_height).round() as isize;
+        let lines = if lines == 0 { dy.signum() as isize } else { lines };
+        self.page.scroll_input(input, field.scroll.saturating_add_signed(lines));
+        if self.editing == Some(input) {
+            self.show_field_selection(input);
+        }
+        self.text_index = None;
+        self.request_redraw();
+        true
+    }
+
+    /// Type the clipboard's text into the text input being edited; a
+    /// single-line input takes line breaks as spaces.
+    fn paste(&mut self) {
+        let Some(input) = self.editing else { return };
+        let text = match clipboard::get_text() {
+            Ok(text) => text.replace("\r\n", "\n"),
+            Err(e) => return eprintln!("radium: paste failed: {e}"),
+        };
+        let text = if self.page.inputs[input].multiline { text } else { text.replace(['\n', '\r'], " ") };
+        let mut edit = self.field_edit(input);
+        edit.insert(&text);
+        self.set_field(input, edit);
+    }
+
+    /// Route a key press to the text input being edited. Returns whether it
+    /// was consumed.
+    fn input_key(&mut self, event: &KeyEvent) -> bool {
+        let Some(input) = self.editing else { return false };
+        let extend = self.modifiers.state().shift_key();
+        let field = &self.page.inputs[input];
+        let (multiline, rows) = (field.multiline, field.rows as isize);
+        let lines = field.lines();
+        let mut edit = self.field_edit(input);
+        match &event.logical_key {
+            Key::Named(NamedKey::ArrowLeft) => edit.left(extend),
+            Key::Named(NamedKey::ArrowRight) => edit.right(extend),
+            Key::Named(NamedKey::ArrowUp) if multiline => edit.vertical(&lines, -1, extend),
+            Key::Named(NamedKey::ArrowDown) if multiline => edit.vertical(&lines, 1, extend),
+            Key::Named(NamedKey::PageUp) if multiline => edit.vertical(&lines, -rows, extend),
+            Key::Named(NamedKey::PageDown) if multiline => edit.vertical(&lines, rows, extend),
+            Key::Named(NamedKey::Home) if multiline => edit.line_start(&lines, extend),
+            Key::Named(NamedKey::End) if multiline => edit.line_end(&lines, extend),
+            Key::Named(NamedKey::Home) => edit.move_to(0, extend),
+            Key::Named(NamedKey::End) => edit.move_to(edit.end(), extend),
+            Key::Named(NamedKey::Enter) if multiline => edit.insert("\n"),
+            Key::Named(NamedKey::Backspace) => edit.backspace(),
+            Key::Named(NamedKey::Delete) => edit.delete(),
+            Key::Named(NamedKey::Escape) => {
+                self.blur_input();
+                self.focus.clear();
+                self.selection = None;
+                return true;
+            }
+            // Enter in a single-line input submits its form, as its first
+            // submit button would.
+            Key::Named(NamedKey::Enter) => {
+                let Some(form) = self.page.inputs[input].form else { return false };
+                let submitter = self.page.buttons.iter().position(|b| b.form == Some(form) && b.kind == ButtonKind::Submit);
+                match submitter {
+                    Some(button) => self.press_button(button),
+                    None => self.submit(form, None),
+                }
+                return true;
+            }
+            Key::Named(NamedKey::Tab) => return false,
+            _ => match event.text.as_deref().filter(|t| !t.chars().any(char::is_control)) {
+                Some(text) => edit.insert(text),
+                None => return false,
+            },
+        }
+        self.set_field(input, edit);
+        true
+    }
+
+    /// Where to draw the caret of the text input being edited, unless text
+    /// is selected in it or its line is scrolled out of view.
+    fn caret_rect(&self) -> Option<Rect> {
+        let (anchor, caret) = self.field_selection;
+        if anchor != caret {
+            return None;
+        }
+        self.caret_at().map(|(rect, _)| rect)
+    }
+
+    /// Where the caret of the text input being edited is, selection or not,
+    /// and the font size of its line; `None` if the line is out of view.
+    fn caret_at(&self) -> Option<(Rect, f32)> {
+        let field = &self.page.inputs[self.editing?];
+        let caret = self.field_selection.1;
+        let lines = field.lines();
+        let line = line_of(&lines, caret);
+        let row = line.checked_sub(field.scroll).filter(|&row| row < field.rows)?;
+        // The row shows the line (masked, in a password field) or, while
+        // the value is empty, the placeholder with the caret at its start.
+        let b = &self.page.boxes[field.text_box + row];
+        let PaintCmd::Text { content, font_size, .. } = &b.cmd else { return None };
+        let x = b.x + prefix_width(self.fonts.get(false, false), content, *font_size, caret - lines[line].start);
+        Some((Rect { x, y: b.y, width: 1.0, height: b.height }, *font_size))
+    }
+}
+
+// ── Dropdowns ─────────────────────────────────────────────────────────────────
+
+impl Browser {
+    /// Index into `page.regions` of the dropdown under the pointer, and
+    /// into `page.selects`.
+    fn select_under_cursor(&self) -> Option<(usize, usize)> {
+        let (dx, dy) = self.doc_cursor()?;
+        self.page.regions.iter().enumerate().find_map(|(i, r)| match r.kind {
+            HitKind::Select(select) if r.rect.contains(dx, dy) && !self.page.selects[select].disabled => Some((i, select)),
+            _ => None,
+        })
+    }
+
+    fn open_dropdown(&mut self, select: usize) {
+        self.dropdown = Some(Dropdown::new(select, &self.page.selects[select]));
+        let list = Dropdown::rect(&self.page.selects[select]);
+        self.scroll_into_view(list);
+        self.request_redraw();
+    }
+
+    /// Choose the option under the pointer, if any, and close the list. The
+    /// press is used up: its release activates nothing under the list.
+    fn click_dropdown(&mut self) {
+        let Some(dropdown) = self.dropdown.take() else { return };
+        let select = &self.page.selects[dropdown.select];
+        if let Some(option) = self.doc_cursor().and_then(|(x, y)| dropdown.option_at(select, x, y)) {
+            self.page.set_selected(dropdown.select, option);
+        }
+        self.click_count = 0;
+        self.request_redraw();
+    }
+
+    /// Highlight the option under the pointer.
+    fn hover_dropdown(&mut self) {
+        let cursor = self.doc_cursor();
+        let Some(dropdown) = &mut self.dropdown else { return };
+        let select = &self.page.selects[dropdown.select];
+        if let Some(option) = cursor.and_then(|(x, y)| dropdown.option_at(select, x, y)) {
+            if option != dropdown.highlighted {
+                dropdown.highlight(select, option);
+                self.request_redraw();
+            }
+        }
+    }
+
+    /// Handle a key press while the list is open: arrows, `PageUp` /
+    /// `PageDown`, `Home` / `End` move the highlight, `Enter` / `Space`
+    /// choose it, `Escape` / `Tab` close the list. Other keys do nothing.
+    fn dropdown_key(&mut self, event: &KeyEvent) {
+        let Some(dropdown) = &mut self.dropdown else { return };
+        let select = &self.page.selects[dropdown.select];
+        match &event.logical_key {
+            Key::Named(NamedKey::ArrowDown) => dropdown.step(select, 1),
+            Key::Named(NamedKey::ArrowUp) => dropdown.step(select, -1),
+            Key::Named(NamedKey::PageDown) => dropdown.step(select, MAX_ROWS as isize),
+            Key::Named(NamedKey::PageUp) => dropdown.step(select, -(MAX_ROWS as isize)),
+            Key::Named(NamedKey::Home) => dropdown.highlight(select, 0),
+            Key::Named(NamedKey::End) => dropdown.highlight(select, usize::MAX),
+            Key::Named(NamedKey::Enter | NamedKey::Space) => {
+                let (index, option) = (dropdown.select, dropdown.highlighted);
+                if option < select.options.len() {
+                    self.page.set_selected(index, option);
+                }
+                self.dropdown = None;
+            }
+            Key::Named(NamedKey::Escape | NamedKey::Tab) => self.dropdown = None,
+            _ => return,
+        }
+        self.request_redraw();
+    }
+}
+
+// ── Find helpers ──────────────────────────────────────────────────────────────
+
+/// Background of every find match, and of the active one.
+const MATCH_COLOR: u32 = 0xFFFF66;
+const CURRENT_MATCH_COLOR: u32 = 0xFF9632;
+
+impl Browser {
+    fn open_find(&mut self) {
+        if self.find.is_none() {
+            self.find = Some(FindBar::new());
+        }
+        self.request_redraw();
+    }
+
+    /// Route a key press to the open find bar. Returns whether it was consumed.
+    fn find_key(&mut self, event: &KeyEvent) -> bool {
+        let Some(find) = &mut self.find else { return false };
+        match &event.logical_key {
+            Key::Named(NamedKey::Escape) => {
+                self.find = None;
+            }
+            Key::Named(NamedKey::Enter) => {
+                find.step(!self.modifiers.state().shift_key());
+                self.reveal_current_match();
+            }
+            Key::Named(NamedKey::Backspace) => {
+                find.query.pop();
+                self.run_search();
+            }
+            _ => match event.text.as_deref().filter(|t| !t.chars().any(char::is_control)) {
+                Some(text) => {
+                    find.query.push_str(text);
+                    self.run_search();
+                }
+                None => return false,
+            },
+        }
+        self.request_redraw();
+        true
+    }
+
+    /// Re-run the search and scroll to the first match in or below the viewport.
+    fn run_search(&mut self) {
+        self.refresh_matches();
+        self.reveal_current_match();
+    }
+
+    /// Recompute matches against the current page without scrolling.
+    fn refresh_matches(&mut self) {
+        let index = self.text_index.get_or_insert_with(|| TextIndex::new(&self.page));
+        let top = hit_test(&self.page, &self.fonts, 0.0, self.scroll_y);
+        if let Some(find) = &mut self.find {
+            find.search(index, top);
+        }
+    }
+
+    fn reveal_current_match(&mut self) {
+        let Some(m) = self.find.as_ref().and_then(|f| f.current_match()) else { return };
+        let b = &self.page.boxes[m.anchor.box_index];
+        let rect = Rect { x: b.x, y: b.y, width: b.width, height: b.height };
+        self.scroll_into_view(rect);
+    }
+
+    fn find_marks(&self) -> Vec<Mark> {
+        let Some(find) = &self.find else { return Vec::new() };
+        find.matches.iter().enumerate().map(|(i, m)| Mark {
+            range: *m,
+            color: if Some(i) == find.current { CURRENT_MATCH_COLOR } else { MATCH_COLOR },
+        }).collect()
+    }
+}
+
+// ── Outline ───────────────────────────────────────────────────────────────────
+
+impl Browser {
+    fn toggle_outline(&mut self) {
+        self.outline = match self.outline {
+            Some(_) => None,
+            None => Some(Outline::new()),
+        };
+        self.request_redraw();
+    }
+
+    /// Jump to the heading whose entry is under the pointer.
+    fn click_outline(&mut self) {
+        let (Some((_, cy)), Some(outline)) = (self.cursor, &self.outline) else { return };
+        let Some(i) = outline.entry_at(&self.page.headings, cy - self.chrome_height()) else { return };
+        let heading = &self.page.headings[i];
+        // Through the anchor map when the heading has an id, like following `#id`.
+        let rect = heading.id.as_ref().and_then(|id| self.page.anchors.get(id)).copied().unwrap_or(heading.rect);
+        self.flash_to(rect);
+    }
+}
+
+// ── Inspector ─────────────────────────────────────────────────────────────────
+
+/// Highlight over the inspected element.
+const INSPECT_COLOR: u32 = 0x1A73E8;
+
+impl Browser {
+    fn toggle_inspector(&mut self) {
+        self.inspector = match self.inspector {
+            Some(_) => None,
+            None => Some(Inspector::new(self.layout_elements())),
+        };
+        self.request_redraw();
+    }
+
+    /// Inspect the element under the pointer.
+    fn inspect_at_cursor(&mut self) {
+        let Some((dx, dy)) = self.doc_cursor() else { return };
+        if let Some(inspector) = &mut self.inspector {
+            inspector.pick(dx, dy);
+        }
+        self.request_redraw();
+    }
+
+    /// The nodes `page` was laid out from: the article in reader mode.
+    fn shown_nodes(&self) -> &[Node] {
+        self.reader.as_deref().unwrap_or(&self.nodes)
+    }
+}
+
+// ── Link and focus helpers ────────────────────────────────────────────────────
+
+/// Outline drawn around the keyboard-focused element.
+const FOCUS_RING_COLOR: u32 = 0x1A73E8;
+
+impl Browser {
+    /// `href` of the innermost link under the pointer.
+    fn link_under_cursor(&self) -> Option<&str> {
+        let (dx, dy) = self.doc_cursor()?;
+        if dy < self.scroll_y {
+            return None; // over the chrome
+        }
+        match &self.page.region_at(dx, dy, |k| matches!(k, HitKind::Link(_)))?.kind {
+            HitKind::Link(href) => Some(href),
+            _ => None,
+        }
+    }
+
+    fn update_cursor_icon(&mut self) {
+        let over_link = self.link_under_cursor().is_some();
+        if over_link != self.over_link {
+            self.over_link = over_link;
+            let (icon, cursor) = if over_link {
+                (CursorIcon::Pointer, Cursor::Pointer)
+            } else {
+                (CursorIcon::Default, Cursor::Default)
+            };
+            if let Some(w) = &self.window {
+                w.set_cursor(icon);
+            }
+            self.embedder.cursor_changed(cursor);
+        }
+    }
+
+    /// Route a key press to the focused element: Tab and Shift+Tab move
+    /// focus, Enter activates it, and Space toggles a checkbox or opens a
+    /// dropdown. Returns whether it was consumed.
+    fn focus_key(&mut self, event: &KeyEvent) -> bool {
+        let focused = self.focus.current().map(|i| &self.page.regions[i].kind);
+        match &event.logical_key {
+            Key::Named(NamedKey::Tab) => {
+                if let Some(next) = self.focus.next(&self.page, !self.modifiers.state().shift_key()) {
+                    self.focus_region(next);
+                }
+            }
+            Key::Named(NamedKey::Enter) => self.activate_focused(),
+            Key::Named(NamedKey::Space) if matches!(focused, Some(HitKind::Toggle(_) | HitKind::Select(_))) => {
+                self.activate_focused();
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Focus the element of region `region`, editing it if it is a text
+    /// input, and scroll it into view; the focus ring moves to it.
+    fn focus_region(&mut self, region: usize) {
+        self.focus.set(Some(region));
+        match self.page.regions[region].kind {
+            HitKind::Input(input) => self.focus_input(input),
+            _ => self.blur_input(),
+        }
+        self.scroll_into_view(self.page.regions[region].rect);
+        self.request_redraw();
+    }
+
+    /// Focus the control `kind` names, as with [`Browser::focus_region`].
+    fn focus_control(&mut self, kind: HitKind) {
+        if let Some(region) = self.page.regions.iter().position(|r| r.kind == kind) {
+            self.focus_region(region);
+        }
+    }
+
+    /// Index into `page.regions` of the control labelled by the `<label>`
+    /// under the pointer.
+    fn label_under_cursor(&self) -> Option<usize> {
+        let (dx, dy) = self.doc_cursor()?;
+        match self.page.region_at(dx, dy, |k| matches!(k, HitKind::Label(_)) && self.page.is_enabled(k))?.kind {
+            HitKind::Label(control) => Some(control),
+            _ => None,
+        }
+    }
+
+    /// Click on a label of the control of region `control`: focus it, and
+    /// toggle or press it if it is a checkbox, radio button or button.
+    fn activate_label(&mut self, control: usize) {
+        self.focus_region(control);
+        match self.page.regions[control].kind {
+            HitKind::Toggle(toggle) => self.flip(toggle),
+            HitKind::Button(button) => self.press_button(button),
+            _ => {}
+        }
+    }
+
+    fn activate_focused(&mut self) {
+        let Some(i) = self.focus.current() else { return };
+        match &self.page.regions[i].kind {
+            HitKind::Link(href) => {
+                let href = href.clone();
+                self.activate_link(&href);
+            }
+            &HitKind::Toggle(toggle) => self.flip(toggle),
+            &HitKind::Button(button) => self.press_button(button),
+            &HitKind::Select(select) => self.open_dropdown(select),
+            _ => {}
+        }
+    }
+
+    /// Index into `page.buttons` of the button under the pointer.
+    fn button_under_cursor(&self) -> Option<usize> {
+        let (dx, dy) = self.doc_cursor()?;
+        match self.page.region_at(dx, dy, |k| matches!(k, HitKind::Button(_)) && self.page.is_enabled(k))?.kind {
+            HitKind::Button(button) => Some(button),
+            _ => None,
+        }
+    }
+
+    /// Draw the button under the pointer hovered (or pressed, if the press
+    /// started on it), and any other normally.
+    fn update_buttons(&mut self) {
+        let lit = self.button_under_cursor().map(|button| match self.pressed_button == Some(button) {
+            true => (button, ButtonState::Pressed),
+            false => (button, ButtonState::Hover),
+        });
+        if lit == self.lit_button {
+            return;
+        }
+        if let Some((old, _)) = self.lit_button {
+            self.page.set_button_state(old, ButtonState::Normal);
+        }
+        if let Some((button, state)) = lit {
+            self.page.set_button_state(button, state);
+        }
+        self.lit_button = lit;
+        self.request_redraw();
+    }
+
+    /// Tell the embedder `button` was pressed, then submit its form if it
+    /// is a submit button in one.
+    fn press_button(&mut self, button: usize) {
+        let Button { kind, label, name, value, form, .. } = self.page.buttons[button].clone();
+        let data = match form {
+            Some(form) => self.page.form_data(form, Some(button)),
+            None => self.page.form_values(),
+        };
+        let activation = Activation { url: self.source.url.clone(), kind, label, name, value, form: data };
+        self.embedder.button_activated(&activation);
+        if let (ButtonKind::Submit, Some(form)) = (kind, form) {
+            self.submit(form, Some(button));
+        }
+    }
+
+    /// Submit `form` (by button `submitter`, if any): load its action with
+    /// the form data as the query string. Only GET is supported.
+    fn submit(&mut self, form: usize, submitter: Option<usize>) {
+        let Form { action, method } = &self.page.forms[form];
+        if method != "get" {
+            eprintln!("radium: cannot submit a form with method '{method}'");
+            return;
+        }
+        let Some(mut url) = self.resolve_link(action) else { return };
+        url.query = Some(net::form_urlencoded(&self.page.form_data(form, submitter)));
+        url.fragment = None;
+        let (source, _) = Source::from_url(url.clone()).unwrap_or_else(|_| (Source::unresolved(&url.to_string()), None));
+        self.navigate(source, None);
+    }
+
+    /// Index into `page.toggles` of the checkbox or radio button under the
+    /// pointer.
+    fn toggle_under_cursor(&self) -> Option<usize> {
+        let (dx, dy) = self.doc_cursor()?;
+        match self.page.region_at(dx, dy, |k| matches!(k, HitKind::Toggle(_)) && self.page.is_enabled(k))?.kind {
+            HitKind::Toggle(toggle) => Some(toggle),
+            _ => None,
+        }
+    }
+
+    /// Click `toggle`: a checkbox flips, a radio button becomes the checked
+    /// one of its group.
+    fn flip(&mut self, toggle: usize) {
+        let checked = match self.page.toggles[toggle].kind {
+            ToggleKind::Checkbox => !self.page.toggles[toggle].checked,
+            ToggleKind::Radio => true,
+        };
+        self.page.set_checked(toggle, checked);
+        self.request_redraw();
+    }
+
+    /// Follow `href`: scroll within this document, or load the target in place.
+    fn activate_link(&mut self, href: &str) {
+        let Some(url) = self.resolve_link(href) else { return };
+        if url.without_fragment() == self.source.url {
+            self.go_to_fragment(url.fragment.as_deref().unwrap_or(""));
+            return;
+        }
+        if !self.embedder.link_activated(&url, false) {
+            return;
+        }
+        if external::handles(&url.scheme) {
+            return self.open_externally(&url);
+        }
+        let (source, fragment) = Source::from_url(url.clone()).unwrap_or_else(|_| (Source::unresolved(&url.to_string()), None));
+        self.navigate(source, fragment);
+    }
+
+    fn open_link_in_new_window(&mut self, href: &str) {
+        let Some(url) = self.resolve_link(href) else { return };
+        if !self.embedder.link_activated(&url, true) {
+            return;
+        }
+        if external::handles(&url.scheme) {
+            return self.open_externally(&url);
+        }
+        let (source, fragment) = Source::from_url(url.clone()).unwrap_or_else(|_| (Source::unresolved(&url.to_string()), None));
+        self.requests.push(Request::OpenWindow { source, fragment });
+    }
+
+    /// Hand `url` to the platform's default handler (mail client, dialer, ...).
+    fn open_externally(&self, url: &Url) {
+        if let Err(e) = external::open(&url.to_string()) {
+            eprintln!("radium: cannot open {url}: {e}");
+        }
+    }
+
+    /// Resolve `href` against the document URL.
+    fn resolve_link(&self, href: &str) -> Option<Url> {
+        match self.source.url.join(href) {
+            Ok(url) => Some(url),
+            Err(e) => {
+                eprintln!("radium: bad link '{href}': {e}");
+                None
+            }
+        }
+    }
+
+    /// Scroll the element with id/name `fragment` to the top and flash it.
+    /// An empty fragment or `#top` without a matching element scrolls to the top.
+    fn go_to_fragment(&mut self, fragment: &str) {
+        match self.page.anchors.get(fragment).copied() {
+            Some(rect) => self.flash_to(rect),
+            None if fragment.is_empty() || fragment.eq_ignore_ascii_case("top") => self.scroll_to(0.0),
+            None => eprintln!("radium: no element with id '{fragment}'"),
+        }
+    }
+
+    /// Scroll `rect` to near the top of the viewport and flash it.
+    fn flash_to(&mut self, rect: Rect) {
+        self.scroll_to(rect.y - 8.0);
+        self.highlight = Some((rect, Instant::now()));
+    }
+}
+
+// ── Scroll helpers ────────────────────────────────────────────────────────────
+
+impl Browser {
+    /// Maximum logical-pixel scroll offset for the current viewport.
+    fn max_scroll(&self) -> f32 {
+        (self.page.height() - self.viewport_height()).max(0.0)
+    }
+
+    fn scroll_by(&mut self, dy: f32) {
+        self.scroll_to(self.scroll_y + dy);
+    }
+
+    /// Logical height of the document area (the window minus chrome).
+    fn viewport_height(&self) -> f32 {
+        let window_h = self.window.as_ref()
+            .map(|w| w.inner_size().height as f32 / self.scale_factor())
+            .unwrap_or(600.0);
+        window_h - self.chrome_height()
+    }
+
+    /// Scroll just enough for `rect` to be visible, placing it a third of the
+    /// way down the viewport when it is off-screen.
+    fn scroll_into_view(&mut self, rect: Rect) {
+        let viewport = self.viewport_height();
+        if rect.y < self.scroll_y || rect.y + rect.height > self.scroll_y + viewport {
+            self.scroll_to(rect.y - viewport / 3.0);
+        }
+    }
+
+    fn scroll_to(&mut self, y: f32) {
+        self.dismiss_tooltip();
+        self.scroll_y = y.clamp(0.0, self.max_scroll());
+        self.fetch_images();
+        self.request_redraw();
+    }
+}
@@ -0,0 +1,65 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Clipboard helpers, tried in order until one accepts the text.
+/// winit has no clipboard API, so we hand off to the platform's tools.
+#[cfg(target_os = "macos")]
+const PROVIDERS: &[(&str, &[&str])] = &[("pbcopy", &[])];
+
+#[cfg(target_os = "windows")]
+const PROVIDERS: &[(&str, &[&str])] = &[("clip", &[])];
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const PROVIDERS: &[(&str, &[&str])] = &[
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+];
+
+/// Tools that print the clipboard's text, tried in the same order.
+#[cfg(target_os = "macos")]
+const READERS: &[(&str, &[&str])] = &[("pbpaste", &[])];
+
+#[cfg(target_os = "windows")]
+const READERS: &[(&str, &[&str])] = &[("powershell", &["-NoProfile", "-Command", "Get-Clipboard -Raw"])];
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const READERS: &[(&str, &[&str])] = &[
+    ("wl-paste", &["--no-newline"]),
+    ("xclip", &["-selection", "clipboard", "-out"]),
+    ("xsel", &["--clipboard", "--output"]),
+];
+
+/// Place `text` on the system clipboard.
+pub fn set_text(text: &str) -> Result<(), String> {
+    for (program, args) in PROVIDERS {
+        let child = Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        let Ok(mut child) = child else { continue };
+
+        let written = child.stdin.take().is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+        if written && child.wait().is_ok_and(|status| status.success()) {
+            return Ok(());
+        }
+    }
+
+    let names: Vec<&str> = PROVIDERS.iter().map(|(p, _)| *p).collect();
+    Err(format!("no clipboard tool available (tried {})", names.join(", ")))
+}
+
+/// The text on the system clipboard.
+pub fn get_text() -> Result<String, String> {
+    for (program, args) in READERS {
+        let output = Command::new(program).args(*args).stdin(Stdio::null()).stderr(Stdio::null()).output();
+        if let Some(output) = output.ok().filter(|output| output.status.success()) {
+            return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+        }
+    }
+
+    let names: Vec<&str> = READERS.iter().map(|(p, _)| *p).collect();
+    Err(format!("no clipboard tool available (tried {})", names.join(", ")))
+}
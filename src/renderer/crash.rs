@@ -0,0 +1,91 @@
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+
+use crate::document;
+use crate::parser::dom::Node;
+
+/// Frames of the backtrace shown on the diagnostic page.
+const BACKTRACE_FRAMES: usize = 12;
+
+/// What a caught panic left behind.
+pub struct Report {
+    pub message: String,
+    /// `file:line:column` of the panic.
+    pub location: Option<String>,
+    /// Innermost frames first, radium's own when there are symbols for them.
+    pub backtrace: Vec<String>,
+}
+
+thread_local! {
+    /// The last panic on this thread, recorded by the hook for `caught`.
+    static LAST: RefCell<Option<Report>> = const { RefCell::new(None) };
+}
+
+/// Install, once per process, a panic hook that records a [`Report`] for
+/// [`caught`] before running the previous hook (which prints to stderr).
+pub fn install_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let report = Report {
+                message: message(info.payload()),
+                location: info.location().map(ToString::to_string),
+                backtrace: excerpt(&Backtrace::force_capture().to_string()),
+            };
+            LAST.with(|last| *last.borrow_mut() = Some(report));
+            previous(info);
+        }));
+    });
+}
+
+/// Run `f`, turning a panic into its report.
+pub fn caught<T>(f: impl FnOnce() -> T) -> Result<T, Report> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        LAST.with(|last| last.borrow_mut().take())
+            .unwrap_or_else(|| Report { message: message(&*payload), location: None, backtrace: Vec::new() })
+    })
+}
+
+impl Report {
+    /// A diagnostic page for this panic, which happened while showing `target`.
+    pub fn document(&self, target: &str) -> Vec<Node> {
+        document::crash_document(target, &self.message, self.location.as_deref(), &self.backtrace)
+    }
+}
+
+fn message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(s) => s.to_string(),
+        None => payload.downcast_ref::<String>().cloned().unwrap_or_else(|| "unknown panic".into()),
+    }
+}
+
+/// `function (file:line:column)` for the frames of a formatted backtrace,
+/// keeping only radium's own (hook frames aside) if any are named.
+fn excerpt(backtrace: &str) -> Vec<String> {
+    let mut frames: Vec<(String, Option<String>)> = Vec::new();
+    for line in backtrace.lines().map(str::trim) {
+        if let Some(location) = line.strip_prefix("at ") {
+            if let Some((_, at)) = frames.last_mut() {
+                at.get_or_insert_with(|| location.to_string());
+            }
+        } else if let Some((_, function)) = line.split_once(": ").filter(|(n, _)| n.parse::<usize>().is_ok()) {
+            frames.push((function.to_string(), None));
+        }
+    }
+
+    let own = |function: &str| function.starts_with("radium::") && !function.contains("::crash::");
+    let keep_own = frames.iter().any(|(function, _)| own(function));
+    frames.into_iter()
+        .filter(|(function, _)| !keep_own || own(function))
+        .take(BACKTRACE_FRAMES)
+        .map(|(function, at)| match at {
+            Some(at) => format!("{function} ({at})"),
+            None => function,
+        })
+        .collect()
+}
@@ -0,0 +1,112 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroU32;
+
+use softbuffer::Rect as PixelRect;
+
+use crate::layout::{Page, PaintCmd, Rect};
+
+use super::paint::{Mark, Viewport};
+use super::selection::Selection;
+
+/// What the last frame showed, kept to find the part of the next frame that
+/// differs from it.
+pub struct Retained<S> {
+    /// Everything else the frame depended on (window size, scroll,
+    /// overlays, ...); when it changes, the whole frame is repainted.
+    scene: Option<S>,
+    /// Fingerprint and screen rectangle of each document box on screen.
+    boxes: Vec<(u64, Rect)>,
+    /// Screen rectangles of decorations drawn over the boxes (the caret, the
+    /// focus ring).
+    extras: Vec<Rect>,
+}
+
+/// The part of a frame that needs painting.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Damage {
+    /// Nothing changed since the last frame.
+    None,
+    /// Only this screen rectangle changed.
+    Area(Rect),
+    Full,
+}
+
+impl<S: PartialEq> Retained<S> {
+    pub fn new() -> Self {
+        Retained { scene: None, boxes: Vec::new(), extras: Vec::new() }
+    }
+
+    /// Compare the frame about to be painted with the last one, and keep
+    /// it for the next comparison. `boxes` comes from [`fingerprints`].
+    pub fn update(&mut self, scene: S, boxes: Vec<(u64, Rect)>, extras: Vec<Rect>) -> Damage {
+        let same_scene = self.scene.as_ref() == Some(&scene);
+        self.scene = Some(scene);
+        let old_boxes = std::mem::replace(&mut self.boxes, boxes);
+        let old_extras = std::mem::replace(&mut self.extras, extras);
+        if !same_scene || old_boxes.len() != self.boxes.len() {
+            return Damage::Full;
+        }
+
+        let mut changed = old_boxes.iter().zip(&self.boxes)
+            .filter(|(old, new)| old.0 != new.0)
+            .flat_map(|(old, new)| [old.1, new.1])
+            .collect::<Vec<_>>();
+        if old_extras != self.extras {
+            changed.extend(old_extras.iter().chain(&self.extras));
+        }
+        match changed.into_iter().reduce(union) {
+            Some(area) => Damage::Area(area),
+            None => Damage::None,
+        }
+    }
+}
+
+/// A fingerprint of every box of `page` that may show on a buffer `height`
+/// pixels high, as painted, with the selection and marks over it, and where
+/// it lands on screen. A text box reaches to the right edge, since its text
+/// may overflow it.
+pub fn fingerprints(page: &Page, view: &Viewport, height: u32, selection: Option<&Selection>, marks: &[Mark]) -> Vec<(u64, Rect)> {
+    view.visible(page, height).map(|i| {
+        let b = &page.boxes[i];
+        let mut hasher = DefaultHasher::new();
+        (i, [b.x, b.y, b.width, b.height].map(f32::to_bits)).hash(&mut hasher);
+        let mut width = b.width * view.scale;
+        match &b.cmd {
+            PaintCmd::FillRect { color } | PaintCmd::HLine { color } => color.hash(&mut hasher),
+            PaintCmd::Text { content, font_size, bold, italic, monospace, color, underline } => {
+                (content, font_size.to_bits(), bold, italic, monospace, color, underline).hash(&mut hasher);
+                // `marks` is sorted and non-overlapping, as for painting.
+                let len = content.chars().count();
+                let first = marks.partition_point(|m| m.range.ordered().1.box_index < i);
+                let in_box = marks[first..].iter().take_while(|m| m.range.ordered().0.box_index <= i);
+                for mark in in_box {
+                    (mark.range.range_in_box(i, len), mark.color).hash(&mut hasher);
+                }
+                selection.and_then(|s| s.range_in_box(i, len)).hash(&mut hasher);
+                width = f32::INFINITY;
+            }
+            // Pixels are immutable once laid out; where they live identifies them.
+            PaintCmd::Image { data, img_width, img_height } => (data.as_ptr(), data.len(), img_width, img_height).hash(&mut hasher),
+        }
+        let (x, y) = view.to_screen(b.x, b.y);
+        (hasher.finish(), Rect { x, y, width, height: b.height * view.scale })
+    }).collect()
+}
+
+/// The smallest rectangle holding both `a` and `b`.
+fn union(a: Rect, b: Rect) -> Rect {
+    let (x0, y0) = (a.x.min(b.x), a.y.min(b.y));
+    let (x1, y1) = ((a.x + a.width).max(b.x + b.width), (a.y + a.height).max(b.y + b.height));
+    Rect { x: x0, y: y0, width: x1 - x0, height: y1 - y0 }
+}
+
+/// `area` rounded out to whole pixels and cut to a `width × height` buffer,
+/// as a buffer presents it; `None` if nothing is left.
+pub fn pixel_area(area: Rect, width: u32, height: u32) -> Option<PixelRect> {
+    let x0 = area.x.floor().clamp(0.0, width as f32) as u32;
+    let y0 = area.y.floor().clamp(0.0, height as f32) as u32;
+    let x1 = (area.x + area.width).ceil().clamp(0.0, width as f32) as u32;
+    let y1 = (area.y + area.height).ceil().clamp(0.0, height as f32) as u32;
+    Some(PixelRect { x: x0, y: y0, width: NonZeroU32::new(x1.saturating_sub(x0))?, height: NonZeroU32::new(y1.saturating_sub(y0))? })
+}
@@ -0,0 +1,69 @@
+use crate::layout::{Rect, Select};
+
+/// Logical height of one option in the list.
+pub const ROW_HEIGHT: f32 = 24.0;
+/// Options shown at once; longer lists scroll to keep the highlighted one in view.
+pub const MAX_ROWS: usize = 10;
+
+/// State of an open `<select>` popup, which lists its options below it.
+pub struct Dropdown {
+    /// Index into `Page::selects`.
+    pub select: usize,
+    /// Option under the pointer, or moved to with the arrow keys.
+    pub highlighted: usize,
+    /// First option shown.
+    pub first: usize,
+}
+
+impl Dropdown {
+    /// Open the list of `select` (index `index`) at its chosen option.
+    pub fn new(index: usize, select: &Select) -> Self {
+        let mut dropdown = Dropdown { select: index, highlighted: 0, first: 0 };
+        dropdown.highlight(select, select.selected.unwrap_or(0));
+        dropdown
+    }
+
+    /// Options shown at once for `select`.
+    pub fn rows(select: &Select) -> usize {
+        select.options.len().min(MAX_ROWS)
+    }
+
+    /// Where the list goes, in document coordinates: right under the
+    /// control and as wide, with a one-pixel border.
+    pub fn rect(select: &Select) -> Rect {
+        Rect {
+            x: select.rect.x,
+            y: select.rect.y + select.rect.height,
+            width: select.rect.width,
+            height: Self::rows(select) as f32 * ROW_HEIGHT + 2.0,
+        }
+    }
+
+    /// The option at document point `(x, y)`, if it is over the list.
+    pub fn option_at(&self, select: &Select, x: f32, y: f32) -> Option<usize> {
+        let rect = Self::rect(select);
+        if !rect.contains(x, y) {
+            return None;
+        }
+        let row = ((y - rect.y - 1.0).max(0.0) / ROW_HEIGHT) as usize;
+        let option = self.first + row.min(Self::rows(select).saturating_sub(1));
+        (option < select.options.len()).then_some(option)
+    }
+
+    /// Highlight `option`, scrolling the list to show it.
+    pub fn highlight(&mut self, select: &Select, option: usize) {
+        let Some(last) = select.options.len().checked_sub(1) else { return };
+        self.highlighted = option.min(last);
+        let rows = Self::rows(select);
+        if self.highlighted < self.first {
+            self.first = self.highlighted;
+        } else if self.highlighted >= self.first + rows {
+            self.first = self.highlighted + 1 - rows;
+        }
+    }
+
+    /// Move the highlight `delta` options down (up, if negative).
+    pub fn step(&mut self, select: &Select, delta: isize) {
+        self.highlight(select, self.highlighted.saturating_add_signed(delta));
+    }
+}
@@ -0,0 +1,93 @@
+use std::time::Instant;
+
+use crate::document::Source;
+use crate::layout::ButtonKind;
+use crate::net::loader::Kind;
+use crate::net::Url;
+use crate::parser::dom::Node;
+use crate::stats::Stats;
+
+/// Callbacks from the browser windows to an application embedding them
+/// (see [`run_embedded`](super::run_embedded)).
+///
+/// Every method has a default that behaves like the standalone browser, so
+/// an embedder implements only what it cares about. `document` and
+/// `resource` may be called from loader threads.
+pub trait Embedder: Send + Sync {
+    /// A link to `url` was followed, in place or (`new_window`) in a new
+    /// window. Return `false` to stay on the current page.
+    fn link_activated(&self, _url: &Url, _new_window: bool) -> bool {
+        true
+    }
+
+    /// The document title shown in the window's title bar changed.
+    fn title_changed(&self, _title: &str) {}
+
+    /// The pointer moved onto or off a link.
+    fn cursor_changed(&self, _cursor: Cursor) {}
+
+    /// A button on the page was clicked, or pressed from the keyboard.
+    fn button_activated(&self, _activation: &Activation) {}
+
+    /// The document at `url` is about to be read or fetched. Return its
+    /// body to supply it instead; it is classified as a file at `url` would be.
+    fn document(&self, _url: &Url) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// A subresource at a non-`file:` `url` is about to be fetched. Return
+    /// its body to supply it instead.
+    fn resource(&self, _url: &Url, _kind: Kind) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+/// A button being pressed, as told to [`Embedder::button_activated`].
+#[derive(Debug, Clone)]
+pub struct Activation {
+    /// The document the button is on.
+    pub url: Url,
+    pub kind: ButtonKind,
+    pub label: String,
+    pub name: Option<String>,
+    pub value: String,
+    /// The data of its form when it was pressed (see
+    /// [`Page::form_data`](crate::layout::Page::form_data)), or the page's
+    /// [`form_values`](crate::layout::Page::form_values) if it is in none.
+    pub form: Vec<(String, String)>,
+}
+
+/// Pointer shape over the page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cursor {
+    Default,
+    /// Over a link.
+    Pointer,
+}
+
+/// The embedder of the `radium` binary: every default.
+pub(crate) struct Standalone;
+
+impl Embedder for Standalone {}
+
+/// Load `source`, taking the body from `embedder` if it supplies one;
+/// `preview` gets the part of a download received so far now and then.
+pub(crate) fn load_document(
+    embedder: &dyn Embedder,
+    source: &mut Source,
+    stats: &mut Stats,
+    preview: &mut dyn FnMut(Vec<Node>),
+) -> Vec<Node> {
+    let started = Instant::now();
+    match embedder.document(&source.url) {
+        Some(body) => {
+            stats.read += started.elapsed();
+            stats.bytes += body.len();
+            let started = Instant::now();
+            let nodes = source.parse(&body);
+            stats.parse += started.elapsed();
+            nodes
+        }
+        None => source.load_or_error_progressively(stats, preview),
+    }
+}
@@ -0,0 +1,34 @@
+use std::process::{Command, Stdio};
+
+/// The platform's "open with the default application" command.
+#[cfg(target_os = "macos")]
+const OPENER: (&str, &[&str]) = ("open", &[]);
+
+// `start` is a cmd builtin; the empty argument is the window title.
+#[cfg(target_os = "windows")]
+const OPENER: (&str, &[&str]) = ("cmd", &["/C", "start", ""]);
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const OPENER: (&str, &[&str]) = ("xdg-open", &[]);
+
+/// Whether links with this scheme are handed to the OS rather than loaded.
+/// `javascript:` links are ignored entirely.
+pub fn handles(scheme: &str) -> bool {
+    !matches!(scheme, "http" | "https" | "file" | "javascript")
+}
+
+/// Open `url` (e.g. `mailto:`, `tel:`) with the user's default handler.
+pub fn open(url: &str) -> Result<(), String> {
+    let (program, args) = OPENER;
+    let mut child = Command::new(program)
+        .args(args)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("running {program}: {e}"))?;
+    // Reap the opener without blocking the event loop.
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use std::ops::Range;
+
+/// A text input's value being edited, with the selection in it as character
+/// offsets.
+pub struct FieldEdit {
+    pub value: String,
+    /// Where the selection started; equal to `caret` when nothing is selected.
+    pub anchor: usize,
+    pub caret: usize,
+}
+
+impl FieldEdit {
+    /// Offset of the end of the value.
+    pub fn end(&self) -> usize {
+        self.value.chars().count()
+    }
+
+    /// `(start, end)` of the selection.
+    pub fn selected(&self) -> (usize, usize) {
+        (self.anchor.min(self.caret), self.anchor.max(self.caret))
+    }
+
+    /// Type `text` over the selection.
+    pub fn insert(&mut self, text: &str) {
+        let (start, end) = self.selected();
+        self.replace(start, end, text);
+    }
+
+    /// Delete the selection, or else the character before the caret.
+    pub fn backspace(&mut self) {
+        match self.selected() {
+            (start, end) if start < end => self.replace(start, end, ""),
+            (0, _) => {}
+            (caret, _) => self.replace(caret - 1, caret, ""),
+        }
+    }
+
+    /// Delete the selection, or else the character after the caret.
+    pub fn delete(&mut self) {
+        match self.selected() {
+            (start, end) if start < end => self.replace(start, end, ""),
+            (caret, _) if caret < self.end() => self.replace(caret, caret + 1, ""),
+            _ => {}
+        }
+    }
+
+    /// Move the caret one character left; without `extend`, a selection
+    /// collapses to its start instead.
+    pub fn left(&mut self, extend: bool) {
+        match self.selected() {
+            (start, end) if start < end && !extend => self.move_to(start, false),
+            _ => self.move_to(self.caret.saturating_sub(1), extend),
+        }
+    }
+
+    /// Move the caret one character right; without `extend`, a selection
+    /// collapses to its end instead.
+    pub fn right(&mut self, extend: bool) {
+        match self.selected() {
+            (start, end) if start < end && !extend => self.move_to(end, false),
+            _ => self.move_to((self.caret + 1).min(self.end()), extend),
+        }
+    }
+
+    /// Put the caret at `caret`, selecting from the anchor if `extend`.
+    pub fn move_to(&mut self, caret: usize, extend: bool) {
+        self.caret = caret.min(self.end());
+        if !extend {
+            self.anchor = self.caret;
+        }
+    }
+
+    /// Move the caret `delta` lines down (up, if negative) through `lines`
+    /// (as from `InputField::lines`), keeping its column where the line is
+    /// long enough.
+    pub fn vertical(&mut self, lines: &[Range<usize>], delta: isize, extend: bool) {
+        let line = line_of(lines, self.caret);
+        let column = self.caret - lines[line].start;
+        let target = line.saturating_add_signed(delta).min(lines.len() - 1);
+        self.move_to((lines[target].start + column).min(line_end(lines, target)), extend);
+    }
+
+    /// Move the caret to the start of its line.
+    pub fn line_start(&mut self, lines: &[Range<usize>], extend: bool) {
+        self.move_to(lines[line_of(lines, self.caret)].start, extend);
+    }
+
+    /// Move the caret to the end of its line.
+    pub fn line_end(&mut self, lines: &[Range<usize>], extend: bool) {
+        self.move_to(line_end(lines, line_of(lines, self.caret)), extend);
+    }
+
+    /// The selected text.
+    pub fn selected_text(&self) -> String {
+        let (start, end) = self.selected();
+        self.value.chars().skip(start).take(end - start).collect()
+    }
+
+    pub fn select_all(&mut self) {
+        self.anchor = 0;
+        self.caret = self.end();
+    }
+
+    /// Replace characters `start..end` with `text`, leaving the caret after it.
+    fn replace(&mut self, start: usize, end: usize, text: &str) {
+        let (from, to) = (self.byte(start), self.byte(end));
+        self.value.replace_range(from..to, text);
+        self.move_to(start + text.chars().count(), false);
+    }
+
+    fn byte(&self, offset: usize) -> usize {
+        self.value.char_indices().nth(offset).map_or(self.value.len(), |(i, _)| i)
+    }
+}
+
+/// Text an input method is composing, shown at the caret of the input being
+/// edited until it is committed.
+pub struct Preedit {
+    pub text: String,
+    /// Byte range of the input method's cursor in `text`, if it shows one.
+    pub cursor: Option<(usize, usize)>,
+}
+
+/// Index into `lines` of the line holding `offset`. Where a line wraps, the
+/// offset between the two is on the later one.
+pub fn line_of(lines: &[Range<usize>], offset: usize) -> usize {
+    lines.iter().rposition(|line| line.start <= offset).unwrap_or(0)
+}
+
+/// Last caret offset on line `line`: before the space or character it
+/// wraps after, since an offset at the wrap is on the next line.
+fn line_end(lines: &[Range<usize>], line: usize) -> usize {
+    let end = lines[line].end;
+    match lines.get(line + 1) {
+        Some(next) if next.start == end && end > lines[line].start => end - 1,
+        _ => end,
+    }
+}
@@ -0,0 +1,74 @@
+use crate::layout::TextIndex;
+
+use super::selection::{Selection, TextPos};
+
+/// State of the find-in-page bar.
+pub struct FindBar {
+    pub query: String,
+    /// Every match in document order.
+    pub matches: Vec<Selection>,
+    /// Index into `matches` of the active match.
+    pub current: Option<usize>,
+}
+
+impl FindBar {
+    pub fn new() -> Self {
+        FindBar { query: String::new(), matches: Vec::new(), current: None }
+    }
+
+    /// Recompute matches for the current query (case-insensitive).
+    /// The active match becomes the first one at or after `from`.
+    pub fn search(&mut self, index: &TextIndex, from: Option<TextPos>) {
+        self.matches.clear();
+        self.current = None;
+
+        let needle: Vec<char> = self.query.chars().map(fold_case).collect();
+        if needle.is_empty() {
+            return;
+        }
+        let haystack: Vec<char> = index.chars.iter().copied().map(fold_case).collect();
+
+        let mut at = 0;
+        while at + needle.len() <= haystack.len() {
+            if haystack[at..at + needle.len()] == needle[..] {
+                let start = index.locate(at);
+                let end = index.locate(at + needle.len() - 1);
+                if let (Some((sb, so)), Some((eb, eo))) = (start, end) {
+                    self.matches.push(Selection {
+                        anchor: TextPos { box_index: sb, offset: so },
+                        focus: TextPos { box_index: eb, offset: eo + 1 },
+                    });
+                }
+                at += needle.len();
+            } else {
+                at += 1;
+            }
+        }
+
+        if !self.matches.is_empty() {
+            let first = from.map_or(0, |pos| self.matches.partition_point(|m| m.anchor < pos));
+            self.current = Some(first % self.matches.len());
+        }
+    }
+
+    /// Move to the next (or previous) match, wrapping around.
+    pub fn step(&mut self, forward: bool) {
+        let n = self.matches.len();
+        if n == 0 {
+            return;
+        }
+        self.current = Some(match self.current {
+            Some(i) if forward => (i + 1) % n,
+            Some(i) => (i + n - 1) % n,
+            None => 0,
+        });
+    }
+
+    pub fn current_match(&self) -> Option<&Selection> {
+        self.matches.get(self.current?)
+    }
+}
+
+fn fold_case(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
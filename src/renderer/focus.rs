@@ -0,0 +1,64 @@
+use crate::layout::{HitKind, Page};
+
+/// Keyboard focus on a page: the focused element, and the order Tab moves
+/// it through the others in.
+#[derive(Default)]
+pub struct Focus {
+    /// Index into `Page::regions` of the focused element.
+    current: Option<usize>,
+}
+
+impl Focus {
+    pub fn current(&self) -> Option<usize> {
+        self.current
+    }
+
+    /// Focus `region`, or nothing. Returns whether that changed anything.
+    pub fn set(&mut self, region: Option<usize>) -> bool {
+        std::mem::replace(&mut self.current, region) != region
+    }
+
+    /// Focus nothing. Returns whether something was focused.
+    pub fn clear(&mut self) -> bool {
+        self.set(None)
+    }
+
+    /// Where Tab (or Shift+Tab, unless `forward`) moves focus on `page`,
+    /// wrapping around; from an element out of the order, the first (last)
+    /// one in it.
+    pub fn next(&self, page: &Page, forward: bool) -> Option<usize> {
+        let order = tab_order(page);
+        let n = order.len();
+        let pos = self.current.and_then(|f| order.iter().position(|&i| i == f));
+        let next = match pos {
+            _ if n == 0 => return None,
+            Some(p) if forward => (p + 1) % n,
+            Some(p) => (p + n - 1) % n,
+            None if forward => 0,
+            None => n - 1,
+        };
+        Some(order[next])
+    }
+}
+
+/// Whether the element of a region of kind `kind` can take focus: links
+/// and controls that are not disabled.
+pub fn is_focusable(page: &Page, kind: &HitKind) -> bool {
+    matches!(kind, HitKind::Link(_) | HitKind::Input(_) | HitKind::Toggle(_) | HitKind::Button(_) | HitKind::Select(_))
+        && page.is_enabled(kind)
+}
+
+/// Indices into `page.regions` of the elements Tab stops at, in order:
+/// those with a positive `tabindex`, lowest first, then those with
+/// `tabindex=0` or none, each group in document order. A negative
+/// `tabindex` leaves an element out (it can still be clicked into focus).
+pub fn tab_order(page: &Page) -> Vec<usize> {
+    let mut order: Vec<(i32, usize)> = page.regions.iter().enumerate()
+        .filter(|(_, r)| is_focusable(page, &r.kind))
+        .map(|(i, _)| (page.tab_indices.get(&i).copied().unwrap_or(0), i))
+        .filter(|&(index, _)| index >= 0)
+        .collect();
+    // Zero sorts after every positive index; the sort is stable.
+    order.sort_by_key(|&(index, _)| if index == 0 { i32::MAX } else { index });
+    order.into_iter().map(|(_, i)| i).collect()
+}
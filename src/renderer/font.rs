@@ -0,0 +1,474 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use fontdue::{Font, FontSettings, LineMetrics, Metrics};
+
+use crate::layout::{TextMeasurer, SOFT_HYPHEN, WORD_JOINER, ZERO_WIDTH_SPACE};
+use crate::Error;
+
+// ── Font set ──────────────────────────────────────────────────────────────────
+
+/// The four faces of a typeface family.
+pub struct FontSet {
+    regular: Face,
+    bold: Face,
+    italic: Face,
+    bold_italic: Face,
+    /// For preformatted text and code: upright only, italics being drawn
+    /// upright too.
+    monospace: Face,
+    monospace_bold: Face,
+}
+
+/// One face of a family, and the fallback fonts it takes the glyphs it
+/// lacks (e.g. CJK ideographs) from.
+#[derive(Clone)]
+pub struct Face {
+    font: Font,
+    fallbacks: Arc<Vec<Font>>,
+    /// Advance of each character measured so far, per pixel of font size:
+    /// advances scale linearly, so one entry serves every size, zoom and
+    /// window width. Clones of the face share it.
+    advances: Arc<RwLock<HashMap<char, f32>>>,
+}
+
+impl Face {
+    fn new(font: Font) -> Self {
+        Face { font, fallbacks: Arc::default(), advances: Arc::default() }
+    }
+
+    /// The font `ch` is drawn in: this face if it has a glyph for it, else
+    /// the first fallback that does, else this face (and its missing-glyph
+    /// box).
+    pub fn font_for(&self, ch: char) -> &Font {
+        if self.font.lookup_glyph_index(ch) != 0 {
+            return &self.font;
+        }
+        self.fallbacks.iter().find(|f| f.lookup_glyph_index(ch) != 0).unwrap_or(&self.font)
+    }
+
+    /// Metrics of `ch`. A soft hyphen takes no space (layout puts a hyphen
+    /// in its place where a line breaks at it), and neither do zero-width
+    /// spaces and word joiners. No-break spaces the fonts lack are as wide as the space
+    /// they stand for.
+    pub fn metrics(&self, ch: char, font_size: f32) -> Metrics {
+        let blank = |metrics: Metrics, advance_width: f32| Metrics { width: 0, height: 0, advance_width, ..metrics };
+        match ch {
+            SOFT_HYPHEN | ZERO_WIDTH_SPACE | WORD_JOINER | '\u{FEFF}' => blank(self.font.metrics(ch, font_size), 0.0),
+            '\u{A0}' | '\u{2007}' | '\u{202F}' if !self.has_glyph(ch) => {
+                let advance = match ch {
+                    '\u{A0}' => self.metrics(' ', font_size).advance_width,
+                    // A figure space is as wide as a digit.
+                    '\u{2007}' => self.metrics('0', font_size).advance_width,
+                    // A narrow one, as a thin space: a fifth of an em.
+                    _ => font_size / 5.0,
+                };
+                blank(self.font.metrics(ch, font_size), advance)
+            }
+            _ => self.font_for(ch).metrics(ch, font_size),
+        }
+    }
+
+    /// Advance width of `ch`, as [`metrics`](Self::metrics) gives it,
+    /// memoized.
+    pub fn advance(&self, ch: char, font_size: f32) -> f32 {
+        if let Some(&advance) = self.advances.read().unwrap_or_else(|e| e.into_inner()).get(&ch) {
+            return advance * font_size;
+        }
+        let advance = self.metrics(ch, 1.0).advance_width;
+        self.advances.write().unwrap_or_else(|e| e.into_inner()).insert(ch, advance);
+        advance * font_size
+    }
+
+    pub fn rasterize(&self, ch: char, font_size: f32) -> (Metrics, Vec<u8>) {
+        match ch {
+            SOFT_HYPHEN | ZERO_WIDTH_SPACE | WORD_JOINER | '\u{FEFF}' | '\u{A0}' | '\u{2007}' | '\u{202F}' => (self.metrics(ch, font_size), Vec::new()),
+            _ => self.font_for(ch).rasterize(ch, font_size),
+        }
+    }
+
+    fn has_glyph(&self, ch: char) -> bool {
+        std::iter::once(&self.font).chain(self.fallbacks.iter()).any(|f| f.lookup_glyph_index(ch) != 0)
+    }
+
+    /// Line metrics of the face itself; fallback glyphs share its baseline.
+    pub fn horizontal_line_metrics(&self, font_size: f32) -> Option<LineMetrics> {
+        self.font.horizontal_line_metrics(font_size)
+    }
+}
+
+impl FontSet {
+    pub fn get(&self, bold: bool, italic: bool) -> &Face {
+        match (bold, italic) {
+            (true,  true)  => &self.bold_italic,
+            (true,  false) => &self.bold,
+            (false, true)  => &self.italic,
+            (false, false) => &self.regular,
+        }
+    }
+
+    /// The face text in that style is drawn in.
+    pub fn face(&self, bold: bool, italic: bool, monospace: bool) -> &Face {
+        match (monospace, bold) {
+            (true, true) => &self.monospace_bold,
+            (true, false) => &self.monospace,
+            (false, _) => self.get(bold, italic),
+        }
+    }
+}
+
+impl TextMeasurer for FontSet {
+    fn advance(&self, ch: char, bold: bool, italic: bool, font_size: f32) -> f32 {
+        self.get(bold, italic).advance(ch, font_size)
+    }
+
+    fn monospace_advance(&self, ch: char, bold: bool, italic: bool, font_size: f32) -> f32 {
+        self.face(bold, italic, true).advance(ch, font_size)
+    }
+
+    fn ascent(&self, bold: bool, italic: bool, monospace: bool, font_size: f32) -> f32 {
+        self.face(bold, italic, monospace).horizontal_line_metrics(font_size).map_or(font_size * 0.8, |m| m.ascent)
+    }
+}
+
+// ── Metrics ───────────────────────────────────────────────────────────────────
+
+/// Total advance width of `text`, in the same units as `font_size`.
+pub fn text_width(font: &Face, text: &str, font_size: f32) -> f32 {
+    text.chars().map(|ch| font.advance(ch, font_size)).sum()
+}
+
+/// Advance width of the first `n` characters of `text`.
+pub fn prefix_width(font: &Face, text: &str, font_size: f32, n: usize) -> f32 {
+    text.chars().take(n).map(|ch| font.advance(ch, font_size)).sum()
+}
+
+/// `text`, or if it is wider than `max_width` as much of it as fits followed
+/// by "…".
+pub fn ellipsize<'a>(font: &Face, text: &'a str, font_size: f32, max_width: f32) -> Cow<'a, str> {
+    if text_width(font, text, font_size) <= max_width {
+        return Cow::Borrowed(text);
+    }
+    let room = max_width - text_width(font, "…", font_size);
+    let mut width = 0.0;
+    let mut end = 0;
+    for (i, ch) in text.char_indices() {
+        width += font.advance(ch, font_size);
+        if width > room {
+            break;
+        }
+        end = i + ch.len_utf8();
+    }
+    Cow::Owned(format!("{}…", text[..end].trim_end()))
+}
+
+/// Character offset whose caret position is nearest to `x` (relative to the text start).
+pub fn offset_at(font: &Face, text: &str, font_size: f32, x: f32) -> usize {
+    let mut cursor = 0.0;
+    for (i, ch) in text.chars().enumerate() {
+        let advance = font.advance(ch, font_size);
+        if x < cursor + advance / 2.0 {
+            return i;
+        }
+        cursor += advance;
+    }
+    text.chars().count()
+}
+
+/// Ascent-to-descent height of one line of `font` in physical pixels.
+pub fn line_height_px(font: &Face, font_size: f32) -> f32 {
+    font.horizontal_line_metrics(font_size)
+        .map(|m| m.ascent - m.descent)
+        .unwrap_or(font_size * 1.2)
+}
+
+// ── Font loading ──────────────────────────────────────────────────────────────
+
+/// Where to look for the document font.
+#[derive(Debug, Clone, Default)]
+pub struct FontConfig {
+    /// Family name, e.g. "DejaVu Serif", matched against font file names in
+    /// `dirs` and the system font directories.
+    pub family: Option<String>,
+    /// Directories searched before the built-in locations, both for `family`
+    /// and for `font.ttf`, `font-bold.ttf`, ... files like `./assets` holds.
+    pub dirs: Vec<PathBuf>,
+}
+
+impl FontConfig {
+    /// Whether `family` is set and installed, so [`FontSet::load`] will use
+    /// it rather than falling back to the default font.
+    pub fn has_family(&self) -> bool {
+        self.family.as_deref().is_some_and(|family| find_family(family, &self.dirs).is_some())
+    }
+}
+
+/// Faces in `FontSet` order: regular, bold, italic, bold italic.
+type Faces = [Option<Vec<u8>>; 4];
+
+fn try_load_bytes(candidates: &[PathBuf]) -> Option<Vec<u8>> {
+    for path in candidates {
+        if let Ok(data) = std::fs::read(path) {
+            eprintln!("radium: loaded font from {}", path.display());
+            return Some(data);
+        }
+    }
+    None
+}
+
+fn make_font(data: &[u8]) -> Result<Font, Error> {
+    Font::from_bytes(data, FontSettings::default()).map_err(|e| Error::Font(e.to_string()))
+}
+
+/// Parse an optional variant face; an unparsable one is skipped with a warning.
+fn make_variant(data: Option<&[u8]>) -> Option<Font> {
+    make_font(data?).map_err(|e| eprintln!("radium: skipping font face: {e}")).ok()
+}
+
+pub fn load_font_set() -> Result<FontSet, Error> {
+    FontSet::load(&FontConfig::default())
+}
+
+impl FontSet {
+    /// Load the configured family, falling back to the built-in list of
+    /// well-known fonts when it is not set or not found.
+    pub fn load(config: &FontConfig) -> Result<FontSet, Error> {
+        let family = config.family.as_deref().and_then(|family| {
+            let faces = find_family(family, &config.dirs);
+            if faces.is_none() {
+                eprintln!("radium: font family '{family}' not found; using the default font");
+            }
+            faces
+        });
+        let [regular_data, bold_data, italic_data, bold_italic_data] =
+            family.unwrap_or_else(|| default_faces(&config.dirs));
+        let regular_data = regular_data
+            .ok_or_else(|| Error::Font("no font found; place a TTF font at ./assets/font.ttf".into()))?;
+        let mut set = FontSet::from_bytes(&regular_data, bold_data.as_deref(), italic_data.as_deref(), bold_italic_data.as_deref())?;
+        if let [Some(regular), bold] = monospace_faces(&config.dirs) {
+            if let Err(e) = set.set_monospace(&regular, bold.as_deref()) {
+                eprintln!("radium: skipping monospace font: {e}");
+            }
+        }
+        if let Some(data) = fallback_face(&config.dirs) {
+            if let Err(e) = set.add_fallback(&data) {
+                eprintln!("radium: skipping fallback font: {e}");
+            }
+        }
+        Ok(set)
+    }
+
+    /// Build a set from TTF/OTF file contents, without touching the file
+    /// system (e.g. fonts fetched by a web page). Missing or unparsable
+    /// variants fall back to the closest face given, and the monospace
+    /// faces are the regular and bold ones until [`set_monospace`](Self::set_monospace).
+    pub fn from_bytes(
+        regular: &[u8],
+        bold: Option<&[u8]>,
+        italic: Option<&[u8]>,
+        bold_italic: Option<&[u8]>,
+    ) -> Result<FontSet, Error> {
+        let regular     = make_font(regular)?;
+        let bold        = make_variant(bold);
+        let italic      = make_variant(italic).unwrap_or_else(|| regular.clone());
+        let bold_italic = make_variant(bold_italic)
+                              // Prefer bold face over regular as fallback.
+                              .or_else(|| bold.clone())
+                              .unwrap_or_else(|| regular.clone());
+        let bold        = bold.unwrap_or_else(|| regular.clone());
+
+        Ok(FontSet {
+            monospace: Face::new(regular.clone()),
+            monospace_bold: Face::new(bold.clone()),
+            regular: Face::new(regular),
+            bold: Face::new(bold),
+            italic: Face::new(italic),
+            bold_italic: Face::new(bold_italic),
+        })
+    }
+
+    /// Use a monospace font (TTF/OTF contents) for preformatted text and
+    /// code; its bold face falls back to the regular one.
+    pub fn set_monospace(&mut self, regular: &[u8], bold: Option<&[u8]>) -> Result<(), Error> {
+        let regular = make_font(regular)?;
+        let bold = make_variant(bold).unwrap_or_else(|| regular.clone());
+        let fallbacks = self.regular.fallbacks.clone();
+        self.monospace = Face { fallbacks: fallbacks.clone(), ..Face::new(regular) };
+        self.monospace_bold = Face { fallbacks, ..Face::new(bold) };
+        Ok(())
+    }
+
+    /// Add a fallback font (TTF/OTF, or the first font of a TTC collection)
+    /// that every face draws the characters it has no glyph for in, after
+    /// those added before it.
+    pub fn add_fallback(&mut self, data: &[u8]) -> Result<(), Error> {
+        let font = make_font(data)?;
+        let mut fallbacks = Vec::clone(&self.regular.fallbacks);
+        fallbacks.push(font);
+        let fallbacks = Arc::new(fallbacks);
+        for face in [&mut self.regular, &mut self.bold, &mut self.italic, &mut self.bold_italic, &mut self.monospace, &mut self.monospace_bold] {
+            face.fallbacks = fallbacks.clone();
+            // Characters the face lacked may now come from the fallback.
+            face.advances = Arc::default();
+        }
+        Ok(())
+    }
+}
+
+/// `file` in each of `dirs` and in `./assets`, then the `system` paths.
+fn candidates(dirs: &[PathBuf], file: &str, system: &[&str]) -> Vec<PathBuf> {
+    dirs.iter()
+        .map(|dir| dir.join(file))
+        .chain(std::iter::once(PathBuf::from("./assets").join(file)))
+        .chain(system.iter().map(PathBuf::from))
+        .collect()
+}
+
+/// `font.ttf`-style files in `dirs` and `./assets`, then well-known system fonts.
+fn default_faces(dirs: &[PathBuf]) -> Faces {
+    // Regular — required.
+    let regular_data = try_load_bytes(&candidates(dirs, "font.ttf", &[
+        "/System/Library/Fonts/Supplemental/Arial.ttf",
+        "/System/Library/Fonts/Supplemental/Verdana.ttf",
+        "/Library/Fonts/Arial.ttf",
+        "/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf",
+        "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+        "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    ]));
+
+    // Variants — fall back to regular if not found.
+    let bold_data = try_load_bytes(&candidates(dirs, "font-bold.ttf", &[
+        "/System/Library/Fonts/Supplemental/Arial Bold.ttf",
+        "/usr/share/fonts/truetype/liberation/LiberationSans-Bold.ttf",
+        "/usr/share/fonts/truetype/dejavu/DejaVuSans-Bold.ttf",
+        "/usr/share/fonts/TTF/DejaVuSans-Bold.ttf",
+    ]));
+
+    let italic_data = try_load_bytes(&candidates(dirs, "font-italic.ttf", &[
+        "/System/Library/Fonts/Supplemental/Arial Italic.ttf",
+        "/usr/share/fonts/truetype/liberation/LiberationSans-Italic.ttf",
+        "/usr/share/fonts/truetype/dejavu/DejaVuSans-Oblique.ttf",
+        "/usr/share/fonts/TTF/DejaVuSans-Oblique.ttf",
+    ]));
+
+    let bold_italic_data = try_load_bytes(&candidates(dirs, "font-bold-italic.ttf", &[
+        "/System/Library/Fonts/Supplemental/Arial Bold Italic.ttf",
+        "/usr/share/fonts/truetype/liberation/LiberationSans-BoldItalic.ttf",
+        "/usr/share/fonts/truetype/dejavu/DejaVuSans-BoldOblique.ttf",
+        "/usr/share/fonts/TTF/DejaVuSans-BoldOblique.ttf",
+    ]));
+
+    [regular_data, bold_data, italic_data, bold_italic_data]
+}
+
+/// `font-mono.ttf` and `font-mono-bold.ttf` in `dirs` or `./assets`, else
+/// the first well-known system monospace font found: regular and bold.
+fn monospace_faces(dirs: &[PathBuf]) -> [Option<Vec<u8>>; 2] {
+    let regular = try_load_bytes(&candidates(dirs, "font-mono.ttf", &[
+        "/System/Library/Fonts/Supplemental/Courier New.ttf",
+        "/Library/Fonts/Courier New.ttf",
+        "/usr/share/fonts/truetype/dejavu/DejaVuSansMono.ttf",
+        "/usr/share/fonts/TTF/DejaVuSansMono.ttf",
+        "/usr/share/fonts/truetype/liberation/LiberationMono-Regular.ttf",
+    ]));
+    let bold = try_load_bytes(&candidates(dirs, "font-mono-bold.ttf", &[
+        "/System/Library/Fonts/Supplemental/Courier New Bold.ttf",
+        "/Library/Fonts/Courier New Bold.ttf",
+        "/usr/share/fonts/truetype/dejavu/DejaVuSansMono-Bold.ttf",
+        "/usr/share/fonts/TTF/DejaVuSansMono-Bold.ttf",
+        "/usr/share/fonts/truetype/liberation/LiberationMono-Bold.ttf",
+    ]));
+    [regular, bold]
+}
+
+/// A font covering CJK ideographs, kana and hangul: `font-fallback.ttf` in
+/// `dirs` or `./assets`, else the first well-known system one found.
+fn fallback_face(dirs: &[PathBuf]) -> Option<Vec<u8>> {
+    let candidates = dirs.iter()
+        .map(|dir| dir.join("font-fallback.ttf"))
+        .chain(std::iter::once(PathBuf::from("./assets/font-fallback.ttf")))
+        .chain([
+            "/System/Library/Fonts/PingFang.ttc",
+            "/System/Library/Fonts/Hiragino Sans GB.ttc",
+            "/Library/Fonts/Arial Unicode.ttf",
+            "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+            "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+            "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+        ].map(PathBuf::from))
+        .collect::<Vec<_>>();
+    try_load_bytes(&candidates)
+}
+
+// ── Family lookup ─────────────────────────────────────────────────────────────
+
+/// Find the faces of `family` by file name: `DejaVuSerif.ttf`,
+/// `DejaVuSerif-Bold.ttf`, `DejaVu Serif Italic.otf`, ... Comparison ignores
+/// case, spaces and punctuation. `None` if there is no regular face.
+fn find_family(family: &str, dirs: &[PathBuf]) -> Option<Faces> {
+    let key = normalize(family);
+    let mut files = Vec::new();
+    for dir in dirs.iter().cloned().chain(system_font_dirs()) {
+        collect_fonts(&dir, 4, &mut files);
+    }
+    files.sort();
+
+    let mut paths: [Option<PathBuf>; 4] = Default::default();
+    for file in files {
+        let stem = normalize(&file.file_stem().unwrap_or_default().to_string_lossy());
+        let Some(style) = stem.strip_prefix(&key) else { continue };
+        let slot = match style {
+            "" | "regular" | "book" | "roman" => 0,
+            "bold" => 1,
+            "italic" | "oblique" => 2,
+            "bolditalic" | "boldoblique" => 3,
+            _ => continue,
+        };
+        paths[slot].get_or_insert(file);
+    }
+    paths[0].as_ref()?;
+    Some(paths.map(|path| path.and_then(|p| try_load_bytes(&[p]))))
+}
+
+fn normalize(name: &str) -> String {
+    name.chars().filter(char::is_ascii_alphanumeric).map(|c| c.to_ascii_lowercase()).collect()
+}
+
+/// `.ttf` / `.otf` files under `dir`, at most `depth` levels down.
+fn collect_fonts(dir: &Path, depth: usize, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.is_dir() {
+            if depth > 0 {
+                collect_fonts(&path, depth - 1, out);
+            }
+        } else if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("ttf") || e.eq_ignore_ascii_case("otf")) {
+            out.push(path);
+        }
+    }
+}
+
+fn system_font_dirs() -> Vec<PathBuf> {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let mut dirs: Vec<PathBuf> = if cfg!(target_os = "macos") {
+        vec!["/System/Library/Fonts".into(), "/Library/Fonts".into()]
+    } else if cfg!(windows) {
+        vec!["C:\\Windows\\Fonts".into()]
+    } else {
+        vec!["/usr/share/fonts".into(), "/usr/local/share/fonts".into()]
+    };
+    if let Some(home) = home {
+        if cfg!(target_os = "macos") {
+            dirs.push(home.join("Library/Fonts"));
+        } else if !cfg!(windows) {
+            dirs.push(home.join(".local/share/fonts"));
+            dirs.push(home.join(".fonts"));
+        }
+    }
+    dirs
+}
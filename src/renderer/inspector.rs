@@ -0,0 +1,76 @@
+use std::fmt::Write;
+
+use crate::css;
+use crate::layout::{self, ElementBox, Rect};
+use crate::parser::dom::{self, Node};
+
+/// State of the DOM and layout inspector (F12).
+pub struct Inspector {
+    /// Where the elements of the current page were laid out.
+    elements: Vec<ElementBox>,
+    /// Index into `elements` of the inspected element.
+    selected: Option<usize>,
+}
+
+impl Inspector {
+    /// An inspector for a page laid out as `elements` describes.
+    pub fn new(elements: Vec<ElementBox>) -> Self {
+        Inspector { elements, selected: None }
+    }
+
+    /// Inspect the innermost element at document point `(x, y)`, or nothing
+    /// if there is none.
+    pub fn pick(&mut self, x: f32, y: f32) {
+        self.selected = layout::element_at(&self.elements, x, y);
+    }
+
+    /// Extent of the inspected element, to highlight.
+    pub fn selected_rect(&self) -> Option<Rect> {
+        Some(self.elements[self.selected?].rect)
+    }
+
+    /// The panel's lines, for a page laid out from `nodes`: the element's
+    /// ancestry, its start tag, computed style and box geometry.
+    pub fn describe(&self, nodes: &[Node]) -> Vec<String> {
+        let Some(element) = self.selected.map(|i| &self.elements[i]) else {
+            return vec!["Click an element to inspect it".into()];
+        };
+
+        let ancestry: Vec<&str> = (1..=element.path.len())
+            .filter_map(|depth| match dom::node_at(nodes, &element.path[..depth]) {
+                Some(Node::Element { tag, .. }) => Some(tag.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        let mut tag = String::new();
+        if let Some(Node::Element { tag: name, attrs, .. }) = dom::node_at(nodes, &element.path) {
+            let _ = write!(tag, "<{name}");
+            let mut names: Vec<&String> = attrs.keys().collect();
+            names.sort();
+            for attr in names {
+                let _ = write!(tag, " {attr}={:?}", attrs[attr]);
+            }
+            tag.push('>');
+        }
+
+        let s = element.style;
+        let style = format!(
+            "font-size: {}px; font-weight: {}; font-style: {}; color: {}; text-decoration: {}; indent: {}px",
+            s.font_size,
+            if s.bold { "bold" } else { "normal" },
+            if s.italic { "italic" } else { "normal" },
+            css::hex(s.color),
+            if s.underline { "underline" } else { "none" },
+            s.indent,
+        );
+
+        let r = element.rect;
+        let geometry = format!(
+            "x {:.1}  y {:.1}  {:.1} × {:.1}  (boxes {}..{})",
+            r.x, r.y, r.width, r.height, element.boxes.start, element.boxes.end,
+        );
+
+        vec![ancestry.join(" › "), tag, style, geometry]
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+
+/// Something a keyboard shortcut can do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Copy,
+    Cut,
+    Paste,
+    Find,
+    Reload,
+    Location,
+    Save,
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+    NewWindow,
+    CloseWindow,
+    SelectAll,
+    Reader,
+    Inspector,
+    Outline,
+}
+
+impl Action {
+    /// Every action with its name in the config file's `[keys]` table.
+    pub const ALL: [(Action, &'static str); 16] = [
+        (Action::Copy, "copy"),
+        (Action::Cut, "cut"),
+        (Action::Paste, "paste"),
+        (Action::Find, "find"),
+        (Action::Reload, "reload"),
+        (Action::Location, "location"),
+        (Action::Save, "save"),
+        (Action::ZoomIn, "zoom_in"),
+        (Action::ZoomOut, "zoom_out"),
+        (Action::ZoomReset, "zoom_reset"),
+        (Action::NewWindow, "new_window"),
+        (Action::CloseWindow, "close_window"),
+        (Action::SelectAll, "select_all"),
+        (Action::Reader, "reader"),
+        (Action::Inspector, "inspector"),
+        (Action::Outline, "outline"),
+    ];
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.iter().find(|(_, n)| *n == name).map(|&(action, _)| action)
+    }
+}
+
+/// A key plus modifiers, e.g. `Ctrl+Shift+S`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Binding {
+    /// Ctrl, or Cmd on macOS; the two are not told apart.
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    /// Lower-cased character, or a named key such as `F5` or `PageDown`.
+    pub key: String,
+}
+
+impl Binding {
+    /// Parse `Ctrl+Shift+S`, `Cmd+=`, `F9`, ... Modifier and key names are
+    /// case-insensitive; `Plus` and `Minus` stand for `+` and `-`.
+    pub fn parse(text: &str) -> Result<Binding, String> {
+        let mut binding = Binding { ctrl: false, shift: false, alt: false, key: String::new() };
+        // A trailing `+` is the key itself: `Ctrl++`.
+        let (mods, key) = match text.strip_suffix("++") {
+            Some(mods) => (mods, "+"),
+            None if text == "+" => ("", "+"),
+            None => text.rsplit_once('+').unwrap_or(("", text)),
+        };
+        for m in mods.split('+').filter(|m| !m.is_empty()) {
+            match m.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" | "cmd" | "command" | "super" => binding.ctrl = true,
+                "shift" => binding.shift = true,
+                "alt" | "option" => binding.alt = true,
+                _ => return Err(format!("unknown modifier '{m}' in '{text}'")),
+            }
+        }
+        binding.key = match key.to_ascii_lowercase().as_str() {
+            "" => return Err(format!("missing key in '{text}'")),
+            "plus" => "+".into(),
+            "minus" => "-".into(),
+            k if k.chars().count() == 1 => k.into(),
+            _ => NAMED_KEYS.iter()
+                .find(|(_, name)| name.eq_ignore_ascii_case(key))
+                .map(|(_, name)| name.to_string())
+                .ok_or_else(|| format!("unknown key '{key}' in '{text}'"))?,
+        };
+        Ok(binding)
+    }
+
+    /// The binding for a key press, if the key is one that can be bound.
+    pub fn from_key(key: &Key, modifiers: ModifiersState) -> Option<Binding> {
+        let key = match key {
+            Key::Character(c) => c.to_lowercase(),
+            Key::Named(named) => NAMED_KEYS.iter().find(|(k, _)| k == named)?.1.to_string(),
+            _ => return None,
+        };
+        Some(Binding {
+            ctrl: modifiers.control_key() || modifiers.super_key(),
+            shift: modifiers.shift_key(),
+            alt: modifiers.alt_key(),
+            key,
+        })
+    }
+}
+
+/// Named keys that can appear in bindings, with their config-file spelling.
+const NAMED_KEYS: &[(NamedKey, &str)] = &[
+    (NamedKey::F1, "F1"), (NamedKey::F2, "F2"), (NamedKey::F3, "F3"), (NamedKey::F4, "F4"),
+    (NamedKey::F5, "F5"), (NamedKey::F6, "F6"), (NamedKey::F7, "F7"), (NamedKey::F8, "F8"),
+    (NamedKey::F9, "F9"), (NamedKey::F10, "F10"), (NamedKey::F11, "F11"), (NamedKey::F12, "F12"),
+    (NamedKey::Escape, "Escape"), (NamedKey::Insert, "Insert"), (NamedKey::Delete, "Delete"),
+    (NamedKey::Home, "Home"), (NamedKey::End, "End"),
+    (NamedKey::PageUp, "PageUp"), (NamedKey::PageDown, "PageDown"),
+];
+
+/// Keyboard shortcuts, by key.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<Binding, Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let defaults = [
+            ("Ctrl+C", Action::Copy),
+            ("Ctrl+X", Action::Cut),
+            ("Ctrl+V", Action::Paste),
+            ("Ctrl+F", Action::Find),
+            ("Ctrl+R", Action::Reload),
+            ("F5", Action::Reload),
+            ("Ctrl+L", Action::Location),
+            ("Ctrl+Shift+S", Action::Save),
+            ("Ctrl+=", Action::ZoomIn),
+            ("Ctrl++", Action::ZoomIn),
+            ("Ctrl+-", Action::ZoomOut),
+            ("Ctrl+0", Action::ZoomReset),
+            ("Ctrl+N", Action::NewWindow),
+            ("Ctrl+W", Action::CloseWindow),
+            ("Ctrl+A", Action::SelectAll),
+            ("F9", Action::Reader),
+            ("F12", Action::Inspector),
+            ("Ctrl+Shift+O", Action::Outline),
+        ];
+        let bindings = defaults.into_iter()
+            .map(|(text, action)| (Binding::parse(text).expect("default binding"), action))
+            .collect();
+        Keymap { bindings }
+    }
+}
+
+impl Keymap {
+    /// Replace every binding of `action` with `bindings`; a binding taken
+    /// from another action moves to this one.
+    pub fn bind(&mut self, action: Action, bindings: Vec<Binding>) {
+        self.bindings.retain(|_, a| *a != action);
+        for binding in bindings {
+            self.bindings.insert(binding, action);
+        }
+    }
+
+    /// The action bound to `binding`. Shift is ignored when nothing is bound
+    /// with it: Ctrl+Shift+R still reloads, and `+` (typed with Shift on
+    /// most layouts) matches `Ctrl++`.
+    pub fn get(&self, binding: &Binding) -> Option<Action> {
+        self.bindings.get(binding).copied().or_else(|| {
+            let unshifted = Binding { shift: false, ..binding.clone() };
+            binding.shift.then(|| self.bindings.get(&unshifted).copied()).flatten()
+        })
+    }
+}
@@ -0,0 +1,58 @@
+use std::path::{Path, MAIN_SEPARATOR};
+
+/// State of the location bar strip above the document.
+pub struct LocationBar {
+    /// Keep the strip visible when not editing (`--location-bar`).
+    pub pinned: bool,
+    /// Text being edited, while the bar has keyboard focus.
+    pub editing: Option<String>,
+    /// Last failed open, shown until the text changes.
+    pub error: Option<String>,
+}
+
+impl LocationBar {
+    pub fn new(pinned: bool) -> Self {
+        LocationBar { pinned, editing: None, error: None }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.pinned || self.editing.is_some()
+    }
+}
+
+/// Complete the last path component of `input` against the filesystem.
+///
+/// A unique match is completed fully (with a trailing separator for
+/// directories); several matches are completed to their common prefix.
+pub fn complete_path(input: &str) -> Option<String> {
+    let (dir, prefix) = match input.rfind(['/', MAIN_SEPARATOR]) {
+        Some(i) => (&input[..=i], &input[i + 1..]),
+        None => ("", input),
+    };
+    let read_from = if dir.is_empty() { Path::new(".") } else { Path::new(dir) };
+
+    let mut candidates: Vec<(String, bool)> = std::fs::read_dir(read_from).ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+            (name.starts_with(prefix) && (!name.starts_with('.') || prefix.starts_with('.')))
+                .then_some((name, is_dir))
+        })
+        .collect();
+    candidates.sort();
+
+    let completed = match candidates.as_slice() {
+        [] => return None,
+        [(name, true)] => format!("{name}/"),
+        [(name, false)] => name.clone(),
+        [(first, _), rest @ ..] => {
+            let common = rest.iter().fold(first.chars().count(), |len, (name, _)| {
+                first.chars().zip(name.chars()).take_while(|(a, b)| a == b).count().min(len)
+            });
+            first.chars().take(common).collect()
+        }
+    };
+
+    (completed.len() > prefix.len()).then(|| format!("{dir}{completed}"))
+}
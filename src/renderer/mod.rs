@@ -1,476 +1,285 @@
-use std::num::NonZeroU32;
+pub mod backend;
+mod browser;
+mod clipboard;
+mod crash;
+mod damage;
+mod dropdown;
+mod embed;
+mod external;
+mod field;
+mod focus;
+mod find;
+mod font;
+mod inspector;
+pub mod keys;
+mod location;
+mod outline;
+mod pipeline;
+pub(crate) mod paint;
+pub(crate) mod selection;
+mod session;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
 use std::sync::Arc;
 
-use fontdue::{Font, FontSettings};
-use softbuffer::{Context, Surface};
 use winit::application::ApplicationHandler;
-use winit::event::{ElementState, MouseScrollDelta, WindowEvent};
-use winit::event_loop::{ActiveEventLoop, EventLoop};
-use winit::keyboard::{Key, NamedKey};
-use winit::window::{Window, WindowId};
+use winit::event::WindowEvent;
+use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopProxy};
+use winit::window::WindowId;
+
+use crate::document::Source;
+use crate::layout;
+use crate::net::loader::{Loaded, Loader};
+use crate::parser::dom::Node;
+use crate::stats::Stats;
+use crate::Error;
+
+use browser::{Browser, Request};
+use pipeline::Prepared;
+pub use embed::{Activation, Cursor, Embedder};
+use embed::Standalone;
+use keys::Keymap;
+pub use font::{load_font_set, Face, FontConfig, FontSet};
+pub use paint::Theme;
+use session::Session;
 
-use crate::layout::{LayoutBox, PaintCmd};
+// ── Public entry point ────────────────────────────────────────────────────────
 
-// ── Font set ──────────────────────────────────────────────────────────────────
+/// Width in logical pixels that documents are laid out at.
+pub const LAYOUT_WIDTH: f32 = 800.0;
+
+/// Events delivered to the event loop from other threads.
+#[derive(Debug)]
+pub enum UserEvent {
+    /// A watched file of the given window's document changed on disk.
+    SourceChanged(WindowId),
+    /// A subresource requested through the shared loader finished (or failed).
+    ResourceLoaded(Loaded),
+    /// A pipeline thread finished loading or laying out a window's document.
+    Prepared(Box<Prepared>),
+}
 
-/// The four faces of a typeface family.
-struct FontSet {
-    regular: Font,
-    bold: Font,
-    italic: Font,
-    bold_italic: Font,
+/// Height in logical pixels of the location bar strip.
+const CHROME_HEIGHT: f32 = 36.0;
+
+/// Window behaviour chosen on the command line.
+#[derive(Debug, Default, Clone)]
+pub struct Options {
+    /// Element id to scroll to once the window opens.
+    pub fragment: Option<String>,
+    /// Reload when the document file or any asset it references changes.
+    pub watch: bool,
+    /// Always show the location bar, not just while editing it.
+    pub location_bar: bool,
+    /// Initial window size in logical pixels, instead of the remembered one.
+    pub size: Option<(u32, u32)>,
+    /// Physical pixels per logical pixel, instead of the display's.
+    pub scale: Option<f32>,
+    /// Initial zoom, instead of the remembered one.
+    pub zoom: Option<f32>,
+    /// Logical pixels scrolled per arrow key press or wheel notch.
+    pub scroll_speed: Option<f32>,
+    pub theme: Theme,
+    pub fonts: FontConfig,
+    pub keys: Keymap,
+    /// The reader's style sheet, applied to every document under its own
+    /// rules (see [`layout::set_user_stylesheet`]).
+    pub user_stylesheet: Option<String>,
+    /// Print [`Stats`] after every load when set. Holds what was already
+    /// gathered for the initial document (reading and parsing it).
+    pub stats: Option<Stats>,
 }
 
-impl FontSet {
-    fn get(&self, bold: bool, italic: bool) -> &Font {
-        match (bold, italic) {
-            (true,  true)  => &self.bold_italic,
-            (true,  false) => &self.bold,
-            (false, true)  => &self.italic,
-            (false, false) => &self.regular,
-        }
-    }
+/// Open a window showing the document `nodes` (loaded from `source`), and
+/// run until every window is closed.
+pub fn run(source: Source, nodes: Vec<Node>, options: Options) -> Result<(), Error> {
+    run_embedded(source, nodes, options, Arc::new(Standalone))
 }
 
-// ── Public entry point ────────────────────────────────────────────────────────
+/// Like [`run`], but reporting to `embedder`, which can veto navigation and
+/// supply documents and subresources from memory.
+pub fn run_embedded(source: Source, nodes: Vec<Node>, options: Options, embedder: Arc<dyn Embedder>) -> Result<(), Error> {
+    crash::install_hook();
+    layout::set_user_stylesheet(options.user_stylesheet.as_deref());
+    let fonts = Arc::new(FontSet::load(&options.fonts)?);
+    let event_loop = EventLoop::<UserEvent>::with_user_event().build().map_err(window_error)?;
+    let proxy = event_loop.create_proxy();
+    let loader = {
+        let proxy = proxy.clone();
+        let embedder = embedder.clone();
+        Loader::with_supplier(
+            move |url, kind| embedder.resource(url, kind),
+            move |loaded| {
+                let _ = proxy.send_event(UserEvent::ResourceLoaded(loaded));
+            },
+        )
+    };
+
+    let session = Rc::new(RefCell::new(Session::load()));
 
-pub fn run(title: String, boxes: Vec<LayoutBox>) {
-    let fonts = load_font_set();
-    let event_loop = EventLoop::new().unwrap();
+    let first = open_browser(
+        source, nodes, options.clone(), fonts.clone(), proxy.clone(), loader.clone(), session.clone(), embedder.clone(),
+    );
     let mut app = App {
-        title,
-        boxes,
         fonts,
-        window: None,
-        context: None,
-        surface: None,
-        scroll_y: 0.0,
+        embedder,
+        session,
+        proxy,
+        loader,
+        options: Options { fragment: None, stats: options.stats.as_ref().map(|_| Stats::default()), ..options },
+        browsers: HashMap::new(),
+        unattached: vec![first],
+        error: None,
     };
-    event_loop.run_app(&mut app).unwrap();
+    event_loop.run_app(&mut app).map_err(window_error)?;
+    app.error.map_or(Ok(()), Err)
 }
 
-// ── App state ─────────────────────────────────────────────────────────────────
-
-struct App {
-    title: String,
-    boxes: Vec<LayoutBox>,
-    fonts: FontSet,
-    window: Option<Arc<Window>>,
-    context: Option<Context<Arc<Window>>>,
-    surface: Option<Surface<Arc<Window>, Arc<Window>>>,
-    scroll_y: f32,
+pub(crate) fn window_error(e: impl std::fmt::Display) -> Error {
+    Error::Window(e.to_string())
 }
 
-impl ApplicationHandler for App {
-    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        let attrs = Window::default_attributes()
-            .with_title(&self.title)
-            .with_inner_size(winit::dpi::LogicalSize::new(800u32, 600u32));
-
-        let window = Arc::new(event_loop.create_window(attrs).unwrap());
-        let context = Context::new(window.clone()).unwrap();
-        let surface = Surface::new(&context, window.clone()).unwrap();
-
-        self.window = Some(window);
-        self.context = Some(context);
-        self.surface = Some(surface);
-    }
-
-    fn window_event(
-        &mut self,
-        event_loop: &ActiveEventLoop,
-        _id: WindowId,
-        event: WindowEvent,
-    ) {
-        match event {
-            WindowEvent::CloseRequested => event_loop.exit(),
-
-            WindowEvent::MouseWheel { delta, .. } => {
-                let dy = match delta {
-                    // LineDelta: positive y = scroll up (content moves up = see further down).
-                    // We negate so that scroll_y increases when scrolling down.
-                    MouseScrollDelta::LineDelta(_, y) => -y * 40.0,
-                    MouseScrollDelta::PixelDelta(pos) => -pos.y as f32,
-                };
-                self.scroll_by(dy);
-            }
-
-            WindowEvent::KeyboardInput { event, .. } => {
-                if event.state == ElementState::Pressed {
-                    let page = self.window.as_ref()
-                        .map(|w| w.inner_size().height as f32 / w.scale_factor() as f32 * 0.9)
-                        .unwrap_or(500.0);
-
-                    let dy: Option<f32> = match &event.logical_key {
-                        Key::Named(NamedKey::ArrowDown)  => Some(40.0),
-                        Key::Named(NamedKey::ArrowUp)    => Some(-40.0),
-                        Key::Named(NamedKey::PageDown)
-                        | Key::Named(NamedKey::Space)    => Some(page),
-                        Key::Named(NamedKey::PageUp)     => Some(-page),
-                        Key::Named(NamedKey::Home)       => { self.scroll_by(-f32::INFINITY); None }
-                        Key::Named(NamedKey::End)        => { self.scroll_by(f32::INFINITY);  None }
-                        _ => None,
-                    };
-                    if let Some(d) = dy { self.scroll_by(d); }
-                }
-            }
-
-            WindowEvent::Resized(_) => {
-                if let Some(w) = &self.window {
-                    w.request_redraw();
-                }
-            }
-            WindowEvent::RedrawRequested => {
-                let (size, scale) = match &self.window {
-                    Some(w) => (w.inner_size(), w.scale_factor() as f32),
-                    None => return,
-                };
-                let (Some(pw), Some(ph)) =
-                    (NonZeroU32::new(size.width), NonZeroU32::new(size.height))
-                else {
-                    return;
-                };
-
-                if let Some(surface) = &mut self.surface {
-                    surface.resize(pw, ph).unwrap();
-                    let mut buffer = surface.buffer_mut().unwrap();
-                    buffer.fill(0x00FFFFFF);
-
-                    render_frame(
-                        &mut buffer,
-                        size.width,
-                        size.height,
-                        scale,
-                        &self.boxes,
-                        &self.fonts,
-                        self.scroll_y,
-                    );
-
-                    buffer.present().unwrap();
-                }
-            }
-            _ => {}
-        }
-    }
+/// A browser for `nodes`, or for a diagnostic page if laying them out panics.
+#[allow(clippy::too_many_arguments)]
+fn open_browser(
+    source: Source,
+    nodes: Vec<Node>,
+    options: Options,
+    fonts: Arc<FontSet>,
+    proxy: EventLoopProxy<UserEvent>,
+    loader: Loader,
+    session: Rc<RefCell<Session>>,
+    embedder: Arc<dyn Embedder>,
+) -> Browser {
+    let target = source.to_string();
+    let opened = crash::caught(|| Browser::new(
+        source.clone(), nodes, options.clone(), fonts.clone(), proxy.clone(), loader.clone(), session.clone(), embedder.clone(),
+    ));
+    opened.unwrap_or_else(|report| {
+        Browser::new(source, report.document(&target), options, fonts, proxy, loader, session, embedder)
+    })
 }
 
-// ── Scroll helpers ────────────────────────────────────────────────────────────
-
-impl App {
-    /// Maximum logical-pixel scroll offset for the current viewport.
-    fn max_scroll(&self) -> f32 {
-        let doc_h = self.boxes.iter()
-            .map(|b| b.y + b.height)
-            .fold(0.0_f32, f32::max);
-
-        let (viewport_h, scale) = self.window.as_ref()
-            .map(|w| (w.inner_size().height, w.scale_factor() as f32))
-            .unwrap_or((600, 1.0));
-
-        let viewport_logical = viewport_h as f32 / scale;
-        (doc_h - viewport_logical + 16.0).max(0.0)
-    }
-
-    fn scroll_by(&mut self, dy: f32) {
-        self.scroll_y = (self.scroll_y + dy).clamp(0.0, self.max_scroll());
-        if let Some(w) = &self.window {
-            w.request_redraw();
-        }
+/// Run `f` on `browser`. If it panics, the browser shows a diagnostic page
+/// instead (and closes if even that fails), so other windows live on.
+fn guard<T>(browser: &mut Browser, f: impl FnOnce(&mut Browser) -> T) -> Option<T> {
+    let report = match crash::caught(|| f(&mut *browser)) {
+        Ok(value) => return Some(value),
+        Err(report) => report,
+    };
+    if let Err(again) = crash::caught(|| browser.show_crash(&report)) {
+        eprintln!("radium: cannot show the crash report: {}", again.message);
+        browser.request_close();
     }
+    None
 }
 
-// ── Rendering ─────────────────────────────────────────────────────────────────
-
-fn render_frame(
-    buffer: &mut [u32],
-    width: u32,
-    height: u32,
-    scale: f32,
-    boxes: &[LayoutBox],
-    fonts: &FontSet,
-    scroll_y: f32,
-) {
-    // ── Document boxes ────────────────────────────────────────────────────
-    for b in boxes {
-        let x = b.x * scale;
-        let y = (b.y - scroll_y) * scale;
-
-        if y + b.height * scale < 0.0 || y > height as f32 {
-            continue;
-        }
+// ── Application ───────────────────────────────────────────────────────────────
 
-        match &b.cmd {
-            PaintCmd::FillRect { color } => {
-                blit_rect(
-                    buffer, width, height,
-                    x as u32, y as u32,
-                    (b.width * scale) as u32, (b.height * scale) as u32,
-                    *color,
-                );
-            }
-            PaintCmd::Text { content, font_size, bold, italic, color, underline } => {
-                let font = fonts.get(*bold, *italic);
-                blit_text(
-                    buffer, width, height,
-                    font, content,
-                    x, y, font_size * scale, *color, *underline,
-                );
-            }
-            PaintCmd::HLine { color } => {
-                blit_hline(
-                    buffer, width, height,
-                    x as u32, y as u32,
-                    (b.width * scale) as u32, *color,
-                );
-            }
-            PaintCmd::Image { data, img_width, img_height } => {
-                blit_image(
-                    buffer, width, height,
-                    x, y,
-                    (b.width * scale) as u32, (b.height * scale) as u32,
-                    data, *img_width, *img_height,
-                );
-            }
-        }
-    }
-
-    // ── Scrollbar ─────────────────────────────────────────────────────────
-    let doc_h_phys = boxes.iter()
-        .map(|b| (b.y + b.height) * scale)
-        .fold(0.0_f32, f32::max);
-
-    if doc_h_phys > height as f32 {
-        draw_scrollbar(buffer, width, height, doc_h_phys, scroll_y * scale);
-    }
+/// Owns every open browser window and routes events to them by window id.
+struct App {
+    fonts: Arc<FontSet>,
+    embedder: Arc<dyn Embedder>,
+    /// State saved between runs; written out whenever a window closes.
+    session: Rc<RefCell<Session>>,
+    proxy: EventLoopProxy<UserEvent>,
+    /// Subresource fetcher shared by every window.
+    loader: Loader,
+    /// Settings new windows inherit.
+    options: Options,
+    browsers: HashMap<WindowId, Browser>,
+    /// Browsers waiting for the event loop to create their windows.
+    unattached: Vec<Browser>,
+    /// Why the last window failed to open, if that left none open.
+    error: Option<Error>,
 }
 
-// ── Glyph blitting ────────────────────────────────────────────────────────────
-
-fn blit_text(
-    buffer: &mut [u32],
-    buf_w: u32,
-    buf_h: u32,
-    font: &Font,
-    text: &str,
-    x: f32,
-    y: f32,
-    font_size: f32,
-    color: u32,
-    underline: bool,
-) {
-    let ascent = font
-        .horizontal_line_metrics(font_size)
-        .map(|m| m.ascent)
-        .unwrap_or(font_size * 0.8);
-
-    let baseline_y = y + ascent;
-    let mut cursor_x = x;
-
-    for ch in text.chars() {
-        let (metrics, bitmap) = font.rasterize(ch, font_size);
-
-        let gx = (cursor_x + metrics.xmin as f32) as i32;
-        let gy = (baseline_y - metrics.ymin as f32 - metrics.height as f32) as i32;
-
-        for row in 0..metrics.height {
-            for col in 0..metrics.width {
-                let alpha = bitmap[row * metrics.width + col] as u32;
-                if alpha == 0 {
-                    continue;
-                }
-                let px = gx + col as i32;
-                let py = gy + row as i32;
-                if px < 0 || py < 0 || px >= buf_w as i32 || py >= buf_h as i32 {
-                    continue;
+impl App {
+    fn attach_pending(&mut self, event_loop: &ActiveEventLoop) {
+        for mut browser in self.unattached.drain(..) {
+            // Only the first window goes back where the last one was.
+            match browser.attach(event_loop, self.browsers.is_empty()) {
+                Ok(id) => {
+                    self.browsers.insert(id, browser);
                 }
-                let idx = (py as u32 * buf_w + px as u32) as usize;
-                buffer[idx] = alpha_blend(buffer[idx], color, alpha);
+                Err(e) if self.browsers.is_empty() => self.error = Some(e),
+                Err(e) => eprintln!("radium: {e}"),
             }
         }
-
-        cursor_x += metrics.advance_width;
-    }
-
-    if underline && cursor_x > x {
-        let uy = (baseline_y + 2.0) as u32;
-        let width = (cursor_x - x) as u32;
-        blit_hline(buffer, buf_w, buf_h, x as u32, uy, width, color);
-    }
-}
-
-fn blit_rect(buffer: &mut [u32], buf_w: u32, buf_h: u32, x: u32, y: u32, w: u32, h: u32, color: u32) {
-    let x_end = (x + w).min(buf_w);
-    let y_end = (y + h).min(buf_h);
-    for row in y..y_end {
-        for col in x..x_end {
-            buffer[(row * buf_w + col) as usize] = color;
+        if self.browsers.is_empty() {
+            event_loop.exit();
         }
     }
-}
-
-/// Blit a scaled RGBA8 image using nearest-neighbor sampling.
-/// `dst_x`/`dst_y` are physical-pixel coordinates (may be negative when scrolled).
-fn blit_image(
-    buffer: &mut [u32],
-    buf_w: u32,
-    buf_h: u32,
-    dst_x: f32,
-    dst_y: f32,
-    dst_w: u32,
-    dst_h: u32,
-    data: &[u8],
-    src_w: u32,
-    src_h: u32,
-) {
-    if dst_w == 0 || dst_h == 0 || src_w == 0 || src_h == 0 {
-        return;
-    }
-    let dst_x = dst_x as i32;
-    let dst_y = dst_y as i32;
 
-    for row in 0..dst_h {
-        let py = dst_y + row as i32;
-        if py < 0 || py >= buf_h as i32 {
-            continue;
-        }
-        let src_row = ((row as f32 / dst_h as f32) * src_h as f32) as u32;
-        let src_row = src_row.min(src_h - 1);
-
-        for col in 0..dst_w {
-            let px = dst_x + col as i32;
-            if px < 0 || px >= buf_w as i32 {
-                continue;
+    /// Act on requests a browser raised while handling an event.
+    fn handle_requests(&mut self, event_loop: &ActiveEventLoop, id: WindowId) {
+        let Some(browser) = self.browsers.get_mut(&id) else { return };
+        for request in browser.take_requests() {
+            match request {
+                Request::OpenWindow { source, fragment } => {
+                    // The window opens right away; its document loads on a pipeline thread.
+                    let options = Options { fragment, ..self.options.clone() };
+                    let mut browser = open_browser(
+                        source, Vec::new(), options,
+                        self.fonts.clone(), self.proxy.clone(), self.loader.clone(), self.session.clone(),
+                        self.embedder.clone(),
+                    );
+                    guard(&mut browser, Browser::reload);
+                    self.unattached.push(browser);
+                }
+                Request::Close => {
+                    if let Some(browser) = self.browsers.remove(&id) {
+                        browser.save_state();
+                        self.session.borrow().save();
+                    }
+                }
             }
-            let src_col = ((col as f32 / dst_w as f32) * src_w as f32) as u32;
-            let src_col = src_col.min(src_w - 1);
-
-            let src_idx = ((src_row * src_w + src_col) * 4) as usize;
-            let r = data[src_idx]     as u32;
-            let g = data[src_idx + 1] as u32;
-            let b = data[src_idx + 2] as u32;
-            let a = data[src_idx + 3] as u32;
-
-            let buf_idx = (py as u32 * buf_w + px as u32) as usize;
-            buffer[buf_idx] = alpha_blend(buffer[buf_idx], (r << 16) | (g << 8) | b, a);
         }
+        self.attach_pending(event_loop);
     }
 }
 
-fn blit_hline(buffer: &mut [u32], buf_w: u32, buf_h: u32, x: u32, y: u32, width: u32, color: u32) {
-    if y >= buf_h {
-        return;
-    }
-    let x_end = (x + width).min(buf_w);
-    for px in x..x_end {
-        buffer[(y * buf_w + px) as usize] = color;
+impl ApplicationHandler<UserEvent> for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        self.attach_pending(event_loop);
     }
-}
-
-/// Draw a minimal scrollbar on the right edge of the buffer.
-/// All coordinates are physical pixels.
-fn draw_scrollbar(buffer: &mut [u32], width: u32, height: u32, doc_h: f32, scroll_y: f32) {
-    const BAR_W: u32 = 6;
-    const MIN_THUMB: u32 = 24;
-    const TRACK_COLOR: u32 = 0xF0F0F0;
-    const THUMB_COLOR: u32 = 0xA8A8A8;
 
-    let bar_x = width.saturating_sub(BAR_W);
-
-    // Track (full height, light gray).
-    for row in 0..height {
-        for col in bar_x..width {
-            buffer[(row * width + col) as usize] = TRACK_COLOR;
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, id: WindowId, event: WindowEvent) {
+        if let Some(browser) = self.browsers.get_mut(&id) {
+            guard(browser, |browser| browser.window_event(event));
+            self.handle_requests(event_loop, id);
         }
     }
 
-    // Thumb: height proportional to viewport / document ratio.
-    let ratio = (height as f32 / doc_h).min(1.0);
-    let thumb_h = ((height as f32 * ratio) as u32).max(MIN_THUMB);
-    let max_scroll = (doc_h - height as f32).max(1.0);
-    let thumb_y = ((scroll_y / max_scroll) * (height - thumb_h) as f32) as u32;
-    let thumb_y = thumb_y.min(height.saturating_sub(thumb_h));
-
-    for row in thumb_y..(thumb_y + thumb_h).min(height) {
-        for col in bar_x..width {
-            buffer[(row * width + col) as usize] = THUMB_COLOR;
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, event: UserEvent) {
+        match event {
+            UserEvent::SourceChanged(id) => {
+                if let Some(browser) = self.browsers.get_mut(&id) {
+                    guard(browser, Browser::reload);
+                }
+            }
+            UserEvent::Prepared(prepared) => {
+                let waiting = self.browsers.values_mut().chain(&mut self.unattached).find(|b| b.expects(&prepared));
+                if let Some(browser) = waiting {
+                    guard(browser, |browser| browser.prepared(*prepared));
+                }
+            }
+            UserEvent::ResourceLoaded(loaded) => {
+                // Requests are shared across windows; each picks out its own.
+                for browser in self.browsers.values_mut().chain(&mut self.unattached) {
+                    guard(browser, |browser| browser.resource_loaded(&loaded));
+                }
+            }
         }
     }
-}
-
-fn alpha_blend(bg: u32, fg: u32, alpha: u32) -> u32 {
-    let ia = 255 - alpha;
-    let r = ((fg >> 16 & 0xFF) * alpha + (bg >> 16 & 0xFF) * ia) / 255;
-    let g = ((fg >>  8 & 0xFF) * alpha + (bg >>  8 & 0xFF) * ia) / 255;
-    let b = ((fg       & 0xFF) * alpha + (bg       & 0xFF) * ia) / 255;
-    (r << 16) | (g << 8) | b
-}
-
-// ── Font loading ──────────────────────────────────────────────────────────────
 
-fn try_load_bytes(candidates: &[&str]) -> Option<Vec<u8>> {
-    for path in candidates {
-        if let Ok(data) = std::fs::read(path) {
-            eprintln!("radium: loaded font from {path}");
-            return Some(data);
-        }
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let wake = self.browsers.values_mut().filter_map(|browser| guard(browser, Browser::tick).flatten()).min();
+        event_loop.set_control_flow(match wake {
+            Some(deadline) => ControlFlow::WaitUntil(deadline),
+            None => ControlFlow::Wait,
+        });
     }
-    None
-}
-
-fn make_font(data: &[u8]) -> Font {
-    Font::from_bytes(data, FontSettings::default()).expect("Failed to parse font file")
-}
-
-fn load_font_set() -> FontSet {
-    // Regular — required.
-    let regular_data = try_load_bytes(&[
-        "./assets/font.ttf",
-        "/System/Library/Fonts/Supplemental/Arial.ttf",
-        "/System/Library/Fonts/Supplemental/Verdana.ttf",
-        "/Library/Fonts/Arial.ttf",
-        "/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf",
-        "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
-        "/usr/share/fonts/TTF/DejaVuSans.ttf",
-    ])
-    .expect("No font found. Place a TTF font at ./assets/font.ttf");
-
-    // Variants — fall back to regular if not found.
-    let bold_data = try_load_bytes(&[
-        "./assets/font-bold.ttf",
-        "/System/Library/Fonts/Supplemental/Arial Bold.ttf",
-        "/usr/share/fonts/truetype/liberation/LiberationSans-Bold.ttf",
-        "/usr/share/fonts/truetype/dejavu/DejaVuSans-Bold.ttf",
-        "/usr/share/fonts/TTF/DejaVuSans-Bold.ttf",
-    ]);
-
-    let italic_data = try_load_bytes(&[
-        "./assets/font-italic.ttf",
-        "/System/Library/Fonts/Supplemental/Arial Italic.ttf",
-        "/usr/share/fonts/truetype/liberation/LiberationSans-Italic.ttf",
-        "/usr/share/fonts/truetype/dejavu/DejaVuSans-Oblique.ttf",
-        "/usr/share/fonts/TTF/DejaVuSans-Oblique.ttf",
-    ]);
-
-    let bold_italic_data = try_load_bytes(&[
-        "./assets/font-bold-italic.ttf",
-        "/System/Library/Fonts/Supplemental/Arial Bold Italic.ttf",
-        "/usr/share/fonts/truetype/liberation/LiberationSans-BoldItalic.ttf",
-        "/usr/share/fonts/truetype/dejavu/DejaVuSans-BoldOblique.ttf",
-        "/usr/share/fonts/TTF/DejaVuSans-BoldOblique.ttf",
-    ]);
-
-    let regular    = make_font(&regular_data);
-    let bold       = bold_data.as_deref()
-                              .map(make_font)
-                              .unwrap_or_else(|| make_font(&regular_data));
-    let italic     = italic_data.as_deref()
-                                .map(make_font)
-                                .unwrap_or_else(|| make_font(&regular_data));
-    let bold_italic = bold_italic_data.as_deref()
-                                      .map(make_font)
-                                      // Prefer bold face over regular as fallback.
-                                      .or_else(|| bold_data.as_deref().map(make_font))
-                                      .unwrap_or_else(|| make_font(&regular_data));
-
-    FontSet { regular, bold, italic, bold_italic }
 }
@@ -0,0 +1,47 @@
+use crate::layout::Heading;
+
+/// Logical width of the outline sidebar.
+pub const WIDTH: f32 = 240.0;
+/// Logical height of one entry.
+pub const ENTRY_HEIGHT: f32 = 24.0;
+/// Logical space above the first entry.
+pub const PAD: f32 = 8.0;
+/// Indent per heading level below the top one.
+pub const LEVEL_INDENT: f32 = 12.0;
+/// How far below the top of the viewport a heading still counts as scrolled to.
+const REACHED: f32 = 16.0;
+
+/// State of the outline sidebar, which lists the page's headings.
+pub struct Outline {
+    /// Logical offset the entry list is scrolled by.
+    pub scroll: f32,
+}
+
+impl Outline {
+    pub fn new() -> Self {
+        Outline { scroll: 0.0 }
+    }
+
+    /// The entry at logical `y` below the top of the sidebar.
+    pub fn entry_at(&self, headings: &[Heading], y: f32) -> Option<usize> {
+        let offset = y - PAD + self.scroll;
+        if offset < 0.0 {
+            return None;
+        }
+        let i = (offset / ENTRY_HEIGHT) as usize;
+        (i < headings.len()).then_some(i)
+    }
+
+    /// Scroll the list by `dy`, keeping it within a sidebar `height` tall.
+    pub fn scroll_by(&mut self, headings: &[Heading], height: f32, dy: f32) {
+        let max = (PAD * 2.0 + ENTRY_HEIGHT * headings.len() as f32 - height).max(0.0);
+        self.scroll = (self.scroll + dy).clamp(0.0, max);
+    }
+}
+
+/// The heading whose section contains document offset `scroll_y`: the last
+/// one starting at or above it.
+pub fn current(headings: &[Heading], scroll_y: f32) -> Option<usize> {
+    // Jumping to a heading leaves it just below the top; count it as reached.
+    headings.iter().rposition(|h| h.rect.y <= scroll_y + REACHED)
+}
@@ -0,0 +1,508 @@
+use std::ops::Range;
+
+use super::backend::PaintBackend;
+use super::font::{ellipsize, line_height_px, prefix_width, text_width, FontSet};
+use super::dropdown::{self, Dropdown};
+use super::field::Preedit;
+use super::outline;
+use super::selection::Selection;
+use crate::layout::{Heading, Page, PaintCmd, Rect, Select};
+
+/// Background color of selected text.
+const SELECTION_COLOR: u32 = 0xB4D5FE;
+
+/// A colored background behind a text range (e.g. a find match).
+pub struct Mark {
+    pub range: Selection,
+    pub color: u32,
+}
+
+/// How the document maps onto the paint target.
+pub struct Viewport {
+    /// Physical pixels per logical pixel.
+    pub scale: f32,
+    /// Logical document offset at the top of the document area.
+    pub scroll_y: f32,
+    /// Logical height of window chrome drawn above the document area.
+    pub top: f32,
+    /// Logical width of a sidebar drawn left of the document area.
+    pub left: f32,
+    /// Draw a scrollbar when the document is taller than the buffer.
+    pub scrollbar: bool,
+    pub theme: Theme,
+}
+
+/// Page colors applied at paint time.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Theme {
+    /// Page background; white when unset (dark grey in dark mode).
+    pub background: Option<u32>,
+    /// Invert the lightness of document colors, leaving images alone.
+    pub dark: bool,
+}
+
+impl Theme {
+    pub fn background(&self) -> u32 {
+        self.background.unwrap_or_else(|| self.color(0xFFFFFF))
+    }
+
+    /// `color` as it should appear on screen.
+    pub fn color(&self, color: u32) -> u32 {
+        if self.dark { darken(color) } else { color }
+    }
+}
+
+/// Flip luma, keeping hue: black text becomes light grey, white backgrounds
+/// become dark grey, and dark link blue turns pale enough to read on them.
+fn darken(color: u32) -> u32 {
+    let channels = [color >> 16 & 0xFF, color >> 8 & 0xFF, color & 0xFF].map(|c| c as f32 / 255.0);
+    let luma = 0.2126 * channels[0] + 0.7152 * channels[1] + 0.0722 * channels[2];
+    let target = 0.12 + (1.0 - luma) * 0.82;
+    // Mix towards white to lighten, scale towards black to darken.
+    let mapped = if target > luma {
+        let t = (target - luma) / (1.0 - luma);
+        channels.map(|c| c + (1.0 - c) * t)
+    } else {
+        channels.map(|c| c * target / luma)
+    };
+    let [r, g, b] = mapped.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u32);
+    (color & 0xFF00_0000) | (r << 16) | (g << 8) | b
+}
+
+impl Viewport {
+    /// Physical buffer position of document point `(x, y)`.
+    pub fn to_screen(&self, x: f32, y: f32) -> (f32, f32) {
+        ((x + self.left) * self.scale, (y - self.scroll_y + self.top) * self.scale)
+    }
+
+    /// Indices of the boxes of `page` that may show on a buffer `height`
+    /// pixels high (see [`Page::visible`]).
+    pub fn visible(&self, page: &Page, height: u32) -> Range<usize> {
+        let top = self.scroll_y - self.top;
+        page.visible(top, top + height as f32 / self.scale)
+    }
+}
+
+// ── Rendering ─────────────────────────────────────────────────────────────────
+
+/// Paint the page background and the document boxes (plus any selection,
+/// marks and scrollbar) onto `target`.
+pub fn render_frame(
+    target: &mut impl PaintBackend,
+    view: &Viewport,
+    page: &Page,
+    fonts: &FontSet,
+    selection: Option<&Selection>,
+    marks: &[Mark],
+) {
+    let Viewport { scale, theme, .. } = *view;
+    let (width, height) = target.size();
+    let top = view.top * scale;
+    let left = view.left * scale;
+
+    target.fill_rect(Rect { x: 0.0, y: 0.0, width: width as f32, height: height as f32 }, theme.background(), 255);
+    // Boxes scrolled above the document area must not paint over the chrome.
+    target.push_clip(Rect { x: left, y: top, width: width as f32 - left, height: height as f32 - top });
+
+    // ── Document boxes ────────────────────────────────────────────────────
+    for i in view.visible(page, height) {
+        let b = &page.boxes[i];
+        let (x, y) = view.to_screen(b.x, b.y);
+        let rect = Rect { x, y, width: b.width * scale, height: b.height * scale };
+
+        if y + rect.height < 0.0 || y > height as f32 {
+            continue;
+        }
+
+        match &b.cmd {
+            PaintCmd::FillRect { color } => {
+                target.fill_rect(rect, theme.color(*color), 255);
+            }
+            PaintCmd::Text { content, font_size, bold, italic, monospace, color, underline } => {
+                let font = fonts.face(*bold, *italic, *monospace);
+                let size = font_size * scale;
+                let color = theme.color(*color);
+
+                // Marks and the selection go behind the glyphs.
+                // `marks` is sorted and non-overlapping, so ends are sorted too.
+                let len = content.chars().count();
+                let first = marks.partition_point(|m| m.range.ordered().1.box_index < i);
+                let in_box = marks[first..].iter().take_while(|m| m.range.ordered().0.box_index <= i);
+                let ranges = in_box.map(|m| (&m.range, m.color))
+                    .chain(selection.map(|s| (s, SELECTION_COLOR)));
+                for (range, mark_color) in ranges {
+                    if let Some((from, to)) = range.range_in_box(i, len) {
+                        let x0 = x + prefix_width(font, content, size, from);
+                        let x1 = x + prefix_width(font, content, size, to);
+                        target.fill_rect(Rect { x: x0, width: x1 - x0, ..rect }, mark_color, 255);
+                    }
+                }
+
+                target.glyph_run(font, content, x, y, size, color);
+                if *underline {
+                    let ascent = font.horizontal_line_metrics(size).map_or(size * 0.8, |m| m.ascent);
+                    // Two logical pixels below the baseline; one device pixel
+                    // thick, two from 2× displays up.
+                    let underline_y = (y + ascent + 2.0 * scale).floor();
+                    let thickness = scale.round().clamp(1.0, 2.0);
+                    let width = text_width(font, content, size);
+                    target.fill_rect(Rect { x, y: underline_y, width, height: thickness }, color, 255);
+                }
+            }
+            PaintCmd::HLine { color } if y > -1.0 => {
+                target.fill_rect(Rect { x, y: y.floor(), width: rect.width, height: 1.0 }, theme.color(*color), 255);
+            }
+            PaintCmd::Image { data, img_width, img_height } => {
+                target.image(rect, data, *img_width, *img_height);
+            }
+            PaintCmd::HLine { .. } => {}
+        }
+    }
+
+    // ── Scrollbar ─────────────────────────────────────────────────────────
+    let doc_h_phys = page.bottom() * scale;
+
+    if view.scrollbar && doc_h_phys > height as f32 - top {
+        draw_scrollbar(target, top, doc_h_phys, view.scroll_y * scale);
+    }
+    target.pop_clip();
+}
+
+// ── Overlays ──────────────────────────────────────────────────────────────────
+
+/// Draw a tooltip box just below-right of the pointer, kept inside the window;
+/// text wider than the window is cut short with "…".
+pub fn draw_tooltip(
+    target: &mut impl PaintBackend,
+    scale: f32,
+    fonts: &FontSet,
+    text: &str,
+    cursor_x: f32,
+    cursor_y: f32,
+) {
+    const FONT_SIZE: f32 = 13.0;
+    const PAD: f32 = 4.0;
+    const BG_COLOR: u32 = 0xFFFFE1;
+    const BORDER_COLOR: u32 = 0x767676;
+
+    let (width, height) = target.size();
+    let font = fonts.get(false, false);
+    let size = FONT_SIZE * scale;
+    let pad = PAD * scale;
+    let text = ellipsize(font, text, size, width as f32 - pad * 2.0);
+    let box_w = text_width(font, &text, size) + pad * 2.0;
+    let box_h = line_height_px(font, size) + pad * 2.0;
+
+    // Offset from the pointer so the cursor does not cover the text.
+    let mut x = (cursor_x + 12.0) * scale;
+    let mut y = (cursor_y + 18.0) * scale;
+    if x + box_w > width as f32 {
+        x = (width as f32 - box_w).max(0.0);
+    }
+    if y + box_h > height as f32 {
+        y = (cursor_y * scale - box_h - 4.0 * scale).max(0.0);
+    }
+
+    draw_panel(target, Rect { x, y, width: box_w, height: box_h }, BG_COLOR, BORDER_COLOR);
+    target.glyph_run(font, &text, x + pad, y + pad, size, 0x000000);
+}
+
+/// Draw the location strip across the top `chrome_h` logical pixels:
+/// a text field showing `text`, with a caret while `editing`.
+pub fn draw_location_bar(
+    target: &mut impl PaintBackend,
+    scale: f32,
+    fonts: &FontSet,
+    chrome_h: f32,
+    text: &str,
+    editing: bool,
+    error: Option<&str>,
+) {
+    const FONT_SIZE: f32 = 14.0;
+    const STRIP_COLOR: u32 = 0xEDEDED;
+    const RULE_COLOR: u32 = 0xC8C8C8;
+    const FIELD_COLOR: u32 = 0xFFFFFF;
+    const FOCUS_COLOR: u32 = 0x1A73E8;
+    const ERROR_COLOR: u32 = 0xC00000;
+
+    let width = target.size().0 as f32;
+    let strip_h = chrome_h * scale;
+    target.fill_rect(Rect { x: 0.0, y: 0.0, width, height: strip_h }, STRIP_COLOR, 255);
+    target.fill_rect(Rect { x: 0.0, y: strip_h - 1.0, width, height: 1.0 }, RULE_COLOR, 255);
+
+    let margin = 4.0 * scale;
+    let field = Rect { x: margin * 2.0, y: margin, width: width - margin * 4.0, height: strip_h - margin * 2.0 - 1.0 };
+    target.fill_rect(field, FIELD_COLOR, 255);
+    let border = if editing { FOCUS_COLOR } else { RULE_COLOR };
+    target.stroke_rect(field, if editing { 2.0 * scale } else { 1.0 }, border);
+
+    let font = fonts.get(false, false);
+    let size = FONT_SIZE * scale;
+    let line_h = line_height_px(font, size);
+    let text_x = field.x + 6.0 * scale;
+    let text_y = field.y + (field.height - line_h) / 2.0;
+    target.push_clip(field);
+    target.glyph_run(font, text, text_x, text_y, size, 0x000000);
+
+    if editing {
+        let caret_x = text_x + text_width(font, text, size) + 1.0;
+        target.fill_rect(Rect { x: caret_x, y: text_y, width: scale.max(1.0), height: line_h }, 0x000000, 255);
+    }
+
+    if let Some(error) = error {
+        let error_w = text_width(font, error, size);
+        let error_x = field.x + field.width - error_w - 6.0 * scale;
+        target.glyph_run(font, error, error_x, text_y, size, ERROR_COLOR);
+    }
+    target.pop_clip();
+}
+
+/// Draw the find bar in the top-right corner of the document area (which
+/// starts `top` logical pixels down): query, caret and match count.
+pub fn draw_find_bar(
+    target: &mut impl PaintBackend,
+    scale: f32,
+    fonts: &FontSet,
+    top: f32,
+    query: &str,
+    current: Option<usize>,
+    total: usize,
+) {
+    const BAR_W: f32 = 320.0;
+    const BAR_H: f32 = 30.0;
+    const MARGIN: f32 = 8.0;
+    const FONT_SIZE: f32 = 14.0;
+    const BG_COLOR: u32 = 0xF4F4F4;
+    const BORDER_COLOR: u32 = 0x999999;
+    const MISS_COLOR: u32 = 0xC00000;
+
+    let width = target.size().0 as f32;
+    let font = fonts.get(false, false);
+    let size = FONT_SIZE * scale;
+    let bar_w = (BAR_W * scale).min(width);
+    let x = (width - bar_w - MARGIN * scale).max(0.0);
+    let y = (top + MARGIN) * scale;
+    let h = BAR_H * scale;
+
+    let bar = Rect { x, y, width: bar_w, height: h };
+    draw_panel(target, bar, BG_COLOR, BORDER_COLOR);
+    target.push_clip(bar);
+
+    let text_y = y + (h - line_height_px(font, size)) / 2.0;
+    let (status, status_color) = match current {
+        Some(i) => (format!("{}/{}", i + 1, total), 0x555555),
+        None if query.is_empty() => (String::new(), 0x555555),
+        None => ("No matches".to_string(), MISS_COLOR),
+    };
+    let status_w = text_width(font, &status, size);
+    let pad = 8.0 * scale;
+    target.glyph_run(font, &status, x + bar_w - pad - status_w, text_y, size, status_color);
+
+    let label = format!("Find: {query}|");
+    target.glyph_run(font, &label, x + pad, text_y, size, 0x000000);
+    target.pop_clip();
+}
+
+/// Draw a "Loading…" badge at the top right of the document area, below
+/// `top` logical pixels of chrome.
+pub fn draw_loading(target: &mut impl PaintBackend, scale: f32, fonts: &FontSet, top: f32) {
+    const FONT_SIZE: f32 = 13.0;
+    const PAD: f32 = 4.0;
+    const MARGIN: f32 = 8.0;
+    const BG_COLOR: u32 = 0xF4F4F4;
+    const BORDER_COLOR: u32 = 0x999999;
+    const LABEL: &str = "Loading…";
+
+    let (width, _) = target.size();
+    let font = fonts.get(false, false);
+    let size = FONT_SIZE * scale;
+    let pad = PAD * scale;
+    let box_w = text_width(font, LABEL, size) + pad * 2.0;
+    let box_h = line_height_px(font, size) + pad * 2.0;
+    let x = (width as f32 - box_w - MARGIN * scale).max(0.0);
+    let y = (top + MARGIN) * scale;
+
+    draw_panel(target, Rect { x, y, width: box_w, height: box_h }, BG_COLOR, BORDER_COLOR);
+    target.glyph_run(font, LABEL, x + pad, y + pad, size, 0x333333);
+}
+
+/// Draw the inspector panel across the bottom of the window: the first
+/// line in bold, then the rest.
+pub fn draw_inspector(target: &mut impl PaintBackend, scale: f32, fonts: &FontSet, lines: &[String]) {
+    const FONT_SIZE: f32 = 13.0;
+    const PAD: f32 = 6.0;
+    const BG_COLOR: u32 = 0xF4F4F4;
+    const BORDER_COLOR: u32 = 0x999999;
+
+    let (width, height) = target.size();
+    let size = FONT_SIZE * scale;
+    let pad = PAD * scale;
+    let line_h = line_height_px(fonts.get(false, false), size);
+    let panel_h = (line_h * lines.len() as f32 + pad * 2.0).min(height as f32);
+    let panel = Rect { x: 0.0, y: height as f32 - panel_h, width: width as f32, height: panel_h };
+    draw_panel(target, panel, BG_COLOR, BORDER_COLOR);
+
+    target.push_clip(panel);
+    for (i, line) in lines.iter().enumerate() {
+        let font = fonts.get(i == 0, false);
+        target.glyph_run(font, line, pad, panel.y + pad + line_h * i as f32, size, 0x000000);
+    }
+    target.pop_clip();
+}
+
+/// Draw the outline sidebar down the left edge, below `top` logical pixels
+/// of chrome: one entry per heading, indented by level, with `current`
+/// (the section in view) emphasized, and cut short with "…" if it does
+/// not fit.
+pub fn draw_outline(
+    target: &mut impl PaintBackend,
+    scale: f32,
+    fonts: &FontSet,
+    top: f32,
+    headings: &[Heading],
+    current: Option<usize>,
+    scroll: f32,
+) {
+    const FONT_SIZE: f32 = 13.0;
+    const BG_COLOR: u32 = 0xF4F4F4;
+    const RULE_COLOR: u32 = 0xC8C8C8;
+    const TEXT_COLOR: u32 = 0x333333;
+    const CURRENT_COLOR: u32 = 0x1A73E8;
+    const EMPTY_COLOR: u32 = 0x888888;
+
+    let height = target.size().1 as f32;
+    let y0 = top * scale;
+    let panel = Rect { x: 0.0, y: y0, width: outline::WIDTH * scale, height: height - y0 };
+    target.fill_rect(panel, BG_COLOR, 255);
+    target.fill_rect(Rect { x: panel.width - 1.0, width: 1.0, ..panel }, RULE_COLOR, 255);
+
+    let size = FONT_SIZE * scale;
+    let entry_h = outline::ENTRY_HEIGHT * scale;
+    let text_dy = (entry_h - line_height_px(fonts.get(false, false), size)) / 2.0;
+    let pad = outline::PAD * scale;
+    target.push_clip(Rect { width: panel.width - 1.0, ..panel });
+    if headings.is_empty() {
+        target.glyph_run(fonts.get(false, true), "No headings", pad, y0 + pad + text_dy, size, EMPTY_COLOR);
+    }
+    for (i, heading) in headings.iter().enumerate() {
+        let y = y0 + pad + i as f32 * entry_h - scroll * scale;
+        if y + entry_h < y0 || y > height {
+            continue;
+        }
+        let is_current = current == Some(i);
+        let x = pad + (heading.level.saturating_sub(1)) as f32 * outline::LEVEL_INDENT * scale;
+        let color = if is_current { CURRENT_COLOR } else { TEXT_COLOR };
+        let font = fonts.get(is_current, false);
+        let text = ellipsize(font, &heading.text, size, panel.width - 1.0 - x - pad);
+        target.glyph_run(font, &text, x, y + text_dy, size, color);
+    }
+    target.pop_clip();
+}
+
+/// Draw the option list of an open dropdown over the page, under `select`;
+/// the chosen option in bold, the highlighted one on blue. Labels too long
+/// for the list end in "…".
+pub fn draw_dropdown(target: &mut impl PaintBackend, view: &Viewport, fonts: &FontSet, select: &Select, dropdown: &Dropdown) {
+    const FONT_SIZE: f32 = 14.0;
+    const PAD: f32 = 6.0;
+    const BG_COLOR: u32 = 0xFFFFFF;
+    const BORDER_COLOR: u32 = 0x767676;
+    const TEXT_COLOR: u32 = 0x000000;
+    const HIGHLIGHT_COLOR: u32 = 0x1A73E8;
+
+    let scale = view.scale;
+    let rect = Dropdown::rect(select);
+    let (x, y) = view.to_screen(rect.x, rect.y);
+    let panel = Rect { x, y, width: rect.width * scale, height: rect.height * scale };
+    draw_panel(target, panel, BG_COLOR, BORDER_COLOR);
+
+    let size = FONT_SIZE * scale;
+    let row_h = dropdown::ROW_HEIGHT * scale;
+    let inner = Rect { x: x + scale, y: y + scale, width: panel.width - 2.0 * scale, height: panel.height - 2.0 * scale };
+    target.push_clip(inner);
+    let shown = select.options.iter().enumerate().skip(dropdown.first).take(Dropdown::rows(select));
+    for (row, (i, option)) in shown.enumerate() {
+        let row_y = inner.y + row as f32 * row_h;
+        let color = if i == dropdown.highlighted {
+            target.fill_rect(Rect { y: row_y, height: row_h, ..inner }, HIGHLIGHT_COLOR, 255);
+            0xFFFFFF
+        } else {
+            TEXT_COLOR
+        };
+        let font = fonts.get(select.selected == Some(i), false);
+        let text_dy = (row_h - line_height_px(font, size)) / 2.0;
+        let label = ellipsize(font, &option.label, size, inner.width - 2.0 * PAD * scale);
+        target.glyph_run(font, &label, inner.x + PAD * scale, row_y + text_dy, size, color);
+    }
+    target.pop_clip();
+}
+
+/// Draw the composition `preedit` over the text input whose border box is
+/// `field`, starting at the caret `caret` (in document coordinates): on the
+/// field's face and underlined, with a caret at the input method's cursor.
+pub fn draw_preedit(
+    target: &mut impl PaintBackend,
+    view: &Viewport,
+    fonts: &FontSet,
+    field: Rect,
+    caret: Rect,
+    font_size: f32,
+    preedit: &Preedit,
+) {
+    const FACE_COLOR: u32 = 0xFFFFFF;
+    const TEXT_COLOR: u32 = 0x000000;
+
+    let scale = view.scale;
+    let font = fonts.get(false, false);
+    let size = font_size * scale;
+    let (x, y) = view.to_screen(caret.x, caret.y);
+    let height = caret.height * scale;
+    let width = text_width(font, &preedit.text, size);
+    let (fx, fy) = view.to_screen(field.x + 1.0, field.y + 1.0);
+    target.push_clip(Rect { x: fx, y: fy, width: (field.width - 2.0) * scale, height: (field.height - 2.0) * scale });
+    target.fill_rect(Rect { x, y, width, height }, view.theme.color(FACE_COLOR), 255);
+    let color = view.theme.color(TEXT_COLOR);
+    target.glyph_run(font, &preedit.text, x, y, size, color);
+    let thickness = scale.max(1.0).round();
+    target.fill_rect(Rect { x, y: (y + height - thickness).floor(), width, height: thickness }, color, 255);
+    if let Some((start, _)) = preedit.cursor {
+        let chars = preedit.text.get(..start).map_or(0, |s| s.chars().count());
+        let cx = x + prefix_width(font, &preedit.text, size, chars);
+        target.fill_rect(Rect { x: cx.floor(), y, width: thickness, height }, color, 255);
+    }
+    target.pop_clip();
+}
+
+/// A filled box with a one-pixel border, snapped to whole pixels.
+fn draw_panel(target: &mut impl PaintBackend, rect: Rect, fill: u32, border: u32) {
+    let rect = Rect { x: rect.x.floor(), y: rect.y.floor(), width: rect.width.floor(), height: rect.height.floor() };
+    target.fill_rect(rect, border, 255);
+    let inner = Rect { x: rect.x + 1.0, y: rect.y + 1.0, width: rect.width - 2.0, height: rect.height - 2.0 };
+    target.fill_rect(inner, fill, 255);
+}
+
+/// Draw a minimal scrollbar on the right edge of the document area, which
+/// spans from `top` to the bottom of the target.
+/// All coordinates are physical pixels.
+fn draw_scrollbar(target: &mut impl PaintBackend, top: f32, doc_h: f32, scroll_y: f32) {
+    const BAR_W: f32 = 6.0;
+    const MIN_THUMB: f32 = 24.0;
+    const TRACK_COLOR: u32 = 0xF0F0F0;
+    const THUMB_COLOR: u32 = 0xA8A8A8;
+
+    let (width, buf_h) = target.size();
+    let top = top.floor();
+    let bar_x = (width as f32 - BAR_W).max(0.0);
+    let height = (buf_h as f32 - top).max(0.0);
+
+    // Track (full height, light gray).
+    target.fill_rect(Rect { x: bar_x, y: top, width: BAR_W, height }, TRACK_COLOR, 255);
+
+    // Thumb: height proportional to viewport / document ratio.
+    let ratio = (height / doc_h).min(1.0);
+    let thumb_h = (height * ratio).floor().max(MIN_THUMB);
+    let max_scroll = (doc_h - height).max(1.0);
+    let travel = (height - thumb_h).max(0.0);
+    let thumb_y = top + ((scroll_y / max_scroll) * travel).floor().min(travel);
+    target.fill_rect(Rect { x: bar_x, y: thumb_y, width: BAR_W, height: thumb_h }, THUMB_COLOR, 255);
+}
@@ -0,0 +1,193 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use winit::event_loop::EventLoopProxy;
+
+use crate::document::{reader, Source};
+use crate::layout::{self, Page, RemoteImages};
+use crate::parser::dom::Node;
+use crate::stats::Stats;
+
+use super::crash;
+use super::font::FontSet;
+use super::embed::{self, Embedder};
+use super::UserEvent;
+
+/// Document work for a pipeline thread.
+pub struct Job {
+    /// The document; its URL is also the base for relative references.
+    pub source: Source,
+    /// Parsed nodes to lay out, or `None` to read and parse `source` first.
+    pub nodes: Option<Arc<Vec<Node>>>,
+    /// Width to lay out at, in logical pixels.
+    pub width: f32,
+    /// In reader mode, the width of the column the article is laid out in.
+    pub column: Option<f32>,
+    /// Remote images fetched so far.
+    pub images: RemoteImages,
+    /// How long layout may take before the top of the document is
+    /// delivered on its own (as a [`Page::partial`] page) ahead of the rest;
+    /// `None` to deliver the whole page at once.
+    pub budget: Option<Duration>,
+    /// The fonts the page will be painted in, to measure its text with.
+    pub fonts: Arc<FontSet>,
+}
+
+/// A laid-out document, ready to be shown.
+#[derive(Debug)]
+pub struct Prepared {
+    /// Ties the result to the [`Ticket`] of its job.
+    pub generation: u64,
+    /// The document, its URL updated if loading it was redirected.
+    pub source: Source,
+    /// Whether `source` was (re)read, rather than laid out again.
+    pub loaded: bool,
+    pub nodes: Arc<Vec<Node>>,
+    /// The main article, laid out instead of `nodes` in reader mode.
+    pub article: Option<Arc<Vec<Node>>>,
+    pub page: Page,
+    /// Reading, parsing and layout times, and image memory.
+    pub stats: Stats,
+}
+
+/// Claim on a job running on a pipeline thread.
+pub struct Ticket {
+    generation: u64,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Ticket {
+    /// Matches the [`Prepared::generation`] of the job's result.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Give up on the job. The thread stops at the next stage boundary and
+    /// delivers nothing.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Run `job` on a new thread, delivering the result to the event loop as
+/// [`UserEvent::Prepared`] unless it is cancelled first. A job that runs
+/// over its layout budget delivers twice: the top of the page, then all of
+/// it.
+pub fn spawn(job: Job, embedder: Arc<dyn Embedder>, proxy: EventLoopProxy<UserEvent>) -> Ticket {
+    static GENERATION: AtomicU64 = AtomicU64::new(1);
+    let generation = GENERATION.fetch_add(1, Ordering::Relaxed);
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let ticket = Ticket { generation, cancelled: cancelled.clone() };
+
+    std::thread::spawn(move || {
+        let deliver = |prepared: Prepared| {
+            if !cancelled.load(Ordering::Relaxed) {
+                let _ = proxy.send_event(UserEvent::Prepared(Box::new(prepared)));
+            }
+        };
+        if let Some(prepared) = prepare(job, generation, &*embedder, &cancelled, &deliver) {
+            deliver(prepared);
+        }
+    });
+    ticket
+}
+
+/// Do `job` on this thread, or return `None` if `cancelled` is set before it
+/// is done; a partial page laid out first goes to `early`. A panic yields a
+/// diagnostic page in place of the document.
+fn prepare(
+    job: Job,
+    generation: u64,
+    embedder: &dyn Embedder,
+    cancelled: &AtomicBool,
+    early: &dyn Fn(Prepared),
+) -> Option<Prepared> {
+    let target = job.source.clone();
+    let (width, fonts) = (job.width, job.fonts.clone());
+    match crash::caught(|| run(job, generation, embedder, cancelled, early)) {
+        Ok(prepared) => prepared,
+        Err(report) => {
+            let nodes = report.document(&target.to_string());
+            let page = crash::caught(|| layout::layout(&nodes, width, &target.url, &RemoteImages::new(), &*fonts))
+                .unwrap_or_default();
+            Some(Prepared {
+                generation,
+                source: target,
+                loaded: true,
+                nodes: Arc::new(nodes),
+                article: None,
+                page,
+                stats: Stats::default(),
+            })
+        }
+    }
+}
+
+fn run(job: Job, generation: u64, embedder: &dyn Embedder, cancelled: &AtomicBool, early: &dyn Fn(Prepared)) -> Option<Prepared> {
+    let Job { mut source, nodes, width, column, images, budget, fonts } = job;
+    let mut stats = Stats::default();
+    let mut loaded = nodes.is_none();
+    let nodes = match nodes {
+        Some(nodes) => nodes,
+        None => {
+            // Show what has arrived of a slow download as it comes in.
+            let target = source.clone();
+            let mut preview = |nodes: Vec<Node>| {
+                if cancelled.load(Ordering::Relaxed) {
+                    return;
+                }
+                let nodes = Arc::new(nodes);
+                let article = column.map(|_| Arc::new(reader::extract(&nodes)));
+                let (laid_out, column) = content(&nodes, &article, column);
+                let mut page = layout::layout_column(laid_out, width, column, &target.url, &images, &*fonts);
+                page.partial = true;
+                let (source, stats) = (target.clone(), Stats::default());
+                early(Prepared { generation, source, loaded: true, nodes, article, page, stats });
+            };
+            Arc::new(embed::load_document(embedder, &mut source, &mut stats, &mut preview))
+        }
+    };
+    if cancelled.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    let article = column.map(|_| Arc::new(reader::extract(&nodes)));
+    if cancelled.load(Ordering::Relaxed) {
+        return None;
+    }
+    let laid_out = content(&nodes, &article, column);
+
+    if let Some(budget) = budget {
+        let started = Instant::now();
+        let top = layout::layout_until(laid_out.0, width, laid_out.1, &source.url, &images, &*fonts, started + budget);
+        stats.layout += started.elapsed();
+        stats.note_images(&top, &images);
+        if !top.partial {
+            return Some(Prepared { generation, source, loaded, nodes, article, page: top, stats });
+        }
+        let (nodes, article, stats) = (nodes.clone(), article.clone(), std::mem::take(&mut stats));
+        early(Prepared { generation, source: source.clone(), loaded, nodes, article, page: top, stats });
+        if cancelled.load(Ordering::Relaxed) {
+            return None;
+        }
+        // The rest is the same document laid out again, not a new load.
+        loaded = false;
+    }
+
+    let started = Instant::now();
+    let page = layout::layout_column(laid_out.0, width, laid_out.1, &source.url, &images, &*fonts);
+    stats.layout += started.elapsed();
+    stats.note_images(&page, &images);
+
+    Some(Prepared { generation, source, loaded, nodes, article, page, stats })
+}
+
+/// The nodes to lay out, and the column width to lay them out in: the
+/// article in reader mode, or else the whole document at full width.
+fn content<'a>(nodes: &'a [Node], article: &'a Option<Arc<Vec<Node>>>, column: Option<f32>) -> (&'a [Node], f32) {
+    match (article, column) {
+        (Some(article), Some(column)) => (article, column),
+        _ => (nodes, f32::INFINITY),
+    }
+}
@@ -0,0 +1,180 @@
+use crate::layout::{LayoutBox, Page, PaintCmd};
+
+use super::font::{offset_at, FontSet};
+
+// ── Positions ─────────────────────────────────────────────────────────────────
+
+/// A caret position: character offset within a text box.
+/// Ordering follows document order (box index first).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TextPos {
+    pub box_index: usize,
+    pub offset: usize,
+}
+
+/// A selection between the press point (`anchor`) and the drag point (`focus`).
+#[derive(Debug, Clone, Copy)]
+pub struct Selection {
+    pub anchor: TextPos,
+    pub focus: TextPos,
+}
+
+impl Selection {
+    pub fn collapsed(pos: TextPos) -> Self {
+        Selection { anchor: pos, focus: pos }
+    }
+
+    pub fn is_collapsed(&self) -> bool {
+        self.anchor == self.focus
+    }
+
+    /// `(start, end)` in document order.
+    pub fn ordered(&self) -> (TextPos, TextPos) {
+        if self.anchor <= self.focus {
+            (self.anchor, self.focus)
+        } else {
+            (self.focus, self.anchor)
+        }
+    }
+
+    /// Selected character range within box `index` whose text has `len` chars.
+    pub fn range_in_box(&self, index: usize, len: usize) -> Option<(usize, usize)> {
+        let (start, end) = self.ordered();
+        if index < start.box_index || index > end.box_index {
+            return None;
+        }
+        let from = if index == start.box_index { start.offset } else { 0 };
+        let to = if index == end.box_index { end.offset } else { len };
+        (from < to).then_some((from, to))
+    }
+}
+
+// ── Hit testing ───────────────────────────────────────────────────────────────
+
+/// Map a document point to the nearest caret position in a text box.
+///
+/// Points on a line snap to the closest box on that line; points between
+/// lines snap to the end of the preceding text.
+pub fn hit_test(page: &Page, fonts: &FontSet, x: f32, y: f32) -> Option<TextPos> {
+    let texts = page.boxes.iter().enumerate().filter_map(|(i, b)| match &b.cmd {
+        PaintCmd::Text { content, font_size, bold, italic, monospace, .. } => Some((i, b, content, *font_size, fonts.face(*bold, *italic, *monospace))),
+        _ => None,
+    });
+
+    // (box index, horizontal distance, offset) of the best box on the pointer's line.
+    let mut on_line: Option<(usize, f32, usize)> = None;
+    let mut before = None;
+    let mut first = None;
+    for (i, b, content, font_size, face) in texts {
+        first.get_or_insert(i);
+        if y >= b.y && y < b.y + b.height {
+            let dist = if x < b.x { b.x - x } else { 0.0 };
+            let better = on_line.is_none_or(|(_, best, _)| dist < best);
+            if better {
+                let offset = offset_at(face, content, font_size, x - b.x);
+                on_line = Some((i, dist, offset));
+            }
+        } else if b.y + b.height <= y {
+            before = Some(TextPos { box_index: i, offset: content.chars().count() });
+        }
+    }
+
+    on_line
+        .map(|(box_index, _, offset)| TextPos { box_index, offset })
+        .or(before)
+        .or(first.map(|box_index| TextPos { box_index, offset: 0 }))
+}
+
+// ── Granular selection ───────────────────────────────────────────────────────
+
+/// The word (run of alphanumerics, or a single other character) around `pos`.
+pub fn word_at(page: &Page, pos: TextPos) -> Selection {
+    let Some(PaintCmd::Text { content, .. }) = page.boxes.get(pos.box_index).map(|b| &b.cmd) else {
+        return Selection::collapsed(pos);
+    };
+    let chars: Vec<char> = content.chars().collect();
+    if chars.is_empty() {
+        return Selection::collapsed(pos);
+    }
+
+    let at = pos.offset.min(chars.len() - 1);
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let (mut start, mut end) = (at, at + 1);
+    if is_word(chars[at]) {
+        while start > 0 && is_word(chars[start - 1]) {
+            start -= 1;
+        }
+        while end < chars.len() && is_word(chars[end]) {
+            end += 1;
+        }
+    }
+
+    Selection {
+        anchor: TextPos { box_index: pos.box_index, offset: start },
+        focus: TextPos { box_index: pos.box_index, offset: end },
+    }
+}
+
+/// The innermost paragraph-like block containing `pos`, or just its box.
+pub fn block_at(page: &Page, pos: TextPos) -> Selection {
+    let range = page.blocks.iter()
+        .filter(|r| r.contains(&pos.box_index))
+        .min_by_key(|r| r.len())
+        .cloned()
+        .unwrap_or(pos.box_index..pos.box_index + 1);
+
+    let text_len = |i: usize| match &page.boxes[i].cmd {
+        PaintCmd::Text { content, .. } => Some(content.chars().count()),
+        _ => None,
+    };
+    let first = range.clone().find(|&i| text_len(i).is_some()).unwrap_or(pos.box_index);
+    let last = range.rev().find(|&i| text_len(i).is_some()).unwrap_or(pos.box_index);
+
+    Selection {
+        anchor: TextPos { box_index: first, offset: 0 },
+        focus: TextPos { box_index: last, offset: text_len(last).unwrap_or(0) },
+    }
+}
+
+/// Everything from the first to the last text box.
+pub fn select_all(page: &Page) -> Option<Selection> {
+    let text_len = |b: &LayoutBox| match &b.cmd {
+        PaintCmd::Text { content, .. } => Some(content.chars().count()),
+        _ => None,
+    };
+    let first = page.boxes.iter().position(|b| text_len(b).is_some())?;
+    let last = page.boxes.iter().rposition(|b| text_len(b).is_some())?;
+    Some(Selection {
+        anchor: TextPos { box_index: first, offset: 0 },
+        focus: TextPos { box_index: last, offset: text_len(&page.boxes[last]).unwrap_or(0) },
+    })
+}
+
+// ── Text extraction ───────────────────────────────────────────────────────────
+
+/// The selected text, with line breaks between boxes on different lines and
+/// a blank line where the vertical gap suggests a block boundary.
+pub fn selected_text(page: &Page, sel: &Selection) -> String {
+    let (start, end) = sel.ordered();
+    let mut out = String::new();
+    let mut prev: Option<(f32, f32)> = None; // (top, bottom) of the previous box
+
+    for i in start.box_index..=end.box_index.min(page.boxes.len().saturating_sub(1)) {
+        let b = &page.boxes[i];
+        let PaintCmd::Text { content, .. } = &b.cmd else { continue };
+        let Some((from, to)) = sel.range_in_box(i, content.chars().count()) else { continue };
+
+        if let Some((top, bottom)) = prev {
+            if (b.y - top).abs() < 1.0 {
+                out.push(' ');
+            } else if b.y - bottom > b.height / 2.0 {
+                out.push_str("\n\n");
+            } else {
+                out.push('\n');
+            }
+        }
+        out.extend(content.chars().skip(from).take(to - from));
+        prev = Some((b.y, b.y + b.height));
+    }
+    out
+}
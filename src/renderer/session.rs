@@ -0,0 +1,116 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::net::Url;
+
+/// How many documents' scroll offsets are remembered.
+const MAX_DOCUMENTS: usize = 200;
+
+/// State carried between runs: the last window geometry, the zoom level and
+/// where each document was scrolled to.
+///
+/// Stored as plain text lines in `$XDG_STATE_HOME/radium/session`:
+/// `window <x> <y> <width> <height>`, `zoom <factor>` and
+/// `scroll <offset> <url>`, most recently used document first.
+#[derive(Debug)]
+pub struct Session {
+    pub window: Option<Geometry>,
+    pub zoom: f32,
+    scroll: Vec<(String, f32)>,
+}
+
+/// Window position (physical pixels) and inner size (logical pixels).
+#[derive(Debug, Clone, Copy)]
+pub struct Geometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session { window: None, zoom: 1.0, scroll: Vec::new() }
+    }
+}
+
+impl Session {
+    /// Read the saved session; a missing or unreadable file gives defaults.
+    pub fn load() -> Session {
+        let mut session = Session::default();
+        let Some(text) = state_file().and_then(|path| fs::read_to_string(path).ok()) else { return session };
+        for line in text.lines() {
+            match line.split_once(' ') {
+                Some(("window", rest)) => {
+                    let fields: Vec<i32> = rest.split(' ').filter_map(|f| f.parse().ok()).collect();
+                    if let [x, y, width, height] = fields[..] {
+                        session.window = Some(Geometry { x, y, width: width.max(1) as u32, height: height.max(1) as u32 });
+                    }
+                }
+                Some(("zoom", zoom)) => {
+                    session.zoom = zoom.parse().ok().filter(|z: &f32| z.is_finite() && *z > 0.0).unwrap_or(1.0);
+                }
+                Some(("scroll", rest)) => {
+                    if let Some((Ok(offset), url)) = rest.split_once(' ').map(|(o, u)| (o.parse(), u)) {
+                        session.scroll.push((url.to_string(), offset));
+                    }
+                }
+                _ => {}
+            }
+        }
+        session
+    }
+
+    /// Write the session back to disk, reporting failures on stderr.
+    pub fn save(&self) {
+        let Some(path) = state_file() else { return };
+        let mut text = String::new();
+        if let Some(Geometry { x, y, width, height }) = self.window {
+            text.push_str(&format!("window {x} {y} {width} {height}\n"));
+        }
+        text.push_str(&format!("zoom {}\n", self.zoom));
+        for (url, offset) in &self.scroll {
+            text.push_str(&format!("scroll {offset} {url}\n"));
+        }
+
+        // Write then rename, so a crash never leaves half a file behind.
+        let tmp = path.with_extension("tmp");
+        let written = path.parent().map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&tmp, text))
+            .and_then(|()| fs::rename(&tmp, &path));
+        if let Err(e) = written {
+            eprintln!("radium: saving session to {}: {e}", path.display());
+        }
+    }
+
+    /// Where `url` was last scrolled to, if remembered.
+    pub fn scroll(&self, url: &Url) -> Option<f32> {
+        let key = url.without_fragment().to_string();
+        self.scroll.iter().find(|(u, _)| *u == key).map(|&(_, offset)| offset)
+    }
+
+    /// Remember `offset` for `url`; the top of the page is not worth a line.
+    pub fn set_scroll(&mut self, url: &Url, offset: f32) {
+        let key = url.without_fragment().to_string();
+        self.scroll.retain(|(u, _)| *u != key);
+        if offset > 0.0 {
+            self.scroll.insert(0, (key, offset.round()));
+            self.scroll.truncate(MAX_DOCUMENTS);
+        }
+    }
+}
+
+/// `$XDG_STATE_HOME/radium/session`, or the platform's usual location.
+fn state_file() -> Option<PathBuf> {
+    let base = if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_STATE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))
+    };
+    Some(base?.join("radium").join("session"))
+}
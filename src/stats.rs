@@ -0,0 +1,83 @@
+//! `--stats`: how much work the pipeline did for one load of a document,
+//! printed as one JSON object per line once the page has settled:
+//!
+//! ```text
+//! {"url": "file:///home/me/big.html", "bytes": 1048576, "nodes": 52013, "boxes": 26001,
+//!  "read_ms": 0.412, "parse_ms": 31.207, "layout_ms": 64.880, "paint_ms": 3.115, "image_bytes": 0}
+//! ```
+//!
+//! (on one line). Times are wall-clock milliseconds spent in each stage,
+//! relayouts and repaints until the page settled included; `image_bytes` is
+//! the most memory decoded images held at once.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::control;
+use crate::layout::{Page, PaintCmd, RemoteImages};
+use crate::net::Url;
+use crate::parser::dom::Node;
+
+/// Statistics for one load, filled in as its stages run.
+#[derive(Debug, Default, Clone)]
+pub struct Stats {
+    /// Size of the (decompressed) body parsed.
+    pub bytes: usize,
+    /// Elements and text nodes in the parsed document.
+    pub nodes: usize,
+    /// Paint boxes in the latest layout.
+    pub boxes: usize,
+    /// Reading or fetching the body (for directories and archives, building
+    /// the whole document).
+    pub read: Duration,
+    /// Decoding, tokenizing and building the tree.
+    pub parse: Duration,
+    pub layout: Duration,
+    pub paint: Duration,
+    /// Peak bytes of decoded image pixels held.
+    pub image_bytes: usize,
+}
+
+impl Stats {
+    /// Note the memory held by the images of `page` and the image cache it
+    /// was laid out with. Boxes share pixels with the cache, so each image
+    /// counts once.
+    pub fn note_images(&mut self, page: &Page, images: &RemoteImages) {
+        let laid_out = page.boxes.iter().filter_map(|b| match &b.cmd {
+            PaintCmd::Image { data, .. } => Some(data),
+            _ => None,
+        });
+        let mut seen = HashSet::new();
+        let held: usize = laid_out.chain(images.values())
+            .filter(|image| seen.insert(Arc::as_ptr(image)))
+            .map(|image| image.as_raw().len())
+            .sum();
+        self.image_bytes = self.image_bytes.max(held);
+    }
+
+    /// The report line for a load of `url`.
+    pub fn to_json(&self, url: &Url) -> String {
+        let mut out = String::from("{\"url\": ");
+        control::write_string(&url.to_string(), &mut out);
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        out.push_str(&format!(
+            ", \"bytes\": {}, \"nodes\": {}, \"boxes\": {}, \"read_ms\": {:.3}, \"parse_ms\": {:.3}, \
+             \"layout_ms\": {:.3}, \"paint_ms\": {:.3}, \"image_bytes\": {}}}",
+            self.bytes, self.nodes, self.boxes,
+            ms(self.read), ms(self.parse), ms(self.layout), ms(self.paint),
+            self.image_bytes,
+        ));
+        out
+    }
+}
+
+/// Elements and text nodes in `nodes`, descendants included.
+pub fn count_nodes(nodes: &[Node]) -> usize {
+    nodes.iter()
+        .map(|node| match node {
+            Node::Element { children, .. } => 1 + count_nodes(children),
+            Node::Text(_) => 1,
+        })
+        .sum()
+}
@@ -0,0 +1,76 @@
+//! Browser bindings for the `wasm32-unknown-unknown` build.
+//!
+//! There is no file system to probe for fonts, so the page supplies them as
+//! bytes; documents are laid out without fetching images and painted into an
+//! RGBA array or straight onto a `<canvas>`:
+//!
+//! ```js
+//! import init, { Renderer } from "./pkg/radium.js";
+//!
+//! await init();
+//! const font = new Uint8Array(await (await fetch("DejaVuSans.ttf")).arrayBuffer());
+//! const renderer = new Renderer(font);
+//! renderer.render_to_canvas("<h1>Hello</h1>", document.querySelector("canvas"));
+//! ```
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::{Clamped, JsCast};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
+
+use crate::{FontSet, Viewport};
+
+fn js_error(e: impl std::fmt::Display) -> JsError {
+    JsError::new(&e.to_string())
+}
+
+/// Fonts plus the render pipeline, created once per page.
+#[wasm_bindgen]
+pub struct Renderer {
+    fonts: FontSet,
+}
+
+#[wasm_bindgen]
+impl Renderer {
+    /// Fonts as TTF/OTF file contents; missing variants fall back to
+    /// `regular` (or `bold`, for bold italic).
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        regular: &[u8],
+        bold: Option<Vec<u8>>,
+        italic: Option<Vec<u8>>,
+        bold_italic: Option<Vec<u8>>,
+    ) -> Result<Renderer, JsError> {
+        let fonts = FontSet::from_bytes(regular, bold.as_deref(), italic.as_deref(), bold_italic.as_deref())
+            .map_err(js_error)?;
+        Ok(Renderer { fonts })
+    }
+
+    /// Render `html` as `width × height` RGBA8 pixels, e.g. for `new
+    /// ImageData(new Uint8ClampedArray(pixels), width, height)`. `scale` is
+    /// physical pixels per logical pixel (`window.devicePixelRatio`).
+    pub fn render(&self, html: &str, width: u32, height: u32, scale: f32) -> Result<Vec<u8>, JsError> {
+        let document = crate::parse(html);
+        let viewport = Viewport { scale, ..Viewport::new(width, height) };
+        let list = crate::layout(&document, viewport, &self.fonts);
+        let mut pixels = vec![0; width as usize * height as usize * 4];
+        crate::render_rgba(&list, &mut pixels, viewport, &self.fonts).map_err(js_error)?;
+        Ok(pixels)
+    }
+
+    /// Render `html` over the whole of `canvas`, at its current pixel size.
+    pub fn render_to_canvas(&self, html: &str, canvas: &HtmlCanvasElement) -> Result<(), JsError> {
+        let (width, height) = (canvas.width(), canvas.height());
+        let scale = web_sys::window().map_or(1.0, |w| w.device_pixel_ratio() as f32);
+        let pixels = self.render(html, width, height, scale)?;
+
+        let context = canvas
+            .get_context("2d")
+            .ok()
+            .flatten()
+            .and_then(|context| context.dyn_into::<CanvasRenderingContext2d>().ok())
+            .ok_or_else(|| JsError::new("canvas has no 2d context"))?;
+        let image = ImageData::new_with_u8_clamped_array_and_sh(Clamped(&pixels), width, height)
+            .map_err(|_| JsError::new("creating ImageData failed"))?;
+        context.put_image_data(&image, 0.0, 0.0).map_err(|_| JsError::new("drawing to the canvas failed"))
+    }
+}
@@ -0,0 +1,79 @@
+//! The `--control stdio` protocol, driven in memory.
+
+use std::path::PathBuf;
+
+use radium::control;
+use radium::renderer::Options;
+
+/// Run `commands` (one JSON object per line) and return the output lines.
+fn session(commands: &[&str]) -> Vec<String> {
+    let input = commands.join("\n");
+    let mut output = Vec::new();
+    control::run(input.as_bytes(), &mut output, &Options::default(), None).unwrap();
+    String::from_utf8(output).unwrap().lines().map(str::to_string).collect()
+}
+
+fn write_document(name: &str, html: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("radium-control-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, html).unwrap();
+    path
+}
+
+#[test]
+fn load_scroll_and_text() {
+    let path = write_document("page.html", "<title>Page</title><h1>Hello</h1><p>World</p><h2 id=\"end\">End</h2>");
+    let load = format!(r#"{{"id": 1, "command": "load", "url": "{}"}}"#, path.display());
+    let out = session(&[
+        &load,
+        r#"{"id": 2, "command": "viewport", "width": 300, "height": 100}"#,
+        r#"{"id": 3, "command": "scroll", "fragment": "end"}"#,
+        r#"{"id": 4, "command": "scroll", "y": -10}"#,
+        r#"{"id": 5, "command": "text"}"#,
+        r#"{"id": 6, "command": "quit"}"#,
+        r#"{"id": 7, "command": "text"}"#,
+    ]);
+    assert_eq!(out, [
+        r#"{"event": "title_changed", "title": "Page"}"#.to_string(),
+        format!(r#"{{"event": "loaded", "url": "file://{}", "title": "Page"}}"#, path.display()),
+        r#"{"id": 1, "ok": true}"#.into(),
+        r#"{"id": 2, "ok": true}"#.into(),
+        r#"{"id": 3, "ok": true, "y": 108.80002}"#.into(),
+        r#"{"id": 4, "ok": true, "y": 0}"#.into(),
+        r#"{"id": 5, "ok": true, "text": "Hello\n\nWorld\n\nEnd"}"#.into(),
+        r#"{"id": 6, "ok": true}"#.into(),
+    ]);
+}
+
+#[test]
+fn title_changes_only_when_it_differs() {
+    let first = write_document("first.html", "<title>Same</title><p>one</p>");
+    let second = write_document("second.html", "<title>Same</title><p>two</p>");
+    let out = session(&[
+        &format!(r#"{{"command": "load", "url": "{}"}}"#, first.display()),
+        &format!(r#"{{"command": "load", "url": "{}"}}"#, second.display()),
+    ]);
+    let titles = out.iter().filter(|line| line.contains("title_changed")).count();
+    assert_eq!(titles, 1, "{out:?}");
+}
+
+#[test]
+fn errors_leave_the_session_usable() {
+    let out = session(&[
+        "not json",
+        r#"{"command": "fly"}"#,
+        r#"{"id": [1, "é😀"], "command": "text"}"#,
+        r#"{"command": "scroll"}"#,
+        r#"{"command": "viewport", "width": 0}"#,
+        r#"{"command": "load", "url": 5}"#,
+    ]);
+    assert_eq!(out, [
+        r#"{"ok": false, "error": "invalid JSON: unexpected 'not' at 0"}"#,
+        r#"{"ok": false, "error": "unknown command 'fly'"}"#,
+        r#"{"id": [1, "é😀"], "ok": false, "error": "no document loaded"}"#,
+        r#"{"ok": false, "error": "scroll needs y or fragment"}"#,
+        r#"{"ok": false, "error": "width must be positive"}"#,
+        r#"{"ok": false, "error": "url must be a string"}"#,
+    ]);
+}
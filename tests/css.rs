@@ -0,0 +1,162 @@
+//! The CSS tokenizer, declaration lists and value parsing.
+
+use std::collections::HashMap;
+
+use radium::css::{self, Declaration, Selector, Stylesheet, Token};
+
+#[test]
+fn tokens_cover_numbers_names_and_strings() {
+    assert_eq!(css::tokenize("a:hover{margin:-1.5em 50% 0}/* x */#f00 'it\\'s' url(x.png) !important"), [
+        Token::Ident("a".into()),
+        Token::Colon,
+        Token::Ident("hover".into()),
+        Token::OpenBrace,
+        Token::Ident("margin".into()),
+        Token::Colon,
+        Token::Dimension(-1.5, "em".into()),
+        Token::Whitespace,
+        Token::Percentage(50.0),
+        Token::Whitespace,
+        Token::Number(0.0),
+        Token::CloseBrace,
+        Token::Whitespace,
+        Token::Hash("f00".into()),
+        Token::Whitespace,
+        Token::String("it's".into()),
+        Token::Whitespace,
+        Token::Function("url".into()),
+        Token::Ident("x".into()),
+        Token::Delim('.'),
+        Token::Ident("png".into()),
+        Token::CloseParen,
+        Token::Whitespace,
+        Token::Delim('!'),
+        Token::Ident("important".into()),
+    ]);
+}
+
+#[test]
+fn declarations_skip_what_does_not_parse() {
+    let declarations = css::parse_declarations("COLOR: Red ; ; font-size:; margin 0; font: a(b; c) ; padding: 1px !IMPORTANT");
+    assert_eq!(declarations, [
+        Declaration { name: "color".into(), value: vec![Token::Ident("Red".into())], important: false },
+        Declaration {
+            name: "font".into(),
+            value: vec![Token::Function("a".into()), Token::Ident("b".into()), Token::Semicolon, Token::Whitespace, Token::Ident("c".into()), Token::CloseParen],
+            important: false,
+        },
+        Declaration { name: "padding".into(), value: vec![Token::Dimension(1.0, "px".into())], important: true },
+    ]);
+}
+
+#[test]
+fn colors_and_lengths() {
+    let value = |s: &str| css::parse_declarations(&format!("x: {s}")).remove(0).value;
+    assert_eq!(css::color(&value("rebeccapurple")), Some(0x663399));
+    assert_eq!(css::color(&value("#AbC")), Some(0xAABBCC));
+    assert_eq!(css::color(&value("#11223344")), Some(0xBB112233));
+    assert_eq!(css::color(&value("#0f08")), Some(0x7700FF00));
+    assert_eq!(css::color(&value("rgb(255, 0, 128)")), Some(0xFF0080));
+    assert_eq!(css::color(&value("rgba(100% 0% 0% / 50%)")), Some(0x7FFF0000));
+    assert_eq!(css::color(&value("hsl(120, 100%, 25%)")), Some(0x008000));
+    assert_eq!(css::color(&value("hsla(0.5turn 100% 50% / 0.25)")), Some(0xBF00FFFF));
+    assert_eq!(css::color(&value("transparent")), Some(0xFF000000));
+    assert_eq!(css::color(&value("hsl(120, 100, 25)")), None);
+    assert_eq!(css::alpha(0x7FFF0000), 128);
+    assert_eq!(css::hex(0x7FFF0000), "#ff000080");
+    assert_eq!(css::hex(0x663399), "#663399");
+    assert_eq!(css::color(&value("#12")), None);
+    assert_eq!(css::color(&value("nocolor")), None);
+
+    assert_eq!(css::length(&value("12pt")[0], 10.0, 200.0), Some(16.0));
+    assert_eq!(css::length(&value("1.5em")[0], 10.0, 200.0), Some(15.0));
+    assert_eq!(css::length(&value("2rem")[0], 10.0, 200.0), Some(32.0));
+    assert_eq!(css::length(&value("25%")[0], 10.0, 200.0), Some(50.0));
+    assert_eq!(css::length(&value("0")[0], 10.0, 200.0), Some(0.0));
+    assert_eq!(css::length(&value("3")[0], 10.0, 200.0), None);
+}
+
+#[test]
+fn style_sheets_keep_the_rules_they_can_match() {
+    let sheet = Stylesheet::parse(
+        "@import url(x.css); P, a.ext.new { color: red } @media print { p { color: blue } } \
+         #main{margin:0} nav a { color: green } a:hover { color: green } .x, { } h1 { font: x",
+    );
+    let selectors: Vec<_> = sheet.rules.iter().map(|rule| rule.selectors.clone()).collect();
+    assert_eq!(selectors, [
+        vec![
+            Selector { tag: Some("p".into()), ..Selector::default() },
+            Selector { tag: Some("a".into()), id: None, classes: vec!["ext".into(), "new".into()] },
+        ],
+        vec![Selector { id: Some("main".into()), ..Selector::default() }],
+        vec![Selector { tag: Some("h1".into()), ..Selector::default() }],
+    ]);
+    assert_eq!(sheet.rules[0].declarations[0].name, "color");
+    assert_eq!(sheet.rules[2].declarations[0].name, "font");
+
+    let attrs = HashMap::from([("class".to_string(), "new ext  big".to_string())]);
+    assert!(sheet.rules[0].selectors[1].matches("a", &attrs));
+    assert!(!sheet.rules[0].selectors[1].matches("p", &HashMap::new()));
+    assert_eq!(sheet.matching("a", &attrs).count(), 1);
+}
+
+#[test]
+fn cascade_orders_by_origin_importance_and_specificity() {
+    let defaults = Stylesheet::parse("p { color: ua } p { margin: ua !important }");
+    let author = Stylesheet::parse(
+        "#main { color: id } .note { color: class } p { color: tag } \
+         p { font-size: tag !important } .note { font-size: class } p { margin: author !important }",
+    );
+    let inline = css::parse_declarations("color: inline; font-size: inline; width: inline !important");
+    let attrs = HashMap::from([("id".to_string(), "main".to_string()), ("class".to_string(), "note".to_string())]);
+    let sheets = [(css::Origin::UserAgent, &defaults), (css::Origin::Author, &author)];
+
+    let winners: Vec<String> = ["color", "font-size", "margin", "width"].iter()
+        .map(|property| {
+            let declarations = css::cascade(&sheets, &inline, "p", &attrs);
+            let winner = declarations.iter().rev().find(|d| d.name == *property).unwrap();
+            match &winner.value[..] {
+                [Token::Ident(value)] => value.clone(),
+                value => panic!("{value:?}"),
+            }
+        })
+        .collect();
+    assert_eq!(winners, ["inline", "tag", "ua", "inline"]);
+
+    // Without the style attribute, the id beats the class beats the tag.
+    let declarations = css::cascade(&sheets, &[], "p", &attrs);
+    let colors: Vec<_> = declarations.iter().filter(|d| d.name == "color").map(|d| format!("{:?}", d.value)).collect();
+    assert_eq!(colors, ["[Ident(\"ua\")]", "[Ident(\"tag\")]", "[Ident(\"class\")]", "[Ident(\"id\")]"]);
+}
+
+#[test]
+fn user_rules_sit_between_the_defaults_and_the_document() {
+    let defaults = Stylesheet::parse("p { color: ua; margin: ua !important }");
+    let user = Stylesheet::parse("p { color: user; font-size: user; margin: user !important; width: user !important }");
+    let author = Stylesheet::parse("p { font-size: author; width: author !important }");
+    let sheets = [(css::Origin::UserAgent, &defaults), (css::Origin::User, &user), (css::Origin::Author, &author)];
+    let attrs = HashMap::new();
+    let declarations = css::cascade(&sheets, &[], "p", &attrs);
+    let winners: Vec<String> = ["color", "font-size", "margin", "width"].iter()
+        .map(|property| match &declarations.iter().rev().find(|d| d.name == *property).unwrap().value[..] {
+            [Token::Ident(value)] => value.clone(),
+            value => panic!("{value:?}"),
+        })
+        .collect();
+    assert_eq!(winners, ["user", "author", "ua", "user"]);
+}
+
+#[test]
+fn the_user_stylesheet_styles_every_document() {
+    radium::layout::set_user_stylesheet(Some("p { color: #123456; font-size: 20px !important } em { color: red }"));
+    let document = radium::parse("<p style=\"font-size: 10px\">a <em style=\"color: blue\">b</em></p>");
+    let page = radium::layout(&document, radium::Viewport::new(800, 600), &radium::layout::Estimate);
+    radium::layout::set_user_stylesheet(None);
+    let runs: Vec<_> = page.boxes.iter()
+        .filter_map(|b| match b.cmd {
+            radium::layout::PaintCmd::Text { font_size, color, .. } => Some((font_size, color)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(runs, [(20.0, 0x123456), (20.0, 0x0000FF)]);
+}
@@ -0,0 +1,130 @@
+//! Turning document bodies into DOMs: Markdown and character decoding.
+
+use radium::document::{ContentKind, Source};
+use radium::dump;
+use radium::net::Url;
+
+/// The DOM of `body` served as the file `name`.
+fn parse(name: &str, body: &[u8]) -> String {
+    let source = Source { url: Url::parse(&format!("file:///docs/{name}")).unwrap(), kind: None };
+    dump::dom(&source.parse(body))
+}
+
+fn markdown(text: &str) -> String {
+    parse("notes.md", text.as_bytes())
+}
+
+#[test]
+fn markdown_blocks() {
+    assert_eq!(
+        markdown("# Title #\n\nSetext\n---\n\nOne\nparagraph\n\n> quoted\n> text\n\n***\n\n```\nfn main() {}\n\tx\n```\n\n    indented\n"),
+        "\
+body
+  h1
+    \"Title\"
+  h2
+    \"Setext\"
+  p
+    \"One paragraph\"
+  blockquote
+    p
+      \"quoted text\"
+  hr
+  pre
+    code
+      div
+        \"fn main() {}\"
+      div
+        \"    x\"
+  pre
+    code
+      div
+        \"indented\"
+"
+    );
+}
+
+#[test]
+fn markdown_lists_nest_and_stay_tight() {
+    assert_eq!(
+        markdown("- one\n- two\n  - inner\n\n3. three\n4. four\n   lazy\n-\n"),
+        "\
+body
+  ul
+    li
+      \"one\"
+    li
+      \"two\"
+      ul
+        li
+          \"inner\"
+  ol start=\"3\"
+    li
+      \"three\"
+    li
+      \"four lazy\"
+  ul
+    li
+"
+    );
+}
+
+#[test]
+fn markdown_inlines() {
+    assert_eq!(
+        markdown("*em* **strong** ***both*** `a * b` snake_case_name \\*not\\* [link *text*](a.html \"t\") ![alt](i.png) <https://x.org>"),
+        "\
+body
+  p
+    em
+      \"em\"
+    \" \"
+    strong
+      \"strong\"
+    \" \"
+    strong
+      em
+        \"both\"
+    \" \"
+    code
+      \"a * b\"
+    \" snake_case_name *not* \"
+    a href=\"a.html\"
+      \"link \"
+      em
+        \"text\"
+    \" \"
+    img alt=\"alt\" src=\"i.png\"
+    \" \"
+    a href=\"https://x.org\"
+      \"https://x.org\"
+"
+    );
+}
+
+#[test]
+fn markdown_shows_raw_html_and_unclosed_markup_as_text() {
+    assert_eq!(markdown("<b>bold?</b> *open `tick [x](y"), "body\n  p\n    \"<b>bold?</b> *open `tick [x](y\"\n");
+}
+
+#[test]
+fn markdown_is_chosen_by_kind_over_the_extension() {
+    let source = Source { url: Url::parse("file:///docs/notes.txt").unwrap(), kind: Some(ContentKind::Markdown) };
+    assert_eq!(dump::dom(&source.parse(b"# Hi")), "body\n  h1\n    \"Hi\"\n");
+}
+
+#[test]
+fn byte_order_marks_choose_the_encoding() {
+    let utf16 = |unit: fn(u16) -> [u8; 2], bom: [u8; 2]| -> Vec<u8> {
+        bom.into_iter().chain("<p>caf\u{e9} \u{1F600}</p>".encode_utf16().flat_map(unit)).collect()
+    };
+    let expected = "p\n  \"caf\u{e9} \u{1F600}\"\n";
+    assert_eq!(parse("page.html", &utf16(u16::to_le_bytes, [0xFF, 0xFE])), expected);
+    assert_eq!(parse("page.html", &utf16(u16::to_be_bytes, [0xFE, 0xFF])), expected);
+    assert_eq!(parse("page.html", "\u{FEFF}<p>caf\u{e9} \u{1F600}</p>".as_bytes()), expected);
+}
+
+#[test]
+fn bodies_without_a_charset_are_utf8() {
+    assert_eq!(parse("page.html", b"<p>caf\xC3\xA9 \xFF</p>"), "p\n  \"caf\u{e9} \u{FFFD}\"\n");
+}
@@ -0,0 +1,57 @@
+//! Documents and subresources supplied from memory by an embedding application.
+
+use std::io::Cursor;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use image::{ImageFormat, Rgba, RgbaImage};
+use radium::document::Source;
+use radium::net::loader::{Kind, Loader, Resource};
+use radium::net::Url;
+
+#[test]
+fn supplied_subresources_skip_the_network() {
+    let mut png = Vec::new();
+    RgbaImage::from_pixel(3, 2, Rgba([255, 0, 0, 255])).write_to(&mut Cursor::new(&mut png), ImageFormat::Png).unwrap();
+
+    let (sender, received) = mpsc::channel();
+    let loader = Loader::with_supplier(
+        move |url, _| (url.path == "/logo.png").then(|| png.clone()),
+        move |loaded| sender.send(loaded).unwrap(),
+    );
+    loader.request(&Url::parse("app://bundle/logo.png").unwrap(), Kind::Image);
+
+    let loaded = received.recv_timeout(Duration::from_secs(10)).unwrap();
+    match loaded.result {
+        Ok(Resource::Image(img)) => assert_eq!(img.dimensions(), (3, 2)),
+        other => panic!("unexpected result {other:?}"),
+    }
+}
+
+#[test]
+fn supplied_documents_are_classified_by_url() {
+    let source = |url: &str| Source { url: Url::parse(url).unwrap(), kind: None };
+    let html = radium::dump::dom(&source("https://app.invalid/index.html").parse(b"<p>*hi*</p>"));
+    assert_eq!(html, "p\n  \"*hi*\"\n");
+    let markdown = radium::dump::dom(&source("https://app.invalid/notes.md").parse(b"# Notes"));
+    assert!(markdown.contains("h1\n"), "{markdown}");
+}
+
+#[test]
+fn a_panicking_load_fails_only_that_request() {
+    let (sender, received) = mpsc::channel();
+    let loader = Loader::with_supplier(
+        |url, _| match url.path.as_str() {
+            "/bad.css" => panic!("supplier bug"),
+            _ => Some(b"p { color: red }".to_vec()),
+        },
+        move |loaded| sender.send(loaded).unwrap(),
+    );
+    loader.request(&Url::parse("app://bundle/bad.css").unwrap(), Kind::Stylesheet);
+    let loaded = received.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(loaded.result.unwrap_err(), "loading app://bundle/bad.css panicked");
+
+    loader.request(&Url::parse("app://bundle/good.css").unwrap(), Kind::Stylesheet);
+    let loaded = received.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert!(loaded.result.is_ok(), "{:?}", loaded.result);
+}
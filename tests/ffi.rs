@@ -0,0 +1,39 @@
+//! The C API, called as a C program would (`cargo test --features ffi`).
+#![cfg(feature = "ffi")]
+
+use std::ffi::{c_void, CStr};
+
+use radium::ffi::*;
+
+extern "C" fn collect(cmd: *const RadiumPaintCmd, user_data: *mut c_void) {
+    let (cmd, out) = unsafe { (&*cmd, &mut *(user_data as *mut Vec<String>)) };
+    let text = match cmd.kind {
+        RADIUM_PAINT_TEXT => {
+            let bytes = unsafe { std::slice::from_raw_parts(cmd.text, cmd.text_len) };
+            String::from_utf8(bytes.to_vec()).unwrap()
+        }
+        _ => String::new(),
+    };
+    out.push(format!("{} {:.1} {:.1} #{:06x} {} {text}", cmd.kind, cmd.x, cmd.y, cmd.color, cmd.flags));
+}
+
+#[test]
+fn paint_commands_reach_the_callback() {
+    let mut out: Vec<String> = Vec::new();
+    let code = unsafe {
+        radium_paint_html(c"<h1>Hi</h1><a href=x>link</a><hr>".as_ptr(), 800, Some(collect), &mut out as *mut _ as *mut c_void)
+    };
+    assert_eq!(code, RADIUM_OK);
+    assert_eq!(out, ["0 16.0 40.0 #000000 1 Hi", "0 16.0 100.8 #0000ee 4 link", "2 16.0 131.2 #aaaaaa 0 "]);
+}
+
+#[test]
+fn errors_are_reported() {
+    let mut pixels = [0u8; 4];
+    let code = unsafe { radium_render_html(std::ptr::null(), 1, 1, pixels.as_mut_ptr()) };
+    assert_eq!(code, RADIUM_ERROR_ARGUMENT);
+    assert_eq!(unsafe { CStr::from_ptr(radium_last_error()) }.to_str().unwrap(), "html is null");
+
+    let code = unsafe { radium_paint_html(c"<p>".as_ptr(), 800, None, std::ptr::null_mut()) };
+    assert_eq!(code, RADIUM_ERROR_ARGUMENT);
+}
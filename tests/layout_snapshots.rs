@@ -0,0 +1,981 @@
+//! Layout assertions against the textual display-list snapshot
+//! ([`radium::dump::layout`]). Text is measured by [`Estimate`] rather than
+//! fonts, so these run everywhere, unlike the reftests.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use radium::parser::dom::{node_at, Node};
+use radium::layout::{ButtonState, CustomLayout, Estimate, LayoutBox, PaintCmd, TextMeasurer};
+use radium::{dump, layout, Viewport};
+
+fn snapshot(html: &str) -> String {
+    let document = radium::parse(html);
+    dump::layout(&radium::layout(&document, Viewport::new(800, 600), &Estimate))
+}
+
+#[test]
+fn headings_and_paragraphs() {
+    assert_eq!(
+        snapshot("<h1>Title</h1><p>First</p><p>Second</p>"),
+        "\
+box 16.0 40.0 80.0 44.8 text 32px #000000 bold \"Title\"
+box 16.0 100.8 40.0 22.4 text 16px #000000 \"First\"
+box 16.0 139.2 48.0 22.4 text 16px #000000 \"Second\"
+"
+    );
+}
+
+#[test]
+fn links_titles_and_anchors() {
+    assert_eq!(
+        snapshot("<h2 id=\"top\">Top</h2><p><a href=\"/next\" title=\"Next page\">next</a></p>"),
+        "\
+box 16.0 36.0 36.0 33.6 text 24px #000000 bold \"Top\"
+box 16.0 81.6 32.0 22.4 text 16px #0000ee underline \"next\"
+title 16.0 81.6 32.0 22.4 \"Next page\"
+link 16.0 81.6 32.0 22.4 \"/next\"
+anchor 16.0 36.0 36.0 33.6 \"top\"
+"
+    );
+}
+
+#[test]
+fn rules_and_list_markers() {
+    assert_eq!(
+        snapshot("<hr><ul><li>one</li><li>two</li></ul>"),
+        "\
+box 16.0 24.0 768.0 1.0 hline #aaaaaa
+box 16.0 41.0 24.0 22.4 text 16px #555555 \"•\"
+box 40.0 41.0 24.0 22.4 text 16px #000000 \"one\"
+box 16.0 67.4 24.0 22.4 text 16px #555555 \"•\"
+box 40.0 67.4 24.0 22.4 text 16px #000000 \"two\"
+"
+    );
+}
+
+#[test]
+fn list_items_are_anchors_with_titles() {
+    assert_eq!(
+        snapshot("<p><a href=\"#fn1\">1</a></p><ol><li id=\"fn1\" title=\"The source\">Note</li><li id=\"fn2\"></li></ol>"),
+        "\
+box 16.0 16.0 8.0 22.4 text 16px #0000ee underline \"1\"
+box 16.0 62.4 24.0 22.4 text 16px #555555 \"1.\"
+box 40.0 62.4 32.0 22.4 text 16px #000000 \"Note\"
+box 16.0 88.8 24.0 22.4 text 16px #555555 \"2.\"
+link 16.0 16.0 8.0 22.4 \"#fn1\"
+title 40.0 62.4 32.0 22.4 \"The source\"
+anchor 40.0 62.4 32.0 22.4 \"fn1\"
+anchor 40.0 88.8 744.0 22.4 \"fn2\"
+"
+    );
+}
+
+#[test]
+fn narrower_viewport_narrows_boxes() {
+    // 63 characters: 504px by the estimate, which fits 768px but not 368px.
+    let document = radium::parse(&format!("<p>{}</p>", ["abcdefg"; 8].join(" ")));
+    let viewport = Viewport { scale: 2.0, ..Viewport::new(800, 600) };
+    let text = dump::layout(&radium::layout(&document, viewport, &Estimate));
+    assert!(text.starts_with("box 16.0 16.0 312.0 22.4 text 16px #000000 \"abcdefg abcdefg abcdefg abcdefg abcdefg\"\n"), "{text}");
+    assert_eq!(text.lines().count(), 2, "{text}");
+}
+
+#[test]
+fn elements_point_back_to_the_dom() {
+    let document = radium::parse("<p>Intro <a href=\"/next\">next</a></p><ul><li>Item</li></ul>");
+    let elements = layout::layout_elements(&document.nodes, 800.0, f32::INFINITY, &document.base, &Default::default(), &Estimate);
+
+    // "next" follows "Intro" on its line, inside the `<a>` inside the `<p>`.
+    let link = &elements[layout::element_at(&elements, 70.0, 20.0).unwrap()];
+    let Some(Node::Element { tag, attrs, .. }) = node_at(&document.nodes, &link.path) else { panic!() };
+    assert_eq!((tag.as_str(), attrs["href"].as_str()), ("a", "/next"));
+    assert_eq!((link.style.color, link.style.underline), (0x0000EE, true));
+    assert_eq!(link.boxes, 1..2);
+    assert_eq!(format!("{:.1} {:.1} {:.1}", link.rect.x, link.rect.y, link.rect.height), "64.0 16.0 22.4");
+
+    let item = &elements[layout::element_at(&elements, 50.0, 70.0).unwrap()];
+    assert!(matches!(node_at(&document.nodes, &item.path), Some(Node::Element { tag, .. }) if tag == "li"));
+    assert_eq!(item.style.indent, 24.0);
+
+    assert_eq!(layout::element_at(&elements, 400.0, 5.0), None);
+}
+
+#[test]
+fn headings_form_the_outline() {
+    let document = radium::parse("<h1>Guide</h1><p>Intro</p><h2 id=\"install\">Installing <em>it</em></h2><h3>From source</h3><h6>Notes</h6>");
+    let page = radium::layout(&document, Viewport::new(800, 600), &Estimate);
+    let outline: Vec<_> = page.headings.iter()
+        .map(|h| (h.level, h.text.as_str(), h.id.as_deref(), format!("{:.1}", h.rect.y)))
+        .collect();
+    assert_eq!(outline, [
+        (1, "Guide", None, "40.0".to_string()),
+        (2, "Installing it", Some("install"), "159.2".into()),
+        (3, "From source", None, "220.8".into()),
+        (6, "Notes", None, "268.8".into()),
+    ]);
+    assert_eq!(page.anchors["install"].y, page.headings[1].rect.y);
+}
+
+#[test]
+fn registered_elements_lay_themselves_out() {
+    // One bar per value, 10px wide and 4px tall per unit.
+    layout::register_element("x-chart", |element| {
+        let values: Vec<f32> = element.attrs.get("data")?.split(',').map(|v| v.trim().parse().ok()).collect::<Option<_>>()?;
+        let height = values.iter().fold(0.0, |max: f32, v| max.max(v * 4.0));
+        let boxes = values.iter().enumerate()
+            .map(|(i, v)| LayoutBox {
+                x: i as f32 * 12.0,
+                y: height - v * 4.0,
+                width: 10.0,
+                height: v * 4.0,
+                cmd: PaintCmd::FillRect { color: element.style.color },
+            })
+            .collect();
+        Some(CustomLayout { boxes, height })
+    });
+
+    assert_eq!(
+        snapshot("<ul><li><x-chart data=\"1, 3\">fallback</x-chart></li></ul><x-chart data=\"oops\">fallback</x-chart>"),
+        "\
+box 16.0 24.0 24.0 22.4 text 16px #555555 \"•\"
+box 40.0 32.0 10.0 4.0 rect #000000
+box 52.0 24.0 10.0 12.0 rect #000000
+box 16.0 58.4 64.0 22.4 text 16px #000000 \"fallback\"
+"
+    );
+
+    assert!(layout::unregister_element("X-Chart"));
+    assert!(snapshot("<x-chart data=\"1\">fallback</x-chart>").contains("\"fallback\""));
+}
+
+#[test]
+fn text_inputs_show_their_value_or_placeholder() {
+    let document = radium::parse("<p><input name=\"q\" placeholder=\"Search\"><input type=\"text\" value=\"radium\" size=\"10\"><input type=\"hidden\" name=\"token\"></p>");
+    let mut page = radium::layout(&document, Viewport::new(800, 600), &Estimate);
+    assert_eq!(
+        dump::layout(&page),
+        "\
+box 16.0 16.0 170.0 32.4 rect #767676
+box 17.0 17.0 168.0 30.4 rect #ffffff
+box 21.0 21.0 160.0 22.4 text 16px #757575 \"Search\"
+box 16.0 48.4 90.0 32.4 rect #767676
+box 17.0 49.4 88.0 30.4 rect #ffffff
+box 21.0 53.4 80.0 22.4 text 16px #000000 \"radium\"
+input 16.0 16.0 170.0 32.4 \"q\"
+input 16.0 48.4 90.0 32.4 \"\"
+"
+    );
+
+    // Editing updates the text box in place; an empty value shows the placeholder.
+    page.set_input_value(0, "rust".into());
+    page.set_input_value(1, String::new());
+    let shown: Vec<_> = page.inputs.iter()
+        .map(|input| match &page.boxes[input.text_box].cmd {
+            PaintCmd::Text { content, color, .. } => (input.value.as_str(), content.as_str(), *color),
+            _ => panic!("not a text box"),
+        })
+        .collect();
+    assert_eq!(shown, [("rust", "rust", 0x000000), ("", "", 0x757575)]);
+}
+
+#[test]
+fn password_fields_show_bullets() {
+    let document = radium::parse("<input type=\"password\" name=\"pw\" value=\"höhle\">");
+    let mut page = radium::layout(&document, Viewport::new(800, 600), &Estimate);
+    assert!(page.inputs[0].masked);
+    assert!(dump::layout(&page).contains("text 16px #000000 \"•••••\""));
+
+    page.set_input_value(0, "ab".into());
+    assert!(dump::layout(&page).contains("text 16px #000000 \"••\""));
+    // The real value is what a form sends.
+    assert_eq!(page.form_values(), [("pw".to_string(), "ab".to_string())]);
+}
+
+#[test]
+fn checkboxes_and_radio_groups() {
+    let document = radium::parse(
+        "<input type=\"checkbox\" name=\"tos\" checked>\
+         <input type=\"radio\" name=\"size\" value=\"s\" checked>\
+         <input type=\"radio\" name=\"size\" value=\"m\" checked>\
+         <input name=\"who\" value=\"me\">",
+    );
+    let mut page = radium::layout(&document, Viewport::new(800, 600), &Estimate);
+    let regions: Vec<_> = dump::layout(&page).lines().filter(|l| !l.starts_with("box ")).map(str::to_string).collect();
+    assert_eq!(regions, [
+        "checkbox 16.0 19.2 16.0 16.0 checked \"tos\"",
+        "radio 16.0 41.6 16.0 16.0 \"size\"",
+        "radio 16.0 64.0 16.0 16.0 checked \"size\"",
+        "input 16.0 83.2 170.0 32.4 \"who\"",
+    ]);
+    let mark = |page: &layout::Page, toggle: usize| match page.boxes[page.toggles[toggle].mark_box].cmd {
+        PaintCmd::FillRect { color } => color,
+        _ => panic!("not a mark"),
+    };
+    assert_eq!((mark(&page, 0), mark(&page, 1), mark(&page, 2)), (0x1A73E8, 0xFFFFFF, 0x1A73E8));
+
+    // Checking a radio button unchecks the rest of its group.
+    page.set_checked(1, true);
+    page.set_checked(0, false);
+    assert_eq!((mark(&page, 1), mark(&page, 2)), (0x1A73E8, 0xFFFFFF));
+    assert_eq!(page.form_values(), [("size".to_string(), "s".to_string()), ("who".into(), "me".into())]);
+}
+
+#[test]
+fn buttons_and_their_states() {
+    let document = radium::parse(
+        "<button name=\"go\" value=\"1\">Go <em>now</em></button>\
+         <input type=\"reset\">\
+         <input type=\"button\" value=\"Help\">",
+    );
+    let mut page = radium::layout(&document, Viewport::new(800, 600), &Estimate);
+    assert_eq!(
+        dump::layout(&page),
+        "\
+box 16.0 16.0 74.0 32.4 rect #767676
+box 17.0 17.0 72.0 30.4 rect #efefef
+box 29.0 21.0 48.0 22.4 text 16px #000000 \"Go now\"
+box 16.0 48.4 66.0 32.4 rect #767676
+box 17.0 49.4 64.0 30.4 rect #efefef
+box 29.0 53.4 40.0 22.4 text 16px #000000 \"Reset\"
+box 16.0 80.8 58.0 32.4 rect #767676
+box 17.0 81.8 56.0 30.4 rect #efefef
+box 29.0 85.8 32.0 22.4 text 16px #000000 \"Help\"
+button 16.0 16.0 74.0 32.4 submit \"Go now\"
+button 16.0 48.4 66.0 32.4 reset \"Reset\"
+button 16.0 80.8 58.0 32.4 button \"Help\"
+"
+    );
+    assert_eq!((page.buttons[0].name.as_deref(), page.buttons[0].value.as_str()), (Some("go"), "1"));
+
+    let face = |page: &layout::Page| match page.boxes[page.buttons[0].face_box].cmd {
+        PaintCmd::FillRect { color } => color,
+        _ => panic!("not a face"),
+    };
+    page.set_button_state(0, ButtonState::Pressed);
+    assert_eq!(face(&page), 0xD0D0D0);
+    page.set_button_state(0, ButtonState::Normal);
+    assert_eq!(face(&page), 0xEFEFEF);
+}
+
+#[test]
+fn selects_show_their_chosen_option() {
+    let document = radium::parse(
+        "<select name=\"size\">\
+           <option value=\"s\">Small</option>\
+           <optgroup label=\"Big\"><option selected>Large</option><option value=\"xl\">Huge</option></optgroup>\
+         </select>\
+         <select></select>",
+    );
+    let mut page = radium::layout(&document, Viewport::new(800, 600), &Estimate);
+    assert_eq!(
+        dump::layout(&page),
+        "\
+box 16.0 16.0 70.0 32.4 rect #767676
+box 17.0 17.0 68.0 30.4 rect #ffffff
+box 21.0 21.0 40.0 22.4 text 16px #000000 \"Large\"
+box 69.0 21.0 16.0 22.4 text 16px #555555 \"▾\"
+box 16.0 48.4 30.0 32.4 rect #767676
+box 17.0 49.4 28.0 30.4 rect #ffffff
+box 21.0 53.4 0.0 22.4 text 16px #000000 \"\"
+box 29.0 53.4 16.0 22.4 text 16px #555555 \"▾\"
+select 16.0 16.0 70.0 32.4 2/3 \"size\"
+select 16.0 48.4 30.0 32.4 -/0 \"\"
+"
+    );
+    assert_eq!(page.form_values(), [("size".to_string(), "Large".to_string())]);
+
+    page.set_selected(0, 2);
+    assert!(dump::layout(&page).contains("text 16px #000000 \"Huge\""));
+    assert_eq!(page.form_values(), [("size".to_string(), "xl".to_string())]);
+}
+
+#[test]
+fn textareas_wrap_and_scroll() {
+    let document = radium::parse(
+        "<textarea name=\"note\" cols=\"10\" rows=\"2\" wrap=\"hard\">\nhello wide world\nbye</textarea>\
+         <textarea wrap=\"off\" placeholder=\"Say more\"></textarea>",
+    );
+    let mut page = radium::layout(&document, Viewport::new(800, 600), &Estimate);
+    assert_eq!(
+        dump::layout(&page),
+        "\
+box 16.0 16.0 90.0 54.8 rect #767676
+box 17.0 17.0 88.0 52.8 rect #ffffff
+box 21.0 21.0 80.0 22.4 text 16px #000000 \"hello \"
+box 21.0 43.4 80.0 22.4 text 16px #000000 \"wide world\"
+box 16.0 70.8 170.0 54.8 rect #767676
+box 17.0 71.8 168.0 52.8 rect #ffffff
+box 21.0 75.8 160.0 22.4 text 16px #757575 \"Say more\"
+box 21.0 98.2 160.0 22.4 text 16px #000000 \"\"
+textarea 16.0 16.0 90.0 54.8 \"note\"
+textarea 16.0 70.8 170.0 54.8 \"\"
+"
+    );
+    assert_eq!(page.inputs[0].lines(), [0..6, 6..16, 17..20]);
+    assert_eq!(page.inputs[1].cols, None);
+    // Hard wrapping submits a line break wherever the text wraps.
+    assert_eq!(page.form_values(), [("note".to_string(), "hello \nwide world\nbye".to_string())]);
+
+    // Scrolling stops with the last line at the bottom.
+    page.scroll_input(0, 5);
+    assert_eq!(page.inputs[0].scroll, 1);
+    let shown: Vec<_> = (0..2)
+        .map(|row| match &page.boxes[page.inputs[0].text_box + row].cmd {
+            PaintCmd::Text { content, .. } => content.clone(),
+            _ => panic!("not a text box"),
+        })
+        .collect();
+    assert_eq!(shown, ["wide world", "bye"]);
+}
+
+#[test]
+fn textareas_wrap_between_ideographs() {
+    let document = radium::parse("<textarea cols=\"4\">日本。です\nabc漢字</textarea>");
+    let page = radium::layout(&document, Viewport::new(800, 600), &Estimate);
+    // Ideographs take two columns and a line may break between any two,
+    // but not before the full stop.
+    assert_eq!(page.inputs[0].lines(), [0..1, 1..3, 3..5, 6..9, 9..11]);
+}
+
+#[test]
+fn textareas_wrap_around_empty_lines() {
+    let document = radium::parse("<textarea></textarea><textarea cols=\"5\">abc\n\ndef</textarea><textarea cols=\"3\">x\n\n</textarea>");
+    let page = radium::layout(&document, Viewport::new(800, 600), &Estimate);
+    assert_eq!(page.inputs[0].lines(), [0..0; 1]);
+    assert_eq!(page.inputs[1].lines(), [0..3, 4..4, 5..8]);
+    assert_eq!(page.inputs[2].lines(), [0..1, 2..2, 3..3]);
+}
+
+#[test]
+fn soft_hyphens_show_only_where_lines_break() {
+    let document = radium::parse("<textarea cols=\"8\" rows=\"3\">auto\u{AD}matic\u{AD}ally</textarea>");
+    let page = radium::layout(&document, Viewport::new(800, 600), &Estimate);
+    assert_eq!(page.inputs[0].lines(), [0..5, 5..11, 11..15]);
+    let shown: Vec<_> = (0..3)
+        .map(|row| match &page.boxes[page.inputs[0].text_box + row].cmd {
+            PaintCmd::Text { content, .. } => content.clone(),
+            _ => panic!("not a text box"),
+        })
+        .collect();
+    assert_eq!(shown, ["auto-", "matic-", "ally"]);
+}
+
+#[test]
+fn lines_never_break_at_no_break_spaces() {
+    let document = radium::parse("<p>\u{A0}kept\u{A0}</p><textarea cols=\"6\">aa bb\u{A0}cc</textarea>");
+    let page = radium::layout(&document, Viewport::new(800, 600), &Estimate);
+    assert!(matches!(&page.boxes[0].cmd, PaintCmd::Text { content, .. } if content == "\u{A0}kept\u{A0}"));
+    assert_eq!(page.inputs[0].lines(), [0..3, 3..8]);
+}
+
+#[test]
+fn zero_width_spaces_are_break_opportunities() {
+    let document = radium::parse("<textarea cols=\"8\">foo\u{200B}bar\u{200B}baz</textarea>");
+    let page = radium::layout(&document, Viewport::new(800, 600), &Estimate);
+    assert_eq!(page.inputs[0].lines(), [0..8, 8..11]);
+}
+
+#[test]
+fn quotes_wrap_their_text_in_language_marks() {
+    let document = radium::parse("<p lang=\"de\">Er sagte <q>ich <q>weiß</q> es</q></p><q lang=\"ja\"></q>");
+    let page = radium::layout(&document, Viewport::new(800, 600), &Estimate);
+    let texts: Vec<_> = page.boxes.iter()
+        .filter_map(|b| match &b.cmd {
+            PaintCmd::Text { content, .. } => Some(content.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(texts, ["Er sagte", "\u{201E}ich", "\u{201A}weiß\u{2018}", "es\u{201C}", "\u{300C}\u{300D}"]);
+}
+
+#[test]
+fn forms_collect_their_own_controls() {
+    let document = radium::parse(
+        "<input name=\"outside\" value=\"x\">\
+         <form action=\"search.html\">\
+           <input name=\"q\" value=\"rust &amp; wasm\">\
+           <input type=\"radio\" name=\"sort\" value=\"new\" checked>\
+           <textarea name=\"note\">a\nb</textarea>\
+           <button name=\"go\" value=\"1\">Go</button>\
+         </form>\
+         <form method=\"POST\"><input type=\"radio\" name=\"sort\" value=\"old\" checked></form>",
+    );
+    let page = radium::layout(&document, Viewport::new(800, 600), &Estimate);
+    assert_eq!(page.forms.len(), 2);
+    assert_eq!((page.forms[0].action.as_str(), page.forms[0].method.as_str()), ("search.html", "get"));
+    assert_eq!(page.forms[1].method, "post");
+    // Radio groups are per form, so both stay checked.
+    assert!(page.toggles.iter().all(|t| t.checked));
+
+    let data = page.form_data(0, Some(0));
+    let pairs: Vec<_> = data.iter().map(|(n, v)| (n.as_str(), v.as_str())).collect();
+    assert_eq!(pairs, [("q", "rust & wasm"), ("sort", "new"), ("note", "a\nb"), ("go", "1")]);
+    assert_eq!(radium::net::form_urlencoded(&data), "q=rust+%26+wasm&sort=new&note=a%0D%0Ab&go=1");
+    assert_eq!(page.form_values().len(), 5);
+}
+
+#[test]
+fn form_values_follow_document_order_across_a_row() {
+    let document = radium::parse(
+        "<div style=\"display: flex\"><select name=\"a\"><option>1</option></select><input name=\"b\" value=\"2\">\
+           <input type=\"checkbox\" name=\"c\" checked></div>\
+         <div style=\"display: flex; flex-direction: column-reverse\"><input name=\"d\" value=\"4\"><input name=\"e\" value=\"5\"></div>\
+         <input name=\"f\" value=\"6\" style=\"float: right\"><input type=\"checkbox\" name=\"g\" checked>",
+    );
+    let page = radium::layout(&document, Viewport::new(800, 600), &Estimate);
+    let values = page.form_values();
+    let names: Vec<_> = values.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["a", "b", "c", "d", "e", "f", "g"]);
+}
+
+#[test]
+fn labels_point_at_their_controls() {
+    let document = radium::parse(
+        "<label for=\"later\">Name</label>\
+         <label>Remember me <input type=\"checkbox\" name=\"remember\"></label>\
+         <label for=\"nowhere\">Orphan</label>\
+         <input id=\"later\" name=\"name\">",
+    );
+    let page = radium::layout(&document, Viewport::new(800, 600), &Estimate);
+    let regions: Vec<_> = dump::layout(&page).lines().filter(|l| !l.starts_with("box ")).map(str::to_string).collect();
+    assert_eq!(regions, [
+        "checkbox 16.0 41.6 16.0 16.0 \"remember\"",
+        "label 16.0 16.0 120.0 41.6 -> 0",
+        "input 16.0 83.2 170.0 32.4 \"name\"",
+        "label 16.0 16.0 32.0 22.4 -> 2",
+        "anchor 16.0 83.2 170.0 32.4 \"later\"",
+    ]);
+}
+
+#[test]
+fn disabled_controls_are_greyed_out_and_left_out() {
+    let document = radium::parse(
+        "<input name=\"a\" value=\"on\" disabled>\
+         <input name=\"b\" value=\"kept\" readonly>\
+         <input type=\"checkbox\" name=\"c\" checked disabled>\
+         <button disabled>Go</button>",
+    );
+    let page = radium::layout(&document, Viewport::new(800, 600), &Estimate);
+    assert_eq!(
+        dump::layout(&page).lines().filter(|l| l.starts_with("box ")).collect::<Vec<_>>(),
+        [
+            "box 16.0 16.0 170.0 32.4 rect #c6c6c6",
+            "box 17.0 17.0 168.0 30.4 rect #f4f4f4",
+            "box 21.0 21.0 160.0 22.4 text 16px #8f8f8f \"on\"",
+            "box 16.0 48.4 170.0 32.4 rect #767676",
+            "box 17.0 49.4 168.0 30.4 rect #ffffff",
+            "box 21.0 53.4 160.0 22.4 text 16px #000000 \"kept\"",
+            "box 16.0 84.0 16.0 16.0 rect #c6c6c6",
+            "box 17.0 85.0 14.0 14.0 rect #f4f4f4",
+            "box 19.0 87.0 10.0 10.0 rect #a8a8a8",
+            "box 16.0 103.2 42.0 32.4 rect #c6c6c6",
+            "box 17.0 104.2 40.0 30.4 rect #f4f4f4",
+            "box 29.0 108.2 16.0 22.4 text 16px #8f8f8f \"Go\"",
+        ]
+    );
+    let enabled: Vec<_> = page.regions.iter().map(|r| page.is_enabled(&r.kind)).collect();
+    assert_eq!(enabled, [false, true, false, false]);
+    assert!(page.inputs[1].readonly);
+    // Disabled controls are not submitted.
+    assert_eq!(page.form_values(), [("b".to_string(), "kept".to_string())]);
+}
+
+#[test]
+fn tabindex_and_autofocus_are_recorded() {
+    let document = radium::parse(
+        "<a href=\"#a\" tabindex=\"2\">A</a>\
+         <input name=\"q\" autofocus tabindex=\"-1\">\
+         <button autofocus>Go</button>\
+         <p tabindex=\"1\">Not focusable</p>",
+    );
+    let page = radium::layout(&document, Viewport::new(800, 600), &Estimate);
+    let kinds: Vec<_> = page.regions.iter().map(|r| format!("{:?}", r.kind)).collect();
+    assert_eq!(kinds, ["Link(\"#a\")", "Input(0)", "Button(0)"]);
+    let mut indices: Vec<_> = page.tab_indices.iter().map(|(&r, &i)| (r, i)).collect();
+    indices.sort();
+    assert_eq!(indices, [(0, 2), (1, -1)]);
+    // The first element with `autofocus` wins.
+    assert_eq!(page.autofocus, Some(1));
+}
+
+#[test]
+fn images_wait_as_placeholders_sized_by_their_attributes() {
+    let dir = std::env::temp_dir().join(format!("radium-images-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    image::RgbaImage::from_pixel(4, 2, image::Rgba([255, 0, 0, 255])).save(dir.join("red.png")).unwrap();
+    let mut document = radium::parse("<img src=\"red.png\" width=\"40\" height=\"20\"><img src=\"red.png#again\" loading=\"lazy\">");
+    document.base = radium::net::Url::from_file_path(&dir.join("page.html"));
+
+    // Layout decodes nothing itself: both wait as placeholders.
+    let waiting = layout::layout(&document.nodes, 800.0, &document.base, &Default::default(), &Estimate);
+    assert_eq!(
+        dump::layout(&waiting),
+        "\
+box 16.0 16.0 40.0 20.0 rect #e8e8e8
+box 16.0 44.0 32.0 32.0 rect #e8e8e8
+"
+    );
+    let pending: Vec<_> = waiting.pending_images.iter().map(|p| (p.box_index, p.lazy)).collect();
+    assert_eq!(pending, [(0, false), (1, true)]);
+
+    // Once decoded, the sized image keeps its box and both share the pixels.
+    let loaded = radium::layout(&document, Viewport::new(800, 600), &Estimate);
+    assert!(loaded.pending_images.is_empty());
+    assert_eq!(
+        dump::layout(&loaded),
+        "\
+box 16.0 16.0 40.0 20.0 image 4x2
+box 16.0 44.0 4.0 2.0 image 4x2
+"
+    );
+    let pixels: Vec<_> = loaded.boxes.iter().filter_map(|b| match &b.cmd {
+        PaintCmd::Image { data, .. } => Some(data),
+        _ => None,
+    }).collect();
+    assert!(Arc::ptr_eq(pixels[0], pixels[1]));
+}
+
+#[test]
+fn layout_until_stops_at_the_deadline() {
+    let html: String = (0..2000).map(|i| format!("<p>Paragraph {i}</p>")).collect();
+    let document = radium::parse(&html);
+    let (images, now) = (Default::default(), Instant::now());
+
+    let top = layout::layout_until(&document.nodes, 800.0, f32::INFINITY, &document.base, &images, &Estimate, now);
+    assert!(top.partial);
+    assert!(!top.boxes.is_empty() && top.boxes.len() < 2000, "{}", top.boxes.len());
+
+    let later = now + Duration::from_secs(3600);
+    let whole = layout::layout_until(&document.nodes, 800.0, f32::INFINITY, &document.base, &images, &Estimate, later);
+    assert!(!whole.partial);
+    assert_eq!(dump::layout(&whole), dump::layout(&layout::layout(&document.nodes, 800.0, &document.base, &images, &Estimate)));
+}
+
+#[test]
+fn visible_finds_the_boxes_in_a_band() {
+    let html: String = (0..500).map(|i| format!("<p>Paragraph {i}</p>")).collect();
+    let page = radium::layout(&radium::parse(&html), Viewport::new(800, 600), &Estimate);
+    let overlapping: Vec<usize> = (0..page.boxes.len())
+        .filter(|&i| page.boxes[i].y <= 1100.0 && page.boxes[i].y + page.boxes[i].height >= 1000.0)
+        .collect();
+    assert!(!overlapping.is_empty());
+
+    let visible = page.visible(1000.0, 1100.0);
+    assert!(visible.len() < 10, "{visible:?}");
+    assert!(overlapping.iter().all(|i| visible.contains(i)), "{visible:?} misses some of {overlapping:?}");
+    assert!(page.visible(-100.0, -1.0).is_empty());
+    assert_eq!(page.height(), page.boxes.last().map_or(0.0, |b| b.y + b.height) + 16.0);
+}
+
+#[test]
+fn long_text_wraps_at_word_boundaries() {
+    // The estimate makes each character 8px wide: ten of them fit in 80px.
+    let document = radium::parse("<p>one two three four</p><p>extra\u{AD}ordinary</p><p>abcdefghijklmn</p>");
+    let text = dump::layout(&radium::layout(&document, Viewport::new(112, 600), &Estimate));
+    assert_eq!(
+        text,
+        "\
+box 16.0 16.0 56.0 22.4 text 16px #000000 \"one two\"
+box 16.0 38.4 80.0 22.4 text 16px #000000 \"three four\"
+box 16.0 76.8 48.0 22.4 text 16px #000000 \"extra-\"
+box 16.0 99.2 64.0 22.4 text 16px #000000 \"ordinary\"
+box 16.0 137.6 80.0 22.4 text 16px #000000 \"abcdefghij\"
+box 16.0 160.0 32.0 22.4 text 16px #000000 \"klmn\"
+"
+    );
+}
+
+#[test]
+fn inline_elements_share_lines() {
+    // 168px of line: 21 characters by the estimate.
+    let document = radium::parse("<p>Say <strong>hello there</strong>, <a href=\"x\">friend</a><br>bye</p>");
+    let text = dump::layout(&radium::layout(&document, Viewport::new(200, 600), &Estimate));
+    let boxes: Vec<_> = text.lines().filter(|l| l.starts_with("box ")).collect();
+    assert_eq!(boxes, [
+        "box 16.0 16.0 24.0 22.4 text 16px #000000 \"Say\"",
+        "box 48.0 16.0 88.0 22.4 text 16px #000000 bold \"hello there\"",
+        "box 136.0 16.0 8.0 22.4 text 16px #000000 \",\"",
+        "box 16.0 38.4 48.0 22.4 text 16px #0000ee underline \"friend\"",
+        "box 16.0 60.8 24.0 22.4 text 16px #000000 \"bye\"",
+    ]);
+}
+
+#[test]
+fn runs_are_spaced_only_where_the_source_has_whitespace() {
+    let document = radium::parse("<p>foo<strong>bar</strong>baz H<span>2</span>O x<q>a</q> <em>one</em>\n<em>two</em></p>");
+    let page = radium::layout(&document, Viewport::new(800, 600), &Estimate);
+    let text = dump::layout(&page);
+    let boxes: Vec<_> = text.lines().filter(|l| l.starts_with("box ")).take(3).collect();
+    assert_eq!(boxes, [
+        "box 16.0 16.0 24.0 22.4 text 16px #000000 \"foo\"",
+        "box 40.0 16.0 24.0 22.4 text 16px #000000 bold \"bar\"",
+        "box 64.0 16.0 40.0 22.4 text 16px #000000 \"baz H\"",
+    ]);
+    let chars: String = layout::TextIndex::new(&page).chars.into_iter().collect();
+    assert_eq!(chars, "foobarbaz H2O x\u{201C}a\u{201D} one two");
+}
+
+#[test]
+fn style_attributes_override_the_defaults() {
+    let document = radium::parse(
+        "<p style=\"color: red; font-size: 20px; margin: 8px 10px; padding-left: 6px\">Big \
+           <span style=\"font-weight: bold; font-style: italic\">red</span> \
+           <a href=\"x\" style=\"text-decoration: none\">link</a></p>\
+         <h1 style=\"font-size: 1.5em; color: #123\">Title</h1>",
+    );
+    let text = dump::layout(&radium::layout(&document, Viewport::new(800, 600), &Estimate));
+    assert_eq!(
+        text,
+        "\
+box 32.0 24.0 30.0 28.0 text 20px #ff0000 \"Big\"
+box 72.0 24.0 30.0 28.0 text 20px #ff0000 bold italic \"red\"
+box 112.0 24.0 40.0 28.0 text 20px #0000ee \"link\"
+box 16.0 84.0 60.0 33.6 text 24px #112233 bold \"Title\"
+link 112.0 24.0 40.0 28.0 \"x\"
+"
+    );
+}
+
+#[test]
+fn borders_wrap_the_padding_box() {
+    assert_eq!(
+        snapshot(
+            "<div style=\"border: 1px solid; padding: 4px; margin: 0 10px\">Box</div>\
+             <p style=\"border-bottom: 2px dashed red; border-top-width: 5px\">Under</p>\
+             <div style=\"border-width: 4px; border-color: blue\">Unstyled</div>"
+        ),
+        "\
+box 31.0 21.0 24.0 22.4 text 16px #000000 \"Box\"
+box 26.0 16.0 748.0 1.0 rect #000000
+box 773.0 17.0 1.0 30.4 rect #000000
+box 26.0 47.4 748.0 1.0 rect #000000
+box 26.0 17.0 1.0 30.4 rect #000000
+box 16.0 48.4 40.0 22.4 text 16px #000000 \"Under\"
+box 16.0 70.8 768.0 2.0 rect #ff0000
+box 16.0 88.8 64.0 22.4 text 16px #000000 \"Unstyled\"
+"
+    );
+}
+
+#[test]
+fn tables_size_columns_to_their_content() {
+    let text = snapshot(
+        "<table><tr><th>Item</th><th colspan=\"2\">Cost</th></tr>\
+         <tbody><tr><td rowspan=\"2\">Tea with milk</td><td>1</td><td>2</td></tr><tr><td>3</td><td>4</td></tr></tbody></table>",
+    );
+    let texts: Vec<&str> = text.lines().filter(|line| line.contains(" text ")).collect();
+    assert_eq!(
+        texts,
+        [
+            "box 21.0 19.0 32.0 22.4 text 16px #000000 bold \"Item\"",
+            "box 135.0 19.0 32.0 22.4 text 16px #000000 bold \"Cost\"",
+            "box 21.0 47.4 104.0 22.4 text 16px #000000 \"Tea with milk\"",
+            "box 135.0 47.4 8.0 22.4 text 16px #000000 \"1\"",
+            "box 156.0 47.4 8.0 22.4 text 16px #000000 \"2\"",
+            "box 135.0 75.8 8.0 22.4 text 16px #000000 \"3\"",
+            "box 156.0 75.8 8.0 22.4 text 16px #000000 \"4\"",
+        ]
+    );
+    // The cell spanning two rows has its borders around both.
+    assert!(text.contains("box 16.0 44.4 114.0 1.0 rect #808080\nbox 129.0 45.4 1.0 54.8 rect #808080\n"));
+
+    // Too wide for the page, the long cell wraps and the short one keeps
+    // its word whole.
+    let long = "word ".repeat(200);
+    let text = snapshot(&format!("<table><tr><td>Name</td><td>{long}</td></tr></table>"));
+    let lines: Vec<&str> = text.lines().filter(|line| line.contains(" text ")).collect();
+    assert!(lines[0].starts_with("box 21.0 19.0 32.0 22.4 text"), "{}", lines[0]);
+    assert!(lines[1].starts_with("box 63.0 19.0 "), "{}", lines[1]);
+    assert!(lines.len() > 2);
+}
+
+#[test]
+fn flex_containers_lay_out_their_items() {
+    assert_eq!(
+        snapshot(
+            "<div style=\"display: flex; justify-content: space-between; align-items: center\">\
+               <b>Logo</b> <p style=\"margin: 0\">Two<br>lines</p></div>\
+             <div style=\"display: flex\"><div style=\"flex: 1; border: 1px solid\">A</div><div style=\"flex: 3\">B<br>C</div></div>\
+             <div style=\"display: flex; flex-direction: column; align-items: flex-end\"><span>One</span><span>Two</span></div>"
+        ),
+        "\
+box 16.0 27.2 32.0 22.4 text 16px #000000 \"Logo\"
+box 712.0 16.0 24.0 22.4 text 16px #000000 \"Two\"
+box 712.0 38.4 40.0 22.4 text 16px #000000 \"lines\"
+box 17.0 61.8 8.0 22.4 text 16px #000000 \"A\"
+box 16.0 60.8 193.5 1.0 rect #000000
+box 208.5 61.8 1.0 42.8 rect #000000
+box 16.0 104.6 193.5 1.0 rect #000000
+box 16.0 61.8 1.0 42.8 rect #000000
+box 209.5 60.8 8.0 22.4 text 16px #000000 \"B\"
+box 209.5 83.2 8.0 22.4 text 16px #000000 \"C\"
+box 760.0 105.6 24.0 22.4 text 16px #000000 \"One\"
+box 760.0 128.0 24.0 22.4 text 16px #000000 \"Two\"
+"
+    );
+}
+
+#[test]
+fn floats_shorten_the_lines_beside_them() {
+    assert_eq!(
+        snapshot(
+            "<div style=\"padding-right: 500px\"><img src=\"a.png\" width=\"100\" height=\"30\" align=\"left\">\
+               Words beside the image wrap and wrap again below it</div>\
+             <div style=\"float: right\">Aside<br>in<br>three</div><p>Short</p>\
+             <p style=\"clear: both\">Under</p>"
+        ),
+        "\
+box 16.0 16.0 100.0 30.0 rect #e8e8e8
+box 116.0 16.0 128.0 22.4 text 16px #000000 \"Words beside the\"
+box 116.0 38.4 152.0 22.4 text 16px #000000 \"image wrap and wrap\"
+box 16.0 60.8 112.0 22.4 text 16px #000000 \"again below it\"
+box 672.0 83.2 40.0 22.4 text 16px #000000 \"Aside\"
+box 672.0 105.6 16.0 22.4 text 16px #000000 \"in\"
+box 672.0 128.0 40.0 22.4 text 16px #000000 \"three\"
+box 16.0 83.2 40.0 22.4 text 16px #000000 \"Short\"
+box 16.0 150.4 40.0 22.4 text 16px #000000 \"Under\"
+"
+    );
+}
+
+#[test]
+fn positioned_elements_move_out_of_the_flow() {
+    assert_eq!(
+        snapshot(
+            "<div style=\"position: relative; padding: 10px\">Card<span style=\"position: absolute; top: 0; right: 0\">New</span> text</div>\
+             <p style=\"position: relative; left: 20px; top: -4px\">Nudged</p>\
+             <div style=\"position: relative\">One<br>Two<b style=\"position: absolute; bottom: 0; left: 50%\">Foot</b></div>"
+        ),
+        "\
+box 26.0 26.0 32.0 22.4 text 16px #000000 \"Card\"
+box 760.0 16.0 24.0 22.4 text 16px #000000 \"New\"
+box 66.0 26.0 32.0 22.4 text 16px #000000 \"text\"
+box 36.0 54.4 48.0 22.4 text 16px #000000 \"Nudged\"
+box 16.0 96.8 24.0 22.4 text 16px #000000 \"One\"
+box 16.0 119.2 24.0 22.4 text 16px #000000 \"Two\"
+box 400.0 119.2 32.0 22.4 text 16px #000000 \"Foot\"
+"
+    );
+}
+
+#[test]
+fn text_align_moves_lines_between_their_edges() {
+    assert_eq!(
+        snapshot(
+            "<p style=\"text-align: center\">Centred <a href=\"x\">link</a></p><div align=\"right\">Right</div>\
+             <p style=\"text-align: justify; padding-right: 600px\">Justified text wraps over several lines here ok</p>"
+        ),
+        "\
+box 352.0 16.0 56.0 22.4 text 16px #000000 \"Centred\"
+box 416.0 16.0 32.0 22.4 text 16px #0000ee underline \"link\"
+box 744.0 54.4 40.0 22.4 text 16px #000000 \"Right\"
+box 16.0 76.8 72.0 22.4 text 16px #000000 \"Justified\"
+box 100.0 76.8 32.0 22.4 text 16px #000000 \"text\"
+box 144.0 76.8 40.0 22.4 text 16px #000000 \"wraps\"
+box 16.0 99.2 32.0 22.4 text 16px #000000 \"over\"
+box 68.0 99.2 56.0 22.4 text 16px #000000 \"several\"
+box 144.0 99.2 40.0 22.4 text 16px #000000 \"lines\"
+box 16.0 121.6 32.0 22.4 text 16px #000000 \"here\"
+box 56.0 121.6 16.0 22.4 text 16px #000000 \"ok\"
+link 416.0 16.0 32.0 22.4 \"x\"
+"
+    );
+}
+
+#[test]
+fn pre_keeps_lines_and_spaces_in_a_monospace_face() {
+    assert_eq!(
+        snapshot("<pre>\nfn main() {\n    go();\n}</pre><p>Run <code>go</code></p>"),
+        "\
+box 16.0 16.0 768.0 83.2 rect #f4f4f4
+box 24.0 24.0 88.0 22.4 text 16px #000000 monospace \"fn main() {\"
+box 24.0 46.4 72.0 22.4 text 16px #000000 monospace \"    go();\"
+box 24.0 68.8 8.0 22.4 text 16px #000000 monospace \"}\"
+box 16.0 115.2 24.0 22.4 text 16px #000000 \"Run\"
+box 48.0 115.2 16.0 22.4 text 16px #000000 monospace \"go\"
+"
+    );
+}
+
+#[test]
+fn tabs_advance_to_the_next_tab_stop() {
+    assert_eq!(
+        snapshot(
+            "<pre>\tgo();\nab\tc\t\td</pre>\
+             <pre style=\"tab-size: 4\">x\ty<strong>\tz</strong></pre>\
+             <pre style=\"tab-size: 16px\">\tw</pre>"
+        ),
+        "\
+box 16.0 16.0 768.0 60.8 rect #f4f4f4
+box 24.0 24.0 104.0 22.4 text 16px #000000 monospace \"        go();\"
+box 24.0 46.4 200.0 22.4 text 16px #000000 monospace \"ab      c               d\"
+box 16.0 92.8 768.0 38.4 rect #f4f4f4
+box 24.0 100.8 40.0 22.4 text 16px #000000 monospace \"x   y\"
+box 64.0 100.8 32.0 22.4 text 16px #000000 bold monospace \"   z\"
+box 16.0 147.2 768.0 38.4 rect #f4f4f4
+box 24.0 155.2 24.0 22.4 text 16px #000000 monospace \"  w\"
+"
+    );
+}
+
+#[test]
+fn nowrap_text_collapses_spaces_but_never_wraps() {
+    assert_eq!(
+        snapshot(
+            "<p style=\"white-space: nowrap; padding-right: 700px\">one   two three</p>\
+             <p style=\"padding-right: 700px\">Text <span style=\"white-space: nowrap\">kept together</span> then</p>\
+             <p style=\"white-space: nowrap; padding-right: 700px\">All <em>in one</em> line</p>"
+        ),
+        "\
+box 16.0 16.0 104.0 22.4 text 16px #000000 \"one two three\"
+box 16.0 54.4 32.0 22.4 text 16px #000000 \"Text\"
+box 16.0 76.8 104.0 22.4 text 16px #000000 \"kept together\"
+box 16.0 99.2 32.0 22.4 text 16px #000000 \"then\"
+box 16.0 137.6 24.0 22.4 text 16px #000000 \"All\"
+box 48.0 137.6 48.0 22.4 text 16px #000000 italic \"in one\"
+box 104.0 137.6 32.0 22.4 text 16px #000000 \"line\"
+"
+    );
+}
+
+#[test]
+fn registered_patterns_hyphenate_words_that_do_not_fit() {
+    // Liang's patterns for "hyphenation": hy-phen-ation.
+    layout::set_hyphenation("en", Some("% from the TeXbook\nhy3ph he2n hena4 hen5at 1na n2at 1tio 2io o2n"));
+    let snapshot = snapshot(
+        "<p lang=\"en-US\" style=\"padding-right: 680px\">A hyphenation test</p>\
+         <p lang=\"fr\" style=\"padding-right: 680px\">A hyphenation test</p>"
+    );
+    layout::set_hyphenation("en", None);
+    assert_eq!(
+        snapshot,
+        "\
+box 16.0 16.0 72.0 22.4 text 16px #000000 \"A hyphen-\"
+box 16.0 38.4 80.0 22.4 text 16px #000000 \"ation test\"
+box 16.0 76.8 8.0 22.4 text 16px #000000 \"A\"
+box 16.0 99.2 88.0 22.4 text 16px #000000 \"hyphenation\"
+box 16.0 121.6 32.0 22.4 text 16px #000000 \"test\"
+"
+    );
+}
+
+/// Estimates text, but with a monospace face that reaches higher above its
+/// baseline, as real ones often do.
+struct TallMonospace;
+
+impl TextMeasurer for TallMonospace {
+    fn advance(&self, ch: char, bold: bool, italic: bool, font_size: f32) -> f32 {
+        Estimate.advance(ch, bold, italic, font_size)
+    }
+
+    fn ascent(&self, _bold: bool, _italic: bool, monospace: bool, font_size: f32) -> f32 {
+        font_size * if monospace { 1.0 } else { 0.8 }
+    }
+}
+
+#[test]
+fn runs_of_different_sizes_and_faces_share_a_baseline() {
+    let document = radium::parse("<p>Big <small>small print</small> <code>code</code></p><p>next</p>");
+    assert_eq!(
+        dump::layout(&radium::layout(&document, Viewport::new(800, 600), &TallMonospace)),
+        "\
+box 16.0 19.2 24.0 22.4 text 16px #000000 \"Big\"
+box 46.7 21.3 73.3 18.7 text 13.333333px #000000 \"small print\"
+box 128.0 16.0 32.0 22.4 text 16px #000000 monospace \"code\"
+box 16.0 57.6 32.0 22.4 text 16px #000000 \"next\"
+"
+    );
+}
+
+#[test]
+fn hidden_overflow_ends_long_lines_in_an_ellipsis() {
+    assert_eq!(
+        snapshot(
+            "<p style=\"white-space: nowrap; overflow: hidden; text-overflow: ellipsis; padding-right: 680px\">one two three four</p>\
+             <div style=\"white-space: nowrap; overflow: hidden; text-overflow: ellipsis; padding-right: 680px\">Go <b>far away</b> now</div>\
+             <p style=\"white-space: nowrap; overflow: hidden; padding-right: 680px\">clipped without one</p>"
+        ),
+        "\
+box 16.0 16.0 88.0 22.4 text 16px #000000 \"one two th\u{2026}\"
+box 16.0 54.4 16.0 22.4 text 16px #000000 \"Go\"
+box 40.0 54.4 64.0 22.4 text 16px #000000 \"far awa\u{2026}\"
+box 112.0 54.4 0.0 22.4 text 16px #000000 \"\"
+box 16.0 76.8 152.0 22.4 text 16px #000000 \"clipped without one\"
+"
+    );
+}
+
+#[test]
+fn blockquotes_are_indented_behind_a_rule() {
+    assert_eq!(
+        snapshot("<p>Said:</p><blockquote><p>First</p><p>Second</p></blockquote><p>After</p>"),
+        "\
+box 16.0 16.0 40.0 22.4 text 16px #000000 \"Said:\"
+box 55.0 54.4 40.0 22.4 text 16px #555555 \"First\"
+box 55.0 92.8 48.0 22.4 text 16px #555555 \"Second\"
+box 40.0 54.4 3.0 76.8 rect #cccccc
+box 16.0 147.2 40.0 22.4 text 16px #000000 \"After\"
+"
+    );
+}
+
+#[test]
+fn definition_lists_indent_descriptions_under_bold_terms() {
+    assert_eq!(
+        snapshot("<dl><dt>Term</dt><dd>Its meaning</dd><dt>Other</dt><dd>More</dd></dl><p>After</p>"),
+        "\
+box 16.0 16.0 32.0 22.4 text 16px #000000 bold \"Term\"
+box 56.0 38.4 88.0 22.4 text 16px #000000 \"Its meaning\"
+box 16.0 68.8 40.0 22.4 text 16px #000000 bold \"Other\"
+box 56.0 91.2 32.0 22.4 text 16px #000000 \"More\"
+box 16.0 137.6 40.0 22.4 text 16px #000000 \"After\"
+"
+    );
+}
+
+#[test]
+fn style_elements_restyle_matching_elements() {
+    let document = radium::parse(
+        "<head><style>p { color: #333 } .note { font-style: italic; margin-bottom: 4px } \
+           #intro, h2 { color: green } a { text-decoration: none } p:first-child { color: red }</style></head>\
+         <p id=\"intro\">Hello</p><p class=\"note big\">See <a href=\"x\">this</a></p>\
+         <p class=\"note\" style=\"font-style: normal\">Plain</p>",
+    );
+    let text = dump::layout(&radium::layout(&document, Viewport::new(800, 600), &Estimate));
+    assert_eq!(
+        text,
+        "\
+box 16.0 16.0 40.0 22.4 text 16px #008000 \"Hello\"
+box 16.0 54.4 24.0 22.4 text 16px #333333 italic \"See\"
+box 48.0 54.4 32.0 22.4 text 16px #0000ee italic \"this\"
+box 16.0 80.8 40.0 22.4 text 16px #333333 \"Plain\"
+link 48.0 54.4 32.0 22.4 \"x\"
+anchor 16.0 16.0 40.0 22.4 \"intro\"
+"
+    );
+}
+
+#[test]
+fn linked_stylesheets_are_read_from_disk() {
+    let dir = std::env::temp_dir().join(format!("radium-stylesheets-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("css")).unwrap();
+    std::fs::write(dir.join("css/site.css"), "p { color: #00f; font-size: 20px } .quiet { color: gray }").unwrap();
+    let mut document = radium::parse(
+        "<link rel=\"Stylesheet\" href=\"css/site.css\"><link rel=\"stylesheet\" href=\"missing.css\">\
+         <link rel=\"alternate stylesheet\" href=\"css/site.css\"><style>p { font-size: 10px }</style>\
+         <p>Blue</p><p class=\"quiet\">Gray</p>",
+    );
+    document.base = radium::net::Url::from_file_path(&dir.join("page.html"));
+
+    // The missing sheet is skipped; the `<style>` after the link wins.
+    let page = radium::layout(&document, Viewport::new(800, 600), &Estimate);
+    assert_eq!(
+        dump::layout(&page),
+        "\
+box 16.0 16.0 20.0 14.0 text 10px #0000ff \"Blue\"
+box 16.0 46.0 20.0 14.0 text 10px #808080 \"Gray\"
+"
+    );
+    assert_eq!(page.assets, [dir.join("css/site.css"), dir.join("missing.css")]);
+}
@@ -0,0 +1,65 @@
+//! Loading remote documents from a local test server.
+
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::time::Duration;
+
+use radium::document::Source;
+use radium::parser::dom::Node;
+use radium::stats::Stats;
+
+#[test]
+fn slow_downloads_are_previewed_as_they_arrive() {
+    let cache = std::env::temp_dir().join(format!("radium-loading-{}", std::process::id()));
+    std::env::set_var("XDG_CACHE_HOME", &cache);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0; 1024];
+        let _ = stream.read(&mut request);
+        let body = ["<p>First</p><p>Sec", "ond</p>"];
+        let head = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\r\n",
+            body.concat().len(),
+        );
+        stream.write_all(head.as_bytes()).unwrap();
+        stream.write_all(body[0].as_bytes()).unwrap();
+        std::thread::sleep(Duration::from_millis(1500));
+        stream.write_all(body[1].as_bytes()).unwrap();
+    });
+
+    let (mut source, _) = Source::resolve(&format!("http://127.0.0.1:{port}/doc.html")).unwrap();
+    let mut previews = Vec::new();
+    let nodes = source.load_or_error_progressively(&mut Stats::default(), &mut |nodes| previews.push(texts(&nodes)));
+    // Cut before the half-received paragraph.
+    assert_eq!(previews.first().map(Vec::as_slice), Some(&["First".to_string()][..]));
+    assert_eq!(texts(&nodes), ["First", "Second"]);
+}
+
+#[test]
+fn declared_charsets_decode_chunked_bodies() {
+    let cache = std::env::temp_dir().join(format!("radium-loading-{}", std::process::id()));
+    std::env::set_var("XDG_CACHE_HOME", &cache);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0; 1024];
+        let _ = stream.read(&mut request);
+        let head = "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=\"ISO-8859-1\"\r\nTransfer-Encoding: chunked\r\n\r\n";
+        stream.write_all(head.as_bytes()).unwrap();
+        // windows-1252, as browsers read Latin-1: an e acute and curly quotes.
+        stream.write_all(b"8\r\n<p>caf\xE9 \r\nC\r\n\x93quoted\x94</p>\r\n0\r\n\r\n").unwrap();
+    });
+
+    let (mut source, _) = Source::resolve(&format!("http://127.0.0.1:{port}/latin1.html")).unwrap();
+    assert_eq!(texts(&source.load().unwrap()), ["caf\u{e9} \u{201C}quoted\u{201D}"]);
+}
+
+fn texts(nodes: &[Node]) -> Vec<String> {
+    nodes.iter().flat_map(|node| match node {
+        Node::Text(text) => vec![text.clone()],
+        Node::Element { children, .. } => texts(children),
+    }).collect()
+}
@@ -0,0 +1,94 @@
+//! Resolving URLs and reading HTTP responses, without a network.
+
+use radium::net::{Response, Url};
+
+#[test]
+fn references_resolve_as_in_rfc_3986() {
+    // The examples of RFC 3986 §5.4, normal and abnormal. Hierarchical paths
+    // are never empty here, so `//g` gets a `/`; and a one-letter scheme is
+    // taken for a Windows drive, so `g:h` stays a relative path.
+    let base = Url::parse("http://a/b/c/d;p?q").unwrap();
+    let examples = [
+        ("g:h", "http://a/b/c/g:h"),
+        ("g", "http://a/b/c/g"),
+        ("./g", "http://a/b/c/g"),
+        ("g/", "http://a/b/c/g/"),
+        ("/g", "http://a/g"),
+        ("//g", "http://g/"),
+        ("?y", "http://a/b/c/d;p?y"),
+        ("g?y", "http://a/b/c/g?y"),
+        ("#s", "http://a/b/c/d;p?q#s"),
+        ("g#s", "http://a/b/c/g#s"),
+        ("g?y#s", "http://a/b/c/g?y#s"),
+        (";x", "http://a/b/c/;x"),
+        ("g;x", "http://a/b/c/g;x"),
+        ("g;x?y#s", "http://a/b/c/g;x?y#s"),
+        ("", "http://a/b/c/d;p?q"),
+        (".", "http://a/b/c/"),
+        ("./", "http://a/b/c/"),
+        ("..", "http://a/b/"),
+        ("../", "http://a/b/"),
+        ("../g", "http://a/b/g"),
+        ("../..", "http://a/"),
+        ("../../", "http://a/"),
+        ("../../g", "http://a/g"),
+        ("../../../g", "http://a/g"),
+        ("../../../../g", "http://a/g"),
+        ("/./g", "http://a/g"),
+        ("/../g", "http://a/g"),
+        ("g.", "http://a/b/c/g."),
+        (".g", "http://a/b/c/.g"),
+        ("g..", "http://a/b/c/g.."),
+        ("..g", "http://a/b/c/..g"),
+        ("./../g", "http://a/b/g"),
+        ("./g/.", "http://a/b/c/g/"),
+        ("g/./h", "http://a/b/c/g/h"),
+        ("g/../h", "http://a/b/c/h"),
+        ("g;x=1/./y", "http://a/b/c/g;x=1/y"),
+        ("g;x=1/../y", "http://a/b/c/y"),
+        ("g?y/./x", "http://a/b/c/g?y/./x"),
+        ("g?y/../x", "http://a/b/c/g?y/../x"),
+        ("g#s/./x", "http://a/b/c/g#s/./x"),
+        ("g#s/../x", "http://a/b/c/g#s/../x"),
+        ("http:g", "http://a/b/c/g"),
+    ];
+    for (reference, expected) in examples {
+        assert_eq!(base.join(reference).map(|url| url.to_string()), Ok(expected.to_string()), "joining {reference:?}");
+    }
+}
+
+#[test]
+fn opaque_urls_resolve_only_absolute_references() {
+    let mail = Url::parse("mailto:someone@example.com").unwrap();
+    assert_eq!(mail.join("https://example.com/x").unwrap().to_string(), "https://example.com/x");
+    assert!(mail.join("x").is_err());
+}
+
+#[test]
+fn responses_are_read_past_interim_ones() {
+    let raw = b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 404 Not Found\r\nContent-Type: text/plain\r\nX-Count: 2\r\n\r\nmissing";
+    let response = Response::partial(raw).unwrap();
+    assert_eq!((response.status, response.reason.as_str()), (404, "Not Found"));
+    assert_eq!(response.header("content-type"), Some("text/plain"));
+    assert_eq!(response.header("x-count"), Some("2"));
+    assert_eq!(response.body, b"missing");
+    assert!(!response.is_success());
+}
+
+#[test]
+fn responses_have_no_body_until_their_headers_are_in() {
+    assert!(Response::partial(b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\n").is_none());
+    assert!(Response::partial(b"garbage\r\n\r\n").is_none());
+}
+
+#[test]
+fn chunked_bodies_are_joined_as_far_as_they_arrived() {
+    let head = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n";
+    let chunks = "5\r\nHello\r\n7;name=value\r\n, world\r\n0\r\n\r\n";
+    let body = |raw: &str| Response::partial(raw.as_bytes()).map(|response| String::from_utf8(response.body).unwrap());
+    assert_eq!(body(&format!("{head}{chunks}")).as_deref(), Some("Hello, world"));
+    // Cut inside the second chunk, then inside its size line.
+    assert_eq!(body(&format!("{head}{}", &chunks[..26])).as_deref(), Some("Hello, "));
+    assert_eq!(body(&format!("{head}{}", &chunks[..15])).as_deref(), Some("Hello"));
+    assert!(body(&format!("{head}zz\r\nHello\r\n")).is_none());
+}
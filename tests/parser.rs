@@ -0,0 +1,85 @@
+//! Tokenizer and tree-builder edge cases, mostly malformed input of the kind
+//! the fuzz targets (`fuzz/`) produce.
+
+use radium::dump;
+use radium::parser::dom::{self, Node, MAX_DEPTH};
+use radium::parser::{tokenize, tokenize_bytes};
+
+fn tokens(html: &str) -> String {
+    dump::tokens(&tokenize(html))
+}
+
+#[test]
+fn lone_angle_bracket_is_text() {
+    assert_eq!(tokens("1 < 2 and <b>x</b>"), "text \"1 < 2 and \"\nopen b\ntext \"x\"\nclose b\n");
+    assert_eq!(tokens("a <"), "text \"a <\"\n");
+    assert_eq!(tokens("<"), "text \"<\"\n");
+    assert_eq!(tokens("<<p>"), "text \"<\"\nopen p\n");
+}
+
+#[test]
+fn markup_cut_off_at_end_of_input() {
+    assert_eq!(tokens("<p"), "open p\n");
+    assert_eq!(tokens("</"), "");
+    assert_eq!(tokens("<!"), "comment \"\"\n");
+    assert_eq!(tokens("<!-- a > b"), "comment \" a > b\"\n");
+    assert_eq!(tokens("<p class"), "open p class=\"\"\n");
+    assert_eq!(tokens("<p class="), "open p class=\"\"\n");
+    assert_eq!(tokens("<a href=\"x"), "open a href=\"x\"\n");
+    assert_eq!(tokens("<br /"), "open br\n");
+}
+
+#[test]
+fn textarea_content_is_raw() {
+    assert_eq!(tokens("<textarea>\n a  <b>\n</TEXTAREA>x"), "open textarea\ntext \"\\n a  <b>\\n\"\nclose textarea\ntext \"x\"\n");
+    assert_eq!(tokens("<textarea>cut"), "open textarea\ntext \"cut\"\n");
+}
+
+#[test]
+fn pre_keeps_its_whitespace() {
+    assert_eq!(
+        tokens("<pre>\n  a\r\n\t<b>b  c</b>\n</pre> x  y"),
+        "open pre\ntext \"  a\\n\\t\"\nopen b\ntext \"b  c\"\nclose b\ntext \"\\n\"\nclose pre\ntext \" x y\"\n"
+    );
+}
+
+#[test]
+fn no_break_spaces_are_not_collapsed() {
+    assert_eq!(tokens("<p> \u{A0}a \u{202F}\u{A0}\n</p>"), "open p\ntext \" \\u{a0}a \\u{202f}\\u{a0} \"\nclose p\n");
+}
+
+#[test]
+fn invalid_utf8_is_replaced() {
+    assert_eq!(dump::tokens(&tokenize_bytes(b"<p>a\xffb</p>")), "open p\ntext \"a\u{fffd}b\"\nclose p\n");
+}
+
+#[test]
+fn nesting_is_capped() {
+    let html = "<div>".repeat(100_000) + "deep";
+    let nodes = dom::build_tree_lossy(html.as_bytes());
+
+    let mut depth = 0;
+    let mut level = &nodes;
+    while let [Node::Element { children, .. }] = level.as_slice() {
+        depth += 1;
+        level = children;
+    }
+    assert_eq!(depth, MAX_DEPTH);
+    assert!(matches!(level.as_slice(), [Node::Text(text)] if text == "deep"));
+}
+
+#[test]
+fn character_references_are_decoded() {
+    assert_eq!(tokens("a &amp; b &lt;p&gt; &#169; &#x2014;&#X41;&copy;&notit;"), "text \"a & b <p> \u{a9} \u{2014}A\u{a9}\u{ac}it;\"\n");
+    assert_eq!(tokens("&#150; &#0; &#xD800; &#99999999999; &#; &bogus; & x"), "text \"\u{2013} \u{fffd} \u{fffd} \u{fffd} &#; &bogus; & x\"\n");
+    assert_eq!(tokens("<a title=\"&quot;x&quot;\" href=\"?a=1&copy=2&amp;b=3&lang\">"), "open a href=\"?a=1&copy=2&b=3&lang\" title=\"\\\"x\\\"\"\n");
+    assert_eq!(tokens("<textarea>&lt;b&gt;</textarea><script>a &amp;&amp; b</script>"), "open textarea\ntext \"<b>\"\nclose textarea\nopen script\ntext \"a &amp;&amp; b\"\nclose script\n");
+}
+
+#[test]
+fn comments_end_at_their_closing_dashes() {
+    assert_eq!(tokens("a<!-- x > y -- z -->b"), "text \"a\"\ncomment \" x > y -- z \"\ntext \"b\"\n");
+    assert_eq!(tokens("<!-->a<!--->b<!--c--!>d"), "comment \"\"\ntext \"a\"\ncomment \"\"\ntext \"b\"\ncomment \"c\"\ntext \"d\"\n");
+    assert_eq!(tokens("<!DOCTYPE html><?xml version=\"1.0\"?><![CDATA[x]]>"), "doctype\ncomment \"?xml version=\\\"1.0\\\"?\"\ncomment \"[CDATA[x]]\"\n");
+    assert_eq!(dump::dom(&dom::build_tree(tokenize("<p>a<!-- <b>x</b> -->b</p>"))), "p\n  \"a\"\n  \"b\"\n");
+}
@@ -0,0 +1,131 @@
+//! Golden-image reference tests.
+//!
+//! Every `tests/reftests/*.html` is rendered headlessly at 400×300 and
+//! compared with `tests/reftests/expected/<name>.png`. Run
+//! `cargo test --test reftests -- --bless` to (re)generate the references
+//! after an intended rendering change, and look at them before committing.
+//!
+//! References are rendered with DejaVu Sans; without it the tests are
+//! skipped. On a mismatch the actual image and a diff (differing pixels in
+//! red) are written to `target/reftests/`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use image::{Rgba, RgbaImage};
+use radium::net::Url;
+use radium::{FontConfig, RenderOptions};
+
+const SIZE: (u32, u32) = (400, 300);
+const FONT_FAMILY: &str = "DejaVu Sans";
+
+/// A channel may differ by this much before the pixel counts as different
+/// (absorbs rounding differences between rasterizer versions).
+const CHANNEL_TOLERANCE: u8 = 8;
+/// How many different pixels a test tolerates.
+const MAX_DIFFERENT_PIXELS: usize = 16;
+
+fn main() -> ExitCode {
+    let bless = std::env::args().any(|arg| arg == "--bless");
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let corpus = root.join("tests/reftests");
+    let out_dir = root.join("target/reftests");
+
+    let fonts = FontConfig { family: Some(FONT_FAMILY.into()), dirs: Vec::new() };
+    if !fonts.has_family() {
+        println!("reftests skipped: {FONT_FAMILY} is not installed");
+        return ExitCode::SUCCESS;
+    }
+    let options = RenderOptions { fonts, ..Default::default() };
+
+    let mut cases: Vec<PathBuf> = fs::read_dir(&corpus)
+        .expect("reading tests/reftests")
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "html"))
+        .collect();
+    cases.sort();
+
+    let mut failed = Vec::new();
+    for case in &cases {
+        let name = case.file_stem().unwrap().to_string_lossy().into_owned();
+        let expected_path = corpus.join("expected").join(format!("{name}.png"));
+        let actual = match render(case, &options) {
+            Ok(image) => image,
+            Err(e) => {
+                println!("reftest {name} ... FAILED ({e})");
+                failed.push(name);
+                continue;
+            }
+        };
+
+        if bless {
+            fs::create_dir_all(expected_path.parent().unwrap()).unwrap();
+            actual.save(&expected_path).unwrap();
+            println!("reftest {name} ... blessed");
+            continue;
+        }
+
+        let verdict = match image::open(&expected_path) {
+            Ok(expected) => compare(&expected.to_rgba8(), &actual),
+            Err(e) => Err((format!("no reference: {e}; run with --bless"), None)),
+        };
+        match verdict {
+            Ok(()) => println!("reftest {name} ... ok"),
+            Err((reason, diff)) => {
+                println!("reftest {name} ... FAILED ({reason})");
+                fs::create_dir_all(&out_dir).unwrap();
+                actual.save(out_dir.join(format!("{name}-actual.png"))).unwrap();
+                if let Some(diff) = diff {
+                    diff.save(out_dir.join(format!("{name}-diff.png"))).unwrap();
+                }
+                failed.push(name);
+            }
+        }
+    }
+
+    if bless {
+        println!("\nreftests: {} references written", cases.len() - failed.len());
+    } else {
+        println!("\nreftests: {} passed, {} failed", cases.len() - failed.len(), failed.len());
+    }
+    if failed.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        println!("failures written to {}", out_dir.display());
+        ExitCode::FAILURE
+    }
+}
+
+/// Render `path` with relative URLs resolving against its directory.
+fn render(path: &Path, options: &RenderOptions) -> Result<RgbaImage, String> {
+    let html = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut document = radium::parse(&html);
+    document.base = Url::from_file_path(path);
+    radium::screenshot(&document, SIZE.0, SIZE.1, options).map_err(|e| e.to_string())
+}
+
+/// `Ok` if the images match within tolerance; otherwise why not, plus a diff
+/// image when the sizes agree.
+fn compare(expected: &RgbaImage, actual: &RgbaImage) -> Result<(), (String, Option<RgbaImage>)> {
+    if expected.dimensions() != actual.dimensions() {
+        let reason = format!("size {:?}, expected {:?}", actual.dimensions(), expected.dimensions());
+        return Err((reason, None));
+    }
+
+    let mut different = 0;
+    let diff = RgbaImage::from_fn(actual.width(), actual.height(), |x, y| {
+        let (e, a) = (expected.get_pixel(x, y), actual.get_pixel(x, y));
+        if e.0.iter().zip(a.0).any(|(&e, a)| e.abs_diff(a) > CHANNEL_TOLERANCE) {
+            different += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            // Faded copy of the expected image for context.
+            Rgba(e.0.map(|c| 191 + c / 4))
+        }
+    });
+    if different > MAX_DIFFERENT_PIXELS {
+        return Err((format!("{different} pixels differ"), Some(diff)));
+    }
+    Ok(())
+}
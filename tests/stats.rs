@@ -0,0 +1,26 @@
+//! `--stats` figures for a load.
+
+use radium::document::Source;
+use radium::stats::{self, Stats};
+
+#[test]
+fn loads_record_size_and_stages() {
+    let dir = std::env::temp_dir().join(format!("radium-stats-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("page.html");
+    let html = "<title>T</title><p>one <b>two</b></p>";
+    std::fs::write(&path, html).unwrap();
+
+    let (mut source, _) = Source::resolve(path.to_str().unwrap()).unwrap();
+    let mut stats = Stats::default();
+    let nodes = source.load_with_stats(&mut stats).unwrap();
+    assert_eq!(stats.bytes, html.len());
+    // title, "T", p, "one", b, "two"
+    assert_eq!(stats::count_nodes(&nodes), 6);
+
+    stats.nodes = 6;
+    let json = stats.to_json(&source.url);
+    let prefix = format!(r#"{{"url": "file://{}", "bytes": {}, "nodes": 6, "boxes": 0, "read_ms": "#, path.display(), html.len());
+    assert!(json.starts_with(&prefix), "{json}");
+    assert!(json.ends_with(r#", "image_bytes": 0}"#), "{json}");
+}
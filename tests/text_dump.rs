@@ -0,0 +1,128 @@
+//! Plain-text extraction (`--dump-text`, [`radium::dump::text`]).
+
+use radium::dump;
+use radium::net::Url;
+
+fn text(html: &str) -> String {
+    let document = radium::parse(html);
+    dump::text(&document.nodes, &Url::parse("https://example.com/docs/index.html").unwrap())
+}
+
+#[test]
+fn headings_and_paragraphs() {
+    assert_eq!(
+        text("<title>Skipped</title><h1>Title</h1><p>Some <em>emphasis</em>.</p><h2>Section</h2><p>One<br>Two</p><h3>Minor</h3>"),
+        "\
+Title
+=====
+
+Some emphasis.
+
+Section
+-------
+
+One
+Two
+
+Minor
+"
+    );
+}
+
+#[test]
+fn lists_use_layout_markers() {
+    assert_eq!(
+        text("<ul><li>One</li><li>Two<ol><li>a</li><li>b</li></ol></li></ul><p>After</p>"),
+        "  \u{2022} One\n  \u{2022} Two\n     1. a\n     2. b\n\nAfter\n"
+    );
+}
+
+#[test]
+fn table_rows_are_lines() {
+    assert_eq!(
+        text("<p>Before</p><table><caption>Prices</caption><tr><th>Item</th><th>Cost</th></tr><tr><td>Tea</td><td>1</td></tr></table>After"),
+        "Before\n\nPrices\nItem Cost\nTea 1\n\nAfter\n"
+    );
+}
+
+#[test]
+fn inline_elements_are_spaced_as_written() {
+    assert_eq!(
+        text("<p>foo<strong>bar</strong>baz, H<sub>2</sub>O, x<q>a</q> and <em>one</em>\n<em>two</em> (<a href=\"#n\">note</a>).</p>"),
+        "foobarbaz, H2O, x\u{201C}a\u{201D} and one two (note [#n]).\n"
+    );
+}
+
+#[test]
+fn wbr_joins_words() {
+    assert_eq!(text("<p>very<wbr>long<wbr>Identifier here</p>"), "verylongIdentifier here\n");
+}
+
+#[test]
+fn quotes_follow_the_language() {
+    assert_eq!(
+        text("<p>He said <q>stop <q>now</q></q>.</p><p lang=\"fr\">Il a dit <q>non</q></p><p lang=\"de-AT\"><q></q></p>"),
+        "He said \u{201C}stop \u{2018}now\u{2019}\u{201D}.\n\nIl a dit \u{AB}non\u{BB}\n\n\u{201E}\u{201C}\n"
+    );
+}
+
+#[test]
+fn links_show_their_targets() {
+    assert_eq!(
+        text("<p>See <a href=\"guide.html#setup\">the guide</a>, <a href=\"#faq\">the FAQ</a> or <img src=\"x.png\" alt=\"a diagram\"><img src=\"y.png\"></p><hr>"),
+        "\
+See the guide [https://example.com/docs/guide.html#setup], the FAQ [#faq] or [a diagram]
+
+----------------------------------------
+"
+    );
+}
+
+#[test]
+fn preformatted_text_keeps_its_lines() {
+    assert_eq!(text("<p>Before</p><pre>a  b\n\n\tc <b>d</b>\n</pre><p>After</p>"), "Before\n\na  b\n\n\tc d\n\nAfter\n");
+}
+
+#[test]
+fn blockquotes_are_indented() {
+    assert_eq!(text("<p>He wrote:</p><blockquote><p>One</p><p>Two</p></blockquote><p>Done</p>"), "He wrote:\n\n    One\n\n    Two\n\nDone\n");
+}
+
+#[test]
+fn ordered_lists_follow_start_reversed_and_type() {
+    assert_eq!(
+        text("<ol start=\"9\" type=\"I\"><li>a</li><li>b</li></ol><ol reversed type=\"a\"><li>c</li><li>d</li><li>e</li></ol>\
+              <ol type=\"i\" start=\"1994\"><li>f</li></ol><ol start=\"-1\" type=\"A\"><li>g</li></ol><ol type=\"a\" start=\"28\"><li>h</li></ol>\
+              <ol start=\"9223372036854775807\"><li>i</li><li>j</li></ol><ol reversed start=\"-9223372036854775808\"><li>k</li><li>l</li></ol>"),
+        "IX. a\n X. b\n\n c. c\n b. d\n a. e\n\nmcmxciv. f\n\n-1. g\n\nab. h\n\n\
+         9223372036854775807. i\n9223372036854775807. j\n\n-9223372036854775808. k\n-9223372036854775808. l\n"
+    );
+}
+
+#[test]
+fn nested_ordered_lists_can_be_numbered_as_an_outline() {
+    let html = "<ol><li>One<ol><li>a<ol type=\"i\"><li>x</li></ol></li><li>b</li></ol></li><li>Two<ul><li>c<ol><li>d</li></ol></li></ul></li></ol>";
+    assert_eq!(text(html), " 1. One\n     1. a\n         i. x\n     2. b\n 2. Two\n      \u{25E6} c\n         1. d\n");
+    radium::layout::set_outline_numbering(true);
+    let outline = text(html);
+    let page = radium::layout(&radium::parse(html), radium::Viewport::new(800, 600), &radium::layout::Estimate);
+    radium::layout::set_outline_numbering(false);
+    assert_eq!(outline, " 1. One\n   1.1. a\n     1.1.i. x\n   1.2. b\n 2. Two\n      \u{25E6} c\n       2.1. d\n");
+    let markers: Vec<_> = page.boxes.iter()
+        .filter_map(|b| match &b.cmd {
+            radium::layout::PaintCmd::Text { content, color: 0x555555, .. } => Some(content.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(markers, ["1.", "1.1.", "1.1.i.", "1.2.", "2.", "\u{25E6}", "2.1."]);
+}
+
+#[test]
+fn definition_lists_indent_descriptions() {
+    assert_eq!(text("<dl><dt>Term</dt><dd>Its meaning</dd><dt>Other</dt><dd>More</dd></dl><p>After</p>"), "Term\n    Its meaning\nOther\n    More\n\nAfter\n");
+}
+
+#[test]
+fn empty_list_items_leave_their_markers_behind() {
+    assert_eq!(text("<ul><li></li></ul><p>x</p>"), "x\n");
+}